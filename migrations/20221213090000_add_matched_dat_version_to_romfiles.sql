@@ -0,0 +1,2 @@
+ALTER TABLE romfiles
+ADD COLUMN matched_dat_version VARCHAR;
@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS headerless_crcs (
+    id INTEGER NOT NULL PRIMARY KEY,
+    archive_path VARCHAR NOT NULL,
+    member_name VARCHAR NOT NULL,
+    mtime BIGINT NOT NULL,
+    size BIGINT NOT NULL,
+    crc VARCHAR(8) NOT NULL,
+    UNIQUE (archive_path, member_name)
+);
@@ -0,0 +1,2 @@
+ALTER TABLE games ADD COLUMN year VARCHAR;
+ALTER TABLE games ADD COLUMN manufacturer VARCHAR;
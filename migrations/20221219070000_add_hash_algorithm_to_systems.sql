@@ -0,0 +1 @@
+ALTER TABLE systems ADD COLUMN hash_algorithm VARCHAR;
@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES('UNTRACKED_DESTINATION', NULL);
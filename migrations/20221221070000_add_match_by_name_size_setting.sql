@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES('MATCH_BY_NAME_SIZE', 'false');
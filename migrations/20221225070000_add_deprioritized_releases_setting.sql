@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES('DEPRIORITIZED_RELEASES', 'Beta,Proto,Demo,Sample');
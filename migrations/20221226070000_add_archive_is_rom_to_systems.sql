@@ -0,0 +1,2 @@
+ALTER TABLE systems
+ADD COLUMN archive_is_rom BOOLEAN NOT NULL DEFAULT false;
@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES('IMPORT_ISO_CONTENTS', 'false');
@@ -0,0 +1,8 @@
+CREATE TABLE IF NOT EXISTS match_preferences (
+    id INTEGER NOT NULL PRIMARY KEY,
+    size BIGINT NOT NULL,
+    hash VARCHAR NOT NULL,
+    rom_id INTEGER NOT NULL,
+    FOREIGN KEY (rom_id) REFERENCES roms(id) ON DELETE CASCADE,
+    UNIQUE (size, hash, rom_id)
+);
@@ -0,0 +1,18 @@
+ALTER TABLE rules
+ADD COLUMN rule_index INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE rules
+ADD COLUMN start_offset BIGINT NOT NULL DEFAULT 0;
+
+ALTER TABLE rules
+ADD COLUMN end_offset BIGINT;
+
+ALTER TABLE rules
+ADD COLUMN result BOOLEAN NOT NULL DEFAULT true;
+
+UPDATE rules
+SET start_offset = (
+    SELECT size
+    FROM headers
+    WHERE headers.id = rules.header_id
+);
@@ -0,0 +1,2 @@
+INSERT OR REPLACE INTO settings ("key", value)
+VALUES('GROUP_MULTI_PART_GAMES', 'false');
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS run_history (
+    id INTEGER NOT NULL PRIMARY KEY,
+    timestamp BIGINT NOT NULL,
+    command VARCHAR NOT NULL,
+    system_id INTEGER NOT NULL,
+    files BIGINT NOT NULL,
+    duration BIGINT NOT NULL,
+    outcome VARCHAR NOT NULL,
+    FOREIGN KEY (system_id) REFERENCES systems(id) ON DELETE CASCADE
+);
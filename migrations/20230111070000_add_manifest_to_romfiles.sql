@@ -0,0 +1,2 @@
+ALTER TABLE romfiles
+ADD COLUMN manifest VARCHAR;
@@ -0,0 +1,14 @@
+ALTER TABLE romfiles
+ADD COLUMN cached_size INTEGER;
+
+ALTER TABLE romfiles
+ADD COLUMN cached_mtime INTEGER;
+
+ALTER TABLE romfiles
+ADD COLUMN cached_crc VARCHAR;
+
+ALTER TABLE romfiles
+ADD COLUMN cached_md5 VARCHAR;
+
+ALTER TABLE romfiles
+ADD COLUMN cached_sha1 VARCHAR;
@@ -0,0 +1,5 @@
+-- cached entries were keyed on whole seconds only
+DELETE FROM headerless_crcs;
+
+ALTER TABLE headerless_crcs
+ADD COLUMN archive_size BIGINT NOT NULL DEFAULT 0;
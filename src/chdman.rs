@@ -9,6 +9,21 @@ use indicatif::ProgressBar;
 use std::process::Command;
 use std::time::Duration;
 
+const CHDMAN: &str = "chdman";
+const CHDMAN_PATH: &str = "CHDMAN_PATH";
+const CHDMAN_MINIMUM_VERSION: &str = "0.145";
+
+pub fn check_tool_available() -> SimpleResult<String> {
+    get_cached_tool_version(CHDMAN, CHDMAN_PATH, || {
+        check_tool_version(
+            CHDMAN,
+            get_tool_version(CHDMAN, CHDMAN_PATH, &[])?,
+            CHDMAN_MINIMUM_VERSION,
+            get_continue_on_tool_version_mismatch(),
+        )
+    })
+}
+
 pub fn create_chd<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     romfile_path: &P,
@@ -25,18 +40,23 @@ pub fn create_chd<P: AsRef<Path>, Q: AsRef<Path>>(
 
     progress_bar.println(format!("Creating {:?}", chd_path.file_name().unwrap()));
 
-    let output = Command::new("chdman")
-        .arg("createcd")
-        .arg("-i")
-        .arg(romfile_path.as_ref())
-        .arg("-o")
-        .arg(&chd_path)
-        .output()
-        .expect("Failed to create CHD");
-
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+    write_atomically(progress_bar, &chd_path, |part_path| {
+        let output = get_tool_output(
+            progress_bar,
+            Command::new(get_tool_path(CHDMAN, CHDMAN_PATH))
+                .arg("createcd")
+                .arg("-i")
+                .arg(romfile_path.as_ref())
+                .arg("-o")
+                .arg(part_path),
+            CHDMAN,
+            CHDMAN_PATH,
+            "Failed to create CHD",
+        )?;
+
+        check_tool_status(CHDMAN, &output)?;
+        Ok(())
+    })?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
@@ -44,6 +64,59 @@ pub fn create_chd<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(chd_path)
 }
 
+// DVD images are stored as a single stream of sectors rather than as CD tracks
+pub fn is_dvd_chd<P: AsRef<Path>>(progress_bar: &ProgressBar, chd_path: &P) -> SimpleResult<bool> {
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(CHDMAN, CHDMAN_PATH))
+            .arg("info")
+            .arg("-i")
+            .arg(chd_path.as_ref()),
+        CHDMAN,
+        CHDMAN_PATH,
+        "Failed to spawn chdman process",
+    )?;
+
+    check_tool_status(CHDMAN, &output)?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).contains("Tag='DVD '"))
+}
+
+pub fn extract_chd_to_iso<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    chd_path: &P,
+    directory: &Q,
+) -> SimpleResult<PathBuf> {
+    progress_bar.set_message("Extracting CHD");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    let mut iso_path = directory
+        .as_ref()
+        .join(chd_path.as_ref().file_name().unwrap());
+    iso_path.set_extension(ISO_EXTENSION);
+
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(CHDMAN, CHDMAN_PATH))
+            .arg("extractdvd")
+            .arg("-i")
+            .arg(chd_path.as_ref())
+            .arg("-o")
+            .arg(&iso_path),
+        CHDMAN,
+        CHDMAN_PATH,
+        "Failed to spawn chdman process",
+    )?;
+
+    check_tool_status(CHDMAN, &output)?;
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    Ok(iso_path)
+}
+
 pub async fn extract_chd_to_multiple_tracks<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     chd_path: &P,
@@ -70,22 +143,24 @@ pub async fn extract_chd_to_multiple_tracks<P: AsRef<Path>, Q: AsRef<Path>>(
         .join(chd_path.as_ref().file_name().unwrap());
     bin_path.set_extension(BIN_EXTENSION);
 
-    let output = Command::new("chdman")
-        .arg("extractcd")
-        .arg("-i")
-        .arg(chd_path.as_ref())
-        .arg("-o")
-        .arg(&cue_path)
-        .arg("-ob")
-        .arg(&bin_path)
-        .output()
-        .expect("Failed to spawn chdman process");
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(CHDMAN, CHDMAN_PATH))
+            .arg("extractcd")
+            .arg("-i")
+            .arg(chd_path.as_ref())
+            .arg("-o")
+            .arg(&cue_path)
+            .arg("-ob")
+            .arg(&bin_path),
+        CHDMAN,
+        CHDMAN_PATH,
+        "Failed to spawn chdman process",
+    )?;
 
     remove_file(progress_bar, &cue_path, true).await?;
 
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str());
-    }
+    check_tool_status(CHDMAN, &output)?;
 
     if bin_names_sizes.len() == 1 {
         let new_bin_path = directory.as_ref().join(bin_names_sizes.first().unwrap().0);
@@ -121,6 +196,65 @@ pub async fn extract_chd_to_multiple_tracks<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(bin_paths)
 }
 
+// chdman can only extract whole discs, keep the requested track and discard the others
+pub async fn extract_chd_track<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    chd_path: &P,
+    directory: &Q,
+    bin_names_sizes: &[(&str, u64)],
+    track: usize,
+    quiet: bool,
+) -> SimpleResult<PathBuf> {
+    if track == 0 || track > bin_names_sizes.len() {
+        bail!(
+            "Track {} doesn't exist, CHD contains {} track(s)",
+            track,
+            bin_names_sizes.len()
+        );
+    }
+    if bin_names_sizes.len() == 1 {
+        return Ok(extract_chd_to_multiple_tracks(
+            progress_bar,
+            chd_path,
+            directory,
+            bin_names_sizes,
+            quiet,
+        )
+        .await?
+        .remove(0));
+    }
+
+    progress_bar.println(format!(
+        "Extracting track {} of {:?}",
+        track,
+        chd_path.as_ref().file_name().unwrap()
+    ));
+    let bin_path = extract_chd_to_single_track(progress_bar, chd_path, directory).await?;
+
+    let (bin_name, size) = bin_names_sizes[track - 1];
+    let offset: u64 = bin_names_sizes[..track - 1]
+        .iter()
+        .map(|(_, size)| size)
+        .sum();
+    let mut bin_file = open_file(&bin_path).await?;
+    try_with!(
+        bin_file.seek(io::SeekFrom::Start(offset)).await,
+        "Failed to seek file"
+    );
+
+    let track_path = directory.as_ref().join(bin_name);
+    let mut track_file = create_file(progress_bar, &track_path, quiet).await?;
+    let mut handle = (&bin_file).take(size);
+    try_with!(
+        io::copy(&mut handle, &mut track_file).await,
+        "Failed to copy data"
+    );
+
+    remove_file(progress_bar, &bin_path, quiet).await?;
+
+    Ok(track_path)
+}
+
 pub async fn extract_chd_to_single_track<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     chd_path: &P,
@@ -140,22 +274,24 @@ pub async fn extract_chd_to_single_track<P: AsRef<Path>, Q: AsRef<Path>>(
         .join(chd_path.as_ref().file_name().unwrap());
     bin_path.set_extension(BIN_EXTENSION);
 
-    let output = Command::new("chdman")
-        .arg("extractcd")
-        .arg("-i")
-        .arg(chd_path.as_ref())
-        .arg("-o")
-        .arg(&cue_path)
-        .arg("-ob")
-        .arg(&bin_path)
-        .output()
-        .expect("Failed to spawn chdman process");
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(CHDMAN, CHDMAN_PATH))
+            .arg("extractcd")
+            .arg("-i")
+            .arg(chd_path.as_ref())
+            .arg("-o")
+            .arg(&cue_path)
+            .arg("-ob")
+            .arg(&bin_path),
+        CHDMAN,
+        CHDMAN_PATH,
+        "Failed to spawn chdman process",
+    )?;
 
     remove_file(progress_bar, &cue_path, true).await?;
 
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str());
-    }
+    check_tool_status(CHDMAN, &output)?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
@@ -5,22 +5,25 @@ use super::config::*;
 use super::database::*;
 #[cfg(feature = "rvz")]
 use super::dolphin;
+use super::history::record_run;
 #[cfg(feature = "cso")]
 use super::maxcso;
 use super::model::*;
+#[cfg(feature = "pbp")]
+use super::pbp;
 use super::progress::*;
 use super::prompt::*;
 use super::sevenzip;
 use super::util::*;
-use async_std::path::Path;
+use async_std::path::{Path, PathBuf};
 use cfg_if::cfg_if;
-use clap::{Arg, ArgAction, ArgMatches, Command};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use indicatif::ProgressBar;
+use itertools::Itertools;
 use simple_error::SimpleResult;
 use sqlx::sqlite::SqliteConnection;
-use std::collections::HashMap;
-use std::convert::TryFrom;
-use std::time::Duration;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime};
 
 pub fn subcommand() -> Command {
     Command::new("check-roms")
@@ -41,6 +44,86 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("REMATCH")
+                .short('r')
+                .long("rematch")
+                .help("Rematch ROM files that fail the check instead of trashing them")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("THOROUGH")
+                .short('t')
+                .long("thorough")
+                .help("Check every available hash instead of the configured one")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("QUICK")
+                .short('q')
+                .long("quick")
+                .help(
+                    "Only compare archive members against their stored manifest when there is one",
+                )
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("FORCE")
+                .short('f')
+                .long("force")
+                .help("Ignore cached checksums and rehash every ROM file")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("LIMIT")
+                .short('l')
+                .long("limit")
+                .help("Stop after checking N ROM files")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("TRACK")
+                .long("track")
+                .help("Only check track N of CHD files")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("MOVE_INCOMPLETE_TO")
+                .long("move-incomplete-to")
+                .help("Move the remaining files of games made incomplete to a directory")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("LIMIT_RATE")
+                .long("limit-rate")
+                .help("Throttle hashing to N MB/s, 0 means unlimited")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(u64)),
+        )
+}
+
+// set from the command line, verify-against checks with the defaults
+#[derive(Clone, Default)]
+pub struct CheckOptions {
+    pub size: bool,
+    pub rematch: bool,
+    pub thorough: bool,
+    pub quick: bool,
+    pub force: bool,
+    pub limit: Option<usize>,
+    pub track: Option<usize>,
+    pub incomplete_directory: Option<PathBuf>,
 }
 
 pub async fn main(
@@ -48,43 +131,83 @@ pub async fn main(
     matches: &ArgMatches,
     progress_bar: &ProgressBar,
 ) -> SimpleResult<()> {
+    set_limit_rate(
+        matches
+            .get_one::<u64>("LIMIT_RATE")
+            .copied()
+            .unwrap_or(0)
+            .saturating_mul(1_000_000),
+    );
     let systems = prompt_for_systems(connection, None, false, matches.get_flag("ALL")).await?;
-    let hash_algorithm = match find_setting_by_key(connection, "HASH_ALGORITHM")
-        .await
-        .unwrap()
-        .value
-        .as_deref()
-    {
-        Some("CRC") => HashAlgorithm::Crc,
-        Some("MD5") => HashAlgorithm::Md5,
-        Some("SHA1") => HashAlgorithm::Sha1,
-        Some(_) | None => bail!("Not possible"),
+    let multi_progress = get_multi_progress(progress_bar);
+    let overall_progress_bar = multi_progress.add(get_progress_bar(0, get_count_progress_style()));
+    let progress_bar = &multi_progress.add(progress_bar.clone());
+    let mut limit = matches.get_one::<usize>("LIMIT").copied();
+    let options = CheckOptions {
+        size: matches.get_flag("SIZE"),
+        rematch: matches.get_flag("REMATCH"),
+        thorough: matches.get_flag("THOROUGH"),
+        quick: matches.get_flag("QUICK"),
+        force: matches.get_flag("FORCE"),
+        limit: None,
+        track: matches.get_one::<usize>("TRACK").copied(),
+        incomplete_directory: matches.get_one::<PathBuf>("MOVE_INCOMPLETE_TO").cloned(),
     };
     for system in systems {
+        if limit == Some(0) {
+            break;
+        }
         progress_bar.println(&format!("Processing \"{}\"", system.name));
-        check_system(
+        let hash_algorithm = get_hash_algorithm(connection, &system).await?;
+        let started_at = SystemTime::now();
+        let result = check_system(
             connection,
             progress_bar,
+            &overall_progress_bar,
             &system,
-            matches.get_flag("SIZE"),
             &hash_algorithm,
+            &CheckOptions {
+                limit,
+                ..options.clone()
+            },
         )
-        .await?;
+        .await;
+        record_run(
+            connection,
+            "check-roms",
+            &system,
+            *result.as_ref().unwrap_or(&0),
+            started_at,
+            &result,
+        )
+        .await;
+        let checked = result?;
+        limit = limit.map(|limit| limit - checked);
         progress_bar.println("");
     }
+    overall_progress_bar.finish_and_clear();
     Ok(())
 }
 
-async fn check_system(
+pub async fn check_system(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
+    overall_progress_bar: &ProgressBar,
     system: &System,
-    size: bool,
     hash_algorithm: &HashAlgorithm,
-) -> SimpleResult<()> {
+    options: &CheckOptions,
+) -> SimpleResult<usize> {
     let header = find_header_by_system_id(connection, system.id).await;
     let roms = find_roms_with_romfile_by_system_id(connection, system.id).await;
-    let romfiles = find_romfiles_by_system_id(connection, system.id).await;
+    let mut romfiles = find_romfiles_by_system_id(connection, system.id).await;
+    if let Some(limit) = options.limit {
+        romfiles.truncate(limit);
+    }
+    let checked = romfiles.len();
+    check_tools_available_for_files(
+        romfiles.iter().map(|romfile| &romfile.path),
+        system.archive_is_rom,
+    )?;
     let mut roms_by_romfile_id: HashMap<i64, Vec<Rom>> = HashMap::new();
     roms.into_iter().for_each(|rom| {
         let group = roms_by_romfile_id
@@ -96,25 +219,84 @@ async fn check_system(
     let mut transaction = begin_transaction(connection).await;
 
     let mut errors = 0;
+    let mut trashed_romfile_ids: HashSet<i64> = HashSet::new();
+    let mut incomplete_game_ids: HashSet<i64> = HashSet::new();
+
+    let hash_algorithms = if options.thorough {
+        vec![
+            &HashAlgorithm::Crc,
+            &HashAlgorithm::Md5,
+            &HashAlgorithm::Sha1,
+        ]
+    } else {
+        vec![hash_algorithm]
+    };
+
+    overall_progress_bar.reset();
+    overall_progress_bar.set_message(format!("Checking \"{}\"", system.name));
+    overall_progress_bar.set_length(romfiles.len() as u64);
 
     for romfile in romfiles {
-        let romfile_path = get_canonicalized_path(&romfile.path).await?;
+        overall_progress_bar.inc(1);
+        // a broken symlink or a vanished directory is a missing file, not a fatal error
+        let romfile_path = match get_canonicalized_path(&romfile.path).await {
+            Ok(romfile_path) => romfile_path,
+            Err(_) => {
+                progress_bar.println(format!(
+                    "Missing {:?}, run purge-roms --missing to clean it up",
+                    &romfile.path
+                ));
+                continue;
+            }
+        };
         let romfile_extension = romfile_path.extension().unwrap().to_str().unwrap();
         let roms = roms_by_romfile_id.remove(&romfile.id).unwrap();
+        let game_ids: Vec<i64> = roms.iter().map(|rom| rom.game_id).collect();
 
         progress_bar.println(&format!(
             "Processing {:?}",
             romfile_path.file_name().unwrap()
         ));
 
+        // archives whose member list was stored during the import can skip the extraction
+        let manifest = romfile.manifest.as_deref().filter(|_| {
+            options.quick
+                && ARCHIVE_EXTENSIONS.contains(&romfile_extension)
+                && !system.archive_is_rom
+        });
+
+        // files left untouched since their last successful check don't need to be rehashed
+        let size_mtime = get_size_and_mtime(&romfile_path).await.ok();
+        let expected_hashes: Vec<(&HashAlgorithm, String)> = hash_algorithms
+            .iter()
+            .map(|&hash_algorithm| (hash_algorithm, get_expected_hashes(&roms, hash_algorithm)))
+            .collect();
+        let cached = !options.force
+            && size_mtime.is_some_and(|(romfile_size, romfile_mtime)| {
+                romfile.cached_size == Some(romfile_size)
+                    && romfile.cached_mtime == Some(romfile_mtime)
+            })
+            && expected_hashes.iter().all(|(hash_algorithm, hashes)| {
+                get_cached_hash(&romfile, hash_algorithm) == Some(hashes.as_str())
+            });
+        // CHD tracks checked on their own don't vouch for the whole file
+        let cacheable =
+            manifest.is_none() && (CHD_EXTENSION != romfile_extension || options.track.is_none());
+
         let result;
-        if ARCHIVE_EXTENSIONS.contains(&romfile_extension) {
+        if cached {
+            progress_bar.println("Checksums are cached, skipping");
+            result = Ok(());
+        } else if let Some(manifest) = manifest {
+            result = sevenzip::check_manifest(progress_bar, &romfile_path, manifest);
+        } else if ARCHIVE_EXTENSIONS.contains(&romfile_extension) && !system.archive_is_rom {
             result = check_archive(
                 &mut transaction,
                 progress_bar,
                 &header,
                 &romfile_path,
                 roms,
+                options.thorough,
                 hash_algorithm,
             )
             .await;
@@ -126,6 +308,8 @@ async fn check_system(
                         progress_bar, &header,
                         &romfile_path,
                         roms,
+                        options.thorough,
+                        options.track,
                         hash_algorithm
                     )
                     .await;
@@ -143,6 +327,25 @@ async fn check_system(
                         &header,
                         &romfile_path,
                         roms.get(0).unwrap(),
+                        options.thorough,
+                        hash_algorithm
+                    )
+                    .await;
+                } else {
+                    progress_bar.println("Please rebuild with the CSO feature enabled");
+                    continue;
+                }
+            }
+        } else if ZSO_EXTENSION == romfile_extension {
+            cfg_if! {
+                if #[cfg(feature = "cso")] {
+                    result = check_zso(
+                        &mut transaction,
+                        progress_bar,
+                        &header,
+                        &romfile_path,
+                        roms.get(0).unwrap(),
+                        options.thorough,
                         hash_algorithm
                     )
                     .await;
@@ -160,6 +363,7 @@ async fn check_system(
                         &header,
                         &romfile_path,
                         roms.get(0).unwrap(),
+                        options.thorough,
                         hash_algorithm
                     )
                     .await;
@@ -168,6 +372,24 @@ async fn check_system(
                     continue;
                 }
             }
+        } else if PBP_EXTENSION == romfile_extension {
+            cfg_if! {
+                if #[cfg(feature = "pbp")] {
+                    result = check_pbp(
+                        &mut transaction,
+                        progress_bar,
+                        &header,
+                        &romfile_path,
+                        roms,
+                        options.thorough,
+                        hash_algorithm
+                    )
+                    .await;
+                } else {
+                    progress_bar.println("Please rebuild with the PBP feature enabled");
+                    continue;
+                }
+            }
         } else {
             result = check_original(
                 &mut transaction,
@@ -175,22 +397,65 @@ async fn check_system(
                 &header,
                 &romfile_path,
                 roms.get(0).unwrap(),
+                options.thorough,
                 hash_algorithm,
             )
             .await;
         }
 
-        if result.is_err() {
+        if let Err(error) = result {
             errors += 1;
-            move_to_trash(&mut transaction, progress_bar, system, &romfile).await?;
-        } else if size {
-            update_romfile(
-                &mut transaction,
-                romfile.id,
-                &romfile.path,
-                Path::new(&romfile.path).metadata().await.unwrap().len(),
-            )
-            .await;
+            if options.rematch {
+                rematch_romfile(
+                    &mut transaction,
+                    progress_bar,
+                    system,
+                    &header,
+                    &romfile,
+                    &romfile_path,
+                    hash_algorithm,
+                )
+                .await?;
+            } else {
+                let reason = get_trash_reason(error.as_str(), hash_algorithm);
+                move_to_trash(&mut transaction, progress_bar, system, &romfile, reason).await?;
+                trashed_romfile_ids.insert(romfile.id);
+                incomplete_game_ids.extend(game_ids);
+            }
+        } else {
+            if options.size {
+                update_romfile(
+                    &mut transaction,
+                    romfile.id,
+                    &romfile.path,
+                    Path::new(&romfile.path).metadata().await.unwrap().len(),
+                )
+                .await;
+            }
+            if let Some((romfile_size, romfile_mtime)) = size_mtime.filter(|_| !cached && cacheable)
+            {
+                // keep the other cached hashes as long as the file hasn't changed
+                let unchanged = romfile.cached_size == Some(romfile_size)
+                    && romfile.cached_mtime == Some(romfile_mtime);
+                let get_hash = |hash_algorithm: &HashAlgorithm| match expected_hashes
+                    .iter()
+                    .find(|(expected_hash_algorithm, _)| *expected_hash_algorithm == hash_algorithm)
+                {
+                    Some((_, hashes)) => Some(hashes.as_str()),
+                    None if unchanged => get_cached_hash(&romfile, hash_algorithm),
+                    None => None,
+                };
+                update_romfile_checksum_cache(
+                    &mut transaction,
+                    romfile.id,
+                    romfile_size,
+                    romfile_mtime,
+                    get_hash(&HashAlgorithm::Crc),
+                    get_hash(&HashAlgorithm::Md5),
+                    get_hash(&HashAlgorithm::Sha1),
+                )
+                .await;
+            }
         }
     }
 
@@ -201,6 +466,14 @@ async fn check_system(
         progress_bar.set_style(get_none_progress_style());
         progress_bar.enable_steady_tick(Duration::from_millis(100));
         progress_bar.set_message("Computing system completion");
+        if options.rematch {
+            update_games_by_system_id_mark_complete(connection, system.id).await;
+            cfg_if! {
+                if #[cfg(feature = "ird")] {
+                    update_jbfolder_games_by_system_id_mark_complete(connection, system.id).await;
+                }
+            }
+        }
         update_games_by_system_id_mark_incomplete(connection, system.id).await;
         cfg_if! {
             if #[cfg(feature = "ird")] {
@@ -211,15 +484,182 @@ async fn check_system(
         update_system_mark_incomplete(connection, system.id).await;
     }
 
+    // gather what's left of games that lost files during this run
+    if let Some(incomplete_directory) = &options.incomplete_directory {
+        if !incomplete_game_ids.is_empty() {
+            move_incomplete_romfiles(
+                connection,
+                progress_bar,
+                system,
+                &incomplete_game_ids.into_iter().collect::<Vec<i64>>(),
+                &trashed_romfile_ids,
+                incomplete_directory,
+            )
+            .await?;
+        }
+    }
+
+    Ok(checked)
+}
+
+// relink the file in place to the ROMs its content matches now, nothing is moved
+async fn rematch_romfile<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    header: &Option<Header>,
+    romfile: &Romfile,
+    romfile_path: &P,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<()> {
+    progress_bar.println("Rematching");
+    let romfile_extension = romfile_path.as_ref().extension().unwrap().to_str().unwrap();
+    let mut names_sizes_hashes: Vec<(String, u64, String)> = Vec::new();
+    if ARCHIVE_EXTENSIONS.contains(&romfile_extension) && !system.archive_is_rom {
+        for sevenzip_info in sevenzip::parse_archive(progress_bar, romfile_path)? {
+            let (size, hash) = get_archive_member_size_and_hash(
+                connection,
+                progress_bar,
+                header,
+                romfile_path,
+                &sevenzip_info,
+                hash_algorithm,
+            )
+            .await?;
+            names_sizes_hashes.push((sevenzip_info.path, size, hash));
+        }
+    } else if [
+        CHD_EXTENSION,
+        CSO_EXTENSION,
+        ZSO_EXTENSION,
+        RVZ_EXTENSION,
+        PBP_EXTENSION,
+    ]
+    .contains(&romfile_extension)
+    {
+        progress_bar
+            .println("Only original files and archives can be rematched, leaving it in place");
+        return Ok(());
+    } else {
+        let (size, hash) = get_size_and_hash(
+            connection,
+            progress_bar,
+            romfile_path,
+            header,
+            1,
+            1,
+            hash_algorithm,
+        )
+        .await?;
+        names_sizes_hashes.push((
+            romfile_path
+                .as_ref()
+                .file_name()
+                .unwrap()
+                .to_str()
+                .unwrap()
+                .to_owned(),
+            size,
+            hash,
+        ));
+    }
+
+    // every file must match a ROM that isn't already provided by another file
+    let mut new_roms: Vec<Rom> = Vec::new();
+    for (name, size, hash) in names_sizes_hashes {
+        let roms = match hash_algorithm {
+            HashAlgorithm::Crc => {
+                find_roms_by_size_and_crc_and_system_id(connection, size, &hash, system.id).await?
+            }
+            HashAlgorithm::Md5 => {
+                find_roms_by_size_and_md5_and_system_id(connection, size, &hash, system.id).await?
+            }
+            HashAlgorithm::Sha1 => {
+                find_roms_by_size_and_sha1_and_system_id(connection, size, &hash, system.id).await?
+            }
+        };
+        match roms.into_iter().find(|rom| {
+            rom.romfile_id
+                .is_none_or(|romfile_id| romfile_id == romfile.id)
+                && !new_roms.iter().any(|new_rom| new_rom.id == rom.id)
+        }) {
+            Some(rom) => new_roms.push(rom),
+            None => {
+                progress_bar.println(format!("No match for \"{}\", leaving it in place", name));
+                return Ok(());
+            }
+        }
+    }
+
+    let old_roms = find_roms_by_romfile_id(connection, romfile.id).await;
+    for rom in &old_roms {
+        update_rom_romfile(connection, rom.id, None).await;
+    }
+    for rom in &new_roms {
+        update_rom_romfile(connection, rom.id, Some(romfile.id)).await;
+    }
+    progress_bar.println(format!(
+        "Rematched from \"{}\" to \"{}\"",
+        old_roms.iter().map(|rom| rom.name.as_str()).join("\", \""),
+        new_roms.iter().map(|rom| rom.name.as_str()).join("\", \""),
+    ));
     Ok(())
 }
 
-async fn check_archive<P: AsRef<Path>>(
+async fn move_incomplete_romfiles<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    game_ids: &[i64],
+    trashed_romfile_ids: &HashSet<i64>,
+    incomplete_directory: &P,
+) -> SimpleResult<()> {
+    let system_directory =
+        get_canonicalized_path(&get_system_directory(connection, progress_bar, system).await?)
+            .await?;
+    create_directory(progress_bar, incomplete_directory, true).await?;
+    let incomplete_directory = get_canonicalized_path(incomplete_directory).await?;
+    let romfile_ids: Vec<i64> = find_roms_with_romfile_by_game_ids(connection, game_ids)
+        .await
+        .into_iter()
+        .map(|rom| rom.romfile_id.unwrap())
+        .filter(|romfile_id| !trashed_romfile_ids.contains(romfile_id))
+        .collect::<HashSet<i64>>()
+        .into_iter()
+        .collect();
+    let romfiles = find_romfiles_by_ids(connection, &romfile_ids).await;
+
+    let mut transaction = begin_transaction(connection).await;
+
+    for romfile in romfiles {
+        // keep the layout relative to the system directory to avoid name clashes
+        let romfile_path = Path::new(&romfile.path);
+        let new_path = match romfile_path.strip_prefix(&system_directory) {
+            Ok(relative_path) => incomplete_directory.join(relative_path),
+            Err(_) => incomplete_directory.join(romfile_path.file_name().unwrap()),
+        };
+        rename_file(progress_bar, &romfile.path, &new_path, false).await?;
+        update_romfile(
+            &mut transaction,
+            romfile.id,
+            new_path.as_os_str().to_str().unwrap(),
+            romfile.size as u64,
+        )
+        .await;
+    }
+
+    commit_transaction(transaction).await;
+
+    Ok(())
+}
+
+pub async fn check_archive<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     header: &Option<Header>,
     romfile_path: &P,
     mut roms: Vec<Rom>,
+    thorough: bool,
     hash_algorithm: &HashAlgorithm,
 ) -> SimpleResult<()> {
     let sevenzip_infos = sevenzip::parse_archive(progress_bar, romfile_path)?;
@@ -229,10 +669,7 @@ async fn check_archive<P: AsRef<Path>>(
     }
 
     for sevenzip_info in sevenzip_infos {
-        let size: u64;
-        let hash: String;
-        if header.is_some() || sevenzip_info.crc.is_empty() || hash_algorithm != &HashAlgorithm::Crc
-        {
+        if thorough {
             let tmp_directory = create_tmp_directory(connection).await?;
             let extracted_path = sevenzip::extract_files_from_archive(
                 progress_bar,
@@ -241,71 +678,163 @@ async fn check_archive<P: AsRef<Path>>(
                 &tmp_directory.path(),
             )?
             .remove(0);
-            let size_hash = get_size_and_hash(
-                connection,
-                progress_bar,
-                &extracted_path,
-                header,
-                1,
-                1,
-                hash_algorithm,
-            )
-            .await?;
-            size = size_hash.0;
-            hash = size_hash.1;
-        } else {
-            size = sevenzip_info.size;
-            hash = sevenzip_info.crc.clone();
+            let (size, crc, md5, sha1) =
+                get_size_and_hashes(connection, progress_bar, &extracted_path, header, 1, 1)
+                    .await?;
+            let rom_index = find_rom_index_by(&roms, &sevenzip_info.path, |rom| {
+                check_size_and_hashes(rom, size_to_i64(size)?, &crc, &md5, &sha1)
+            })?;
+            roms.remove(rom_index);
+            continue;
         }
-        let rom_index = roms
-            .iter()
-            .position(|rom| rom.name == sevenzip_info.path)
-            .unwrap();
-        let rom = roms.remove(rom_index);
-        check_size_and_hash(&rom, i64::try_from(size).unwrap(), &hash, hash_algorithm)?;
+        let (size, hash) = get_archive_member_size_and_hash(
+            connection,
+            progress_bar,
+            header,
+            romfile_path,
+            &sevenzip_info,
+            hash_algorithm,
+        )
+        .await?;
+        let rom_index = find_rom_index(
+            &roms,
+            &sevenzip_info.path,
+            size_to_i64(size)?,
+            &hash,
+            hash_algorithm,
+        )?;
+        roms.remove(rom_index);
     }
 
     Ok(())
 }
 
+async fn get_archive_member_size_and_hash<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    header: &Option<Header>,
+    romfile_path: &P,
+    sevenzip_info: &sevenzip::ArchiveInfo,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<(u64, String)> {
+    if header.is_some() && hash_algorithm == &HashAlgorithm::Crc {
+        get_archive_member_size_and_headerless_crc(
+            connection,
+            progress_bar,
+            romfile_path,
+            &sevenzip_info.path,
+            header,
+        )
+        .await
+    } else if header.is_some()
+        || sevenzip_info.crc.is_empty()
+        || hash_algorithm != &HashAlgorithm::Crc
+    {
+        let tmp_directory = create_tmp_directory(connection).await?;
+        let extracted_path = sevenzip::extract_files_from_archive(
+            progress_bar,
+            romfile_path,
+            &[&sevenzip_info.path],
+            &tmp_directory.path(),
+        )?
+        .remove(0);
+        get_size_and_hash(
+            connection,
+            progress_bar,
+            &extracted_path,
+            header,
+            1,
+            1,
+            hash_algorithm,
+        )
+        .await
+    } else {
+        Ok((sevenzip_info.size, sevenzip_info.crc.clone()))
+    }
+}
+
 #[cfg(feature = "chd")]
-async fn check_chd<P: AsRef<Path>>(
+pub async fn check_chd<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     header: &Option<Header>,
     romfile_path: &P,
-    roms: Vec<Rom>,
+    mut roms: Vec<Rom>,
+    thorough: bool,
+    track: Option<usize>,
     hash_algorithm: &HashAlgorithm,
 ) -> SimpleResult<()> {
+    if let Some(track) = track {
+        if track == 0 || track > roms.len() {
+            progress_bar.println(format!(
+                "CHD contains {} track(s), skipping track {}",
+                roms.len(),
+                track
+            ));
+            return Ok(());
+        }
+    }
+
     let tmp_directory = create_tmp_directory(connection).await?;
 
     let names_sizes: Vec<(&str, u64)> = roms
         .iter()
         .map(|rom| (rom.name.as_str(), rom.size as u64))
         .collect();
-    let bin_paths = chdman::extract_chd_to_multiple_tracks(
-        progress_bar,
-        romfile_path,
-        &tmp_directory.path(),
-        &names_sizes,
-        true,
-    )
-    .await?;
-    let mut hashes: Vec<String> = Vec::new();
-    for (i, bin_path) in bin_paths.iter().enumerate() {
-        let (_, hash) = get_size_and_hash(
-            connection,
+    // DVD CHDs hold a single ISO rather than tracks
+    let bin_paths = if roms.len() == 1 && chdman::is_dvd_chd(progress_bar, romfile_path)? {
+        vec![chdman::extract_chd_to_iso(
             progress_bar,
-            &bin_path,
-            header,
-            i,
-            bin_paths.len(),
-            hash_algorithm,
-        )
-        .await?;
-        hashes.push(hash);
+            romfile_path,
+            &tmp_directory.path(),
+        )?]
+    } else {
+        match track {
+            Some(track) => {
+                let bin_path = chdman::extract_chd_track(
+                    progress_bar,
+                    romfile_path,
+                    &tmp_directory.path(),
+                    &names_sizes,
+                    track,
+                    true,
+                )
+                .await?;
+                roms = vec![roms.remove(track - 1)];
+                vec![bin_path]
+            }
+            None => {
+                chdman::extract_chd_to_multiple_tracks(
+                    progress_bar,
+                    romfile_path,
+                    &tmp_directory.path(),
+                    &names_sizes,
+                    true,
+                )
+                .await?
+            }
+        }
+    };
+
+    if thorough {
+        for (i, bin_path) in bin_paths.iter().enumerate() {
+            let (size, crc, md5, sha1) = get_size_and_hashes(
+                connection,
+                progress_bar,
+                &bin_path,
+                header,
+                i + 1,
+                bin_paths.len(),
+            )
+            .await?;
+            check_size_and_hashes(&roms[i], size_to_i64(size)?, &crc, &md5, &sha1)?;
+        }
+        return Ok(());
     }
 
+    let hashes =
+        get_track_hashes(connection, progress_bar, &bin_paths, header, hash_algorithm).await?;
+
     match hash_algorithm {
         HashAlgorithm::Crc => {
             if roms
@@ -340,16 +869,20 @@ async fn check_chd<P: AsRef<Path>>(
 }
 
 #[cfg(feature = "cso")]
-async fn check_cso<P: AsRef<Path>>(
+pub async fn check_cso<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     header: &Option<Header>,
     romfile_path: &P,
     rom: &Rom,
+    thorough: bool,
     hash_algorithm: &HashAlgorithm,
 ) -> SimpleResult<()> {
     let tmp_directory = create_tmp_directory(connection).await?;
     let iso_path = maxcso::extract_cso(progress_bar, romfile_path, &tmp_directory.path())?;
+    if thorough {
+        return check_file_thoroughly(connection, progress_bar, header, &iso_path, rom).await;
+    }
     let (size, hash) = get_size_and_hash(
         connection,
         progress_bar,
@@ -360,21 +893,117 @@ async fn check_cso<P: AsRef<Path>>(
         hash_algorithm,
     )
     .await?;
-    check_size_and_hash(rom, i64::try_from(size).unwrap(), &hash, hash_algorithm)?;
+    check_size_and_hash(rom, size_to_i64(size)?, &hash, hash_algorithm)?;
+    Ok(())
+}
+
+#[cfg(feature = "cso")]
+pub async fn check_zso<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    header: &Option<Header>,
+    romfile_path: &P,
+    rom: &Rom,
+    thorough: bool,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<()> {
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let iso_path = maxcso::extract_zso(progress_bar, romfile_path, &tmp_directory.path())?;
+    if thorough {
+        return check_file_thoroughly(connection, progress_bar, header, &iso_path, rom).await;
+    }
+    let (size, hash) = get_size_and_hash(
+        connection,
+        progress_bar,
+        &iso_path,
+        header,
+        1,
+        1,
+        hash_algorithm,
+    )
+    .await?;
+    check_size_and_hash(rom, size_to_i64(size)?, &hash, hash_algorithm)?;
+    Ok(())
+}
+
+#[cfg(feature = "pbp")]
+async fn check_pbp<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    header: &Option<Header>,
+    romfile_path: &P,
+    mut roms: Vec<Rom>,
+    thorough: bool,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<()> {
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let bin_paths = pbp::extract_pbp(progress_bar, romfile_path, &tmp_directory.path())?;
+
+    if bin_paths.len() != roms.len() {
+        bail!("PBP contains a different number of discs");
+    }
+
+    for (i, bin_path) in bin_paths.iter().enumerate() {
+        if thorough {
+            let (size, crc, md5, sha1) = get_size_and_hashes(
+                connection,
+                progress_bar,
+                bin_path,
+                header,
+                i + 1,
+                bin_paths.len(),
+            )
+            .await?;
+            let size = size_to_i64(size)?;
+            let rom_index = match roms
+                .iter()
+                .position(|rom| check_size_and_hashes(rom, size, &crc, &md5, &sha1).is_ok())
+            {
+                Some(rom_index) => rom_index,
+                None => bail!("Checksum mismatch"),
+            };
+            roms.remove(rom_index);
+            continue;
+        }
+        let (size, hash) = get_size_and_hash(
+            connection,
+            progress_bar,
+            bin_path,
+            header,
+            i + 1,
+            bin_paths.len(),
+            hash_algorithm,
+        )
+        .await?;
+        let size = size_to_i64(size)?;
+        let rom_index = match roms
+            .iter()
+            .position(|rom| check_size_and_hash(rom, size, &hash, hash_algorithm).is_ok())
+        {
+            Some(rom_index) => rom_index,
+            None => bail!("Checksum mismatch"),
+        };
+        roms.remove(rom_index);
+    }
+
     Ok(())
 }
 
 #[cfg(feature = "rvz")]
-async fn check_rvz<P: AsRef<Path>>(
+pub async fn check_rvz<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     header: &Option<Header>,
     romfile_path: &P,
     rom: &Rom,
+    thorough: bool,
     hash_algorithm: &HashAlgorithm,
 ) -> SimpleResult<()> {
     let tmp_directory = create_tmp_directory(connection).await?;
     let iso_path = dolphin::extract_rvz(progress_bar, romfile_path, &tmp_directory.path())?;
+    if thorough {
+        return check_file_thoroughly(connection, progress_bar, header, &iso_path, rom).await;
+    }
     let (size, hash) = get_size_and_hash(
         connection,
         progress_bar,
@@ -385,18 +1014,22 @@ async fn check_rvz<P: AsRef<Path>>(
         hash_algorithm,
     )
     .await?;
-    check_size_and_hash(rom, i64::try_from(size).unwrap(), &hash, hash_algorithm)?;
+    check_size_and_hash(rom, size_to_i64(size)?, &hash, hash_algorithm)?;
     Ok(())
 }
 
-async fn check_original<P: AsRef<Path>>(
+pub async fn check_original<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     header: &Option<Header>,
     romfile_path: &P,
     rom: &Rom,
+    thorough: bool,
     hash_algorithm: &HashAlgorithm,
 ) -> SimpleResult<()> {
+    if thorough {
+        return check_file_thoroughly(connection, progress_bar, header, romfile_path, rom).await;
+    }
     let (size, hash) = get_size_and_hash(
         connection,
         progress_bar,
@@ -407,10 +1040,58 @@ async fn check_original<P: AsRef<Path>>(
         hash_algorithm,
     )
     .await?;
-    check_size_and_hash(rom, i64::try_from(size).unwrap(), &hash, hash_algorithm)?;
+    check_size_and_hash(rom, size_to_i64(size)?, &hash, hash_algorithm)?;
     Ok(())
 }
 
+async fn check_file_thoroughly<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    header: &Option<Header>,
+    file_path: &P,
+    rom: &Rom,
+) -> SimpleResult<()> {
+    let (size, crc, md5, sha1) =
+        get_size_and_hashes(connection, progress_bar, file_path, header, 1, 1).await?;
+    check_size_and_hashes(rom, size_to_i64(size)?, &crc, &md5, &sha1)
+}
+
+fn find_rom_index(
+    roms: &[Rom],
+    name: &str,
+    size: i64,
+    hash: &str,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<usize> {
+    find_rom_index_by(roms, name, |rom| {
+        check_size_and_hash(rom, size, hash, hash_algorithm)
+    })
+}
+
+fn find_rom_index_by<F: Fn(&Rom) -> SimpleResult<()>>(
+    roms: &[Rom],
+    name: &str,
+    check: F,
+) -> SimpleResult<usize> {
+    // DATs may contain multiple ROMs sharing a name, match on size and hash too
+    let rom_indices: Vec<usize> = roms
+        .iter()
+        .enumerate()
+        .filter(|(_, rom)| names_match(&rom.name, name))
+        .map(|(i, _)| i)
+        .collect();
+    if let Some(&rom_index) = rom_indices.iter().find(|&&i| check(&roms[i]).is_ok()) {
+        return Ok(rom_index);
+    }
+    match rom_indices.first() {
+        Some(&rom_index) => {
+            check(&roms[rom_index])?;
+            Ok(rom_index)
+        }
+        None => bail!("Archive contains an unmatched ROM file \"{}\"", name),
+    }
+}
+
 fn check_size_and_hash(
     rom: &Rom,
     size: i64,
@@ -420,6 +1101,10 @@ fn check_size_and_hash(
     if size != rom.size {
         bail!("Size mismatch");
     };
+    // ROMs without any hash can only be checked by size
+    if rom.crc.is_none() && rom.md5.is_none() && rom.sha1.is_none() {
+        return Ok(());
+    }
     match hash_algorithm {
         HashAlgorithm::Crc => {
             if hash != rom.crc.as_ref().unwrap() {
@@ -440,13 +1125,90 @@ fn check_size_and_hash(
     Ok(())
 }
 
-async fn move_to_trash(
+pub fn check_size_and_hashes(
+    rom: &Rom,
+    size: i64,
+    crc: &str,
+    md5: &str,
+    sha1: &str,
+) -> SimpleResult<()> {
+    if size != rom.size {
+        bail!("Size mismatch");
+    };
+    // report every populated hash that doesn't match
+    let mismatches: Vec<&str> = [
+        (HashAlgorithm::Crc, &rom.crc, crc),
+        (HashAlgorithm::Md5, &rom.md5, md5),
+        (HashAlgorithm::Sha1, &rom.sha1, sha1),
+    ]
+    .iter()
+    .filter(|(_, expected, actual)| expected.is_some() && expected.as_deref() != Some(*actual))
+    .map(|(hash_algorithm, _, _)| hash_algorithm.name())
+    .collect();
+    if !mismatches.is_empty() {
+        bail!("Checksum mismatch ({})", mismatches.join(", "));
+    }
+    Ok(())
+}
+
+// the cache holds the hashes of the ROMs a file last matched, in a stable order
+fn get_expected_hashes(roms: &[Rom], hash_algorithm: &HashAlgorithm) -> String {
+    roms.iter()
+        .sorted_by_key(|rom| rom.id)
+        .map(|rom| {
+            match hash_algorithm {
+                HashAlgorithm::Crc => rom.crc.as_deref(),
+                HashAlgorithm::Md5 => rom.md5.as_deref(),
+                HashAlgorithm::Sha1 => rom.sha1.as_deref(),
+            }
+            .unwrap_or_default()
+        })
+        .join(",")
+}
+
+fn get_cached_hash<'a>(romfile: &'a Romfile, hash_algorithm: &HashAlgorithm) -> Option<&'a str> {
+    match hash_algorithm {
+        HashAlgorithm::Crc => romfile.cached_crc.as_deref(),
+        HashAlgorithm::Md5 => romfile.cached_md5.as_deref(),
+        HashAlgorithm::Sha1 => romfile.cached_sha1.as_deref(),
+    }
+}
+
+fn get_trash_reason(error: &str, hash_algorithm: &HashAlgorithm) -> TrashReason {
+    // thorough checks report the mismatching algorithms, use the first one
+    if let Some(hash_algorithms) = error
+        .strip_prefix("Checksum mismatch (")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        if let Ok(hash_algorithm) = hash_algorithms.split(", ").next().unwrap().parse() {
+            return get_trash_reason("Checksum mismatch", &hash_algorithm);
+        }
+    }
+    match error {
+        "Size mismatch" => TrashReason::SizeMismatch,
+        "Checksum mismatch" => match hash_algorithm {
+            HashAlgorithm::Crc => TrashReason::CrcMismatch,
+            HashAlgorithm::Md5 => TrashReason::Md5Mismatch,
+            HashAlgorithm::Sha1 => TrashReason::Sha1Mismatch,
+        },
+        _ if error.starts_with("Archive contains an unmatched ROM file") => TrashReason::NoMatch,
+        _ => TrashReason::Invalid,
+    }
+}
+
+pub async fn move_to_trash(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     system: &System,
     romfile: &Romfile,
+    reason: TrashReason,
 ) -> SimpleResult<()> {
-    let new_path = get_trash_directory(connection, progress_bar, system)
+    if get_trash_backend(connection).await == TrashBackend::SystemTrash {
+        move_to_system_trash(progress_bar, &romfile.path, true).await?;
+        delete_romfile_by_id(connection, romfile.id).await;
+        return Ok(());
+    }
+    let new_path = get_trash_reason_directory(connection, progress_bar, system, &reason)
         .await?
         .join(Path::new(&romfile.path).file_name().unwrap());
     rename_file(progress_bar, &romfile.path, &new_path, true).await?;
@@ -460,23 +1222,47 @@ async fn move_to_trash(
     Ok(())
 }
 
+#[cfg(test)]
+mod test_archive_duplicate_rom_names;
 #[cfg(all(test, feature = "chd"))]
 mod test_chd_multiple_tracks;
 #[cfg(all(test, feature = "chd"))]
+mod test_chd_parallel_track_hashing;
+#[cfg(all(test, feature = "chd"))]
 mod test_chd_single_track;
+#[cfg(all(test, feature = "chd"))]
+mod test_chd_track;
 #[cfg(all(test, feature = "cso"))]
 mod test_cso;
 #[cfg(test)]
 mod test_original;
 #[cfg(test)]
+mod test_original_checksum_cache;
+#[cfg(test)]
 mod test_original_crc_mismatch;
 #[cfg(test)]
+mod test_original_move_incomplete;
+#[cfg(test)]
+mod test_original_overall_progress;
+#[cfg(test)]
 mod test_original_size_mismatch;
 #[cfg(test)]
+mod test_original_system_trash;
+#[cfg(test)]
+mod test_original_thorough_sha1_mismatch;
+#[cfg(test)]
+mod test_original_trash_by_reason;
+#[cfg(test)]
 mod test_original_with_header;
 #[cfg(test)]
+mod test_rematch;
+#[cfg(test)]
 mod test_sevenzip;
 #[cfg(test)]
 mod test_sevenzip_with_header;
 #[cfg(test)]
+mod test_sevenzip_with_header_cached_crc;
+#[cfg(test)]
 mod test_zip;
+#[cfg(test)]
+mod test_zip_quick_manifest;
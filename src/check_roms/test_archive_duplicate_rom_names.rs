@@ -0,0 +1,70 @@
+use super::*;
+
+fn get_rom(id: i64, name: &str, size: i64, crc: &str) -> Rom {
+    Rom {
+        id,
+        name: String::from(name),
+        bios: false,
+        size,
+        crc: Some(String::from(crc)),
+        md5: None,
+        sha1: None,
+        rom_status: None,
+        game_id: 1,
+        romfile_id: None,
+        parent_id: None,
+    }
+}
+
+#[test]
+fn test() {
+    // given
+    let mut roms = vec![
+        get_rom(1, "Test Game (USA, Europe).rom", 256, "cc721e14"),
+        get_rom(2, "Test Game (USA, Europe).rom", 256, "310212e8"),
+        get_rom(3, "Test Game (USA, Europe).sav", 8, "00000000"),
+    ];
+
+    // when
+    let second_index = find_rom_index(
+        &roms,
+        "Test Game (USA, Europe).rom",
+        256,
+        "310212e8",
+        &HashAlgorithm::Crc,
+    )
+    .unwrap();
+    let second_rom = roms.remove(second_index);
+    let first_index = find_rom_index(
+        &roms,
+        "Test Game (USA, Europe).rom",
+        256,
+        "cc721e14",
+        &HashAlgorithm::Crc,
+    )
+    .unwrap();
+    let first_rom = roms.remove(first_index);
+    let unmatched = find_rom_index(
+        &roms,
+        "Test Game (Japan).rom",
+        256,
+        "310212e8",
+        &HashAlgorithm::Crc,
+    );
+    let mismatched = find_rom_index(
+        &roms,
+        "Test Game (USA, Europe).sav",
+        8,
+        "ffffffff",
+        &HashAlgorithm::Crc,
+    );
+
+    // then
+    assert_eq!(second_rom.id, 2);
+    assert_eq!(first_rom.id, 1);
+    assert_eq!(
+        unmatched.unwrap_err().as_str(),
+        "Archive contains an unmatched ROM file \"Test Game (Japan).rom\""
+    );
+    assert_eq!(mismatched.unwrap_err().as_str(), "Checksum mismatch");
+}
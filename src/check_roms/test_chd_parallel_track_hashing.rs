@@ -0,0 +1,114 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (Multiple Tracks).chd");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Multiple Tracks).chd"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let game = find_games_by_system_id(&mut connection, system.id)
+        .await
+        .into_iter()
+        .find(|game| game.name == "Test Game (USA, Europe) (CUE BIN)")
+        .unwrap();
+    let roms: Vec<Rom> = find_roms_by_game_id_no_parents(&mut connection, game.id)
+        .await
+        .into_iter()
+        .filter(|rom| !rom.name.ends_with(".cue"))
+        .collect();
+    let names_sizes: Vec<(&str, u64)> = roms
+        .iter()
+        .map(|rom| (rom.name.as_str(), rom.size as u64))
+        .collect();
+    let extraction_directory = tmp_directory.join("tracks");
+    fs::create_dir_all(&extraction_directory).await.unwrap();
+    let bin_paths = chdman::extract_chd_to_multiple_tracks(
+        &progress_bar,
+        &romfile_path,
+        &extraction_directory,
+        &names_sizes,
+        true,
+    )
+    .await
+    .unwrap();
+
+    let matches = config::subcommand().get_matches_from(&["config", "-s", "PARALLEL_HASHING", "1"]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    let sequential_hashes = get_track_hashes(
+        &mut connection,
+        &progress_bar,
+        &bin_paths,
+        &None,
+        &HashAlgorithm::Crc,
+    )
+    .await
+    .unwrap();
+
+    let matches = config::subcommand().get_matches_from(&["config", "-s", "PARALLEL_HASHING", "4"]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // when
+    let parallel_hashes = get_track_hashes(
+        &mut connection,
+        &progress_bar,
+        &bin_paths,
+        &None,
+        &HashAlgorithm::Crc,
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(parallel_hashes, sequential_hashes);
+    let crcs: Vec<&str> = roms.iter().map(|rom| rom.crc.as_deref().unwrap()).collect();
+    assert_eq!(parallel_hashes, crcs);
+
+    check_chd(
+        &mut connection,
+        &progress_bar,
+        &None,
+        &romfile_path,
+        roms,
+        false,
+        None,
+        &HashAlgorithm::Crc,
+    )
+    .await
+    .unwrap();
+}
@@ -0,0 +1,113 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (Multiple Tracks).cue");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Multiple Tracks).cue"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (Multiple Tracks).chd");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Multiple Tracks).chd"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let chd_romfile = find_romfiles(&mut connection)
+        .await
+        .into_iter()
+        .find(|romfile| romfile.path.ends_with(".chd"))
+        .unwrap();
+    let roms: Vec<Rom> = find_roms_with_romfile_by_system_id(&mut connection, system.id)
+        .await
+        .into_iter()
+        .filter(|rom| rom.romfile_id == Some(chd_romfile.id))
+        .collect();
+    let names_sizes: Vec<(&str, u64)> = roms
+        .iter()
+        .map(|rom| (rom.name.as_str(), rom.size as u64))
+        .collect();
+
+    // when
+    let bin_path = chdman::extract_chd_track(
+        &progress_bar,
+        &chd_romfile.path,
+        &tmp_directory,
+        &names_sizes,
+        2,
+        true,
+    )
+    .await
+    .unwrap();
+    let (size, crc, md5, sha1) =
+        get_size_and_hashes(&mut connection, &progress_bar, &bin_path, &None, 1, 1)
+            .await
+            .unwrap();
+
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &ProgressBar::hidden(),
+        &system,
+        &HashAlgorithm::Crc,
+        &CheckOptions {
+            thorough: true,
+            track: Some(2),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(bin_path.file_name().unwrap(), roms[1].name.as_str());
+    assert!(check_size_and_hashes(&roms[1], size as i64, &crc, &md5, &sha1).is_ok());
+    assert!(check_size_and_hashes(&roms[0], size as i64, &crc, &md5, &sha1).is_err());
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+
+    for romfile in romfiles {
+        assert!(!romfile.path.contains("/Trash/"));
+        assert!(Path::new(&romfile.path).is_file().await);
+    }
+}
@@ -49,9 +49,13 @@ async fn test() {
     check_system(
         &mut connection,
         &progress_bar,
+        &ProgressBar::hidden(),
         &system,
-        true,
         &HashAlgorithm::Crc,
+        &CheckOptions {
+            size: true,
+            ..Default::default()
+        },
     )
     .await
     .unwrap();
@@ -59,9 +59,10 @@ async fn test() {
     check_system(
         &mut connection,
         &progress_bar,
+        &ProgressBar::hidden(),
         &system,
-        false,
         &HashAlgorithm::Crc,
+        &CheckOptions::default(),
     )
     .await
     .unwrap();
@@ -0,0 +1,108 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::prelude::*;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20221226) (Multiple Files).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for romfile_name in ["Test Game (USA, Europe).rom", "Test Game (Japan).rom"] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        let matches = import_roms::subcommand()
+            .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+        import_roms::main(&mut connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(system_directory.join("Test Game (USA, Europe) (Part 2).rom"))
+        .await
+        .unwrap();
+    file.write_all(b"00000000").await.unwrap();
+    file.sync_all().await.unwrap();
+
+    let incomplete_directory = tmp_directory.join("Incomplete");
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &ProgressBar::hidden(),
+        &system,
+        &HashAlgorithm::Crc,
+        &CheckOptions {
+            incomplete_directory: Some(incomplete_directory.clone()),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+
+    let trashed_romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.path.ends_with("(Part 2).rom"))
+        .unwrap();
+    assert!(trashed_romfile.path.contains("/Trash/"));
+    assert!(Path::new(&trashed_romfile.path).is_file().await);
+
+    let surviving_romfile = romfiles
+        .iter()
+        .find(|romfile| romfile.path.ends_with("(Part 1).rom"))
+        .unwrap();
+    assert_eq!(
+        surviving_romfile.path,
+        get_canonicalized_path(&incomplete_directory)
+            .await
+            .unwrap()
+            .join("Test Game (USA, Europe) (Part 1).rom")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+    assert!(Path::new(&surviving_romfile.path).is_file().await);
+    assert!(
+        !system_directory
+            .join("Test Game (USA, Europe) (Part 1).rom")
+            .is_file()
+            .await
+    );
+}
@@ -0,0 +1,69 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<String> = Vec::new();
+    for file_name in &["Test Game (USA, Europe).rom", "Test Game (Japan).rom"] {
+        let romfile_path = tmp_directory.join(file_name);
+        fs::copy(test_directory.join(file_name), &romfile_path)
+            .await
+            .unwrap();
+        romfile_paths.push(romfile_path.as_os_str().to_str().unwrap().to_owned());
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let mut args = vec!["import-roms"];
+    args.extend(romfile_paths.iter().map(String::as_str));
+    let matches = import_roms::subcommand().get_matches_from(&args);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let overall_progress_bar = ProgressBar::hidden();
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &overall_progress_bar,
+        &system,
+        &HashAlgorithm::Crc,
+        &CheckOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert_eq!(overall_progress_bar.length(), Some(2));
+    assert_eq!(overall_progress_bar.position(), 2);
+}
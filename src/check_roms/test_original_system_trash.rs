@@ -0,0 +1,87 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use async_std::prelude::*;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches =
+        config::subcommand().get_matches_from(&["config", "-s", "TRASH_BACKEND", "SYSTEM_TRASH"]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile = find_romfiles(&mut connection).await.remove(0);
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(&romfile.path)
+        .await
+        .unwrap();
+    file.write_all(b"00000000").await.unwrap();
+    file.sync_all().await.unwrap();
+
+    SYSTEM_TRASH.lock().await.clear();
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &ProgressBar::hidden(),
+        &system,
+        &HashAlgorithm::Crc,
+        &CheckOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert!(romfiles.is_empty());
+
+    let system_trash = SYSTEM_TRASH.lock().await;
+    assert_eq!(system_trash.len(), 1);
+    assert_eq!(system_trash[0], PathBuf::from(&romfile.path));
+    assert!(!Path::new(&romfile.path).is_file().await);
+}
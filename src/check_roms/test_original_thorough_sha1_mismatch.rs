@@ -0,0 +1,101 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches =
+        config::subcommand().get_matches_from(&["config", "-s", "TRASH_BY_REASON", "true"]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20221222) (SHA1 Mismatch).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let trash_directory = get_trash_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        "-a",
+        "CRC",
+        romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &ProgressBar::hidden(),
+        &system,
+        &HashAlgorithm::Crc,
+        &CheckOptions::default(),
+    )
+    .await
+    .unwrap();
+    assert!(find_romfiles_in_trash(&mut connection).await.is_empty());
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &ProgressBar::hidden(),
+        &system,
+        &HashAlgorithm::Crc,
+        &CheckOptions {
+            thorough: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // then
+    let romfiles = find_romfiles_in_trash(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(
+        trash_directory
+            .join("Sha1Mismatch")
+            .join("Test Game (USA, Europe).rom")
+            .is_file()
+            .await
+    );
+}
@@ -0,0 +1,115 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use async_std::prelude::*;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches =
+        config::subcommand().get_matches_from(&["config", "-s", "TRASH_BY_REASON", "true"]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let trash_directory = get_trash_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    let unmatched_romfile_path = tmp_directory.join("Test Game (USA, Europe) (Headered).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Headered).rom"),
+        &unmatched_romfile_path,
+    )
+    .await
+    .unwrap();
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        unmatched_romfile_path.as_os_str().to_str().unwrap(),
+        romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile = find_romfiles_in_trash(&mut connection).await;
+    assert_eq!(romfile.len(), 1);
+
+    let romfile = find_romfiles(&mut connection)
+        .await
+        .into_iter()
+        .find(|romfile| !romfile.path.contains("/Trash/"))
+        .unwrap();
+    let mut file = fs::OpenOptions::new()
+        .write(true)
+        .open(&romfile.path)
+        .await
+        .unwrap();
+    file.write_all(b"00000000").await.unwrap();
+    file.sync_all().await.unwrap();
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &ProgressBar::hidden(),
+        &system,
+        &HashAlgorithm::Crc,
+        &CheckOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let romfiles = find_romfiles_in_trash(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+
+    assert!(
+        trash_directory
+            .join("NoMatch")
+            .join("Test Game (USA, Europe) (Headered).rom")
+            .is_file()
+            .await
+    );
+    assert!(
+        trash_directory
+            .join("CrcMismatch")
+            .join("Test Game (USA, Europe).rom")
+            .is_file()
+            .await
+    );
+}
@@ -0,0 +1,102 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use std::io::Write;
+use tempfile::{NamedTempFile, TempDir};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches =
+        config::subcommand().get_matches_from(&["config", "-s", "STORE_ARCHIVE_MANIFESTS", "true"]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom.zip");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom.zip"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile = find_romfiles(&mut connection).await.remove(0);
+    assert_eq!(
+        romfile.manifest.as_deref(),
+        Some(r#"[{"path":"Test Game (USA, Europe).rom","size":256,"crc":"cc721e14"}]"#)
+    );
+
+    // sneak an extra member into the archive
+    let file = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(&romfile.path)
+        .unwrap();
+    let mut zip_writer = ZipWriter::new_append(file).unwrap();
+    zip_writer
+        .start_file(
+            "Extra.txt",
+            FileOptions::default().compression_method(CompressionMethod::Stored),
+        )
+        .unwrap();
+    zip_writer.write_all(b"extra").unwrap();
+    zip_writer.finish().unwrap();
+
+    // when
+    check_system(
+        &mut connection,
+        &progress_bar,
+        &ProgressBar::hidden(),
+        &system,
+        &HashAlgorithm::Crc,
+        &CheckOptions {
+            quick: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // then
+    let mut romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let romfile = romfiles.remove(0);
+    assert!(romfile.path.contains("/Trash/"));
+    assert!(Path::new(&romfile.path).is_file().await);
+}
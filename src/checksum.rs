@@ -1,6 +1,8 @@
+use super::config::*;
 use super::database::*;
-use super::model::Header;
+use super::model::{Header, Rule, System};
 use super::progress::*;
+use super::sevenzip;
 use super::util::*;
 use super::SimpleResult;
 use async_std::path::Path;
@@ -11,12 +13,23 @@ use digest::Digest;
 use digest::OutputSizeUser;
 use digest::{FixedOutput, HashMarker, Reset, Update};
 use indicatif::ProgressBar;
+use itertools::Itertools;
 use md5::Md5;
 use sha1::Sha1;
+use simple_error::SimpleError;
 use sqlx::sqlite::SqliteConnection;
 use std::fs;
 use std::io;
 use std::io::prelude::*;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use tracing::{debug, instrument};
+
+lazy_static! {
+    static ref RATE_LIMITER: Mutex<Option<RateLimiter>> = Mutex::new(None);
+}
 
 #[derive(PartialEq, Eq)]
 pub enum HashAlgorithm {
@@ -25,6 +38,50 @@ pub enum HashAlgorithm {
     Sha1,
 }
 
+impl HashAlgorithm {
+    pub fn name(&self) -> &'static str {
+        match self {
+            HashAlgorithm::Crc => "CRC",
+            HashAlgorithm::Md5 => "MD5",
+            HashAlgorithm::Sha1 => "SHA1",
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = SimpleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "CRC" => Ok(HashAlgorithm::Crc),
+            "MD5" => Ok(HashAlgorithm::Md5),
+            "SHA1" => Ok(HashAlgorithm::Sha1),
+            _ => Err(SimpleError::new(format!(
+                "Unsupported hash algorithm {}",
+                s
+            ))),
+        }
+    }
+}
+
+pub async fn get_hash_algorithm(
+    connection: &mut SqliteConnection,
+    system: &System,
+) -> SimpleResult<HashAlgorithm> {
+    // the system override takes precedence over the DAT recommendation and the global setting
+    match system
+        .hash_algorithm
+        .as_ref()
+        .or(system.recommended_hash_algorithm.as_ref())
+    {
+        Some(hash_algorithm) => HashAlgorithm::from_str(hash_algorithm),
+        None => match get_string(connection, "HASH_ALGORITHM").await {
+            Some(hash_algorithm) => HashAlgorithm::from_str(&hash_algorithm),
+            None => bail!("Not possible"),
+        },
+    }
+}
+
 #[derive(Clone, Default)]
 struct Crc32 {
     hasher: Hasher,
@@ -73,6 +130,81 @@ impl io::Write for Crc32 {
     }
 }
 
+#[derive(Default)]
+struct MultiHasher {
+    crc: Crc32,
+    md5: Md5,
+    sha1: Sha1,
+}
+
+impl io::Write for MultiHasher {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        Update::update(&mut self.crc, buf);
+        Update::update(&mut self.md5, buf);
+        Update::update(&mut self.sha1, buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+// a token bucket shared by all readers, so parallel hashing is throttled as a whole
+struct RateLimiter {
+    bytes_per_second: u64,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl RateLimiter {
+    fn consume(&mut self, bytes: usize) -> Duration {
+        let now = Instant::now();
+        let rate = self.bytes_per_second as f64;
+        // bursts are capped to one second worth of data
+        self.tokens =
+            (self.tokens + now.duration_since(self.refilled_at).as_secs_f64() * rate).min(rate);
+        self.refilled_at = now;
+        self.tokens -= bytes as f64;
+        if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+pub fn set_limit_rate(bytes_per_second: u64) {
+    *RATE_LIMITER.lock().unwrap() = match bytes_per_second {
+        0 => None,
+        _ => Some(RateLimiter {
+            bytes_per_second,
+            tokens: 0.0,
+            refilled_at: Instant::now(),
+        }),
+    };
+}
+
+struct RateLimitedReader<R: Read> {
+    inner: R,
+}
+
+impl<R: Read> Read for RateLimitedReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let bytes = self.inner.read(buf)?;
+        let wait = match RATE_LIMITER.lock().unwrap().as_mut() {
+            Some(rate_limiter) => rate_limiter.consume(bytes),
+            None => Duration::ZERO,
+        };
+        // the lock is released before sleeping, other readers and progress bars keep going
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+        Ok(bytes)
+    }
+}
+
+#[instrument(level = "debug", skip_all, fields(path = %file_path.as_ref().display(), algorithm = hash_algorithm.name()))]
 pub async fn get_size_and_hash<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -82,7 +214,7 @@ pub async fn get_size_and_hash<P: AsRef<Path>>(
     total: usize,
     hash_algorithm: &HashAlgorithm,
 ) -> SimpleResult<(u64, String)> {
-    Ok(match hash_algorithm {
+    let (size, hash) = match hash_algorithm {
         HashAlgorithm::Crc => {
             get_size_and_crc(connection, progress_bar, file_path, header, position, total).await?
         }
@@ -92,7 +224,120 @@ pub async fn get_size_and_hash<P: AsRef<Path>>(
         HashAlgorithm::Sha1 => {
             get_size_and_sha1(connection, progress_bar, file_path, header, position, total).await?
         }
-    })
+    };
+    debug!(size, %hash, "Computed hash");
+    Ok((size, hash))
+}
+
+pub fn get_sizes_and_hashes<P: AsRef<Path> + Sync>(
+    progress_bar: &ProgressBar,
+    file_paths: &[P],
+    hash_algorithm: &HashAlgorithm,
+    parallel_hashing: usize,
+) -> SimpleResult<Vec<(u64, String)>> {
+    progress_bar.reset();
+    progress_bar.set_message(format!("Computing {}", hash_algorithm.name()));
+    progress_bar.set_style(get_count_progress_style());
+    progress_bar.set_length(file_paths.len() as u64);
+
+    let sizes_and_hashes = map_with_parallelism(file_paths, parallel_hashing, |file_path| {
+        let size_and_hash = get_size_and_hash_sync(file_path, hash_algorithm);
+        progress_bar.inc(1);
+        size_and_hash
+    })?
+    .into_iter()
+    .collect::<SimpleResult<Vec<(u64, String)>>>()?;
+
+    progress_bar.set_message("");
+    progress_bar.set_style(get_none_progress_style());
+
+    Ok(sizes_and_hashes)
+}
+
+// tracks are extracted beforehand, hashes are returned in the order of the tracks
+#[cfg(feature = "chd")]
+pub async fn get_track_hashes<P: AsRef<Path> + Sync>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    track_paths: &[P],
+    header: &Option<Header>,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<Vec<String>> {
+    // headers are stripped through the async path only
+    if header.is_some() {
+        let mut hashes: Vec<String> = Vec::new();
+        for (i, track_path) in track_paths.iter().enumerate() {
+            let (_, hash) = get_size_and_hash(
+                connection,
+                progress_bar,
+                track_path,
+                header,
+                i,
+                track_paths.len(),
+                hash_algorithm,
+            )
+            .await?;
+            hashes.push(hash);
+        }
+        return Ok(hashes);
+    }
+    let parallel_hashing = get_parallel_hashing(connection).await;
+    Ok(
+        get_sizes_and_hashes(progress_bar, track_paths, hash_algorithm, parallel_hashing)?
+            .into_iter()
+            .map(|(_, hash)| hash)
+            .collect(),
+    )
+}
+
+pub async fn get_archive_member_size_and_headerless_crc<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    archive_path: &P,
+    member_name: &str,
+    header: &Option<Header>,
+) -> SimpleResult<(u64, String)> {
+    let archive_path_str = archive_path.as_ref().as_os_str().to_str().unwrap();
+    let (archive_size, mtime) = get_size_and_mtime(archive_path).await?;
+
+    // reuse the cached CRC if the archive hasn't changed
+    if let Some(headerless_crc) =
+        find_headerless_crc_by_archive_path_and_member_name_and_archive_size_and_mtime(
+            connection,
+            archive_path_str,
+            member_name,
+            archive_size,
+            mtime,
+        )
+        .await
+    {
+        return Ok((headerless_crc.size as u64, headerless_crc.crc));
+    }
+
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let extracted_path = sevenzip::extract_files_from_archive(
+        progress_bar,
+        archive_path,
+        &[member_name],
+        &tmp_directory.path(),
+    )?
+    .remove(0);
+    let (size, crc) =
+        get_size_and_crc(connection, progress_bar, &extracted_path, header, 1, 1).await?;
+    remove_file(progress_bar, &extracted_path, true).await?;
+
+    create_or_update_headerless_crc(
+        connection,
+        archive_path_str,
+        member_name,
+        archive_size,
+        mtime,
+        size_to_i64(size)?,
+        &crc,
+    )
+    .await;
+
+    Ok((size, crc))
 }
 
 async fn get_size_and_crc<P: AsRef<Path>>(
@@ -180,43 +425,150 @@ async fn get_size_and_sha1<P: AsRef<Path>>(
     Ok((size, md5))
 }
 
+pub async fn get_size_and_hashes<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    file_path: &P,
+    header: &Option<Header>,
+    position: usize,
+    total: usize,
+) -> SimpleResult<(u64, String, String, String)> {
+    let (mut file, size) = get_file_and_size(connection, file_path, header).await?;
+
+    progress_bar.reset();
+    progress_bar.set_message(format!(
+        "Computing CRC, MD5 and SHA1 ({}/{})",
+        position, total
+    ));
+    progress_bar.set_style(get_bytes_progress_style());
+    progress_bar.set_length(size);
+
+    // compute all the checksums in a single pass
+    let mut digest = MultiHasher::default();
+    try_with!(
+        io::copy(&mut file, &mut progress_bar.wrap_write(&mut digest)),
+        "Failed to copy data"
+    );
+    let crc = format!("{:08x}", digest.crc.finalize()).to_lowercase();
+    let md5 = format!("{:032x}", digest.md5.finalize()).to_lowercase();
+    let sha1 = format!("{:040x}", digest.sha1.finalize()).to_lowercase();
+
+    progress_bar.set_message("");
+    progress_bar.set_style(get_none_progress_style());
+
+    Ok((size, crc, md5, sha1))
+}
+
+fn get_size_and_hash_sync<P: AsRef<Path>>(
+    file_path: &P,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<(u64, String)> {
+    let file = open_file_sync(file_path)?;
+    let size = try_with!(file.metadata(), "Failed to read file metadata").len();
+    let mut file = RateLimitedReader { inner: file };
+    let hash = match hash_algorithm {
+        HashAlgorithm::Crc => {
+            let mut digest = Crc32::new();
+            try_with!(io::copy(&mut file, &mut digest), "Failed to copy data");
+            format!("{:08x}", digest.finalize())
+        }
+        HashAlgorithm::Md5 => {
+            let mut digest = Md5::new();
+            try_with!(io::copy(&mut file, &mut digest), "Failed to copy data");
+            format!("{:032x}", digest.finalize())
+        }
+        HashAlgorithm::Sha1 => {
+            let mut digest = Sha1::new();
+            try_with!(io::copy(&mut file, &mut digest), "Failed to copy data");
+            format!("{:040x}", digest.finalize())
+        }
+    };
+    Ok((size, hash))
+}
+
 async fn get_file_and_size<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     file_path: &P,
     header: &Option<Header>,
-) -> SimpleResult<(fs::File, u64)> {
+) -> SimpleResult<(RateLimitedReader<io::Take<fs::File>>, u64)> {
     let mut file = open_file_sync(file_path)?;
-    let mut size = file.metadata().unwrap().len();
+    let file_size = file.metadata().unwrap().len();
 
     // extract a potential header, revert if none is found
     if header.is_some() {
         let header = header.as_ref().unwrap();
         let rules = find_rules_by_header_id(connection, header.id).await;
-        let mut buffer: Vec<u8> = Vec::with_capacity(header.size as usize);
+        let buffer_size = rules
+            .iter()
+            .map(|rule| {
+                rule.start_offset
+                    .max(rule.start_byte + rule.hex_value.len() as i64 / 2)
+            })
+            .max()
+            .unwrap_or(0);
+        let mut buffer: Vec<u8> = Vec::with_capacity(buffer_size as usize);
         try_with!(
             (&mut file)
-                .take(header.size as u64)
+                .take(buffer_size as u64)
                 .read_to_end(&mut buffer),
             "Failed to read into buffer"
         );
 
-        let mut matches: Vec<bool> = Vec::new();
-        for rule in rules {
-            let start_byte = rule.start_byte as usize;
-            let hex_values: Vec<String> = buffer[start_byte..]
-                .iter()
-                .map(|b| format!("{:02x}", b))
-                .collect();
-            let hex_value = hex_values.join("").to_lowercase();
-            matches.push(hex_value.starts_with(&rule.hex_value.to_lowercase()));
-        }
+        // rules are tried in order, the first one whose data all match is applied
+        let matching_rule = rules
+            .iter()
+            .group_by(|rule| rule.rule_index)
+            .into_iter()
+            .map(|(_, rules)| rules.collect::<Vec<&Rule>>())
+            .find(|rules| {
+                rules
+                    .iter()
+                    .all(|rule| rule_matches(rule, &buffer) == rule.result)
+            })
+            .map(|rules| rules[0]);
 
-        if matches.iter().all(|&m| m) {
-            size -= header.size as u64;
-        } else {
-            try_with!(file.seek(io::SeekFrom::Start(0)), "Failed to seek file");
+        if let Some(rule) = matching_rule {
+            let start_offset = rule.start_offset as u64;
+            let end_offset = match rule.end_offset {
+                Some(end_offset) if end_offset < 0 => {
+                    file_size.saturating_sub(end_offset.unsigned_abs())
+                }
+                Some(end_offset) => (end_offset as u64).min(file_size),
+                None => file_size,
+            };
+            if start_offset <= end_offset {
+                try_with!(
+                    file.seek(io::SeekFrom::Start(start_offset)),
+                    "Failed to seek file"
+                );
+                let size = end_offset - start_offset;
+                return Ok((
+                    RateLimitedReader {
+                        inner: file.take(size),
+                    },
+                    size,
+                ));
+            }
         }
+        try_with!(file.seek(io::SeekFrom::Start(0)), "Failed to seek file");
     }
 
-    Ok((file, size))
+    Ok((
+        RateLimitedReader {
+            inner: file.take(file_size),
+        },
+        file_size,
+    ))
 }
+
+fn rule_matches(rule: &Rule, buffer: &[u8]) -> bool {
+    let hex_value: String = buffer
+        .iter()
+        .skip(rule.start_byte as usize)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    hex_value.starts_with(&rule.hex_value.to_lowercase())
+}
+
+#[cfg(test)]
+mod test_limit_rate;
@@ -0,0 +1,46 @@
+use super::super::config::{set_tmp_directory, MUTEX};
+use super::super::database::*;
+use super::*;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let file_path = tmp_directory.join("data.bin");
+    fs::write(&file_path, vec![0u8; 128 * 1024]).unwrap();
+
+    set_limit_rate(256 * 1024);
+
+    // when
+    let started_at = Instant::now();
+    let result = get_size_and_hash(
+        &mut connection,
+        &progress_bar,
+        &file_path,
+        &None,
+        1,
+        1,
+        &HashAlgorithm::Crc,
+    )
+    .await;
+    let elapsed = started_at.elapsed();
+    set_limit_rate(0);
+
+    // then
+    let (size, _) = result.unwrap();
+    assert_eq!(size, 128 * 1024);
+    assert!(elapsed >= Duration::from_millis(500));
+}
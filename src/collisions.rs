@@ -0,0 +1,110 @@
+use super::checksum::*;
+use super::config::*;
+use super::database::*;
+use super::model::*;
+use super::prompt::*;
+use super::SimpleResult;
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use sqlx::sqlite::SqliteConnection;
+use std::str::FromStr;
+
+pub fn subcommand() -> Command {
+    Command::new("collisions")
+        .about("Report ROMs sharing the same size and hash within a system")
+        .arg(
+            Arg::new("HASH")
+                .long("hash")
+                .help("Set the hash algorithms to check")
+                .required(false)
+                .num_args(1..)
+                .value_parser(PossibleValuesParser::new(HASH_ALGORITHMS))
+                .default_value("CRC"),
+        )
+        .arg(
+            Arg::new("ALL")
+                .short('a')
+                .long("all")
+                .help("Check all systems")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let systems = prompt_for_systems(connection, None, false, matches.get_flag("ALL")).await?;
+    for system in systems {
+        progress_bar.println(format!("Processing \"{}\"", system.name));
+        let mut found = false;
+        for hash_algorithm in matches.get_many::<String>("HASH").unwrap() {
+            let hash_algorithm = HashAlgorithm::from_str(hash_algorithm)?;
+            for line in get_collisions(connection, &system, &hash_algorithm).await {
+                found = true;
+                progress_bar.println(line);
+            }
+        }
+        if !found {
+            progress_bar.println("No collision found");
+        }
+        progress_bar.println("");
+    }
+    Ok(())
+}
+
+pub async fn get_collisions(
+    connection: &mut SqliteConnection,
+    system: &System,
+    hash_algorithm: &HashAlgorithm,
+) -> Vec<String> {
+    let roms = match hash_algorithm {
+        HashAlgorithm::Crc => {
+            find_roms_with_duplicate_size_and_crc_by_system_id(connection, system.id).await
+        }
+        HashAlgorithm::Md5 => {
+            find_roms_with_duplicate_size_and_md5_by_system_id(connection, system.id).await
+        }
+        HashAlgorithm::Sha1 => {
+            find_roms_with_duplicate_size_and_sha1_by_system_id(connection, system.id).await
+        }
+    };
+    let games = find_games_by_ids(
+        connection,
+        &roms
+            .iter()
+            .map(|rom| rom.game_id)
+            .unique()
+            .collect::<Vec<i64>>(),
+    )
+    .await;
+
+    let mut lines: Vec<String> = Vec::new();
+    for ((size, hash), roms) in &roms.iter().group_by(|rom| {
+        let hash = match hash_algorithm {
+            HashAlgorithm::Crc => &rom.crc,
+            HashAlgorithm::Md5 => &rom.md5,
+            HashAlgorithm::Sha1 => &rom.sha1,
+        };
+        (rom.size, hash.clone().unwrap())
+    }) {
+        lines.push(format!(
+            "{} {} ({} bytes)",
+            hash_algorithm.name(),
+            hash,
+            size
+        ));
+        for rom in roms {
+            let game = games.iter().find(|game| game.id == rom.game_id).unwrap();
+            lines.push(format!("  \"{}\" in \"{}\"", rom.name, game.name));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test_crc_collision;
@@ -0,0 +1,47 @@
+use super::super::import_dats;
+use super::*;
+use async_std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20221223) (CRC Collision).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    // when
+    let crc_lines = get_collisions(&mut connection, &system, &HashAlgorithm::Crc).await;
+    let sha1_lines = get_collisions(&mut connection, &system, &HashAlgorithm::Sha1).await;
+
+    // then
+    assert_eq!(
+        crc_lines,
+        vec![
+            "CRC cc721e14 (256 bytes)",
+            "  \"Test Game (Japan).rom\" in \"Test Game (Japan)\"",
+            "  \"Test Game (USA, Europe).rom\" in \"Test Game (USA, Europe)\"",
+        ]
+    );
+    assert!(sha1_lines.is_empty());
+}
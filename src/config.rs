@@ -1,4 +1,5 @@
 use super::database::*;
+use super::model::TrashBackend;
 use super::util::*;
 use super::SimpleResult;
 use async_std::path::{Path, PathBuf};
@@ -27,35 +28,74 @@ cfg_if! {
     }
 }
 
-const BOOLEANS: &[&str] = &[];
+const BOOLEANS: &[&str] = &[
+    "DELETE_SOURCE_ARCHIVES",
+    "GROUP_MULTI_DISC_GAMES",
+    "GROUP_MULTI_PART_GAMES",
+    "IMPORT_ISO_CONTENTS",
+    "MATCH_BY_NAME_SIZE",
+    "PRESERVE_EMPTY_DIRS",
+    "STORE_ARCHIVE_MANIFESTS",
+    "TORRENTZIP",
+    "TRASH_BY_REASON",
+];
 const LISTS: &[&str] = &[
+    "DEPRIORITIZED_RELEASES",
     "DISCARD_FLAGS",
     "DISCARD_RELEASES",
+    "IGNORE_PATTERNS",
     "REGIONS_ALL",
     "REGIONS_ONE",
 ];
-const PATHS: &[&str] = &["ROM_DIRECTORY", "TMP_DIRECTORY"];
+const NUMBERS: &[&str] = &["PARALLEL_HASHING"];
+const PATHS: &[&str] = &["BIOS_DIRECTORY", "ROM_DIRECTORY", "TMP_DIRECTORY"];
+const STRINGS: &[&str] = &[
+    "ARCHIVE_COMMENT",
+    "ARCHIVE_FORMAT",
+    "AUTO_CONVERT_CD",
+    "AUTO_CONVERT_ISO",
+    "IGNORE_MARKER",
+    "MIN_FREE_SPACE",
+    "TRASH_BACKEND",
+    "UNTRACKED_DESTINATION",
+];
 
 pub const HASH_ALGORITHMS: &[&str] = &["CRC", "MD5", "SHA1"];
 
-#[cfg(feature = "chd")]
 pub static BIN_EXTENSION: &str = "bin";
 pub static CHD_EXTENSION: &str = "chd";
 pub static CSO_EXTENSION: &str = "cso";
-#[cfg(feature = "chd")]
 pub static CUE_EXTENSION: &str = "cue";
-#[cfg(any(feature = "chd", feature = "cso"))]
+pub static DAT_EXTENSION: &str = "dat";
+pub static GZIP_EXTENSION: &str = "gz";
 pub static ISO_EXTENSION: &str = "iso";
+pub static M3U_EXTENSION: &str = "m3u";
+#[cfg(any(
+    feature = "chd",
+    feature = "cso",
+    feature = "rvz",
+    feature = "torrentzip",
+    feature = "zst"
+))]
+pub static PART_EXTENSION: &str = "part";
+pub static PBP_EXTENSION: &str = "pbp";
 pub static PKG_EXTENSION: &str = "pkg";
 pub static PUP_EXTENSION: &str = "pup";
+pub static RAR_EXTENSION: &str = "rar";
 pub static RAP_EXTENSION: &str = "rap";
 pub static RVZ_EXTENSION: &str = "rvz";
 pub static SEVENZIP_EXTENSION: &str = "7z";
+pub static WAV_EXTENSION: &str = "wav";
+pub static XML_EXTENSION: &str = "xml";
 pub static ZIP_EXTENSION: &str = "zip";
+pub static ZSO_EXTENSION: &str = "zso";
+pub static ZST_EXTENSION: &str = "zst";
 
-pub static ARCHIVE_EXTENSIONS: [&str; 2] = [SEVENZIP_EXTENSION, ZIP_EXTENSION];
+pub static ARCHIVE_EXTENSIONS: [&str; 3] = [RAR_EXTENSION, SEVENZIP_EXTENSION, ZIP_EXTENSION];
 pub static PS3_EXTENSIONS: [&str; 3] = [PKG_EXTENSION, PUP_EXTENSION, RAP_EXTENSION];
 
+pub static SAMPLES_DIRECTORY: &str = "samples";
+
 #[cfg(feature = "ird")]
 pub static PS3_DISC_SFB: &str = "PS3_DISC.SFB";
 
@@ -192,6 +232,10 @@ async fn set_setting(
     } else if BOOLEANS.contains(&key) {
         let b: bool = try_with!(FromStr::from_str(value), "Failed to parse bool");
         set_bool(connection, key, b).await;
+    } else if NUMBERS.contains(&key) {
+        set_number(connection, key, value).await?;
+    } else if STRINGS.contains(&key) {
+        set_string(connection, key, value).await;
     } else if LISTS.contains(&key) {
         println!("Lists can't be set directly, please use ADD or REMOVE instead");
     } else {
@@ -200,15 +244,14 @@ async fn set_setting(
     Ok(())
 }
 
-#[cfg(test)]
-async fn get_bool(connection: &mut SqliteConnection, key: &str) -> bool {
-    find_setting_by_key(connection, key)
-        .await
-        .unwrap()
-        .value
-        .unwrap()
-        .parse()
-        .unwrap()
+pub async fn get_bool(connection: &mut SqliteConnection, key: &str) -> bool {
+    match find_setting_by_key(connection, key).await {
+        Some(setting) => match setting.value {
+            Some(value) => value.parse().unwrap_or(false),
+            None => false,
+        },
+        None => false,
+    }
 }
 
 async fn set_bool(connection: &mut SqliteConnection, key: &str, value: bool) {
@@ -220,6 +263,62 @@ async fn set_bool(connection: &mut SqliteConnection, key: &str, value: bool) {
     };
 }
 
+pub async fn get_string(connection: &mut SqliteConnection, key: &str) -> Option<String> {
+    match find_setting_by_key(connection, key).await {
+        Some(setting) => setting.value,
+        None => None,
+    }
+}
+
+async fn set_string(connection: &mut SqliteConnection, key: &str, value: &str) {
+    let setting = find_setting_by_key(connection, key).await;
+    let value = if value.is_empty() {
+        None
+    } else {
+        Some(value.to_owned())
+    };
+    match setting {
+        Some(setting) => update_setting(connection, setting.id, value).await,
+        None => create_setting(connection, key, value).await,
+    };
+}
+
+async fn set_number(connection: &mut SqliteConnection, key: &str, value: &str) -> SimpleResult<()> {
+    if !value.is_empty() {
+        try_with!(usize::from_str(value), "Failed to parse number");
+    }
+    set_string(connection, key, value).await;
+    Ok(())
+}
+
+pub async fn get_parallel_hashing(connection: &mut SqliteConnection) -> usize {
+    match get_string(connection, "PARALLEL_HASHING")
+        .await
+        .and_then(|value| usize::from_str(&value).ok())
+    {
+        Some(parallel_hashing) if parallel_hashing > 0 => parallel_hashing,
+        // concurrent reads make spinning disks seek constantly
+        _ => {
+            if is_rotational(get_rom_directory(connection).await) {
+                1
+            } else {
+                rayon::current_num_threads()
+            }
+        }
+    }
+}
+
+pub async fn get_trash_backend(connection: &mut SqliteConnection) -> TrashBackend {
+    match get_string(connection, "TRASH_BACKEND")
+        .await
+        .map(|value| value.to_uppercase())
+        .as_deref()
+    {
+        Some("SYSTEM_TRASH") => TrashBackend::SystemTrash,
+        Some(_) | None => TrashBackend::Folder,
+    }
+}
+
 pub async fn get_list(connection: &mut SqliteConnection, key: &str) -> Vec<String> {
     match find_setting_by_key(connection, key).await {
         Some(setting) => match setting.value {
@@ -273,14 +372,21 @@ async fn set_list(connection: &mut SqliteConnection, key: &str, value: &[String]
 
 pub async fn get_directory(connection: &mut SqliteConnection, key: &str) -> Option<PathBuf> {
     match find_setting_by_key(connection, key).await {
-        Some(p) => match get_canonicalized_path(&p.value.unwrap()).await {
-            Ok(path) => Some(path),
-            Err(_) => None,
+        Some(p) => match p.value {
+            Some(value) => get_canonicalized_path(&value).await.ok(),
+            None => None,
         },
         None => None,
     }
 }
 
+// unlike get_tmp_directory the default isn't recorded, read-only commands can use it
+pub async fn find_tmp_directory(connection: &mut SqliteConnection) -> PathBuf {
+    get_directory(connection, "TMP_DIRECTORY")
+        .await
+        .unwrap_or_else(|| PathBuf::from(std::env::temp_dir()))
+}
+
 pub async fn set_directory<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     key: &str,
@@ -375,8 +481,12 @@ mod test_directory;
 #[cfg(test)]
 mod test_list;
 #[cfg(test)]
+mod test_parallel_hashing_sequential;
+#[cfg(test)]
 mod test_remove_from_list;
 #[cfg(test)]
 mod test_remove_from_list_does_not_exist;
 #[cfg(test)]
 mod test_set_new_directory_when_old_is_missing;
+#[cfg(test)]
+mod test_string;
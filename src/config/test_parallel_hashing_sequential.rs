@@ -0,0 +1,41 @@
+use super::*;
+use std::sync::Mutex;
+use std::thread;
+use std::time::Duration;
+use tempfile::NamedTempFile;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let progress_bar = ProgressBar::hidden();
+
+    set_setting(&mut connection, &progress_bar, "PARALLEL_HASHING", "1")
+        .await
+        .unwrap();
+
+    let items: Vec<usize> = (0..8).collect();
+    let events: Mutex<Vec<(usize, bool)>> = Mutex::new(Vec::new());
+
+    // when
+    let parallel_hashing = get_parallel_hashing(&mut connection).await;
+    let results = map_with_parallelism(&items, parallel_hashing, |&item| {
+        events.lock().unwrap().push((item, true));
+        thread::sleep(Duration::from_millis(5));
+        events.lock().unwrap().push((item, false));
+        item * 2
+    })
+    .unwrap();
+
+    // then
+    assert_eq!(parallel_hashing, 1);
+    assert_eq!(results, vec![0, 2, 4, 6, 8, 10, 12, 14]);
+    let expected_events: Vec<(usize, bool)> = items
+        .iter()
+        .flat_map(|&item| vec![(item, true), (item, false)])
+        .collect();
+    assert_eq!(events.into_inner().unwrap(), expected_events);
+}
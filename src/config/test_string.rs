@@ -0,0 +1,19 @@
+use super::*;
+use tempfile::NamedTempFile;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let key = "TEST_STRING";
+
+    // when
+    set_string(&mut connection, key, "test").await;
+    let string = get_string(&mut connection, key).await;
+
+    // then
+    assert_eq!(string, Some(String::from("test")));
+}
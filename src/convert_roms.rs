@@ -1,5 +1,7 @@
 #[cfg(feature = "chd")]
 use super::chdman;
+use super::check_roms::check_size_and_hashes;
+use super::checksum::*;
 use super::config::*;
 use super::database::*;
 #[cfg(feature = "rvz")]
@@ -33,6 +35,7 @@ lazy_static! {
         cfg_if! {
             if #[cfg(feature = "cso")] {
                 all_formats.push("CSO");
+                all_formats.push("ZSO");
             }
         }
         cfg_if! {
@@ -44,6 +47,13 @@ lazy_static! {
     };
 }
 const ARCADE_FORMATS: &[&str] = &["ORIGINAL", "ZIP"];
+#[cfg(feature = "chd")]
+const CHD_UNSUPPORTED_REASON: &str = "only CUE/BIN, ISO, CSO and ZSO games are supported";
+#[cfg(feature = "cso")]
+const CSO_UNSUPPORTED_REASON: &str =
+    "only single ISO, CSO or ZSO games and single track CHDs are supported";
+#[cfg(feature = "rvz")]
+const RVZ_UNSUPPORTED_REASON: &str = "only single ISO games are supported";
 
 pub fn subcommand() -> Command {
     Command::new("convert-roms")
@@ -52,11 +62,20 @@ pub fn subcommand() -> Command {
             Arg::new("FORMAT")
                 .short('f')
                 .long("format")
+                .visible_alias("to")
                 .help("Set the destination format")
                 .required(false)
                 .num_args(1)
                 .value_parser(PossibleValuesParser::new(ALL_FORMATS.iter())),
         )
+        .arg(
+            Arg::new("FROM")
+                .long("from")
+                .help("Only convert games currently stored in this format")
+                .required(false)
+                .num_args(1)
+                .value_parser(PossibleValuesParser::new(ALL_FORMATS.iter())),
+        )
         .arg(
             Arg::new("SOLID")
                 .short('s')
@@ -65,6 +84,20 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("ARCHIVE_COMMENT")
+                .long("archive-comment")
+                .help("Embed a comment in created ZIP archives")
+                .required(false)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("TORRENTZIP")
+                .long("torrent-zip")
+                .help("Create ZIP archives in the TorrentZip format")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("NAME")
                 .short('n')
@@ -89,6 +122,13 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("VERIFY")
+                .long("verify")
+                .help("Verify the members of repacked archives before deleting the originals")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
 }
 
 pub async fn main(
@@ -105,10 +145,25 @@ pub async fn main(
             .map(|&s| s.to_owned())
             .unwrap(),
     };
+    let from_format = matches.get_one::<String>("FROM");
+    if from_format == Some(&format) {
+        bail!("Source and destination formats are the same");
+    }
     let diff = matches.get_flag("DIFF");
+    let archive_comment = match matches.get_one::<String>("ARCHIVE_COMMENT") {
+        Some(archive_comment) => Some(archive_comment.to_owned()),
+        None => get_string(connection, "ARCHIVE_COMMENT").await,
+    };
+    let torrentzip = matches.get_flag("TORRENTZIP") || get_bool(connection, "TORRENTZIP").await;
+    if format == "7Z" && archive_comment.is_some() {
+        progress_bar.println("7Z archives don't support comments, ignoring");
+    }
+
+    load_min_free_space(connection, progress_bar).await?;
 
     for system in systems {
         progress_bar.println(&format!("Processing \"{}\"", system.name));
+        check_min_free_space(get_rom_directory(connection).await)?;
 
         if system.arcade && !ARCADE_FORMATS.contains(&format.as_str()) {
             progress_bar.println(&format!(
@@ -164,6 +219,16 @@ pub async fn main(
             .map(|romfile| (romfile.id, romfile))
             .collect();
 
+        // only keep games entirely stored in the source format
+        if let Some(from_format) = from_format {
+            roms_by_game_id.retain(|_, roms| {
+                roms.iter().all(|rom| {
+                    get_format(&romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap().path)
+                        == from_format
+                })
+            });
+        }
+
         match format.as_str() {
             "ORIGINAL" => {
                 to_original(
@@ -186,6 +251,9 @@ pub async fn main(
                     romfiles_by_id,
                     diff,
                     matches.get_flag("SOLID"),
+                    None,
+                    false,
+                    matches.get_flag("VERIFY"),
                 )
                 .await?
             }
@@ -200,6 +268,9 @@ pub async fn main(
                     romfiles_by_id,
                     diff,
                     false,
+                    archive_comment.as_deref(),
+                    torrentzip,
+                    matches.get_flag("VERIFY"),
                 )
                 .await?
             }
@@ -217,7 +288,7 @@ pub async fn main(
                     }
                 }
             }
-            "CSO" => {
+            "CSO" | "ZSO" => {
                 cfg_if! {
                     if #[cfg(feature = "cso")] {
                         to_cso(
@@ -225,6 +296,7 @@ pub async fn main(
                             progress_bar,
                             roms_by_game_id,
                             romfiles_by_id,
+                            &format,
                             diff,
                         )
                         .await?
@@ -264,8 +336,12 @@ async fn to_archive(
     romfiles_by_id: HashMap<i64, Romfile>,
     diff: bool,
     solid: bool,
+    archive_comment: Option<&str>,
+    torrentzip: bool,
+    verify: bool,
 ) -> SimpleResult<()> {
     let tmp_directory = create_tmp_directory(connection).await?;
+    let header = find_header_by_system_id(connection, system.id).await;
 
     // remove same type archives
     roms_by_game_id.retain(|_, roms| {
@@ -295,15 +371,12 @@ async fn to_archive(
         }
     }
 
-    // partition CSOs
+    // partition CSOs and ZSOs
     let (csos, roms_by_game_id): (HashMap<i64, Vec<Rom>>, HashMap<i64, Vec<Rom>>) =
         roms_by_game_id.into_iter().partition(|(_, roms)| {
             roms.par_iter().any(|rom| {
-                romfiles_by_id
-                    .get(&rom.romfile_id.unwrap())
-                    .unwrap()
-                    .path
-                    .ends_with(CSO_EXTENSION)
+                let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+                romfile.path.ends_with(CSO_EXTENSION) || romfile.path.ends_with(ZSO_EXTENSION)
             })
         });
     cfg_if! {
@@ -372,7 +445,16 @@ async fn to_archive(
                         &[bin_path.file_name().unwrap().to_str().unwrap()],
                         &tmp_directory.path(),
                         solid,
+                        archive_comment,
+                        torrentzip,
                     )?;
+                    verify_archive_crcs(
+                        progress_bar,
+                        &header,
+                        &archive_path,
+                        &roms.iter().collect::<Vec<&Rom>>(),
+                    )
+                    .await?;
                     update_romfile(
                         &mut transaction,
                         romfile.id,
@@ -427,6 +509,8 @@ async fn to_archive(
                         &[&cue_rom.name],
                         &archive_path.parent().unwrap(),
                         solid,
+                        archive_comment,
+                        torrentzip,
                     )?;
                     let bin_names: Vec<&str> = bin_paths
                         .iter()
@@ -438,7 +522,16 @@ async fn to_archive(
                         &bin_names,
                         &tmp_directory.path(),
                         solid,
+                        archive_comment,
+                        torrentzip,
                     )?;
+                    verify_archive_crcs(
+                        progress_bar,
+                        &header,
+                        &archive_path,
+                        &roms.iter().collect::<Vec<&Rom>>(),
+                    )
+                    .await?;
                     update_romfile(
                         &mut transaction,
                         chd_romfile.id,
@@ -468,7 +561,7 @@ async fn to_archive(
         }
     }
 
-    // convert CSOs
+    // convert CSOs and ZSOs
     cfg_if! {
         if #[cfg(feature = "cso")] {
             for roms in csos.values() {
@@ -476,7 +569,7 @@ async fn to_archive(
 
                 let rom = roms.get(0).unwrap();
                 let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-                let iso_path = maxcso::extract_cso(progress_bar, &romfile.path, &tmp_directory.path())?;
+                let iso_path = extract_cso_or_zso(progress_bar, &romfile.path, &tmp_directory.path())?;
                 let mut archive_path = Path::new(&romfile.path).to_path_buf();
                 archive_path.set_extension(
                     match archive_type {
@@ -491,7 +584,16 @@ async fn to_archive(
                     &[iso_path.file_name().unwrap().to_str().unwrap()],
                     &tmp_directory.path(),
                     solid,
+                    archive_comment,
+                    torrentzip,
                 )?;
+                verify_archive_crcs(
+                    progress_bar,
+                    &header,
+                    &archive_path,
+                    &roms.iter().collect::<Vec<&Rom>>(),
+                )
+                .await?;
                 update_romfile(
                     &mut transaction,
                     romfile.id,
@@ -540,7 +642,16 @@ async fn to_archive(
                     &[iso_path.file_name().unwrap().to_str().unwrap()],
                     &tmp_directory.path(),
                     solid,
+                    archive_comment,
+                    torrentzip,
                 )?;
+                verify_archive_crcs(
+                    progress_bar,
+                    &header,
+                    &archive_path,
+                    &roms.iter().collect::<Vec<&Rom>>(),
+                )
+                .await?;
                 update_romfile(
                     &mut transaction,
                     romfile.id,
@@ -581,7 +692,7 @@ async fn to_archive(
                 &[&rom.name],
                 &tmp_directory.path(),
             )?;
-            remove_file(progress_bar, &archive_path, false).await?;
+            let original_archive_path = archive_path.clone();
             archive_path.set_extension(match archive_type {
                 sevenzip::ArchiveType::Sevenzip => SEVENZIP_EXTENSION,
                 sevenzip::ArchiveType::Zip => ZIP_EXTENSION,
@@ -592,7 +703,27 @@ async fn to_archive(
                 &[&rom.name],
                 &tmp_directory.path(),
                 solid,
+                archive_comment,
+                torrentzip,
             )?;
+            verify_archive_crcs(
+                progress_bar,
+                &header,
+                &archive_path,
+                &roms.iter().collect::<Vec<&Rom>>(),
+            )
+            .await?;
+            if verify {
+                verify_archive(
+                    &mut transaction,
+                    progress_bar,
+                    &header,
+                    &archive_path,
+                    &[rom],
+                )
+                .await?;
+            }
+            remove_file(progress_bar, &original_archive_path, false).await?;
             update_romfile(
                 &mut transaction,
                 romfile.id,
@@ -621,7 +752,7 @@ async fn to_archive(
                 &rom_names,
                 &tmp_directory.path(),
             )?;
-            remove_file(progress_bar, &archive_path, false).await?;
+            let original_archive_path = archive_path.clone();
             archive_path.set_extension(match archive_type {
                 sevenzip::ArchiveType::Sevenzip => SEVENZIP_EXTENSION,
                 sevenzip::ArchiveType::Zip => ZIP_EXTENSION,
@@ -632,7 +763,27 @@ async fn to_archive(
                 &rom_names,
                 &tmp_directory.path(),
                 solid,
+                archive_comment,
+                torrentzip,
             )?;
+            verify_archive_crcs(
+                progress_bar,
+                &header,
+                &archive_path,
+                &roms.iter().collect::<Vec<&Rom>>(),
+            )
+            .await?;
+            if verify {
+                verify_archive(
+                    &mut transaction,
+                    progress_bar,
+                    &header,
+                    &archive_path,
+                    &roms.iter().collect::<Vec<&Rom>>(),
+                )
+                .await?;
+            }
+            remove_file(progress_bar, &original_archive_path, false).await?;
             update_romfile(
                 &mut transaction,
                 romfile.id,
@@ -664,7 +815,16 @@ async fn to_archive(
                 &[&rom.name],
                 &archive_path.parent().unwrap(),
                 solid,
+                archive_comment,
+                torrentzip,
             )?;
+            verify_archive_crcs(
+                progress_bar,
+                &header,
+                &archive_path,
+                &roms.iter().collect::<Vec<&Rom>>(),
+            )
+            .await?;
             update_romfile(
                 &mut transaction,
                 romfile.id,
@@ -724,7 +884,16 @@ async fn to_archive(
                 &rom_names,
                 &directory,
                 solid,
+                archive_comment,
+                torrentzip,
             )?;
+            verify_archive_crcs(
+                progress_bar,
+                &header,
+                &archive_path,
+                &roms.iter().collect::<Vec<&Rom>>(),
+            )
+            .await?;
             let archive_romfile_id = match find_romfile_by_path(
                 &mut transaction,
                 archive_path.as_os_str().to_str().unwrap(),
@@ -824,24 +993,27 @@ async fn to_chd(
             })
         });
 
-    // partition CSOs
+    // partition CSOs and ZSOs
     cfg_if! {
         if #[cfg(feature = "cso")] {
             let (csos, others): (HashMap<i64, Vec<Rom>>, HashMap<i64, Vec<Rom>>) =
                 others.into_iter().partition(|(_, roms)| {
                     roms.par_iter().any(|rom| {
-                        romfiles_by_id
-                            .get(&rom.romfile_id.unwrap())
-                            .unwrap()
-                            .path
-                            .ends_with(CSO_EXTENSION)
+                        let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+                        romfile.path.ends_with(CSO_EXTENSION) || romfile.path.ends_with(ZSO_EXTENSION)
                     })
                 });
         }
     }
 
-    // drop others
-    drop(others);
+    // refuse others
+    refuse_conversions(
+        progress_bar,
+        others,
+        &romfiles_by_id,
+        "CHD",
+        CHD_UNSUPPORTED_REASON,
+    );
 
     // convert archives
     for roms in archives.values() {
@@ -860,18 +1032,17 @@ async fn to_chd(
         let romfile = romfiles.get(0).unwrap();
         let file_names: Vec<&str> = roms.par_iter().map(|rom| rom.name.as_str()).collect();
 
-        // skip if not ISO or CUE/BIN
-        if file_names.len() == 1 {
-            if !file_names.first().unwrap().ends_with(ISO_EXTENSION) {
-                continue;
-            }
+        // refuse if not ISO or CUE/BIN
+        let is_supported = if file_names.len() == 1 {
+            file_names.first().unwrap().ends_with(ISO_EXTENSION)
         } else {
-            let is_cue_bin = file_names.par_iter().any(|file_name| {
+            file_names.par_iter().any(|file_name| {
                 file_name.ends_with(CUE_EXTENSION) || file_name.ends_with(BIN_EXTENSION)
-            });
-            if !is_cue_bin {
-                continue;
-            }
+            })
+        };
+        if !is_supported {
+            refuse_conversion(progress_bar, romfile, "CHD", CHD_UNSUPPORTED_REASON);
+            continue;
         }
 
         let extracted_paths = sevenzip::extract_files_from_archive(
@@ -1019,7 +1190,7 @@ async fn to_chd(
         commit_transaction(transaction).await;
     }
 
-    // convert CSOs
+    // convert CSOs and ZSOs
     cfg_if! {
         if #[cfg(feature = "cso")] {
             for roms in csos.values() {
@@ -1027,7 +1198,7 @@ async fn to_chd(
 
                 for rom in roms {
                     let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-                    let iso_path = maxcso::extract_cso(progress_bar, &romfile.path, &tmp_directory.path())?;
+                    let iso_path = extract_cso_or_zso(progress_bar, &romfile.path, &tmp_directory.path())?;
                     let chd_path = chdman::create_chd(
                         progress_bar,
                         &iso_path,
@@ -1060,6 +1231,7 @@ async fn to_cso(
     progress_bar: &ProgressBar,
     roms_by_game_id: HashMap<i64, Vec<Rom>>,
     romfiles_by_id: HashMap<i64, Romfile>,
+    format: &str,
     diff: bool,
 ) -> SimpleResult<()> {
     let tmp_directory = create_tmp_directory(connection).await?;
@@ -1085,6 +1257,17 @@ async fn to_cso(
             })
         });
 
+    // partition CSOs and ZSOs in the other format
+    let (csos, others): (HashMap<i64, Vec<Rom>>, HashMap<i64, Vec<Rom>>) =
+        others.into_iter().partition(|(_, roms)| {
+            roms.par_iter().any(|rom| {
+                let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+                get_format(&romfile.path) != format
+                    && (romfile.path.ends_with(CSO_EXTENSION)
+                        || romfile.path.ends_with(ZSO_EXTENSION))
+            })
+        });
+
     // partition CHDs
     cfg_if! {
         if #[cfg(feature = "chd")] {
@@ -1102,8 +1285,14 @@ async fn to_cso(
         }
     }
 
-    // drop others
-    drop(others);
+    // refuse others
+    refuse_conversions(
+        progress_bar,
+        others,
+        &romfiles_by_id,
+        format,
+        CSO_UNSUPPORTED_REASON,
+    );
 
     // convert archives
     for roms in archives.values() {
@@ -1120,6 +1309,12 @@ async fn to_cso(
         }
 
         if roms.len() > 1 || !roms.get(0).unwrap().name.ends_with(ISO_EXTENSION) {
+            refuse_conversion(
+                progress_bar,
+                romfiles.get(0).unwrap(),
+                format,
+                CSO_UNSUPPORTED_REASON,
+            );
             continue;
         }
 
@@ -1135,8 +1330,9 @@ async fn to_cso(
         )?;
         let extracted_path = extracted_paths.get(0).unwrap();
 
-        let cso_path = maxcso::create_cso(
+        let cso_path = create_cso_or_zso(
             progress_bar,
+            format,
             &extracted_path,
             &Path::new(&romfile.path).parent().unwrap(),
         )?;
@@ -1163,8 +1359,9 @@ async fn to_cso(
 
         for rom in roms {
             let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-            let cso_path = maxcso::create_cso(
+            let cso_path = create_cso_or_zso(
                 progress_bar,
+                format,
                 &romfile.path,
                 &Path::new(&romfile.path).parent().unwrap(),
             )?;
@@ -1184,6 +1381,35 @@ async fn to_cso(
         commit_transaction(transaction).await;
     }
 
+    // convert CSOs and ZSOs
+    for roms in csos.values() {
+        let mut transaction = begin_transaction(connection).await;
+
+        for rom in roms {
+            let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+            let iso_path = extract_cso_or_zso(progress_bar, &romfile.path, &tmp_directory.path())?;
+            let cso_path = create_cso_or_zso(
+                progress_bar,
+                format,
+                &iso_path,
+                &Path::new(&romfile.path).parent().unwrap(),
+            )?;
+            if diff {
+                print_diff(progress_bar, &[rom], &[&romfile.path], &[&cso_path]).await?;
+            }
+            update_romfile(
+                &mut transaction,
+                romfile.id,
+                cso_path.as_os_str().to_str().unwrap(),
+                cso_path.metadata().await.unwrap().len(),
+            )
+            .await;
+            remove_file(progress_bar, &romfile.path, false).await?;
+        }
+
+        commit_transaction(transaction).await;
+    }
+
     // convert CHDs
     cfg_if! {
         if #[cfg(feature = "chd")] {
@@ -1197,8 +1423,9 @@ async fn to_cso(
                         &tmp_directory.path(),
                     )
                     .await?;
-                    let cso_path = maxcso::create_cso(
+                    let cso_path = create_cso_or_zso(
                         progress_bar,
+                        format,
                         &iso_path,
                         &Path::new(&romfile.path).parent().unwrap(),
                     )?;
@@ -1254,8 +1481,14 @@ async fn to_rvz(
             })
         });
 
-    // drop others
-    drop(others);
+    // refuse others
+    refuse_conversions(
+        progress_bar,
+        others,
+        &romfiles_by_id,
+        "RVZ",
+        RVZ_UNSUPPORTED_REASON,
+    );
 
     // convert archives
     for roms in archives.values() {
@@ -1272,6 +1505,12 @@ async fn to_rvz(
         }
 
         if roms.len() > 1 || !roms.get(0).unwrap().name.ends_with(ISO_EXTENSION) {
+            refuse_conversion(
+                progress_bar,
+                romfiles.get(0).unwrap(),
+                "RVZ",
+                RVZ_UNSUPPORTED_REASON,
+            );
             continue;
         }
 
@@ -1371,17 +1610,14 @@ async fn to_original(
         }
     }
 
-    // partition CSOs
+    // partition CSOs and ZSOs
     cfg_if! {
         if #[cfg(feature = "cso")] {
             let (csos, others): (HashMap<i64, Vec<Rom>>, HashMap<i64, Vec<Rom>>) =
                 others.into_iter().partition(|(_, roms)| {
                     roms.par_iter().any(|rom| {
-                        romfiles_by_id
-                            .get(&rom.romfile_id.unwrap())
-                            .unwrap()
-                            .path
-                            .ends_with(CSO_EXTENSION)
+                        let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+                        romfile.path.ends_with(CSO_EXTENSION) || romfile.path.ends_with(ZSO_EXTENSION)
                     })
                 });
         }
@@ -1512,7 +1748,7 @@ async fn to_original(
         }
     }
 
-    // convert CSOs
+    // convert CSOs and ZSOs
     cfg_if! {
         if #[cfg(feature = "cso")] {
             for roms in csos.values() {
@@ -1520,7 +1756,7 @@ async fn to_original(
 
                 for rom in roms {
                     let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
-                    let iso_path = maxcso::extract_cso(
+                    let iso_path = extract_cso_or_zso(
                         progress_bar,
                         &romfile.path,
                         &Path::new(&romfile.path).parent().unwrap(),
@@ -1571,6 +1807,166 @@ async fn to_original(
     Ok(())
 }
 
+fn get_format(romfile_path: &str) -> &'static str {
+    let extension = Path::new(romfile_path)
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default();
+    [
+        (SEVENZIP_EXTENSION, "7Z"),
+        (ZIP_EXTENSION, "ZIP"),
+        (CHD_EXTENSION, "CHD"),
+        (CSO_EXTENSION, "CSO"),
+        (ZSO_EXTENSION, "ZSO"),
+        (RVZ_EXTENSION, "RVZ"),
+    ]
+    .iter()
+    .find(|(format_extension, _)| *format_extension == extension)
+    .map(|(_, format)| *format)
+    .unwrap_or("ORIGINAL")
+}
+
+#[cfg(feature = "cso")]
+fn create_cso_or_zso<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    format: &str,
+    iso_path: &P,
+    directory: &Q,
+) -> SimpleResult<PathBuf> {
+    if format == "ZSO" {
+        maxcso::create_zso(progress_bar, iso_path, directory)
+    } else {
+        maxcso::create_cso(progress_bar, iso_path, directory)
+    }
+}
+
+#[cfg(feature = "cso")]
+fn extract_cso_or_zso<Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    romfile_path: &str,
+    directory: &Q,
+) -> SimpleResult<PathBuf> {
+    if romfile_path.ends_with(ZSO_EXTENSION) {
+        maxcso::extract_zso(progress_bar, &romfile_path, directory)
+    } else {
+        maxcso::extract_cso(progress_bar, &romfile_path, directory)
+    }
+}
+
+// games already in the destination format are left alone, the others are reported
+#[cfg(any(feature = "chd", feature = "cso", feature = "rvz"))]
+fn refuse_conversions(
+    progress_bar: &ProgressBar,
+    roms_by_game_id: HashMap<i64, Vec<Rom>>,
+    romfiles_by_id: &HashMap<i64, Romfile>,
+    format: &str,
+    reason: &str,
+) {
+    for roms in roms_by_game_id.values() {
+        let romfile = romfiles_by_id
+            .get(&roms.get(0).unwrap().romfile_id.unwrap())
+            .unwrap();
+        if get_format(&romfile.path) != format {
+            refuse_conversion(progress_bar, romfile, format, reason);
+        }
+    }
+}
+
+#[cfg(any(feature = "chd", feature = "cso", feature = "rvz"))]
+fn refuse_conversion(progress_bar: &ProgressBar, romfile: &Romfile, format: &str, reason: &str) {
+    progress_bar.println(format!(
+        "Refusing to convert {:?} to {}, {}",
+        Path::new(&romfile.path).file_name().unwrap(),
+        format,
+        reason
+    ));
+}
+
+// compare the member CRCs listed by 7z to the expected ones, much cheaper than extracting them
+async fn verify_archive_crcs<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    header: &Option<Header>,
+    archive_path: &P,
+    roms: &[&Rom],
+) -> SimpleResult<()> {
+    // headered ROMs are hashed without their header, the listed CRCs can't be compared
+    if header.is_some() {
+        return Ok(());
+    }
+    let sevenzip_infos = sevenzip::parse_archive(progress_bar, archive_path)?;
+    for rom in roms {
+        let expected_crc = match &rom.crc {
+            Some(crc) => crc,
+            None => continue,
+        };
+        // extracted CHD, CSO and RVZ files are named after their container, not the ROM
+        let sevenzip_info = match sevenzip_infos.iter().find(|info| info.path == rom.name) {
+            Some(sevenzip_info) => Some(sevenzip_info),
+            None if roms.len() == 1 && sevenzip_infos.len() == 1 => sevenzip_infos.get(0),
+            None => None,
+        };
+        let error = match sevenzip_info {
+            Some(sevenzip_info) if &sevenzip_info.crc == expected_crc => continue,
+            Some(sevenzip_info) => format!(
+                "CRC mismatch (listed {}, expected {})",
+                sevenzip_info.crc, expected_crc
+            ),
+            None => String::from("missing member"),
+        };
+        remove_file(progress_bar, archive_path, false).await?;
+        bail!(
+            "Failed to verify \"{}\" in {:?}: {}",
+            rom.name,
+            archive_path.as_ref().file_name().unwrap(),
+            error
+        );
+    }
+    Ok(())
+}
+
+// extract the members of a freshly written archive and compare them to the expected hashes
+async fn verify_archive<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    header: &Option<Header>,
+    archive_path: &P,
+    roms: &[&Rom],
+) -> SimpleResult<()> {
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let rom_names: Vec<&str> = roms.iter().map(|rom| rom.name.as_str()).collect();
+    let extracted_paths = sevenzip::extract_files_from_archive(
+        progress_bar,
+        archive_path,
+        &rom_names,
+        &tmp_directory.path(),
+    )?;
+    for (i, (rom, extracted_path)) in roms.iter().zip(extracted_paths).enumerate() {
+        let (size, crc, md5, sha1) = get_size_and_hashes(
+            connection,
+            progress_bar,
+            &extracted_path,
+            header,
+            i + 1,
+            roms.len(),
+        )
+        .await?;
+        if let Err(error) = check_size_and_hashes(rom, size_to_i64(size)?, &crc, &md5, &sha1) {
+            remove_file(progress_bar, archive_path, false).await?;
+            bail!(
+                "Failed to verify \"{}\" in {:?}: {}",
+                rom.name,
+                archive_path.as_ref().file_name().unwrap(),
+                error
+            );
+        }
+    }
+    progress_bar.println(format!(
+        "Verified {:?}",
+        archive_path.as_ref().file_name().unwrap()
+    ));
+    Ok(())
+}
+
 async fn print_diff<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     roms: &[&Rom],
@@ -1619,6 +2015,8 @@ mod test_cso_to_sevenzip_iso;
 mod test_cue_bin_to_chd;
 #[cfg(all(test, feature = "chd"))]
 mod test_iso_to_chd;
+#[cfg(all(test, feature = "chd"))]
+mod test_iso_to_chd_with_stale_part;
 #[cfg(all(test, feature = "cso"))]
 mod test_iso_to_cso;
 #[cfg(all(test, feature = "chd", feature = "cso"))]
@@ -1630,6 +2028,10 @@ mod test_original_to_sevenzip;
 #[cfg(test)]
 mod test_original_to_zip;
 #[cfg(test)]
+mod test_original_to_zip_from_sevenzip_should_do_nothing;
+#[cfg(test)]
+mod test_original_to_zip_with_comment;
+#[cfg(test)]
 mod test_original_to_zip_with_correct_name;
 #[cfg(test)]
 mod test_original_to_zip_with_incorrect_name;
@@ -1651,3 +2053,9 @@ mod test_single_track_chd_to_sevenzip_iso;
 mod test_zip_to_original;
 #[cfg(test)]
 mod test_zip_to_sevenzip;
+#[cfg(test)]
+mod test_zip_to_sevenzip_crc_mismatch;
+#[cfg(test)]
+mod test_zip_to_sevenzip_verified;
+#[cfg(test)]
+mod test_zip_to_sevenzip_verify_mismatch;
@@ -67,6 +67,9 @@ async fn test() {
         romfiles_by_id,
         false,
         false,
+        None,
+        false,
+        false,
     )
     .await
     .unwrap();
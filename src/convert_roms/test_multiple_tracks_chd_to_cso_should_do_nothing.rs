@@ -78,6 +78,7 @@ async fn test() {
         &progress_bar,
         roms_by_game_id,
         romfiles_by_id,
+        "CSO",
         true,
     )
     .await
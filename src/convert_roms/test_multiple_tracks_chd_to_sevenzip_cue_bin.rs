@@ -76,6 +76,9 @@ async fn test() {
         romfiles_by_id,
         false,
         false,
+        None,
+        false,
+        false,
     )
     .await
     .unwrap();
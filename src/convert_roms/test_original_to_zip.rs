@@ -68,6 +68,9 @@ async fn test() {
         romfiles_by_id,
         false,
         false,
+        None,
+        false,
+        false,
     )
     .await
     .unwrap();
@@ -0,0 +1,50 @@
+use super::database::*;
+use super::model::*;
+use super::SimpleResult;
+use clap::{Arg, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+
+pub fn subcommand() -> Command {
+    Command::new("create-system")
+        .about("Create an empty system for collections without a DAT file")
+        .arg(
+            Arg::new("NAME")
+                .short('n')
+                .long("name")
+                .help("Set the system name")
+                .required(true)
+                .num_args(1),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let name = matches.get_one::<String>("NAME").unwrap();
+    let system = create_system(connection, name).await?;
+    progress_bar.println(format!(
+        "Created \"{}\", use import-roms --snapshot to add ROM files to it",
+        system.name
+    ));
+    Ok(())
+}
+
+pub async fn create_system(connection: &mut SqliteConnection, name: &str) -> SimpleResult<System> {
+    if find_system_by_name(connection, name).await.is_some() {
+        bail!("System \"{}\" already exists", name);
+    }
+    let system_xml = SystemXml {
+        name: name.to_owned(),
+        description: name.to_owned(),
+        version: String::new(),
+        clrmamepros: Vec::new(),
+        url: None,
+    };
+    let system_id = create_system_from_xml(connection, &system_xml, false).await;
+    // files are snapshotted as is, archives included
+    update_system_archive_is_rom(connection, system_id, true).await;
+    Ok(find_system_by_id(connection, system_id).await)
+}
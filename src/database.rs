@@ -1,25 +1,30 @@
 use super::model::*;
+use super::util::size_to_i64;
+use super::SimpleResult;
 use cfg_if::cfg_if;
+use futures::stream::BoxStream;
 use itertools::Itertools;
 use sqlx::migrate::Migrator;
 use sqlx::prelude::*;
 use sqlx::sqlite::{SqliteConnection, SqlitePool, SqlitePoolOptions};
 use sqlx::{Acquire, Sqlite, Transaction};
-use std::convert::TryFrom;
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
+use tracing::instrument;
 
 static MIGRATOR: Migrator = sqlx::migrate!();
 
+#[cfg(test)]
+pub static FIND_GAME_BY_ID_CALLS: AtomicUsize = AtomicUsize::new(0);
+
 pub async fn establish_connection(url: &str) -> SqlitePool {
     let max_connections: u32;
-    let locking_mode: &str;
     cfg_if! {
         if #[cfg(feature = "server")] {
             max_connections = 5;
-            locking_mode = "NORMAL";
         } else {
             max_connections = 1;
-            locking_mode = "EXCLUSIVE";
         }
     }
 
@@ -31,20 +36,17 @@ pub async fn establish_connection(url: &str) -> SqlitePool {
         .await
         .unwrap_or_else(|_| panic!("Error connecting to {}", url));
 
+    // readers keep working while a write is in progress
     pool.execute(
-        format!(
-            "
-            PRAGMA foreign_keys = ON;
-            PRAGMA locking_mode = {};
-            PRAGMA journal_mode = WAL;
-            PRAGMA synchronous = NORMAL;
-            PRAGMA temp_store = MEMORY;
-            PRAGMA mmap_size = 30000000000;
-            PRAGMA auto_vacuum = INCREMENTAL;
-        ",
-            locking_mode
-        )
-        .as_str(),
+        "
+        PRAGMA foreign_keys = ON;
+        PRAGMA locking_mode = NORMAL;
+        PRAGMA journal_mode = WAL;
+        PRAGMA synchronous = NORMAL;
+        PRAGMA temp_store = MEMORY;
+        PRAGMA mmap_size = 30000000000;
+        PRAGMA auto_vacuum = INCREMENTAL;
+    ",
     )
     .await
     .expect("Failed to setup the database");
@@ -57,6 +59,53 @@ pub async fn establish_connection(url: &str) -> SqlitePool {
     pool
 }
 
+// reports never write, they read alongside imports without taking the write lock
+pub async fn establish_read_only_connection(url: &str) -> SqlitePool {
+    let pool = connect_read_only(url).await;
+    if is_migrated(&pool).await {
+        return pool;
+    }
+    // migrations can't run on a read-only connection
+    pool.close().await;
+    establish_connection(url).await.close().await;
+    connect_read_only(url).await
+}
+
+async fn connect_read_only(url: &str) -> SqlitePool {
+    SqlitePoolOptions::new()
+        .min_connections(1)
+        .max_connections(1)
+        .acquire_timeout(Duration::from_secs(5))
+        .after_connect(|connection, _| {
+            Box::pin(async move {
+                connection
+                    .execute(
+                        "
+                        PRAGMA foreign_keys = ON;
+                        PRAGMA query_only = ON;
+                        PRAGMA temp_store = MEMORY;
+                        PRAGMA mmap_size = 30000000000;
+                    ",
+                    )
+                    .await?;
+                Ok(())
+            })
+        })
+        .connect(url)
+        .await
+        .unwrap_or_else(|_| panic!("Error connecting to {}", url))
+}
+
+async fn is_migrated(pool: &SqlitePool) -> bool {
+    let latest_version = MIGRATOR.iter().map(|migration| migration.version).max();
+    let applied_version: Option<i64> =
+        sqlx::query_scalar("SELECT MAX(version) FROM _sqlx_migrations WHERE success = 1")
+            .fetch_one(pool)
+            .await
+            .unwrap_or(None);
+    applied_version.is_some() && applied_version >= latest_version
+}
+
 pub async fn begin_transaction(connection: &mut SqliteConnection) -> Transaction<'_, Sqlite> {
     Acquire::begin(connection)
         .await
@@ -89,6 +138,28 @@ pub async fn close_connection(pool: &SqlitePool) {
     .expect("Failed to optimize the database");
 }
 
+pub async fn find_database_path(connection: &mut SqliteConnection) -> String {
+    sqlx::query_scalar("SELECT file FROM pragma_database_list WHERE name = 'main'")
+        .fetch_one(connection)
+        .await
+        .expect("Error while finding the database path")
+}
+
+// VACUUM can't run inside a transaction, the WAL is truncated so that the file shrinks right away
+pub async fn vacuum_and_analyze(connection: &mut SqliteConnection) {
+    connection
+        .execute(
+            "
+            VACUUM;
+            ANALYZE;
+            PRAGMA optimize;
+            PRAGMA wal_checkpoint(truncate);
+        ",
+        )
+        .await
+        .expect("Failed to vacuum the database");
+}
+
 pub async fn create_system_from_xml(
     connection: &mut SqliteConnection,
     system_xml: &SystemXml,
@@ -217,6 +288,83 @@ pub async fn update_system_merging(connection: &mut SqliteConnection, id: i64, m
     .unwrap_or_else(|_| panic!("Error while updating system with id {} merging", merging));
 }
 
+pub async fn update_system_hash_algorithm(
+    connection: &mut SqliteConnection,
+    id: i64,
+    hash_algorithm: Option<&str>,
+) {
+    sqlx::query!(
+        "
+        UPDATE systems
+        SET hash_algorithm = ?
+        WHERE id = ?
+        ",
+        hash_algorithm,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating system with id {} hash algorithm", id));
+}
+
+pub async fn update_system_recommended_hash_algorithm(
+    connection: &mut SqliteConnection,
+    id: i64,
+    recommended_hash_algorithm: Option<&str>,
+) {
+    sqlx::query!(
+        "
+        UPDATE systems
+        SET recommended_hash_algorithm = ?
+        WHERE id = ?
+        ",
+        recommended_hash_algorithm,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while updating system with id {} recommended hash algorithm",
+            id
+        )
+    });
+}
+
+pub async fn update_system_archive_is_rom(
+    connection: &mut SqliteConnection,
+    id: i64,
+    archive_is_rom: bool,
+) {
+    sqlx::query!(
+        "
+        UPDATE systems
+        SET archive_is_rom = ?
+        WHERE id = ?
+        ",
+        archive_is_rom,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating system with id {} archive is rom", id));
+}
+
+pub async fn update_system_name(connection: &mut SqliteConnection, id: i64, name: &str) {
+    sqlx::query!(
+        "
+        UPDATE systems
+        SET name = ?
+        WHERE id = ?
+        ",
+        name,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating system with id {} name", id));
+}
+
 pub async fn find_systems(connection: &mut SqliteConnection) -> Vec<System> {
     sqlx::query_as!(
         System,
@@ -262,6 +410,32 @@ pub async fn find_systems_by_url(connection: &mut SqliteConnection, url: &str) -
     .unwrap_or_else(|_| panic!("Error while finding systems with url {}", url))
 }
 
+// headered systems list the size of their roms without the header
+pub async fn find_systems_by_rom_size(connection: &mut SqliteConnection, size: u64) -> Vec<System> {
+    let size = size_to_i64(size).unwrap();
+    sqlx::query_as!(
+        System,
+        "
+        SELECT *
+        FROM systems
+        WHERE id IN (
+            SELECT g.system_id
+            FROM games AS g
+            JOIN roms AS r ON r.game_id = g.id
+            LEFT JOIN headers AS h ON h.system_id = g.system_id
+            WHERE r.size = ?
+            OR r.size = ? - h.size
+        )
+        ORDER BY name
+        ",
+        size,
+        size,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding systems with rom size {}", size))
+}
+
 #[cfg(feature = "ird")]
 pub async fn find_systems_by_name_like(
     connection: &mut SqliteConnection,
@@ -312,6 +486,25 @@ pub async fn find_system_by_name(connection: &mut SqliteConnection, name: &str)
     .unwrap_or_else(|_| panic!("Error while finding system with name {}", name))
 }
 
+pub async fn find_system_by_name_case_insensitive(
+    connection: &mut SqliteConnection,
+    name: &str,
+) -> Option<System> {
+    let name = name.replace(" (Parent-Clone)", "");
+    sqlx::query_as!(
+        System,
+        "
+        SELECT *
+        FROM systems
+        WHERE LOWER(RTRIM(name)) = LOWER(RTRIM(?))
+        ",
+        name,
+    )
+    .fetch_optional(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding system with name {}", name))
+}
+
 pub async fn delete_system_by_id(connection: &mut SqliteConnection, id: i64) {
     sqlx::query!(
         "
@@ -336,12 +529,14 @@ pub async fn create_game_from_xml(
     let bios = game_xml.isbios.is_some() && game_xml.isbios.as_ref().unwrap() == "yes";
     sqlx::query!(
         "
-        INSERT INTO games (name, description, comment, bios, regions, system_id, parent_id, bios_id)
-        VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+        INSERT INTO games (name, description, comment, year, manufacturer, bios, regions, system_id, parent_id, bios_id)
+        VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
         ",
         game_xml.name,
         game_xml.description,
         game_xml.comment,
+        game_xml.year,
+        game_xml.manufacturer,
         bios,
         regions,
         system_id,
@@ -367,12 +562,14 @@ pub async fn update_game_from_xml(
     sqlx::query!(
         "
         UPDATE games
-        SET name = ?, description = ?, comment = ?, bios = ?, regions = ?, system_id = ?, parent_id = ?, bios_id = ?
+        SET name = ?, description = ?, comment = ?, year = ?, manufacturer = ?, bios = ?, regions = ?, system_id = ?, parent_id = ?, bios_id = ?
         WHERE id = ?
         ",
         game_xml.name,
         game_xml.description,
         game_xml.comment,
+        game_xml.year,
+        game_xml.manufacturer,
         bios,
         regions,
         system_id,
@@ -395,7 +592,15 @@ pub async fn update_games_by_system_id_mark_complete(
         SET complete = true
         WHERE system_id = ?
         AND complete = false
-        AND jbfolder = false
+        AND (
+            jbfolder = false
+            OR NOT EXISTS (
+                SELECT r.id
+                FROM roms r
+                WHERE r.game_id = games.id
+                AND r.parent_id IS NOT NULL
+            )
+        )
         AND NOT EXISTS (
             SELECT r.id
             FROM roms r
@@ -423,6 +628,12 @@ pub async fn update_jbfolder_games_by_system_id_mark_complete(
         WHERE system_id = ?
         AND complete = false
         AND jbfolder = true
+        AND EXISTS (
+            SELECT r.id
+            FROM roms r
+            WHERE r.game_id = games.id
+            AND r.parent_id IS NOT NULL
+        )
         AND NOT EXISTS (
             SELECT r.id
             FROM roms r
@@ -451,7 +662,15 @@ pub async fn update_games_by_system_id_mark_incomplete(
         SET complete = false
         WHERE system_id = ?
         AND complete = true
-        AND jbfolder = false
+        AND (
+            jbfolder = false
+            OR NOT EXISTS (
+                SELECT r.id
+                FROM roms r
+                WHERE r.game_id = games.id
+                AND r.parent_id IS NOT NULL
+            )
+        )
         AND EXISTS (
             SELECT r.id
             FROM roms r
@@ -542,7 +761,6 @@ pub async fn update_games_sorting(
         .rows_affected()
 }
 
-#[cfg(feature = "ird")]
 pub async fn update_game_jbfolder(connection: &mut SqliteConnection, id: i64, jbfolder: bool) {
     sqlx::query!(
         "
@@ -558,6 +776,36 @@ pub async fn update_game_jbfolder(connection: &mut SqliteConnection, id: i64, jb
     .unwrap_or_else(|_| panic!("Error while updating game with id {}", id));
 }
 
+pub async fn find_game_ids_without_romfiles_by_rom_count_and_size_and_system_id(
+    connection: &mut SqliteConnection,
+    rom_count: i64,
+    size: i64,
+    system_id: i64,
+) -> Vec<i64> {
+    sqlx::query!(
+        "
+        SELECT r.game_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE g.system_id = ?
+        AND r.parent_id IS NULL
+        GROUP BY r.game_id
+        HAVING COUNT(r.id) = ?
+        AND SUM(r.size) = ?
+        AND COUNT(r.romfile_id) = 0
+        ",
+        system_id,
+        rom_count,
+        size,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding games with system id {}", system_id))
+    .into_iter()
+    .map(|row| row.game_id)
+    .collect()
+}
+
 pub async fn find_games(connection: &mut SqliteConnection) -> Vec<Game> {
     sqlx::query_as!(
         Game,
@@ -612,7 +860,6 @@ pub async fn find_wanted_games_by_system_id(
     .unwrap_or_else(|_| panic!("Error while finding games with system id {}", system_id))
 }
 
-#[cfg(test)]
 pub async fn find_games_by_ids(connection: &mut SqliteConnection, ids: &[i64]) -> Vec<Game> {
     let sql = format!(
         "
@@ -680,6 +927,8 @@ pub async fn find_clone_games_by_system_id(
 }
 
 pub async fn find_game_by_id(connection: &mut SqliteConnection, id: i64) -> Game {
+    #[cfg(test)]
+    FIND_GAME_BY_ID_CALLS.fetch_add(1, Ordering::SeqCst);
     sqlx::query_as!(
         Game,
         "
@@ -694,6 +943,22 @@ pub async fn find_game_by_id(connection: &mut SqliteConnection, id: i64) -> Game
     .unwrap_or_else(|_| panic!("Error while finding game with id {}", id))
 }
 
+pub async fn find_games_by_name(connection: &mut SqliteConnection, name: &str) -> Vec<Game> {
+    sqlx::query_as!(
+        Game,
+        "
+        SELECT *
+        FROM games
+        WHERE name LIKE ?
+        ORDER BY system_id, name
+        ",
+        name,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding games with name {}", name))
+}
+
 pub async fn find_game_by_name_and_bios_and_system_id(
     connection: &mut SqliteConnection,
     name: &str,
@@ -831,7 +1096,7 @@ pub async fn create_rom_from_xml(
     game_id: i64,
     parent_id: Option<i64>,
 ) -> i64 {
-    let crc = rom_xml.crc.as_ref().unwrap().to_lowercase();
+    let crc = rom_xml.crc.as_ref().map(|crc| crc.to_lowercase());
     let md5 = rom_xml.md5.as_ref().map(|md5| md5.to_lowercase());
     let sha1 = rom_xml.sha1.as_ref().map(|sha1| sha1.to_lowercase());
     sqlx::query!(
@@ -891,7 +1156,7 @@ pub async fn update_rom_from_xml(
     game_id: i64,
     parent_id: Option<i64>,
 ) {
-    let crc = rom_xml.crc.as_ref().unwrap().to_lowercase();
+    let crc = rom_xml.crc.as_ref().map(|crc| crc.to_lowercase());
     let md5 = rom_xml.md5.as_ref().map(|md5| md5.to_lowercase());
     let sha1 = rom_xml.sha1.as_ref().map(|sha1| sha1.to_lowercase());
     sqlx::query!(
@@ -987,6 +1252,27 @@ pub async fn find_roms_by_game_id_no_parents(
     .unwrap_or_else(|_| panic!("Error while finding roms with game id {}", game_id))
 }
 
+pub async fn find_roms_by_system_id_no_parents(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Vec<Rom> {
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE g.system_id = ?
+        AND r.parent_id IS NULL
+        ORDER BY r.name
+        ",
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding roms with system id {}", system_id))
+}
+
 pub async fn find_roms_by_game_id_parents_no_parent_bioses(
     connection: &mut SqliteConnection,
     game_id: i64,
@@ -1162,22 +1448,56 @@ pub async fn find_roms_with_romfile_by_size_and_crc_and_system_id(
     .expect("Error while finding roms with romfile")
 }
 
-pub async fn find_roms_without_romfile_by_size_and_md5_and_system_id(
+#[instrument(level = "trace", skip(connection))]
+pub async fn find_roms_by_size_and_crc_and_system_id(
+    connection: &mut SqliteConnection,
+    size: u64,
+    crc: &str,
+    system_id: i64,
+) -> SimpleResult<Vec<Rom>> {
+    let size = size_to_i64(size)?;
+    let crc = crc.to_lowercase();
+    Ok(sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.size = ?
+        AND r.crc = ?
+        AND g.system_id = ?
+        ORDER BY r.name
+        ",
+        size,
+        crc,
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with size {} and CRC {} and system id {}",
+            size, crc, system_id
+        )
+    }))
+}
+
+#[instrument(level = "trace", skip(connection))]
+pub async fn find_roms_by_size_and_md5_and_system_id(
     connection: &mut SqliteConnection,
     size: u64,
     md5: &str,
     system_id: i64,
-) -> Vec<Rom> {
-    let size = i64::try_from(size).unwrap();
+) -> SimpleResult<Vec<Rom>> {
+    let size = size_to_i64(size)?;
     let md5 = md5.to_lowercase();
-    sqlx::query_as!(
+    Ok(sqlx::query_as!(
         Rom,
         "
         SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
         FROM roms AS r
         JOIN games AS g ON r.game_id = g.id
-        WHERE r.romfile_id IS NULL
-        AND r.size = ?
+        WHERE r.size = ?
         AND r.md5 = ?
         AND g.system_id = ?
         ORDER BY r.name
@@ -1193,25 +1513,25 @@ pub async fn find_roms_without_romfile_by_size_and_md5_and_system_id(
             "Error while finding roms with size {} and MD5 {} and system id {}",
             size, md5, system_id
         )
-    })
+    }))
 }
 
-pub async fn find_roms_without_romfile_by_size_and_sha1_and_system_id(
+#[instrument(level = "trace", skip(connection))]
+pub async fn find_roms_by_size_and_sha1_and_system_id(
     connection: &mut SqliteConnection,
     size: u64,
     sha1: &str,
     system_id: i64,
-) -> Vec<Rom> {
-    let size = i64::try_from(size).unwrap();
+) -> SimpleResult<Vec<Rom>> {
+    let size = size_to_i64(size)?;
     let sha1 = sha1.to_lowercase();
-    sqlx::query_as!(
+    Ok(sqlx::query_as!(
         Rom,
         "
         SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
         FROM roms AS r
         JOIN games AS g ON r.game_id = g.id
-        WHERE r.romfile_id IS NULL
-        AND r.size = ?
+        WHERE r.size = ?
         AND r.sha1 = ?
         AND g.system_id = ?
         ORDER BY r.name
@@ -1227,17 +1547,18 @@ pub async fn find_roms_without_romfile_by_size_and_sha1_and_system_id(
             "Error while finding roms with size {} and SHA1 {} and system id {}",
             size, sha1, system_id
         )
-    })
+    }))
 }
 
-pub async fn find_roms_without_romfile_by_size_and_crc_and_system_id(
+#[instrument(level = "trace", skip(connection))]
+pub async fn find_roms_without_romfile_by_size_and_md5_and_system_id(
     connection: &mut SqliteConnection,
     size: u64,
-    crc: &str,
+    md5: &str,
     system_id: i64,
 ) -> Vec<Rom> {
-    let size = i64::try_from(size).unwrap();
-    let crc = crc.to_lowercase();
+    let size = size_to_i64(size).unwrap();
+    let md5 = md5.to_lowercase();
     sqlx::query_as!(
         Rom,
         "
@@ -1246,34 +1567,33 @@ pub async fn find_roms_without_romfile_by_size_and_crc_and_system_id(
         JOIN games AS g ON r.game_id = g.id
         WHERE r.romfile_id IS NULL
         AND r.size = ?
-        AND r.crc = ?
+        AND r.md5 = ?
         AND g.system_id = ?
         ORDER BY r.name
         ",
         size,
-        crc,
+        md5,
         system_id,
     )
     .fetch_all(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with size {} and CRC {} and system id {}",
-            size, crc, system_id
+            "Error while finding roms with size {} and MD5 {} and system id {}",
+            size, md5, system_id
         )
     })
 }
 
-#[cfg(feature = "ird")]
-pub async fn find_roms_without_romfile_by_name_and_size_and_md5_and_system_id(
+#[instrument(level = "trace", skip(connection))]
+pub async fn find_roms_without_romfile_by_size_and_sha1_and_system_id(
     connection: &mut SqliteConnection,
-    name: &str,
     size: u64,
-    md5: &str,
+    sha1: &str,
     system_id: i64,
 ) -> Vec<Rom> {
-    let size = i64::try_from(size).unwrap();
-    let md5 = md5.to_lowercase();
+    let size = size_to_i64(size).unwrap();
+    let sha1 = sha1.to_lowercase();
     sqlx::query_as!(
         Rom,
         "
@@ -1281,36 +1601,255 @@ pub async fn find_roms_without_romfile_by_name_and_size_and_md5_and_system_id(
         FROM roms AS r
         JOIN games AS g ON r.game_id = g.id
         WHERE r.romfile_id IS NULL
-        AND r.name = ?
         AND r.size = ?
-        AND r.md5 = ?
-        AND r.parent_id IS NOT NULL
+        AND r.sha1 = ?
         AND g.system_id = ?
-        ORDER BY g.name
+        ORDER BY r.name
         ",
-        name,
         size,
-        md5,
+        sha1,
         system_id,
     )
     .fetch_all(connection)
     .await
     .unwrap_or_else(|_| {
         panic!(
-            "Error while finding roms with name {} and size {} and MD5 {} and system id {}",
-            name, size, md5, system_id
+            "Error while finding roms with size {} and SHA1 {} and system id {}",
+            size, sha1, system_id
         )
     })
 }
 
-#[cfg(feature = "ird")]
+#[instrument(level = "trace", skip(connection))]
+pub async fn find_roms_without_romfile_by_size_and_crc_and_system_id(
+    connection: &mut SqliteConnection,
+    size: u64,
+    crc: &str,
+    system_id: i64,
+) -> Vec<Rom> {
+    let size = size_to_i64(size).unwrap();
+    let crc = crc.to_lowercase();
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.size = ?
+        AND r.crc = ?
+        AND g.system_id = ?
+        ORDER BY r.name
+        ",
+        size,
+        crc,
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with size {} and CRC {} and system id {}",
+            size, crc, system_id
+        )
+    })
+}
+
+#[instrument(level = "trace", skip(connection))]
+pub async fn find_roms_without_romfile_and_hashes_by_name_and_size_and_system_id(
+    connection: &mut SqliteConnection,
+    name: &str,
+    size: u64,
+    system_id: i64,
+) -> Vec<Rom> {
+    let size = size_to_i64(size).unwrap();
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.name = ?
+        AND r.size = ?
+        AND r.crc IS NULL
+        AND r.md5 IS NULL
+        AND r.sha1 IS NULL
+        AND g.system_id = ?
+        ORDER BY g.name
+        ",
+        name,
+        size,
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms without hashes with name {} and size {}",
+            name, size
+        )
+    })
+}
+
+#[cfg(feature = "ird")]
+pub async fn find_roms_without_romfile_by_name_and_size_and_md5_and_system_id(
+    connection: &mut SqliteConnection,
+    name: &str,
+    size: u64,
+    md5: &str,
+    system_id: i64,
+) -> Vec<Rom> {
+    let size = size_to_i64(size).unwrap();
+    let md5 = md5.to_lowercase();
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.romfile_id IS NULL
+        AND r.name = ?
+        AND r.size = ?
+        AND r.md5 = ?
+        AND r.parent_id IS NOT NULL
+        AND g.system_id = ?
+        ORDER BY g.name
+        ",
+        name,
+        size,
+        md5,
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with name {} and size {} and MD5 {} and system id {}",
+            name, size, md5, system_id
+        )
+    })
+}
+
+pub async fn find_roms_with_duplicate_size_and_crc_by_system_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Vec<Rom> {
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.parent_id IS NULL
+        AND r.crc IS NOT NULL
+        AND g.system_id = ?
+        AND EXISTS (
+            SELECT r2.id
+            FROM roms AS r2
+            JOIN games AS g2 ON r2.game_id = g2.id
+            WHERE r2.id != r.id
+            AND r2.parent_id IS NULL
+            AND r2.size = r.size
+            AND r2.crc = r.crc
+            AND g2.system_id = g.system_id
+        )
+        ORDER BY r.size, r.crc, r.name
+        ",
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with duplicate size and CRC and system id {}",
+            system_id
+        )
+    })
+}
+
+pub async fn find_roms_with_duplicate_size_and_md5_by_system_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Vec<Rom> {
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.parent_id IS NULL
+        AND r.md5 IS NOT NULL
+        AND g.system_id = ?
+        AND EXISTS (
+            SELECT r2.id
+            FROM roms AS r2
+            JOIN games AS g2 ON r2.game_id = g2.id
+            WHERE r2.id != r.id
+            AND r2.parent_id IS NULL
+            AND r2.size = r.size
+            AND r2.md5 = r.md5
+            AND g2.system_id = g.system_id
+        )
+        ORDER BY r.size, r.md5, r.name
+        ",
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with duplicate size and MD5 and system id {}",
+            system_id
+        )
+    })
+}
+
+pub async fn find_roms_with_duplicate_size_and_sha1_by_system_id(
+    connection: &mut SqliteConnection,
+    system_id: i64,
+) -> Vec<Rom> {
+    sqlx::query_as!(
+        Rom,
+        "
+        SELECT r.id, r.name, r.bios, r.size, r.crc, r.md5, r.sha1, r.rom_status, r.game_id, r.romfile_id, r.parent_id
+        FROM roms AS r
+        JOIN games AS g ON r.game_id = g.id
+        WHERE r.parent_id IS NULL
+        AND r.sha1 IS NOT NULL
+        AND g.system_id = ?
+        AND EXISTS (
+            SELECT r2.id
+            FROM roms AS r2
+            JOIN games AS g2 ON r2.game_id = g2.id
+            WHERE r2.id != r.id
+            AND r2.parent_id IS NULL
+            AND r2.size = r.size
+            AND r2.sha1 = r.sha1
+            AND g2.system_id = g.system_id
+        )
+        ORDER BY r.size, r.sha1, r.name
+        ",
+        system_id,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding roms with duplicate size and SHA1 and system id {}",
+            system_id
+        )
+    })
+}
+
+#[cfg(feature = "ird")]
 pub async fn find_roms_without_romfile_by_size_and_md5_and_parent_id(
     connection: &mut SqliteConnection,
     size: u64,
     md5: &str,
     parent_id: i64,
 ) -> Vec<Rom> {
-    let size = i64::try_from(size).unwrap();
+    let size = size_to_i64(size).unwrap();
     let md5 = md5.to_lowercase();
     sqlx::query_as!(
         Rom,
@@ -1418,7 +1957,7 @@ pub async fn delete_rom_by_name_and_game_id(
 }
 
 pub async fn create_romfile(connection: &mut SqliteConnection, path: &str, size: u64) -> i64 {
-    let size = i64::try_from(size).unwrap();
+    let size = size_to_i64(size).unwrap();
     sqlx::query!(
         "
         INSERT INTO romfiles (path, size)
@@ -1434,7 +1973,9 @@ pub async fn create_romfile(connection: &mut SqliteConnection, path: &str, size:
 }
 
 pub async fn update_romfile(connection: &mut SqliteConnection, id: i64, path: &str, size: u64) {
-    let size = i64::try_from(size).unwrap();
+    let size = size_to_i64(size).unwrap();
+    // the cached headerless CRCs follow the archive
+    update_headerless_crcs_archive_path_by_romfile_id(connection, id, path).await;
     sqlx::query!(
         "
         UPDATE romfiles 
@@ -1450,6 +1991,81 @@ pub async fn update_romfile(connection: &mut SqliteConnection, id: i64, path: &s
     .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
 }
 
+pub async fn update_romfile_matched_dat_version(
+    connection: &mut SqliteConnection,
+    id: i64,
+    matched_dat_version: &str,
+) {
+    sqlx::query!(
+        "
+        UPDATE romfiles
+        SET matched_dat_version = ?
+        WHERE id = ?
+        ",
+        matched_dat_version,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+}
+
+pub async fn update_romfile_manifest(connection: &mut SqliteConnection, id: i64, manifest: &str) {
+    sqlx::query!(
+        "
+        UPDATE romfiles
+        SET manifest = ?
+        WHERE id = ?
+        ",
+        manifest,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+}
+
+pub async fn update_romfile_checksum_cache(
+    connection: &mut SqliteConnection,
+    id: i64,
+    cached_size: i64,
+    cached_mtime: i64,
+    cached_crc: Option<&str>,
+    cached_md5: Option<&str>,
+    cached_sha1: Option<&str>,
+) {
+    sqlx::query!(
+        "
+        UPDATE romfiles
+        SET cached_size = ?, cached_mtime = ?, cached_crc = ?, cached_md5 = ?, cached_sha1 = ?
+        WHERE id = ?
+        ",
+        cached_size,
+        cached_mtime,
+        cached_crc,
+        cached_md5,
+        cached_sha1,
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+}
+
+pub async fn clear_romfile_checksum_cache(connection: &mut SqliteConnection, id: i64) {
+    sqlx::query!(
+        "
+        UPDATE romfiles
+        SET cached_size = NULL, cached_mtime = NULL, cached_crc = NULL, cached_md5 = NULL, cached_sha1 = NULL
+        WHERE id = ?
+        ",
+        id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while updating romfile with id {}", id));
+}
+
 pub async fn find_romfiles(connection: &mut SqliteConnection) -> Vec<Romfile> {
     sqlx::query_as!(
         Romfile,
@@ -1520,6 +2136,7 @@ pub async fn find_romfiles_in_trash(connection: &mut SqliteConnection) -> Vec<Ro
     .expect("Error while finding romfiles in trash")
 }
 
+#[instrument(level = "trace", skip(connection))]
 pub async fn find_romfile_by_path(
     connection: &mut SqliteConnection,
     path: &str,
@@ -1554,6 +2171,7 @@ pub async fn find_romfile_by_id(connection: &mut SqliteConnection, id: i64) -> R
 }
 
 pub async fn delete_romfile_by_id(connection: &mut SqliteConnection, id: i64) {
+    delete_headerless_crcs_by_romfile_id(connection, id).await;
     sqlx::query!(
         "
         DELETE FROM romfiles
@@ -1588,7 +2206,13 @@ pub async fn create_header_from_xml(
     detector_xml: &DetectorXml,
     system_id: i64,
 ) -> i64 {
-    let size = i64::from_str_radix(&detector_xml.rule.start_offset, 16).unwrap();
+    // the largest header, each rule is stripped according to its own start offset
+    let size = detector_xml
+        .rules
+        .iter()
+        .map(|rule_xml| i64::from_str_radix(&rule_xml.start_offset, 16).unwrap())
+        .max()
+        .unwrap_or(0);
     sqlx::query!(
         "
         INSERT INTO headers (name, version, size, system_id)
@@ -1611,7 +2235,13 @@ pub async fn update_header_from_xml(
     detector_xml: &DetectorXml,
     system_id: i64,
 ) {
-    let size = i64::from_str_radix(&detector_xml.rule.start_offset, 16).unwrap();
+    // the largest header, each rule is stripped according to its own start offset
+    let size = detector_xml
+        .rules
+        .iter()
+        .map(|rule_xml| i64::from_str_radix(&rule_xml.start_offset, 16).unwrap())
+        .max()
+        .unwrap_or(0);
     sqlx::query!(
         "
         UPDATE headers
@@ -1649,18 +2279,31 @@ pub async fn find_header_by_system_id(
 
 pub async fn create_rule_from_xml(
     connection: &mut SqliteConnection,
+    rule_index: i64,
+    rule_xml: &RuleXml,
     data_xml: &DataXml,
     header_id: i64,
 ) -> i64 {
     let start_byte = i64::from_str_radix(&data_xml.offset, 16).unwrap();
+    let start_offset = i64::from_str_radix(&rule_xml.start_offset, 16).unwrap();
+    let end_offset = rule_xml
+        .end_offset
+        .as_ref()
+        .filter(|end_offset| end_offset.as_str() != "EOF")
+        .map(|end_offset| i64::from_str_radix(end_offset, 16).unwrap());
+    let result = data_xml.result.as_deref() != Some("false");
     sqlx::query!(
         "
-        INSERT INTO rules (start_byte, hex_value, header_id)
-        VALUES (?, ?, ?)
+        INSERT INTO rules (start_byte, hex_value, header_id, rule_index, start_offset, end_offset, result)
+        VALUES (?, ?, ?, ?, ?, ?, ?)
         ",
         start_byte,
         data_xml.value,
         header_id,
+        rule_index,
+        start_offset,
+        end_offset,
+        result,
     )
     .execute(connection)
     .await
@@ -1678,6 +2321,7 @@ pub async fn find_rules_by_header_id(
         SELECT *
         FROM rules
         WHERE header_id = ?
+        ORDER BY rule_index
         ",
         header_id,
     )
@@ -1756,3 +2400,313 @@ pub async fn find_setting_by_key(connection: &mut SqliteConnection, key: &str) -
     .await
     .unwrap_or_else(|_| panic!("Error while finding setting with key {}", key))
 }
+
+pub async fn create_or_update_headerless_crc(
+    connection: &mut SqliteConnection,
+    archive_path: &str,
+    member_name: &str,
+    archive_size: i64,
+    mtime: i64,
+    size: i64,
+    crc: &str,
+) {
+    sqlx::query!(
+        "
+        INSERT INTO headerless_crcs (archive_path, member_name, archive_size, mtime, size, crc)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT (archive_path, member_name)
+        DO UPDATE SET archive_size = excluded.archive_size, mtime = excluded.mtime, size = excluded.size, crc = excluded.crc
+        ",
+        archive_path,
+        member_name,
+        archive_size,
+        mtime,
+        size,
+        crc,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating or updating headerless CRC");
+}
+
+pub async fn find_headerless_crc_by_archive_path_and_member_name_and_archive_size_and_mtime(
+    connection: &mut SqliteConnection,
+    archive_path: &str,
+    member_name: &str,
+    archive_size: i64,
+    mtime: i64,
+) -> Option<HeaderlessCrc> {
+    sqlx::query_as!(
+        HeaderlessCrc,
+        "
+        SELECT size, crc
+        FROM headerless_crcs
+        WHERE archive_path = ?
+        AND member_name = ?
+        AND archive_size = ?
+        AND mtime = ?
+        ",
+        archive_path,
+        member_name,
+        archive_size,
+        mtime,
+    )
+    .fetch_optional(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while finding headerless CRC for {} in {}",
+            member_name, archive_path
+        )
+    })
+}
+
+pub async fn update_headerless_crcs_archive_path_by_romfile_id(
+    connection: &mut SqliteConnection,
+    romfile_id: i64,
+    archive_path: &str,
+) {
+    sqlx::query!(
+        "
+        UPDATE headerless_crcs
+        SET archive_path = ?
+        WHERE archive_path = (
+            SELECT path
+            FROM romfiles
+            WHERE id = ?
+        )
+        ",
+        archive_path,
+        romfile_id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while updating headerless CRCs for romfile with id {}",
+            romfile_id
+        )
+    });
+}
+
+pub async fn delete_headerless_crcs_by_romfile_id(
+    connection: &mut SqliteConnection,
+    romfile_id: i64,
+) {
+    sqlx::query!(
+        "
+        DELETE FROM headerless_crcs
+        WHERE archive_path = (
+            SELECT path
+            FROM romfiles
+            WHERE id = ?
+        )
+        ",
+        romfile_id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| {
+        panic!(
+            "Error while deleting headerless CRCs for romfile with id {}",
+            romfile_id
+        )
+    });
+}
+
+pub async fn delete_headerless_crcs_by_archive_path(
+    connection: &mut SqliteConnection,
+    archive_path: &str,
+) {
+    sqlx::query!(
+        "
+        DELETE FROM headerless_crcs
+        WHERE archive_path = ?
+        ",
+        archive_path,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while deleting headerless CRCs for {}", archive_path));
+}
+
+pub async fn create_match_preference(
+    connection: &mut SqliteConnection,
+    size: i64,
+    hash: &str,
+    rom_id: i64,
+) {
+    sqlx::query!(
+        "
+        INSERT INTO match_preferences (size, hash, rom_id)
+        VALUES (?, ?, ?)
+        ON CONFLICT (size, hash, rom_id)
+        DO NOTHING
+        ",
+        size,
+        hash,
+        rom_id,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating match preference");
+}
+
+pub async fn find_match_preference_rom_ids_by_size_and_hash(
+    connection: &mut SqliteConnection,
+    size: i64,
+    hash: &str,
+) -> Vec<i64> {
+    sqlx::query!(
+        "
+        SELECT rom_id
+        FROM match_preferences
+        WHERE size = ?
+        AND hash = ?
+        ",
+        size,
+        hash,
+    )
+    .fetch_all(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while finding match preferences for {}", hash))
+    .into_iter()
+    .map(|row| row.rom_id)
+    .collect()
+}
+
+pub async fn find_match_preference_rows(
+    connection: &mut SqliteConnection,
+) -> Vec<MatchPreferenceRow> {
+    sqlx::query_as!(
+        MatchPreferenceRow,
+        "
+        SELECT s.name AS system_name, g.name AS game_name, r.name AS rom_name, mp.size, mp.hash
+        FROM match_preferences AS mp
+        JOIN roms AS r ON mp.rom_id = r.id
+        JOIN games AS g ON r.game_id = g.id
+        JOIN systems AS s ON g.system_id = s.id
+        ORDER BY s.name, g.name, r.name
+        ",
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding match preferences")
+}
+
+pub async fn delete_match_preferences_by_size_and_hash_and_system_id(
+    connection: &mut SqliteConnection,
+    size: i64,
+    hash: &str,
+    system_id: i64,
+) {
+    sqlx::query!(
+        "
+        DELETE FROM match_preferences
+        WHERE size = ?
+        AND hash = ?
+        AND rom_id IN (
+            SELECT r.id
+            FROM roms AS r
+            JOIN games AS g ON r.game_id = g.id
+            WHERE g.system_id = ?
+        )
+        ",
+        size,
+        hash,
+        system_id,
+    )
+    .execute(connection)
+    .await
+    .unwrap_or_else(|_| panic!("Error while deleting match preferences for {}", hash));
+}
+
+pub async fn delete_match_preferences(connection: &mut SqliteConnection) {
+    sqlx::query!(
+        "
+        DELETE FROM match_preferences
+        ",
+    )
+    .execute(connection)
+    .await
+    .expect("Error while deleting match preferences");
+}
+
+pub async fn create_run_history(
+    connection: &mut SqliteConnection,
+    timestamp: i64,
+    command: &str,
+    system_id: i64,
+    files: i64,
+    duration: i64,
+    outcome: &str,
+) {
+    sqlx::query!(
+        "
+        INSERT INTO run_history (timestamp, command, system_id, files, duration, outcome)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ",
+        timestamp,
+        command,
+        system_id,
+        files,
+        duration,
+        outcome,
+    )
+    .execute(connection)
+    .await
+    .expect("Error while creating run history");
+}
+
+pub async fn find_run_history_rows(connection: &mut SqliteConnection) -> Vec<RunHistoryRow> {
+    sqlx::query_as!(
+        RunHistoryRow,
+        r#"
+        SELECT datetime(rh.timestamp, 'unixepoch') AS "date!", rh.command, s.name AS system_name, rh.files, rh.duration, rh.outcome
+        FROM run_history AS rh
+        JOIN systems AS s ON rh.system_id = s.id
+        ORDER BY rh.timestamp, rh.id
+        "#,
+    )
+    .fetch_all(connection)
+    .await
+    .expect("Error while finding run history")
+}
+
+pub fn find_inventory_rows(
+    connection: &mut SqliteConnection,
+    have_only: bool,
+) -> BoxStream<'_, Result<InventoryRow, sqlx::Error>> {
+    if have_only {
+        sqlx::query_as!(
+            InventoryRow,
+            r#"
+            SELECT s.name AS system_name, g.name AS game_name, r.name AS rom_name, r.size, r.crc, r.md5, r.sha1, rf.path AS "romfile_path?", g.complete
+            FROM roms AS r
+            JOIN games AS g ON r.game_id = g.id
+            JOIN systems AS s ON g.system_id = s.id
+            JOIN romfiles AS rf ON r.romfile_id = rf.id
+            ORDER BY s.name, g.name, r.name
+            "#,
+        )
+        .fetch(connection)
+    } else {
+        sqlx::query_as!(
+            InventoryRow,
+            r#"
+            SELECT s.name AS system_name, g.name AS game_name, r.name AS rom_name, r.size, r.crc, r.md5, r.sha1, rf.path AS "romfile_path?", g.complete
+            FROM roms AS r
+            JOIN games AS g ON r.game_id = g.id
+            JOIN systems AS s ON g.system_id = s.id
+            LEFT JOIN romfiles AS rf ON r.romfile_id = rf.id
+            ORDER BY s.name, g.name, r.name
+            "#,
+        )
+        .fetch(connection)
+    }
+}
+
+#[cfg(test)]
+mod test_headerless_crcs;
+#[cfg(test)]
+mod test_read_only_connection;
@@ -0,0 +1,79 @@
+use super::*;
+use tempfile::NamedTempFile;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let romfile_id = create_romfile(&mut connection, "/roms/Test Game.rom.7z", 256).await;
+    create_or_update_headerless_crc(
+        &mut connection,
+        "/roms/Test Game.rom.7z",
+        "Test Game.rom",
+        256,
+        1_000_000_001,
+        240,
+        "cc721e14",
+    )
+    .await;
+
+    // when
+    update_romfile(
+        &mut connection,
+        romfile_id,
+        "/roms/Trash/Test Game.rom.7z",
+        256,
+    )
+    .await;
+
+    // then
+    assert!(
+        find_headerless_crc_by_archive_path_and_member_name_and_archive_size_and_mtime(
+            &mut connection,
+            "/roms/Test Game.rom.7z",
+            "Test Game.rom",
+            256,
+            1_000_000_001,
+        )
+        .await
+        .is_none()
+    );
+    let headerless_crc =
+        find_headerless_crc_by_archive_path_and_member_name_and_archive_size_and_mtime(
+            &mut connection,
+            "/roms/Trash/Test Game.rom.7z",
+            "Test Game.rom",
+            256,
+            1_000_000_001,
+        )
+        .await
+        .unwrap();
+    assert_eq!(headerless_crc.size, 240);
+    assert_eq!(headerless_crc.crc, "cc721e14");
+
+    // a rewrite within the same second misses the cache
+    assert!(
+        find_headerless_crc_by_archive_path_and_member_name_and_archive_size_and_mtime(
+            &mut connection,
+            "/roms/Trash/Test Game.rom.7z",
+            "Test Game.rom",
+            256,
+            1_000_000_002,
+        )
+        .await
+        .is_none()
+    );
+
+    // when
+    delete_romfile_by_id(&mut connection, romfile_id).await;
+
+    // then
+    let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM headerless_crcs")
+        .fetch_one(&mut connection)
+        .await
+        .unwrap();
+    assert_eq!(count, 0);
+}
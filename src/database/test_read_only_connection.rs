@@ -0,0 +1,58 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::import_dats;
+use super::*;
+use async_std::path::{Path, PathBuf};
+use indicatif::ProgressBar;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let url = db_file.path().to_str().unwrap();
+    let pool = establish_connection(url).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // when
+    let read_only_pool = establish_read_only_connection(url).await;
+    let mut read_only_connection = read_only_pool.acquire().await.unwrap();
+
+    // then
+    let systems = find_systems(&mut read_only_connection).await;
+    assert_eq!(systems.len(), 1);
+    let games = find_games_by_system_id(&mut read_only_connection, systems[0].id).await;
+    assert!(!games.is_empty());
+
+    let result = sqlx::query("DELETE FROM games")
+        .execute(&mut read_only_connection)
+        .await;
+    assert!(result.is_err());
+    assert_eq!(
+        find_games_by_system_id(&mut read_only_connection, systems[0].id)
+            .await
+            .len(),
+        games.len()
+    );
+
+    // the writer is not blocked by the reader
+    sqlx::query("UPDATE systems SET complete = complete")
+        .execute(&mut connection)
+        .await
+        .unwrap();
+}
@@ -0,0 +1,448 @@
+use super::check_roms;
+use super::checksum::*;
+use super::config::*;
+use super::database::*;
+use super::import_roms;
+use super::model::*;
+use super::prompt::*;
+use super::util::*;
+use super::SimpleResult;
+use async_std::path::Path;
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+use walkdir::WalkDir;
+
+const FORMATS: &[&str] = &["CHD", "CSO", "ZSO", "RVZ", "LOOSE", "ARCHIVE"];
+
+pub fn subcommand() -> Command {
+    Command::new("dedup-formats")
+        .about("Keep a single storage format for games stored in several formats")
+        .arg(
+            Arg::new("PREFER")
+                .short('p')
+                .long("prefer")
+                .help("Set the preferred formats, unlisted formats follow in the default order")
+                .required(false)
+                .num_args(1..)
+                .value_parser(PossibleValuesParser::new(FORMATS)),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Set the system number to use")
+                .required(false)
+                .num_args(1),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let system = prompt_for_system(
+        connection,
+        matches
+            .get_one::<String>("SYSTEM")
+            .map(|s| FromStr::from_str(s).expect("Failed to parse number")),
+    )
+    .await?;
+    let preferred_formats: Vec<&str> = matches
+        .get_many::<String>("PREFER")
+        .map(|formats| formats.map(|format| format.as_str()).collect())
+        .unwrap_or_default();
+    let hash_algorithm = get_hash_algorithm(connection, &system).await?;
+    dedup_formats(
+        connection,
+        progress_bar,
+        &system,
+        &get_format_order(&preferred_formats),
+        &hash_algorithm,
+    )
+    .await
+}
+
+pub fn get_format_order<'a>(preferred_formats: &[&'a str]) -> Vec<&'a str> {
+    let mut format_order: Vec<&str> = preferred_formats.to_vec();
+    for format in FORMATS {
+        if !format_order.contains(format) {
+            format_order.push(format);
+        }
+    }
+    format_order
+}
+
+fn get_format<P: AsRef<Path>>(system: &System, path: &P) -> &'static str {
+    let extension = path
+        .as_ref()
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    if extension == CHD_EXTENSION {
+        "CHD"
+    } else if extension == CSO_EXTENSION {
+        "CSO"
+    } else if extension == ZSO_EXTENSION {
+        "ZSO"
+    } else if extension == RVZ_EXTENSION {
+        "RVZ"
+    } else if ARCHIVE_EXTENSIONS.contains(&extension.as_str()) && !system.archive_is_rom {
+        "ARCHIVE"
+    } else {
+        "LOOSE"
+    }
+}
+
+enum Representation {
+    Tracked,
+    Container(String),
+    // rom ids along with their untracked loose file
+    Loose(Vec<(i64, String)>),
+}
+
+pub async fn dedup_formats(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    format_order: &[&str],
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<()> {
+    let header = find_header_by_system_id(connection, system.id).await;
+    let system_directory = get_system_directory(connection, progress_bar, system).await?;
+    let trash_directory = std::path::PathBuf::from(
+        get_trash_directory(connection, progress_bar, system)
+            .await?
+            .as_os_str(),
+    );
+    let romfiles_by_id: HashMap<i64, Romfile> = find_romfiles_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .map(|romfile| (romfile.id, romfile))
+        .collect();
+    let tracked_paths: HashSet<&str> = romfiles_by_id
+        .values()
+        .map(|romfile| romfile.path.as_str())
+        .collect();
+    let mut roms_by_game_id: HashMap<i64, Vec<Rom>> = HashMap::new();
+    for rom in find_roms_by_system_id_no_parents(connection, system.id).await {
+        roms_by_game_id.entry(rom.game_id).or_default().push(rom);
+    }
+    let mut games = find_games_by_system_id(connection, system.id).await;
+    games.retain(|game| roms_by_game_id.contains_key(&game.id));
+
+    // containers are named after their game or one of its roms
+    let mut game_ids_by_stem: HashMap<String, HashSet<i64>> = HashMap::new();
+    let mut roms_by_name: HashMap<&str, Vec<&Rom>> = HashMap::new();
+    for game in &games {
+        game_ids_by_stem
+            .entry(game.name.clone())
+            .or_default()
+            .insert(game.id);
+        for rom in roms_by_game_id.get(&game.id).unwrap() {
+            if let Some(stem) = Path::new(&rom.name).file_stem() {
+                game_ids_by_stem
+                    .entry(stem.to_str().unwrap().to_owned())
+                    .or_default()
+                    .insert(game.id);
+            }
+            roms_by_name.entry(&rom.name).or_default().push(rom);
+        }
+    }
+
+    let mut containers_by_game_id: HashMap<i64, Vec<String>> = HashMap::new();
+    let mut loose_by_game_id: HashMap<i64, Vec<(i64, String)>> = HashMap::new();
+    let walker = WalkDir::new(&system_directory)
+        .into_iter()
+        .filter_entry(|entry| entry.path() != trash_directory);
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        let path = entry.path().to_str().unwrap();
+        if !entry.path().is_file() || tracked_paths.contains(path) {
+            continue;
+        }
+        if get_format(system, &path) == "LOOSE" {
+            let file_name = entry.file_name().to_str().unwrap();
+            for rom in roms_by_name.get(file_name).into_iter().flatten() {
+                loose_by_game_id
+                    .entry(rom.game_id)
+                    .or_default()
+                    .push((rom.id, path.to_owned()));
+            }
+        } else {
+            let stem = entry.path().file_stem().unwrap().to_str().unwrap();
+            for game_id in game_ids_by_stem.get(stem).into_iter().flatten() {
+                containers_by_game_id
+                    .entry(*game_id)
+                    .or_default()
+                    .push(path.to_owned());
+            }
+        }
+    }
+
+    check_tools_available_for_files(
+        containers_by_game_id.values().flatten(),
+        system.archive_is_rom,
+    )?;
+
+    for game in &games {
+        let containers = containers_by_game_id.remove(&game.id).unwrap_or_default();
+        let loose = loose_by_game_id.remove(&game.id).unwrap_or_default();
+        if containers.is_empty() && loose.is_empty() {
+            continue;
+        }
+        let roms = roms_by_game_id.get(&game.id).unwrap();
+        // only complete games can be stored in several formats
+        if roms.iter().any(|rom| rom.romfile_id.is_none()) {
+            continue;
+        }
+        let tracked_formats: HashSet<&str> = roms
+            .iter()
+            .map(|rom| get_format(system, &romfiles_by_id[&rom.romfile_id.unwrap()].path))
+            .collect();
+        let tracked_format = match tracked_formats.iter().find(|&&format| format != "LOOSE") {
+            Some(format) => *format,
+            None => "LOOSE",
+        };
+
+        let mut representations: Vec<(&str, Representation)> =
+            vec![(tracked_format, Representation::Tracked)];
+        for container in &containers {
+            let format = get_format(system, container);
+            if format != tracked_format {
+                representations.push((format, Representation::Container(container.clone())));
+            }
+        }
+        // loose files complete the roms that are already stored loose, like the CUE of a CHD
+        let loose_complete = roms.iter().all(|rom| {
+            get_format(system, &romfiles_by_id[&rom.romfile_id.unwrap()].path) == "LOOSE"
+                || loose.iter().any(|(rom_id, _)| *rom_id == rom.id)
+        });
+        if tracked_format != "LOOSE" && loose_complete {
+            representations.push(("LOOSE", Representation::Loose(loose.clone())));
+        }
+        if representations.len() == 1 {
+            continue;
+        }
+        representations.sort_by_key(|(format, _)| {
+            format_order
+                .iter()
+                .position(|preferred_format| preferred_format == format)
+                .unwrap()
+        });
+
+        progress_bar.println(format!("Processing \"{}\"", game.name));
+        let mut kept_paths: HashSet<String> = HashSet::new();
+        for (format, representation) in &representations {
+            match representation {
+                Representation::Tracked => {
+                    progress_bar.println(format!("Keeping the {} files", format));
+                    break;
+                }
+                Representation::Container(container) => {
+                    // CHDs leave the CUE file out
+                    let container_roms: Vec<Rom> =
+                        find_roms_by_game_id_no_parents(connection, game.id)
+                            .await
+                            .into_iter()
+                            .filter(|rom| {
+                                *format != "CHD"
+                                    || Path::new(&rom.name).extension().and_then(|e| e.to_str())
+                                        != Some(CUE_EXTENSION)
+                            })
+                            .collect();
+                    let container_rom_ids: Vec<i64> =
+                        container_roms.iter().map(|rom| rom.id).collect();
+                    if let Err(error) = check_container(
+                        connection,
+                        progress_bar,
+                        &header,
+                        container,
+                        format,
+                        container_roms,
+                        hash_algorithm,
+                    )
+                    .await
+                    {
+                        progress_bar.println(format!("Skipping {:?}: {}", container, error));
+                        continue;
+                    }
+                    progress_bar.println(format!("Keeping {:?}", container));
+                    let romfile_id = create_romfile(
+                        connection,
+                        container,
+                        Path::new(container).metadata().await.unwrap().len(),
+                    )
+                    .await;
+                    for rom_id in container_rom_ids {
+                        update_rom_romfile(connection, rom_id, Some(romfile_id)).await;
+                    }
+                    kept_paths.insert(container.clone());
+                    break;
+                }
+                Representation::Loose(loose) => {
+                    let mut verified = true;
+                    for (rom_id, path) in loose {
+                        let rom = roms.iter().find(|rom| rom.id == *rom_id).unwrap();
+                        if let Err(error) = check_roms::check_original(
+                            connection,
+                            progress_bar,
+                            &header,
+                            &path.as_str(),
+                            rom,
+                            false,
+                            hash_algorithm,
+                        )
+                        .await
+                        {
+                            progress_bar.println(format!("Skipping {:?}: {}", path, error));
+                            verified = false;
+                            break;
+                        }
+                    }
+                    if !verified {
+                        continue;
+                    }
+                    progress_bar.println("Keeping the LOOSE files");
+                    for (rom_id, path) in loose {
+                        let romfile_id = create_romfile(
+                            connection,
+                            path,
+                            Path::new(path).metadata().await.unwrap().len(),
+                        )
+                        .await;
+                        update_rom_romfile(connection, *rom_id, Some(romfile_id)).await;
+                        kept_paths.insert(path.clone());
+                    }
+                    break;
+                }
+            }
+        }
+
+        // previously tracked files left without roms are trashed along with the other candidates
+        for romfile_id in roms
+            .iter()
+            .map(|rom| rom.romfile_id.unwrap())
+            .collect::<HashSet<i64>>()
+        {
+            if find_roms_by_romfile_id(connection, romfile_id)
+                .await
+                .is_empty()
+            {
+                check_roms::move_to_trash(
+                    connection,
+                    progress_bar,
+                    system,
+                    &romfiles_by_id[&romfile_id],
+                    TrashReason::Redundant,
+                )
+                .await?;
+            }
+        }
+        let candidate_paths: HashSet<&String> = containers
+            .iter()
+            .chain(loose.iter().map(|(_, path)| path))
+            .collect();
+        for path in candidate_paths {
+            if !kept_paths.contains(path) && Path::new(path).is_file().await {
+                import_roms::move_to_trash(
+                    connection,
+                    progress_bar,
+                    system,
+                    path,
+                    TrashReason::Redundant,
+                    &import_roms::ImportOptions::default(),
+                )
+                .await?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_container(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    header: &Option<Header>,
+    container: &str,
+    format: &str,
+    roms: Vec<Rom>,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<()> {
+    match format {
+        "ARCHIVE" => {
+            check_roms::check_archive(
+                connection,
+                progress_bar,
+                header,
+                &container,
+                roms,
+                false,
+                hash_algorithm,
+            )
+            .await
+        }
+        #[cfg(feature = "chd")]
+        "CHD" => {
+            check_roms::check_chd(
+                connection,
+                progress_bar,
+                header,
+                &container,
+                roms,
+                false,
+                None,
+                hash_algorithm,
+            )
+            .await
+        }
+        #[cfg(feature = "cso")]
+        "CSO" => {
+            check_roms::check_cso(
+                connection,
+                progress_bar,
+                header,
+                &container,
+                roms.get(0).unwrap(),
+                false,
+                hash_algorithm,
+            )
+            .await
+        }
+        #[cfg(feature = "cso")]
+        "ZSO" => {
+            check_roms::check_zso(
+                connection,
+                progress_bar,
+                header,
+                &container,
+                roms.get(0).unwrap(),
+                false,
+                hash_algorithm,
+            )
+            .await
+        }
+        #[cfg(feature = "rvz")]
+        "RVZ" => {
+            check_roms::check_rvz(
+                connection,
+                progress_bar,
+                header,
+                &container,
+                roms.get(0).unwrap(),
+                false,
+                hash_algorithm,
+            )
+            .await
+        }
+        _ => bail!("Please rebuild with the {} feature enabled", format),
+    }
+}
+
+#[cfg(all(test, feature = "chd"))]
+mod test_chd_and_loose;
@@ -0,0 +1,107 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for name in [
+        "Test Game (USA, Europe) (Multiple Tracks).cue",
+        "Test Game (USA, Europe) (Multiple Tracks).chd",
+    ] {
+        fs::copy(test_directory.join(name), tmp_directory.join(name))
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        "-s",
+        "1",
+        "-q",
+        tmp_directory
+            .join("Test Game (USA, Europe) (Multiple Tracks).chd")
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // the same game also lies around as loose BIN files
+    for name in [
+        "Test Game (USA, Europe) (Track 01).bin",
+        "Test Game (USA, Europe) (Track 02).bin",
+    ] {
+        fs::copy(test_directory.join(name), system_directory.join(name))
+            .await
+            .unwrap();
+    }
+
+    // when
+    let matches = subcommand().get_matches_from(&["dedup-formats", "-s", "1", "--prefer", "CHD"]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    let romfile_paths: Vec<&str> = romfiles
+        .iter()
+        .map(|romfile| romfile.path.as_str())
+        .collect();
+    assert!(romfile_paths.contains(
+        &system_directory
+            .join("Test Game (USA, Europe).chd")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    ));
+    assert!(
+        system_directory
+            .join("Test Game (USA, Europe).chd")
+            .is_file()
+            .await
+    );
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 3);
+
+    for name in [
+        "Test Game (USA, Europe) (Track 01).bin",
+        "Test Game (USA, Europe) (Track 02).bin",
+    ] {
+        assert!(!system_directory.join(name).is_file().await);
+        assert!(system_directory.join("Trash").join(name).is_file().await);
+    }
+}
@@ -0,0 +1,105 @@
+#[cfg(feature = "chd")]
+use super::chdman;
+#[cfg(feature = "rvz")]
+use super::dolphin;
+#[cfg(feature = "ird")]
+use super::isoinfo;
+#[cfg(feature = "cso")]
+use super::maxcso;
+use super::sevenzip;
+use super::SimpleResult;
+use cfg_if::cfg_if;
+use clap::Command;
+use indicatif::ProgressBar;
+
+pub fn subcommand() -> Command {
+    Command::new("doctor").about("Check external programs and compiled features")
+}
+
+pub fn main(progress_bar: &ProgressBar) -> SimpleResult<()> {
+    for line in get_report() {
+        progress_bar.println(line);
+    }
+    Ok(())
+}
+
+pub fn get_report() -> Vec<String> {
+    let mut report = vec![get_tool_status("7z", sevenzip::check_tool_available())];
+    cfg_if! {
+        if #[cfg(feature = "chd")] {
+            report.push(get_tool_status("chdman", chdman::check_tool_available()));
+        } else {
+            report.push(get_disabled_status("chdman", "chd"));
+        }
+    }
+    cfg_if! {
+        if #[cfg(feature = "cso")] {
+            report.push(get_tool_status("maxcso", maxcso::check_tool_available()));
+        } else {
+            report.push(get_disabled_status("maxcso", "cso"));
+        }
+    }
+    cfg_if! {
+        if #[cfg(feature = "rvz")] {
+            report.push(get_tool_status("dolphin-tool", dolphin::check_tool_available()));
+        } else {
+            report.push(get_disabled_status("dolphin-tool", "rvz"));
+        }
+    }
+    cfg_if! {
+        if #[cfg(feature = "ird")] {
+            report.push(get_tool_status("isoinfo", isoinfo::check_tool_available()));
+        } else {
+            report.push(get_disabled_status("isoinfo", "ird"));
+        }
+    }
+    report.push(format!("Features: {}", get_features().join(", ")));
+    report
+}
+
+fn get_tool_status(tool: &str, result: SimpleResult<String>) -> String {
+    match result {
+        Ok(version) => format!("{}: {}", tool, version),
+        Err(e) => e.to_string(),
+    }
+}
+
+#[allow(dead_code)]
+fn get_disabled_status(tool: &str, feature: &str) -> String {
+    format!("{}: disabled (requires the {} feature)", tool, feature)
+}
+
+fn get_features() -> Vec<&'static str> {
+    let mut features = Vec::new();
+    if cfg!(feature = "chd") {
+        features.push("chd");
+    }
+    if cfg!(feature = "cso") {
+        features.push("cso");
+    }
+    if cfg!(feature = "ird") {
+        features.push("ird");
+    }
+    if cfg!(feature = "pbp") {
+        features.push("pbp");
+    }
+    if cfg!(feature = "rvz") {
+        features.push("rvz");
+    }
+    if cfg!(feature = "zst") {
+        features.push("zst");
+    }
+    if cfg!(feature = "torrentzip") {
+        features.push("torrentzip");
+    }
+    if cfg!(feature = "benchmark") {
+        features.push("benchmark");
+    }
+    if cfg!(feature = "server") {
+        features.push("server");
+    }
+    features
+}
+
+#[cfg(test)]
+mod test_report;
@@ -0,0 +1,31 @@
+use super::super::config::*;
+use super::*;
+use std::env;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    env::set_var("CHDMAN_PATH", "tests/missing-chdman");
+
+    // when
+    let report = get_report();
+
+    // then
+    env::remove_var("CHDMAN_PATH");
+
+    assert!(report[0].starts_with("7z: "));
+    cfg_if! {
+        if #[cfg(feature = "chd")] {
+            assert!(report.contains(&String::from(
+                "chdman not found in PATH (or set CHDMAN_PATH)"
+            )));
+        } else {
+            assert!(report.contains(&String::from(
+                "chdman: disabled (requires the chd feature)"
+            )));
+        }
+    }
+    assert!(report.last().unwrap().starts_with("Features: "));
+}
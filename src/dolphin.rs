@@ -1,11 +1,21 @@
 use super::config::*;
 use super::progress::*;
+use super::util::*;
 use super::SimpleResult;
 use async_std::path::{Path, PathBuf};
 use indicatif::ProgressBar;
 use std::process::Command;
 use std::time::Duration;
 
+const DOLPHIN_TOOL: &str = "dolphin-tool";
+const DOLPHIN_TOOL_PATH: &str = "DOLPHIN_TOOL_PATH";
+
+pub fn check_tool_available() -> SimpleResult<String> {
+    get_cached_tool_version(DOLPHIN_TOOL, DOLPHIN_TOOL_PATH, || {
+        get_tool_version(DOLPHIN_TOOL, DOLPHIN_TOOL_PATH, &[])
+    })
+}
+
 pub fn create_rvz<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     iso_path: &P,
@@ -22,26 +32,31 @@ pub fn create_rvz<P: AsRef<Path>, Q: AsRef<Path>>(
 
     progress_bar.println(format!("Creating {:?}", rvz_path.file_name().unwrap()));
 
-    let output = Command::new("dolphin-tool")
-        .arg("convert")
-        .arg("-f")
-        .arg("rvz")
-        .arg("-c")
-        .arg("zstd")
-        .arg("-l")
-        .arg("5")
-        .arg("-b")
-        .arg("131072")
-        .arg("-i")
-        .arg(iso_path.as_ref())
-        .arg("-o")
-        .arg(&rvz_path)
-        .output()
-        .expect("Failed to create RVZ");
-
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+    write_atomically(progress_bar, &rvz_path, |part_path| {
+        let output = get_tool_output(
+            progress_bar,
+            Command::new(get_tool_path(DOLPHIN_TOOL, DOLPHIN_TOOL_PATH))
+                .arg("convert")
+                .arg("-f")
+                .arg("rvz")
+                .arg("-c")
+                .arg("zstd")
+                .arg("-l")
+                .arg("5")
+                .arg("-b")
+                .arg("131072")
+                .arg("-i")
+                .arg(iso_path.as_ref())
+                .arg("-o")
+                .arg(part_path),
+            DOLPHIN_TOOL,
+            DOLPHIN_TOOL_PATH,
+            "Failed to create RVZ",
+        )?;
+
+        check_tool_status(DOLPHIN_TOOL, &output)?;
+        Ok(())
+    })?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
@@ -68,20 +83,25 @@ pub fn extract_rvz<P: AsRef<Path>, Q: AsRef<Path>>(
         .join(rvz_path.as_ref().file_name().unwrap());
     iso_path.set_extension(ISO_EXTENSION);
 
-    let output = Command::new("dolphin-tool")
-        .arg("convert")
-        .arg("-f")
-        .arg("iso")
-        .arg("-i")
-        .arg(rvz_path.as_ref())
-        .arg("-o")
-        .arg(&iso_path)
-        .output()
-        .expect("Failed to extract RVZ");
-
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+    write_atomically(progress_bar, &iso_path, |part_path| {
+        let output = get_tool_output(
+            progress_bar,
+            Command::new(get_tool_path(DOLPHIN_TOOL, DOLPHIN_TOOL_PATH))
+                .arg("convert")
+                .arg("-f")
+                .arg("iso")
+                .arg("-i")
+                .arg(rvz_path.as_ref())
+                .arg("-o")
+                .arg(part_path),
+            DOLPHIN_TOOL,
+            DOLPHIN_TOOL_PATH,
+            "Failed to extract RVZ",
+        )?;
+
+        check_tool_status(DOLPHIN_TOOL, &output)?;
+        Ok(())
+    })?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
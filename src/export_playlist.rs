@@ -0,0 +1,206 @@
+use super::database::*;
+use super::model::*;
+use super::prompt::*;
+use super::SimpleResult;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use serde::Serialize;
+use sqlx::sqlite::SqliteConnection;
+
+const FORMATS: &[&str] = &["lpl"];
+const LPL_VERSION: &str = "1.5";
+const LPL_DETECT: &str = "DETECT";
+
+#[derive(Serialize)]
+pub struct LplPlaylist {
+    pub version: String,
+    pub default_core_path: String,
+    pub default_core_name: String,
+    pub label_display_mode: i64,
+    pub right_thumbnail_mode: i64,
+    pub left_thumbnail_mode: i64,
+    pub sort_mode: i64,
+    pub items: Vec<LplItem>,
+}
+
+#[derive(Serialize)]
+pub struct LplItem {
+    pub path: String,
+    pub label: String,
+    pub core_path: String,
+    pub core_name: String,
+    pub crc32: String,
+    pub db_name: String,
+}
+
+pub fn subcommand() -> Command {
+    Command::new("export-playlist")
+        .about("Export playlists of complete games for frontends")
+        .arg(
+            Arg::new("FORMAT")
+                .short('f')
+                .long("format")
+                .help("Set the playlist format")
+                .required(false)
+                .num_args(1)
+                .value_parser(PossibleValuesParser::new(FORMATS))
+                .default_value("lpl"),
+        )
+        .arg(
+            Arg::new("OUTPUT")
+                .short('o')
+                .long("output")
+                .help("Set the output directory")
+                .required(false)
+                .num_args(1)
+                .default_value("."),
+        )
+        .arg(
+            Arg::new("CORE_PATH")
+                .long("core-path")
+                .help("Set the core path, {system} is replaced with the system name")
+                .required(false)
+                .num_args(1)
+                .default_value(LPL_DETECT),
+        )
+        .arg(
+            Arg::new("CORE_NAME")
+                .long("core-name")
+                .help("Set the core name")
+                .required(false)
+                .num_args(1)
+                .default_value(LPL_DETECT),
+        )
+        .arg(
+            Arg::new("ALL")
+                .short('a')
+                .long("all")
+                .help("Export all systems")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let output_directory = PathBuf::from(matches.get_one::<String>("OUTPUT").unwrap());
+    let core_path = matches.get_one::<String>("CORE_PATH").unwrap();
+    let core_name = matches.get_one::<String>("CORE_NAME").unwrap();
+    let systems = prompt_for_systems(connection, None, false, matches.get_flag("ALL")).await?;
+    for system in systems {
+        progress_bar.println(format!("Processing \"{}\"", system.name));
+        match matches.get_one::<String>("FORMAT").map(String::as_str) {
+            Some("lpl") | None => {
+                let playlist_path = output_directory.join(format!("{}.lpl", system.name));
+                write_lpl(
+                    connection,
+                    &system,
+                    &playlist_path,
+                    &core_path.replace("{system}", &system.name),
+                    core_name,
+                )
+                .await?;
+                progress_bar.println(format!(
+                    "Exported \"{}\"",
+                    playlist_path.as_os_str().to_str().unwrap()
+                ));
+            }
+            Some(format) => bail!("Unsupported format {}", format),
+        }
+    }
+    Ok(())
+}
+
+pub async fn write_lpl<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    system: &System,
+    playlist_path: &P,
+    core_path: &str,
+    core_name: &str,
+) -> SimpleResult<()> {
+    let playlist = LplPlaylist {
+        version: String::from(LPL_VERSION),
+        default_core_path: core_path.to_owned(),
+        default_core_name: core_name.to_owned(),
+        label_display_mode: 0,
+        right_thumbnail_mode: 0,
+        left_thumbnail_mode: 0,
+        sort_mode: 0,
+        items: get_lpl_items(connection, system, core_path, core_name).await,
+    };
+    let json = try_with!(
+        serde_json::to_string_pretty(&playlist),
+        "Failed to serialize playlist"
+    );
+    try_with!(
+        fs::write(playlist_path.as_ref(), json).await,
+        "Failed to write \"{}\"",
+        playlist_path.as_ref().as_os_str().to_str().unwrap()
+    );
+    Ok(())
+}
+
+async fn get_lpl_items(
+    connection: &mut SqliteConnection,
+    system: &System,
+    core_path: &str,
+    core_name: &str,
+) -> Vec<LplItem> {
+    let games: Vec<Game> = find_games_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .filter(|game| game.complete)
+        .collect();
+    if games.is_empty() {
+        return Vec::new();
+    }
+    let roms = find_roms_with_romfile_by_game_ids(
+        connection,
+        &games.iter().map(|game| game.id).collect::<Vec<i64>>(),
+    )
+    .await;
+    let mut romfile_ids: Vec<i64> = roms.iter().map(|rom| rom.romfile_id.unwrap()).collect();
+    romfile_ids.sort_unstable();
+    romfile_ids.dedup();
+    let romfiles = find_romfiles_by_ids(connection, &romfile_ids).await;
+
+    let db_name = format!("{}.lpl", system.name);
+    let mut items: Vec<LplItem> = Vec::new();
+    for game in &games {
+        let game_roms: Vec<&Rom> = roms.iter().filter(|rom| rom.game_id == game.id).collect();
+        // multi-file games are launched from their cue sheet when there is one
+        let rom = game_roms
+            .iter()
+            .find(|rom| rom.name.to_lowercase().ends_with(".cue"))
+            .or_else(|| game_roms.first());
+        let rom = match rom {
+            Some(rom) => rom,
+            None => continue,
+        };
+        let romfile = romfiles
+            .iter()
+            .find(|romfile| Some(romfile.id) == rom.romfile_id)
+            .unwrap();
+        items.push(LplItem {
+            path: romfile.path.clone(),
+            label: game.name.clone(),
+            core_path: core_path.to_owned(),
+            core_name: core_name.to_owned(),
+            crc32: match &rom.crc {
+                Some(crc) => format!("{}|crc", crc.to_uppercase()),
+                None => String::from(LPL_DETECT),
+            },
+            db_name: db_name.clone(),
+        });
+    }
+    items
+}
+
+#[cfg(test)]
+mod test_lpl;
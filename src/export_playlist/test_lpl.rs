@@ -0,0 +1,88 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::util::get_system_directory;
+use super::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+    let output_directory = TempDir::new_in(&test_directory).unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (Japan).rom");
+    fs::copy(
+        test_directory.join("Test Game (Japan).rom"),
+        &romfile_path.as_path(),
+    )
+    .await
+    .unwrap();
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "export-playlist",
+        "-a",
+        "-o",
+        output_directory.path().to_str().unwrap(),
+        "--core-path",
+        "/cores/{system}_libretro.so",
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let playlist_path = output_directory.path().join("Test System.lpl");
+    let playlist: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(&playlist_path).await.unwrap()).unwrap();
+    assert_eq!(
+        playlist["default_core_path"],
+        "/cores/Test System_libretro.so"
+    );
+    let items = playlist["items"].as_array().unwrap();
+    assert_eq!(items.len(), 1);
+
+    let item = items.get(0).unwrap();
+    assert_eq!(
+        item["path"],
+        system_directory
+            .join("Test Game (Japan).rom")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+    assert_eq!(item["label"], "Test Game (Japan)");
+    assert_eq!(item["crc32"], "310212E8|crc");
+    assert_eq!(item["db_name"], "Test System.lpl");
+}
@@ -0,0 +1,160 @@
+use super::checksum::*;
+use super::config::*;
+use super::database::*;
+use super::model::*;
+use super::prompt::*;
+use super::SimpleResult;
+use async_std::path::Path;
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use digest::Digest;
+use indicatif::ProgressBar;
+use sha1::Sha1;
+use sqlx::sqlite::SqliteConnection;
+use std::collections::HashMap;
+use std::str::FromStr;
+
+const FINGERPRINT_LENGTH: usize = 16;
+
+pub fn subcommand() -> Command {
+    Command::new("fingerprint")
+        .about("Compute a digest identifying the ROMs of a system")
+        .arg(
+            Arg::new("HAVE_ONLY")
+                .short('o')
+                .long("have-only")
+                .help("Only include ROMs you have")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("HASH")
+                .long("hash")
+                .help("Set the hash algorithm, defaults to the system one")
+                .required(false)
+                .num_args(1)
+                .value_parser(PossibleValuesParser::new(HASH_ALGORITHMS)),
+        )
+        .arg(
+            Arg::new("ALL")
+                .short('a')
+                .long("all")
+                .help("Fingerprint all systems")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let systems = prompt_for_systems(connection, None, false, matches.get_flag("ALL")).await?;
+    for system in systems {
+        let hash_algorithm = match matches.get_one::<String>("HASH") {
+            Some(hash_algorithm) => HashAlgorithm::from_str(hash_algorithm)?,
+            None => get_hash_algorithm(connection, &system).await?,
+        };
+        let fingerprint = get_fingerprint(
+            connection,
+            &system,
+            matches.get_flag("HAVE_ONLY"),
+            &hash_algorithm,
+        )
+        .await;
+        progress_bar.println(format!(
+            "{} {} \"{}\"",
+            hash_algorithm.name(),
+            fingerprint,
+            system.name
+        ));
+    }
+    Ok(())
+}
+
+pub async fn get_fingerprint(
+    connection: &mut SqliteConnection,
+    system: &System,
+    have_only: bool,
+    hash_algorithm: &HashAlgorithm,
+) -> String {
+    let game_names_by_id: HashMap<i64, String> = find_games_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .map(|game| (game.id, game.name))
+        .collect();
+
+    // database ids differ between installations, only names and hashes are relevant
+    let mut lines: Vec<String> = find_roms_by_system_id_no_parents(connection, system.id)
+        .await
+        .into_iter()
+        .filter(|rom| !have_only || rom.romfile_id.is_some())
+        .map(|rom| {
+            let hash = match hash_algorithm {
+                HashAlgorithm::Crc => rom.crc,
+                HashAlgorithm::Md5 => rom.md5,
+                HashAlgorithm::Sha1 => rom.sha1,
+            };
+            format!(
+                "{}\t{}\t{}\t{}",
+                game_names_by_id.get(&rom.game_id).unwrap(),
+                rom.name,
+                rom.size,
+                hash.unwrap_or_default()
+            )
+        })
+        .collect();
+    lines.sort();
+
+    digest_lines(&lines)
+}
+
+// unlike the DAT based fingerprint, this one changes when ROM files are altered on disk
+pub async fn get_romfiles_fingerprint(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<String> {
+    let mut romfile_paths: Vec<String> = Vec::new();
+    let mut lines: Vec<String> = Vec::new();
+    for romfile in find_romfiles_by_system_id(connection, system.id).await {
+        if Path::new(&romfile.path).is_file().await {
+            romfile_paths.push(romfile.path);
+        } else {
+            lines.push(format!("{}\tmissing", romfile.path));
+        }
+    }
+    let parallel_hashing = get_parallel_hashing(connection).await;
+    let sizes_and_hashes = get_sizes_and_hashes(
+        progress_bar,
+        &romfile_paths.iter().map(Path::new).collect::<Vec<&Path>>(),
+        hash_algorithm,
+        parallel_hashing,
+    )?;
+
+    lines.extend(
+        romfile_paths
+            .iter()
+            .zip(sizes_and_hashes)
+            .map(|(path, (size, hash))| format!("{}\t{}\t{}", path, size, hash)),
+    );
+    lines.sort();
+
+    Ok(digest_lines(&lines))
+}
+
+fn digest_lines(lines: &[String]) -> String {
+    let mut digest = Sha1::new();
+    for line in lines {
+        digest.update(line.as_bytes());
+        digest.update(b"\n");
+    }
+    let mut fingerprint = format!("{:x}", digest.finalize());
+    fingerprint.truncate(FINGERPRINT_LENGTH);
+    fingerprint
+}
+
+#[cfg(test)]
+mod test_identical_roms;
@@ -0,0 +1,60 @@
+use super::super::import_dats;
+use super::*;
+use async_std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let db_file_a = NamedTempFile::new().unwrap();
+    let pool_a = establish_connection(db_file_a.path().to_str().unwrap()).await;
+    let mut connection_a = pool_a.acquire().await.unwrap();
+
+    let db_file_b = NamedTempFile::new().unwrap();
+    let pool_b = establish_connection(db_file_b.path().to_str().unwrap()).await;
+    let mut connection_b = pool_b.acquire().await.unwrap();
+
+    for connection in [&mut connection_a, &mut connection_b] {
+        let matches = import_dats::subcommand()
+            .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+        import_dats::main(connection, &matches, &progress_bar)
+            .await
+            .unwrap();
+    }
+
+    let system_a = find_systems(&mut connection_a).await.remove(0);
+    let system_b = find_systems(&mut connection_b).await.remove(0);
+
+    // when
+    let fingerprint_a =
+        get_fingerprint(&mut connection_a, &system_a, false, &HashAlgorithm::Md5).await;
+    let fingerprint_b =
+        get_fingerprint(&mut connection_b, &system_b, false, &HashAlgorithm::Md5).await;
+
+    let rom = find_roms_by_system_id_no_parents(&mut connection_b, system_b.id)
+        .await
+        .remove(0);
+    sqlx::query("UPDATE roms SET md5 = ? WHERE id = ?")
+        .bind("00000000000000000000000000000000")
+        .bind(rom.id)
+        .execute(&mut connection_b)
+        .await
+        .unwrap();
+    let changed_fingerprint_b =
+        get_fingerprint(&mut connection_b, &system_b, false, &HashAlgorithm::Md5).await;
+
+    // then
+    assert_eq!(fingerprint_a.len(), FINGERPRINT_LENGTH);
+    assert_eq!(fingerprint_a, fingerprint_b);
+    assert_ne!(fingerprint_a, changed_fingerprint_b);
+}
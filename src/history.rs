@@ -0,0 +1,75 @@
+use super::database::*;
+use super::model::*;
+use super::SimpleResult;
+use clap::{ArgMatches, Command};
+use indicatif::{HumanDuration, ProgressBar};
+use sqlx::sqlite::SqliteConnection;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub fn subcommand() -> Command {
+    Command::new("history").about("Display the history of import-roms and check-roms runs")
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    _matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let lines = get_history(connection).await;
+    if lines.is_empty() {
+        progress_bar.println("No run recorded");
+    }
+    for line in lines {
+        progress_bar.println(line);
+    }
+    Ok(())
+}
+
+pub async fn get_history(connection: &mut SqliteConnection) -> Vec<String> {
+    find_run_history_rows(connection)
+        .await
+        .into_iter()
+        .map(|row| {
+            format!(
+                "{} {} \"{}\": {} files in {}, {}",
+                row.date,
+                row.command,
+                row.system_name,
+                row.files,
+                HumanDuration(Duration::from_millis(row.duration as u64)),
+                row.outcome
+            )
+        })
+        .collect()
+}
+
+// errors are recorded as the outcome, the result is left for the caller to propagate
+pub async fn record_run<T>(
+    connection: &mut SqliteConnection,
+    command: &str,
+    system: &System,
+    files: usize,
+    started_at: SystemTime,
+    result: &SimpleResult<T>,
+) {
+    let outcome = match result {
+        Ok(_) => String::from("success"),
+        Err(error) => error.to_string(),
+    };
+    create_run_history(
+        connection,
+        started_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64,
+        command,
+        system.id,
+        files as i64,
+        started_at.elapsed().unwrap_or_default().as_millis() as i64,
+        &outcome,
+    )
+    .await;
+}
+
+#[cfg(test)]
+mod test_history;
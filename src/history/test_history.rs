@@ -0,0 +1,62 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::import_dats;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let import_directory = tmp_directory.join("import");
+    fs::create_dir_all(&import_directory).await.unwrap();
+    for name in ["Test Game (USA, Europe).rom", "Test Game (Japan).rom"] {
+        fs::copy(test_directory.join(name), import_directory.join(name))
+            .await
+            .unwrap();
+    }
+
+    // when
+    let matches = import_roms::subcommand().get_matches_from(&[
+        "import-roms",
+        "-s",
+        "1",
+        "-q",
+        import_directory.as_os_str().to_str().unwrap(),
+    ]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let rows = find_run_history_rows(&mut connection).await;
+    assert_eq!(rows.len(), 1);
+
+    let row = rows.get(0).unwrap();
+    assert_eq!(row.command, "import-roms");
+    assert_eq!(row.system_name, "Test System");
+    assert_eq!(row.files, 2);
+    assert!(row.duration >= 0 && row.duration < 60_000);
+    assert_eq!(row.outcome, "success");
+}
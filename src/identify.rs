@@ -0,0 +1,365 @@
+#[cfg(feature = "chd")]
+use super::chdman;
+use super::checksum::*;
+use super::config::*;
+use super::database::*;
+#[cfg(feature = "cso")]
+use super::maxcso;
+use super::model::*;
+use super::prompt::*;
+use super::sevenzip;
+use super::util::*;
+use super::SimpleResult;
+use async_std::path::{Path, PathBuf};
+use cfg_if::cfg_if;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use itertools::Itertools;
+use sqlx::sqlite::SqliteConnection;
+use std::ffi::OsString;
+use std::str::FromStr;
+use tempfile::TempDir;
+
+pub fn subcommand() -> Command {
+    Command::new("identify")
+        .about("Identify ROM files against the DATs without importing them")
+        .arg(
+            Arg::new("ROMS")
+                .help("Set the ROM files to identify")
+                .required(true)
+                .num_args(1..)
+                .index(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Set the system number to use")
+                .required(false)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("AUTO_SYSTEM")
+                .long("auto-system")
+                .help("Identify every file against the systems listing a ROM of its size")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("SYSTEM"),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let romfile_paths: Vec<&PathBuf> = matches.get_many::<PathBuf>("ROMS").unwrap().collect();
+    let system = if matches.get_flag("AUTO_SYSTEM") {
+        None
+    } else {
+        Some(
+            prompt_for_system(
+                connection,
+                matches
+                    .get_one::<String>("SYSTEM")
+                    .map(|s| FromStr::from_str(s).expect("Failed to parse number")),
+            )
+            .await?,
+        )
+    };
+    check_tools_available_for_files(&romfile_paths, false)?;
+    for romfile_path in romfile_paths {
+        for (name, mut games_roms) in
+            identify_romfile(connection, progress_bar, system.as_ref(), romfile_path).await?
+        {
+            match games_roms.len() {
+                0 => progress_bar.println(format!("{:?}: unknown", name)),
+                1 => {
+                    let (game, rom) = games_roms.remove(0);
+                    let line = describe_match(connection, &game, &rom).await;
+                    progress_bar.println(format!("{:?}: {}", name, line));
+                }
+                // the same content is listed more than once, let the user tell them apart
+                _ => {
+                    progress_bar.println(format!(
+                        "{:?}: ambiguous, matches {} ROMs",
+                        name,
+                        games_roms.len()
+                    ));
+                    for (game, rom) in games_roms {
+                        let line = describe_match(connection, &game, &rom).await;
+                        progress_bar.println(format!("    {}", line));
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn describe_match(connection: &mut SqliteConnection, game: &Game, rom: &Rom) -> String {
+    format!(
+        "\"{}\" (\"{}\") in \"{}\"{}",
+        game.name,
+        rom.name,
+        find_system_by_id(connection, game.system_id).await.name,
+        if rom.romfile_id.is_some() {
+            ", already imported"
+        } else {
+            ""
+        }
+    )
+}
+
+// archives and disc images are identified by their contents, like import-roms does
+struct Member {
+    name: String,
+    size: u64,
+    // reported by the archive, saves extracting the member
+    crc: Option<String>,
+    path: Option<PathBuf>,
+}
+
+pub async fn identify_romfile<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: Option<&System>,
+    romfile_path: &P,
+) -> SimpleResult<Vec<(String, Vec<(Game, Rom)>)>> {
+    // identify runs on a read-only connection
+    let tmp_directory = try_with!(
+        TempDir::new_in(find_tmp_directory(connection).await),
+        "Failed to create temp directory"
+    );
+    let romfile_name = romfile_path
+        .as_ref()
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_string();
+    let romfile_extension = romfile_path
+        .as_ref()
+        .extension()
+        .unwrap_or(&OsString::new())
+        .to_str()
+        .unwrap()
+        .to_lowercase();
+    let mut romfile_member = Member {
+        name: romfile_name.clone(),
+        size: try_with!(
+            romfile_path.as_ref().metadata().await,
+            "Failed to read {:?}",
+            romfile_path.as_ref()
+        )
+        .len(),
+        crc: None,
+        path: Some(romfile_path.as_ref().to_path_buf()),
+    };
+
+    let is_archive = ARCHIVE_EXTENSIONS.contains(&romfile_extension.as_str());
+    let mut members: Vec<Member> = if is_archive {
+        sevenzip::parse_archive(progress_bar, romfile_path)?
+            .into_iter()
+            .map(|sevenzip_info| Member {
+                name: sevenzip_info.path,
+                size: sevenzip_info.size,
+                crc: Some(sevenzip_info.crc).filter(|crc| !crc.is_empty()),
+                path: None,
+            })
+            .collect()
+    } else if CHD_EXTENSION == romfile_extension {
+        cfg_if! {
+            if #[cfg(feature = "chd")] {
+                let extracted_path = if chdman::is_dvd_chd(progress_bar, romfile_path)? {
+                    chdman::extract_chd_to_iso(progress_bar, romfile_path, &tmp_directory.path())?
+                } else {
+                    chdman::extract_chd_to_single_track(
+                        progress_bar,
+                        romfile_path,
+                        &tmp_directory.path(),
+                    )
+                    .await?
+                };
+                vec![get_extracted_member(extracted_path).await?]
+            } else {
+                progress_bar.println("Please rebuild with the CHD feature enabled");
+                return Ok(Vec::new());
+            }
+        }
+    } else if CSO_EXTENSION == romfile_extension || ZSO_EXTENSION == romfile_extension {
+        cfg_if! {
+            if #[cfg(feature = "cso")] {
+                let extracted_path = if CSO_EXTENSION == romfile_extension {
+                    maxcso::extract_cso(progress_bar, romfile_path, &tmp_directory.path())?
+                } else {
+                    maxcso::extract_zso(progress_bar, romfile_path, &tmp_directory.path())?
+                };
+                vec![get_extracted_member(extracted_path).await?]
+            } else {
+                progress_bar.println("Please rebuild with the CSO feature enabled");
+                return Ok(Vec::new());
+            }
+        }
+    } else {
+        Vec::new()
+    };
+
+    let size_systems: Vec<System>;
+    let systems: Vec<&System> = match system {
+        Some(system) => vec![system],
+        None => {
+            // systems listing a ROM the size of the file or of anything it holds
+            let mut systems: Vec<System> = Vec::new();
+            let sizes = members
+                .iter()
+                .map(|member| member.size)
+                .chain([romfile_member.size]);
+            for size in sizes.unique() {
+                for system in find_systems_by_rom_size(connection, size).await {
+                    if !systems.iter().any(|other| other.id == system.id) {
+                        systems.push(system);
+                    }
+                }
+            }
+            size_systems = systems;
+            size_systems.iter().collect()
+        }
+    };
+
+    let mut names_games_roms: Vec<(String, Vec<(Game, Rom)>)> = members
+        .iter()
+        .map(|member| (format!("{}/{}", romfile_name, member.name), Vec::new()))
+        .collect();
+    let mut romfile_games_roms: Vec<(Game, Rom)> = Vec::new();
+    for system in systems {
+        let header = find_header_by_system_id(connection, system.id).await;
+        let hash_algorithm = get_hash_algorithm(connection, system).await?;
+        // some DATs hash the archive itself rather than its contents
+        if members.is_empty() || (is_archive && system.archive_is_rom) {
+            let roms = find_roms_by_member(
+                connection,
+                progress_bar,
+                romfile_path,
+                &tmp_directory,
+                &mut romfile_member,
+                system,
+                &header,
+                &hash_algorithm,
+            )
+            .await?;
+            romfile_games_roms.append(&mut get_games_roms(connection, roms).await);
+            continue;
+        }
+        for (member, (_, games_roms)) in members.iter_mut().zip(names_games_roms.iter_mut()) {
+            let roms = find_roms_by_member(
+                connection,
+                progress_bar,
+                romfile_path,
+                &tmp_directory,
+                member,
+                system,
+                &header,
+                &hash_algorithm,
+            )
+            .await?;
+            games_roms.append(&mut get_games_roms(connection, roms).await);
+        }
+    }
+    if members.is_empty() || !romfile_games_roms.is_empty() {
+        names_games_roms.insert(0, (romfile_name, romfile_games_roms));
+    }
+    Ok(names_games_roms)
+}
+
+#[cfg(any(feature = "chd", feature = "cso"))]
+async fn get_extracted_member(extracted_path: PathBuf) -> SimpleResult<Member> {
+    Ok(Member {
+        name: extracted_path
+            .file_name()
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string(),
+        size: try_with!(
+            extracted_path.metadata().await,
+            "Failed to read {:?}",
+            extracted_path
+        )
+        .len(),
+        crc: None,
+        path: Some(extracted_path),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn find_roms_by_member<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile_path: &P,
+    tmp_directory: &TempDir,
+    member: &mut Member,
+    system: &System,
+    header: &Option<Header>,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<Vec<Rom>> {
+    let (size, hash) = match &member.crc {
+        Some(crc) if header.is_none() && hash_algorithm == &HashAlgorithm::Crc => {
+            (member.size, crc.clone())
+        }
+        _ => {
+            // archive members are only extracted when their crc isn't enough
+            if member.path.is_none() {
+                member.path = Some(
+                    sevenzip::extract_files_from_archive(
+                        progress_bar,
+                        romfile_path,
+                        &[&member.name],
+                        &tmp_directory.path(),
+                    )?
+                    .remove(0),
+                );
+            }
+            get_size_and_hash(
+                connection,
+                progress_bar,
+                member.path.as_ref().unwrap(),
+                header,
+                1,
+                1,
+                hash_algorithm,
+            )
+            .await?
+        }
+    };
+    match hash_algorithm {
+        HashAlgorithm::Crc => {
+            find_roms_by_size_and_crc_and_system_id(connection, size, &hash, system.id).await
+        }
+        HashAlgorithm::Md5 => {
+            find_roms_by_size_and_md5_and_system_id(connection, size, &hash, system.id).await
+        }
+        HashAlgorithm::Sha1 => {
+            find_roms_by_size_and_sha1_and_system_id(connection, size, &hash, system.id).await
+        }
+    }
+}
+
+async fn get_games_roms(connection: &mut SqliteConnection, roms: Vec<Rom>) -> Vec<(Game, Rom)> {
+    let mut games_roms: Vec<(Game, Rom)> = Vec::new();
+    for rom in roms {
+        let game = find_game_by_id(connection, rom.game_id).await;
+        games_roms.push((game, rom));
+    }
+    games_roms
+}
+
+#[cfg(test)]
+mod test_identify;
+#[cfg(test)]
+mod test_identify_ambiguous;
+#[cfg(test)]
+mod test_identify_read_only;
+#[cfg(test)]
+mod test_zip;
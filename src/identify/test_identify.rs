@@ -0,0 +1,69 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (Japan).rom");
+    fs::copy(test_directory.join("Test Game (Japan).rom"), &romfile_path)
+        .await
+        .unwrap();
+    let unknown_path = tmp_directory.join("Unknown.rom");
+    fs::write(&unknown_path, vec![0x42; 256]).await.unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    // when
+    let identified = identify_romfile(&mut connection, &progress_bar, Some(&system), &romfile_path)
+        .await
+        .unwrap();
+    let auto_identified = identify_romfile(&mut connection, &progress_bar, None, &romfile_path)
+        .await
+        .unwrap();
+    let unknown = identify_romfile(&mut connection, &progress_bar, None, &unknown_path)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(identified.len(), 1);
+    let (name, games_roms) = identified.first().unwrap();
+    assert_eq!(name, "Test Game (Japan).rom");
+    assert_eq!(games_roms.len(), 1);
+    let (game, rom) = games_roms.first().unwrap();
+    assert_eq!(game.name, "Test Game (Japan)");
+    assert_eq!(rom.name, "Test Game (Japan).rom");
+
+    assert_eq!(auto_identified.len(), 1);
+    let (game, _) = auto_identified[0].1.first().unwrap();
+    assert_eq!(game.name, "Test Game (Japan)");
+
+    assert_eq!(unknown.len(), 1);
+    assert!(unknown[0].1.is_empty());
+
+    assert!(romfile_path.is_file().await);
+    assert!(unknown_path.is_file().await);
+    assert!(find_romfiles(&mut connection).await.is_empty());
+}
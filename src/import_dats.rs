@@ -1,8 +1,10 @@
 use super::checksum::*;
+use super::config::*;
 use super::database::*;
-use super::import_roms::import_rom;
+use super::import_roms::{import_rom, ImportOptions};
 use super::model::*;
 use super::progress::*;
+use super::sevenzip;
 use super::util::*;
 use super::SimpleResult;
 use async_std::path::Path;
@@ -17,6 +19,7 @@ use shiratsu_naming::naming::nointro::{NoIntroName, NoIntroToken};
 use shiratsu_naming::naming::TokenizedName;
 use shiratsu_naming::region::Region;
 use sqlx::sqlite::SqliteConnection;
+use std::ffi::OsString;
 use std::io;
 use std::path::PathBuf;
 use std::str;
@@ -73,6 +76,14 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("NAME")
+                .short('n')
+                .long("name")
+                .help("Override the system name found in the DAT file")
+                .required(false)
+                .num_args(1),
+        )
 }
 
 pub async fn main(
@@ -81,25 +92,49 @@ pub async fn main(
     progress_bar: &ProgressBar,
 ) -> SimpleResult<()> {
     let dat_paths: Vec<&PathBuf> = matches.get_many::<PathBuf>("DATS").unwrap().collect();
+    if matches.contains_id("NAME") && dat_paths.len() > 1 {
+        bail!("--name can only be used with a single DAT file");
+    }
+    // compressed DAT files are extracted with 7z
+    if dat_paths.iter().any(|dat_path| {
+        dat_path
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase())
+            .is_some_and(|extension| {
+                ARCHIVE_EXTENSIONS.contains(&extension.as_str()) || GZIP_EXTENSION == extension
+            })
+    }) {
+        sevenzip::check_tool_available()?;
+    }
 
     for dat_path in dat_paths {
         progress_bar.println(&format!("Processing \"{:?}\"", &dat_path));
-        let (datfile_xml, detector_xml) = parse_dat(
-            progress_bar,
-            &get_canonicalized_path(&dat_path).await?,
-            matches.get_flag("SKIP_HEADER"),
-        )
-        .await?;
-        if !matches.get_flag("INFO") {
-            import_dat(
-                connection,
+        let dat_path = get_canonicalized_path(&dat_path).await?;
+        let dat_extension = dat_path
+            .extension()
+            .unwrap_or(&OsString::new())
+            .to_str()
+            .unwrap()
+            .to_lowercase();
+        // extract compressed DAT files first
+        if ARCHIVE_EXTENSIONS.contains(&dat_extension.as_str()) || GZIP_EXTENSION == dat_extension {
+            let tmp_directory = create_tmp_directory(connection).await?;
+            sevenzip::extract_files_from_archive(
                 progress_bar,
-                &datfile_xml,
-                &detector_xml,
-                matches.get_flag("ARCADE"),
-                matches.get_flag("FORCE"),
-            )
-            .await?;
+                &dat_path,
+                &[],
+                &tmp_directory.path(),
+            )?;
+            let extracted_paths = find_extracted_dat_paths(&tmp_directory.path())?;
+            if extracted_paths.is_empty() {
+                progress_bar.println("No DAT file found in archive");
+            }
+            for extracted_path in extracted_paths {
+                process_dat(connection, matches, progress_bar, &extracted_path).await?;
+            }
+        } else {
+            process_dat(connection, matches, progress_bar, &dat_path).await?;
         }
         progress_bar.println("");
     }
@@ -107,6 +142,67 @@ pub async fn main(
     Ok(())
 }
 
+async fn process_dat<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+    dat_path: &P,
+) -> SimpleResult<()> {
+    let (mut datfile_xml, detector_xml) =
+        parse_dat(progress_bar, dat_path, matches.get_flag("SKIP_HEADER")).await?;
+    // an existing system with the same name is updated like any other
+    if let Some(name) = matches.get_one::<String>("NAME") {
+        datfile_xml.system.name = name.to_owned();
+    }
+    if !matches.get_flag("INFO") {
+        import_dat(
+            connection,
+            progress_bar,
+            &datfile_xml,
+            &detector_xml,
+            matches.get_flag("ARCADE"),
+            matches.get_flag("FORCE"),
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+fn find_extracted_dat_paths<P: AsRef<std::path::Path>>(
+    directory: &P,
+) -> SimpleResult<Vec<PathBuf>> {
+    let entries = try_with!(
+        std::fs::read_dir(directory.as_ref()),
+        "Failed to read {:?}",
+        directory.as_ref()
+    );
+    let mut dat_paths: Vec<PathBuf> = Vec::new();
+    let mut xml_paths: Vec<PathBuf> = Vec::new();
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+        if !path.is_file() {
+            continue;
+        }
+        let extension = path
+            .extension()
+            .unwrap_or(&OsString::new())
+            .to_str()
+            .unwrap()
+            .to_lowercase();
+        if extension == DAT_EXTENSION {
+            dat_paths.push(path);
+        } else if extension == XML_EXTENSION {
+            xml_paths.push(path);
+        }
+    }
+    // XML files are most likely header definitions when proper DAT files are present
+    if dat_paths.is_empty() {
+        dat_paths = xml_paths;
+    }
+    dat_paths.sort();
+    Ok(dat_paths)
+}
+
 pub async fn parse_dat<P: AsRef<Path>>(
     progress_bar: &ProgressBar,
     dat_path: &P,
@@ -180,6 +276,14 @@ pub async fn import_dat(
         create_or_update_header(&mut transaction, detector_xml, system_id).await;
     }
 
+    // recommend a hash algorithm when the DAT doesn't provide all of them
+    update_system_recommended_hash_algorithm(
+        &mut transaction,
+        system_id,
+        get_recommended_hash_algorithm(&datfile_xml.games),
+    )
+    .await;
+
     // persist games
     progress_bar.set_style(get_count_progress_style());
     progress_bar.set_length(datfile_xml.games.len() as u64);
@@ -236,6 +340,36 @@ pub async fn import_dat(
     Ok(())
 }
 
+fn get_recommended_hash_algorithm(games_xml: &[GameXml]) -> Option<&'static str> {
+    let roms_xml: Vec<&RomXml> = games_xml
+        .iter()
+        .flat_map(|game_xml| &game_xml.roms)
+        .filter(|rom_xml| rom_xml.status.as_deref() != Some("nodump"))
+        .collect();
+    let is_covered = |hash: fn(&RomXml) -> &Option<String>| {
+        roms_xml.iter().all(|rom_xml| {
+            hash(rom_xml)
+                .as_deref()
+                .is_some_and(|hash| !hash.is_empty())
+        })
+    };
+    let covered = [
+        is_covered(|rom_xml| &rom_xml.crc),
+        is_covered(|rom_xml| &rom_xml.md5),
+        is_covered(|rom_xml| &rom_xml.sha1),
+    ];
+    // the global setting works whatever it is, don't second guess it
+    if roms_xml.is_empty() || covered.iter().all(|&covered| covered) {
+        return None;
+    }
+    // otherwise pick the fastest algorithm that covers every ROM
+    HASH_ALGORITHMS
+        .iter()
+        .zip(covered)
+        .find(|(_, covered)| *covered)
+        .map(|(hash_algorithm, _)| *hash_algorithm)
+}
+
 fn get_regions_from_game_name(name: &str) -> SimpleResult<String> {
     let name = try_with!(
         NoIntroName::try_parse(name),
@@ -256,7 +390,8 @@ async fn create_or_update_system(
     arcade: bool,
     force: bool,
 ) -> Option<i64> {
-    match find_system_by_name(connection, &system_xml.name).await {
+    // DAT renames sometimes only change the capitalization of the system name
+    match find_system_by_name_case_insensitive(connection, &system_xml.name).await {
         Some(system) => {
             if is_update(progress_bar, &system.version, &system_xml.version) || force {
                 update_system_from_xml(connection, system.id, system_xml, arcade).await;
@@ -282,8 +417,11 @@ async fn create_or_update_header(
         }
         None => create_header_from_xml(connection, detector_xml, system_id).await,
     };
-    for data_xml in &detector_xml.rule.data {
-        create_rule_from_xml(connection, data_xml, header_id).await;
+    for (rule_index, rule_xml) in detector_xml.rules.iter().enumerate() {
+        for data_xml in &rule_xml.data {
+            create_rule_from_xml(connection, rule_index as i64, rule_xml, data_xml, header_id)
+                .await;
+        }
     }
 }
 
@@ -490,7 +628,7 @@ async fn create_or_update_roms(
             Some(rom) => {
                 update_rom_from_xml(connection, rom.id, rom_xml, bios, game_id, parent_id).await;
                 if rom_xml.size != rom.size
-                    || rom_xml.crc.as_ref().unwrap() != rom.crc.as_ref().unwrap()
+                    || rom_xml.crc.as_ref().map(|crc| crc.to_lowercase()) != rom.crc
                 {
                     if let Some(romfile_id) = rom.romfile_id {
                         orphan_romfile_ids.push(romfile_id);
@@ -561,9 +699,16 @@ pub async fn reimport_orphan_romfiles(
 ) -> SimpleResult<()> {
     let system = find_system_by_id(connection, system_id).await;
     let header = find_header_by_system_id(connection, system_id).await;
+    let mut romfiles: Vec<Romfile> = Vec::new();
     for romfile_id in orphan_romfile_ids {
-        let romfile = find_romfile_by_id(connection, romfile_id).await;
-        delete_romfile_by_id(connection, romfile_id).await;
+        romfiles.push(find_romfile_by_id(connection, romfile_id).await);
+    }
+    check_tools_available_for_files(
+        romfiles.iter().map(|romfile| &romfile.path),
+        system.archive_is_rom,
+    )?;
+    for romfile in romfiles {
+        delete_romfile_by_id(connection, romfile.id).await;
         import_rom(
             connection,
             progress_bar,
@@ -571,6 +716,7 @@ pub async fn reimport_orphan_romfiles(
             &header,
             &Path::new(&romfile.path),
             hash_algorithm,
+            &ImportOptions::default(),
         )
         .await?;
     }
@@ -580,6 +726,8 @@ pub async fn reimport_orphan_romfiles(
 #[cfg(test)]
 mod test_dat;
 #[cfg(test)]
+mod test_dat_gzip;
+#[cfg(test)]
 mod test_dat_headered;
 #[cfg(test)]
 mod test_dat_headered_duplicate_clrmamepro;
@@ -588,14 +736,26 @@ mod test_dat_headered_embedded;
 #[cfg(test)]
 mod test_dat_headered_skipped_header;
 #[cfg(test)]
+mod test_dat_name_override;
+#[cfg(test)]
 mod test_dat_outdated_forced;
 #[cfg(test)]
 mod test_dat_outdated_should_do_nothing;
 #[cfg(test)]
+mod test_dat_oversized_rom_should_fail;
+#[cfg(test)]
 mod test_dat_parent_clone;
 #[cfg(test)]
+mod test_dat_recommended_hash_algorithm;
+#[cfg(test)]
 mod test_dat_updated;
 #[cfg(test)]
+mod test_dat_updated_case_insensitive;
+#[cfg(test)]
+mod test_dat_updated_removed_game;
+#[cfg(test)]
+mod test_dat_zip;
+#[cfg(test)]
 mod test_regions_france_germany;
 #[cfg(test)]
 mod test_regions_world;
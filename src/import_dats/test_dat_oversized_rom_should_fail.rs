@@ -0,0 +1,39 @@
+use super::super::database::*;
+use super::*;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20230107) (Oversized ROM).dat",
+    ]);
+
+    // when
+    let result = main(&mut connection, &matches, &progress_bar).await;
+
+    // then
+    let error = result.err().unwrap();
+    assert!(error
+        .as_str()
+        .contains("invalid ROM size \"18446744073709551615\""));
+
+    let systems = find_systems(&mut connection).await;
+    assert!(systems.is_empty());
+}
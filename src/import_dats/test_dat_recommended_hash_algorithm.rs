@@ -0,0 +1,71 @@
+use super::super::config::*;
+use super::super::database::*;
+use super::super::import_roms;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let dat_path = test_directory.join("Test System (20221229) (SHA1 Only).dat");
+    let (datfile_xml, detector_xml) = parse_dat(&progress_bar, &dat_path, false).await.unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    // when
+    import_dat(
+        &mut connection,
+        &progress_bar,
+        &datfile_xml,
+        &detector_xml,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let system = find_systems(&mut connection).await.remove(0);
+    assert_eq!(system.recommended_hash_algorithm.as_deref(), Some("SHA1"));
+    assert_eq!(
+        get_string(&mut connection, "HASH_ALGORITHM")
+            .await
+            .as_deref(),
+        Some("CRC")
+    );
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    assert_eq!(roms.first().unwrap().name, "Test Game (USA, Europe).rom");
+
+    let romfiles = find_romfiles_in_trash(&mut connection).await;
+    assert!(romfiles.is_empty());
+}
@@ -1,4 +1,3 @@
-use super::super::config::*;
 use super::super::database::*;
 use super::*;
 use async_std::fs;
@@ -58,6 +57,7 @@ async fn test() {
             &None,
             &romfile_path,
             &HashAlgorithm::Crc,
+            &ImportOptions::default(),
         )
         .await
         .unwrap();
@@ -0,0 +1,64 @@
+use super::super::database::*;
+use super::*;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let dat_path = test_directory.join("Test System (20200721).dat");
+    let (datfile_xml, detector_xml) = parse_dat(&progress_bar, &dat_path, false).await.unwrap();
+
+    import_dat(
+        &mut connection,
+        &progress_bar,
+        &datfile_xml,
+        &detector_xml,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let dat_path = test_directory.join("Test System (20210401).dat");
+    let (mut datfile_xml, detector_xml) = parse_dat(&progress_bar, &dat_path, false).await.unwrap();
+    datfile_xml.system.name = String::from("TEST system ");
+
+    // when
+    import_dat(
+        &mut connection,
+        &progress_bar,
+        &datfile_xml,
+        &detector_xml,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let systems = find_systems(&mut connection).await;
+    assert_eq!(systems.len(), 1);
+
+    let system = systems.get(0).unwrap();
+    assert_eq!(system.name, "TEST system ");
+    assert_eq!(system.version, "20210401");
+
+    let games = find_games(&mut connection).await;
+    assert_eq!(games.len(), 3);
+    assert!(games.iter().all(|game| game.system_id == system.id));
+}
@@ -0,0 +1,105 @@
+use super::super::database::*;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let dat_path = test_directory.join("Test System (20200721).dat");
+    let (datfile_xml, detector_xml) = parse_dat(&progress_bar, &dat_path, false).await.unwrap();
+
+    import_dat(
+        &mut connection,
+        &progress_bar,
+        &datfile_xml,
+        &detector_xml,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    let romfile_names = vec!["Test Game (Asia).rom", "Test Game (USA, Europe).rom"];
+    for romfile_name in romfile_names {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(
+            test_directory.join(romfile_name),
+            &romfile_path.as_os_str().to_str().unwrap(),
+        )
+        .await
+        .unwrap();
+        import_rom(
+            &mut connection,
+            &progress_bar,
+            &system,
+            &None,
+            &romfile_path,
+            &HashAlgorithm::Crc,
+            &ImportOptions::default(),
+        )
+        .await
+        .unwrap();
+    }
+
+    let dat_path = test_directory.join("Test System (20230110) (Removed Game).dat");
+    let (datfile_xml, detector_xml) = parse_dat(&progress_bar, &dat_path, false).await.unwrap();
+
+    // when
+    import_dat(
+        &mut connection,
+        &progress_bar,
+        &datfile_xml,
+        &detector_xml,
+        false,
+        false,
+    )
+    .await
+    .unwrap();
+
+    // then
+    let games = find_games(&mut connection).await;
+    assert_eq!(games.len(), 5);
+    assert!(games.iter().all(|game| game.name != "Test Game (Asia)"));
+    assert!(find_roms(&mut connection)
+        .await
+        .iter()
+        .all(|rom| rom.name != "Test Game (Asia).rom"));
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(
+        romfile.path,
+        get_system_directory(&mut connection, &progress_bar, &system)
+            .await
+            .unwrap()
+            .join("Test Game (USA, Europe).rom")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+
+    let romfile = romfiles.get(1).unwrap();
+    assert!(romfile.path.contains("/Trash/"));
+    assert!(romfile.path.ends_with("Test Game (Asia).rom"));
+    assert!(Path::new(&romfile.path).is_file().await);
+}
@@ -5,36 +5,109 @@ use super::config::*;
 use super::database::*;
 #[cfg(feature = "rvz")]
 use super::dolphin;
+use super::history::record_run;
+use super::iso9660;
 #[cfg(feature = "cso")]
 use super::maxcso;
 use super::model::*;
+#[cfg(feature = "pbp")]
+use super::pbp;
 use super::progress::*;
 use super::prompt::*;
 use super::sevenzip;
+#[cfg(feature = "torrentzip")]
+use super::torrentzip;
+use super::util;
 use super::util::*;
+#[cfg(feature = "zst")]
+use super::zst;
 use super::SimpleResult;
+use async_std::fs;
 use async_std::path::Path;
 use cfg_if::cfg_if;
 use clap::builder::PossibleValuesParser;
-use clap::{Arg, ArgMatches, Command};
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use indicatif::ProgressBar;
+use lazy_static::lazy_static;
+use num_traits::FromPrimitive;
 use rayon::prelude::*;
+use regex::Regex;
 use sqlx::sqlite::SqliteConnection;
+use sqlx::{Sqlite, Transaction};
+use std::cell::{Cell, RefCell};
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::ffi::OsString;
+use std::io::{self, Read};
 use std::path::PathBuf;
 use std::str::FromStr;
-use std::time::Duration;
-#[cfg(feature = "ird")]
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, SystemTime};
+use tempfile::TempDir;
+use tracing::{debug, info, instrument, warn};
 use walkdir::WalkDir;
 
+const UNTRACKED_DESTINATIONS: &[&str] = &["SKIP", "ADOPT", "OVERWRITE"];
+const STATUS_INTERVAL: u64 = 60;
+const MATCH_RATE_GUARD_FILES: usize = 5;
+
+#[cfg(test)]
+static PROBED_SYSTEMS: AtomicUsize = AtomicUsize::new(0);
+
+lazy_static! {
+    static ref CUE_FILE_REGEX: Regex = Regex::new(r#"(?is)^(\s*FILE\s+)"([^"]*)"(.*)$"#).unwrap();
+}
+
+// set from the command line, other callers of import_rom get the defaults
+#[derive(Clone)]
+pub struct ImportOptions {
+    pub limit: Option<usize>,
+    pub crc_only_fast: bool,
+    pub status_interval: Option<Duration>,
+    pub recursive: bool,
+    pub mirror_directory: Option<PathBuf>,
+    pub assume_yes: bool,
+    pub remember_matches: bool,
+    pub strict_size: bool,
+    pub snapshot: bool,
+    pub repair_cue: bool,
+    pub unmatched_directory: Option<PathBuf>,
+    pub trash_preserve_structure: bool,
+    pub dry_run: bool,
+    // set while walking a directory, trashed files keep their path relative to it
+    pub source_directory: Option<PathBuf>,
+}
+
+// directories are walked recursively unless told otherwise, like on the command line
+impl Default for ImportOptions {
+    fn default() -> Self {
+        ImportOptions {
+            limit: None,
+            crc_only_fast: false,
+            status_interval: None,
+            recursive: true,
+            mirror_directory: None,
+            assume_yes: false,
+            remember_matches: false,
+            strict_size: false,
+            snapshot: false,
+            repair_cue: false,
+            unmatched_directory: None,
+            trash_preserve_structure: false,
+            dry_run: false,
+            source_directory: None,
+        }
+    }
+}
+
 pub fn subcommand() -> Command {
     Command::new("import-roms")
         .about("Validate and import ROM files or directories into oxyromon")
         .arg(
             Arg::new("ROMS")
                 .help("Set the ROM files or directories to import")
-                .required(true)
+                .required_unless_present("STDIN_TAR")
                 .num_args(1..)
                 .index(1)
                 .value_parser(value_parser!(PathBuf)),
@@ -47,6 +120,30 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .num_args(1),
         )
+        .arg(
+            Arg::new("SYSTEM_FROM_DIR")
+                .long("system-from-dir")
+                .help("Import every file into the system named after its parent directory")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["SYSTEM", "STDIN_TAR"]),
+        )
+        .arg(
+            Arg::new("AUTO_SYSTEM")
+                .long("auto-system")
+                .help("Import every file into the system with a matching ROM")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all(["SYSTEM", "SYSTEM_FROM_DIR", "STDIN_TAR"]),
+        )
+        .arg(
+            Arg::new("DUPLICATE_ACROSS_SYSTEMS")
+                .long("duplicate-across-systems")
+                .help("Import files matching several systems into all of them instead of prompting")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .requires("AUTO_SYSTEM"),
+        )
         .arg(
             Arg::new("HASH")
                 .short('a')
@@ -56,6 +153,120 @@ pub fn subcommand() -> Command {
                 .num_args(1)
                 .value_parser(PossibleValuesParser::new(HASH_ALGORITHMS)),
         )
+        .arg(
+            Arg::new("LIMIT")
+                .short('l')
+                .long("limit")
+                .help("Stop after processing N files")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(usize)),
+        )
+        .arg(
+            Arg::new("CRC_ONLY_FAST")
+                .long("crc-only-fast")
+                .help("Trust the CRCs reported by archives and never extract them")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("STRICT_SIZE")
+                .long("strict-size")
+                .help("Reject matches whose actual size differs from the ROM size")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("CRC_ONLY_FAST"),
+        )
+        .arg(
+            Arg::new("STATUS_INTERVAL")
+                .long("status-interval")
+                .help("Print a status line every N seconds")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(u64)),
+        )
+        .arg(
+            Arg::new("QUIET")
+                .short('q')
+                .long("quiet")
+                .help("Don't print status lines")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("NO_RECURSIVE")
+                .long("no-recursive")
+                .help("Only process the top-level files of directories")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("REMEMBER")
+                .long("remember")
+                .help("Remember the choices made for ambiguous matches")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("SNAPSHOT")
+                .long("snapshot")
+                .help("Create a game for every file instead of matching them against the DAT")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("STDIN_TAR")
+                .long("stdin-tar")
+                .help("Read the ROM files to import from a tar stream on stdin")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .conflicts_with("ROMS"),
+        )
+        .arg(
+            Arg::new("REPAIR_CUE")
+                .long("repair-cue")
+                .help("Point the FILE references of imported CUE files to the stored BIN names")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("YES")
+                .short('y')
+                .long("yes")
+                .help("Automatically say yes to prompts")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("MIRROR_LOOSE")
+                .long("mirror-loose")
+                .help("Also extract imported archives into an untracked directory")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("REPORT_UNMATCHED_DIR")
+                .long("report-unmatched-dir")
+                .help("Move files matching no ROM to a review directory instead of the trash")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("TRASH_PRESERVE_STRUCTURE")
+                .long("trash-preserve-structure")
+                .help("Recreate the source directory structure in the trash")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("DRY_RUN")
+                .long("dry-run")
+                .help("Print what would be done without moving files or updating the database")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
 }
 
 pub async fn main(
@@ -63,7 +274,86 @@ pub async fn main(
     matches: &ArgMatches,
     progress_bar: &ProgressBar,
 ) -> SimpleResult<()> {
-    let romfile_paths: Vec<&PathBuf> = matches.get_many::<PathBuf>("ROMS").unwrap().collect();
+    let romfile_paths: Vec<&PathBuf> = matches
+        .get_many::<PathBuf>("ROMS")
+        .map(|romfile_paths| romfile_paths.collect())
+        .unwrap_or_default();
+    let status_interval = if matches.get_flag("QUIET") {
+        None
+    } else {
+        Some(Duration::from_secs(
+            matches
+                .get_one::<u64>("STATUS_INTERVAL")
+                .copied()
+                .unwrap_or(STATUS_INTERVAL),
+        ))
+    };
+    let options = ImportOptions {
+        limit: matches.get_one::<usize>("LIMIT").copied(),
+        crc_only_fast: matches.get_flag("CRC_ONLY_FAST"),
+        status_interval,
+        recursive: !matches.get_flag("NO_RECURSIVE"),
+        mirror_directory: matches.get_one::<PathBuf>("MIRROR_LOOSE").cloned(),
+        assume_yes: matches.get_flag("YES"),
+        remember_matches: matches.get_flag("REMEMBER"),
+        strict_size: matches.get_flag("STRICT_SIZE"),
+        snapshot: matches.get_flag("SNAPSHOT"),
+        repair_cue: matches.get_flag("REPAIR_CUE"),
+        unmatched_directory: matches.get_one::<PathBuf>("REPORT_UNMATCHED_DIR").cloned(),
+        trash_preserve_structure: matches.get_flag("TRASH_PRESERVE_STRUCTURE"),
+        dry_run: matches.get_flag("DRY_RUN"),
+        ..Default::default()
+    };
+
+    if matches.get_flag("SYSTEM_FROM_DIR") || matches.get_flag("AUTO_SYSTEM") {
+        // holds the copies of files imported into several systems until they are moved
+        let duplicates_directory = create_tmp_directory(connection).await?;
+        let romfile_paths_by_system = if matches.get_flag("AUTO_SYSTEM") {
+            group_romfiles_by_matching_system(
+                connection,
+                progress_bar,
+                matches,
+                &romfile_paths,
+                duplicates_directory.path(),
+                options.recursive,
+                options.limit,
+            )
+            .await?
+        } else {
+            group_romfiles_by_system_directory(
+                connection,
+                progress_bar,
+                &romfile_paths,
+                options.recursive,
+                options.limit,
+            )
+            .await?
+        };
+        for (system, romfile_paths) in romfile_paths_by_system {
+            let (header, hash_algorithm, crc_only_fast) =
+                get_import_settings(connection, matches, progress_bar, &system).await?;
+            let romfile_paths: Vec<&PathBuf> = romfile_paths.iter().collect();
+            // files were already walked and limited while grouping them
+            let options = ImportOptions {
+                limit: None,
+                crc_only_fast,
+                recursive: false,
+                ..options.clone()
+            };
+            import_romfiles(
+                connection,
+                progress_bar,
+                &system,
+                &header,
+                &romfile_paths,
+                &hash_algorithm,
+                &options,
+            )
+            .await?;
+        }
+        return Ok(());
+    }
+
     let system = prompt_for_system(
         connection,
         matches
@@ -71,79 +361,714 @@ pub async fn main(
             .map(|s| FromStr::from_str(s).expect("Failed to parse number")),
     )
     .await?;
+    let (header, hash_algorithm, crc_only_fast) =
+        get_import_settings(connection, matches, progress_bar, &system).await?;
+    let options = ImportOptions {
+        crc_only_fast,
+        ..options
+    };
+
+    if matches.get_flag("STDIN_TAR") {
+        return import_tar_stream(
+            connection,
+            progress_bar,
+            &system,
+            &header,
+            io::stdin().lock(),
+            &hash_algorithm,
+            &options,
+        )
+        .await;
+    }
+
+    import_romfiles(
+        connection,
+        progress_bar,
+        &system,
+        &header,
+        &romfile_paths,
+        &hash_algorithm,
+        &options,
+    )
+    .await
+}
+
+async fn get_import_settings(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+    system: &System,
+) -> SimpleResult<(Option<Header>, HashAlgorithm, bool)> {
     let header = find_header_by_system_id(connection, system.id).await;
-    let hash_algorithm = match matches.get_one::<String>("HASH").map(String::as_str) {
-        Some("CRC") => HashAlgorithm::Crc,
-        Some("MD5") => HashAlgorithm::Md5,
-        Some(&_) | None => {
-            match find_setting_by_key(connection, "HASH_ALGORITHM")
-                .await
-                .unwrap()
-                .value
-                .as_deref()
+    let hash_algorithm = match matches.get_one::<String>("HASH") {
+        Some(hash_algorithm) => HashAlgorithm::from_str(hash_algorithm)?,
+        None => get_hash_algorithm(connection, system).await?,
+    };
+    let mut crc_only_fast = matches.get_flag("CRC_ONLY_FAST");
+    // raw crcs are meaningless for headered systems
+    if crc_only_fast && header.is_some() {
+        progress_bar.println("System has a header, ignoring --crc-only-fast");
+        crc_only_fast = false;
+    }
+    Ok((header, hash_algorithm, crc_only_fast))
+}
+
+// files are imported one by one, directories are walked beforehand
+async fn walk_romfiles(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile_paths: &[&PathBuf],
+    recursive: bool,
+) -> SimpleResult<Vec<PathBuf>> {
+    let ignore_marker = get_string(connection, "IGNORE_MARKER").await;
+    let max_depth = if recursive { usize::MAX } else { 1 };
+    let mut walked_paths: Vec<PathBuf> = Vec::new();
+    for romfile_path in romfile_paths {
+        let romfile_path = match get_canonicalized_path(&romfile_path).await {
+            Ok(romfile_path) => romfile_path,
+            Err(error) => {
+                progress_bar.println(format!("{}, skipping", error));
+                warn!(%error, "Skipped");
+                continue;
+            }
+        };
+        let walker = WalkDir::new(&romfile_path)
+            .max_depth(max_depth)
+            .into_iter()
+            .filter_entry(|e| !has_ignore_marker(&e.path(), ignore_marker.as_deref()));
+        for entry in walker.filter_map(|e| e.ok()) {
+            if entry.path().is_file() {
+                walked_paths.push(PathBuf::from(entry.path()));
+            }
+        }
+    }
+    Ok(walked_paths)
+}
+
+fn add_to_system_group(
+    romfile_paths_by_system: &mut Vec<(System, Vec<PathBuf>)>,
+    system: System,
+    romfile_path: PathBuf,
+) {
+    match romfile_paths_by_system
+        .iter_mut()
+        .find(|(group_system, _)| group_system.id == system.id)
+    {
+        Some((_, romfile_paths)) => romfile_paths.push(romfile_path),
+        None => romfile_paths_by_system.push((system, vec![romfile_path])),
+    }
+}
+
+async fn group_romfiles_by_system_directory(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile_paths: &[&PathBuf],
+    recursive: bool,
+    limit: Option<usize>,
+) -> SimpleResult<Vec<(System, Vec<PathBuf>)>> {
+    let mut romfile_paths_by_system: Vec<(System, Vec<PathBuf>)> = Vec::new();
+    let mut unknown_directory_names: HashSet<String> = HashSet::new();
+    let mut count: usize = 0;
+    for romfile_path in walk_romfiles(connection, progress_bar, romfile_paths, recursive).await? {
+        if limit.is_some_and(|limit| count >= limit) {
+            break;
+        }
+        let directory_name = romfile_path
+            .parent()
+            .and_then(|parent| parent.file_name())
+            .and_then(|name| name.to_str())
+            .unwrap_or_default()
+            .to_owned();
+        if unknown_directory_names.contains(&directory_name) {
+            continue;
+        }
+        match find_system_by_name(connection, &directory_name).await {
+            Some(system) => {
+                add_to_system_group(&mut romfile_paths_by_system, system, romfile_path);
+                count += 1;
+            }
+            None => {
+                progress_bar.println(format!(
+                    "No system named \"{}\", skipping its files",
+                    &directory_name
+                ));
+                unknown_directory_names.insert(directory_name);
+            }
+        }
+    }
+    Ok(romfile_paths_by_system)
+}
+
+// the size is a cheap filter, only the systems listing a rom of that size are hashed against
+async fn group_romfiles_by_matching_system(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    matches: &ArgMatches,
+    romfile_paths: &[&PathBuf],
+    duplicates_directory: &std::path::Path,
+    recursive: bool,
+    limit: Option<usize>,
+) -> SimpleResult<Vec<(System, Vec<PathBuf>)>> {
+    let duplicate_across_systems =
+        matches.get_flag("DUPLICATE_ACROSS_SYSTEMS") || matches.get_flag("YES");
+    let mut romfile_paths_by_system: Vec<(System, Vec<PathBuf>)> = Vec::new();
+    let mut count: usize = 0;
+    for romfile_path in walk_romfiles(connection, progress_bar, romfile_paths, recursive).await? {
+        if limit.is_some_and(|limit| count >= limit) {
+            break;
+        }
+        let romfile_extension = romfile_path
+            .extension()
+            .unwrap_or_default()
+            .to_str()
+            .unwrap()
+            .to_lowercase();
+        // the size of archives and containers says nothing about their contents
+        let candidate_systems = if ARCHIVE_EXTENSIONS.contains(&romfile_extension.as_str())
+            || [CHD_EXTENSION, CSO_EXTENSION, ZSO_EXTENSION, RVZ_EXTENSION]
+                .contains(&romfile_extension.as_str())
+        {
+            find_systems(connection).await
+        } else {
+            let size = try_with!(
+                romfile_path.metadata(),
+                "Failed to read {:?}",
+                &romfile_path
+            )
+            .len();
+            // also matches the headerless size of headered systems
+            find_systems_by_rom_size(connection, size).await
+        };
+        let mut systems_games: Vec<(System, Game)> = Vec::new();
+        for system in candidate_systems {
+            if let Some(game) =
+                find_matching_game(connection, progress_bar, matches, &system, &romfile_path)
+                    .await?
             {
-                Some("CRC") => HashAlgorithm::Crc,
-                Some("MD5") => HashAlgorithm::Md5,
-                Some(&_) | None => bail!("Not possible"),
+                systems_games.push((system, game));
             }
         }
+        // a file shared by several systems legitimately belongs to each of them
+        let mut systems: Vec<System> = if systems_games.len() > 1 && !duplicate_across_systems {
+            progress_bar.println(format!(
+                "{:?} matches several systems",
+                romfile_path.file_name().unwrap()
+            ));
+            prompt_for_system_game(&mut systems_games)?
+                .into_iter()
+                .collect()
+        } else {
+            systems_games
+                .into_iter()
+                .map(|(system, _)| system)
+                .collect()
+        };
+        if systems.is_empty() {
+            progress_bar.println(format!(
+                "No system matches {:?}, skipping",
+                romfile_path.file_name().unwrap()
+            ));
+            continue;
+        }
+        // the original is moved by the first import, the other systems get a link to it
+        for system in systems.split_off(1) {
+            let duplicate_path = duplicates_directory
+                .join(system.id.to_string())
+                .join(romfile_path.file_name().unwrap());
+            util::link_or_copy_file(progress_bar, &romfile_path, &duplicate_path, true).await?;
+            add_to_system_group(&mut romfile_paths_by_system, system, duplicate_path);
+        }
+        add_to_system_group(
+            &mut romfile_paths_by_system,
+            systems.remove(0),
+            romfile_path,
+        );
+        count += 1;
+    }
+    Ok(romfile_paths_by_system)
+}
+
+async fn find_matching_game(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    matches: &ArgMatches,
+    system: &System,
+    romfile_path: &PathBuf,
+) -> SimpleResult<Option<Game>> {
+    #[cfg(test)]
+    PROBED_SYSTEMS.fetch_add(1, Ordering::SeqCst);
+    let (header, hash_algorithm, _) =
+        get_import_settings(connection, matches, progress_bar, system).await?;
+    let romfile_extension = romfile_path
+        .extension()
+        .unwrap_or_default()
+        .to_str()
+        .unwrap()
+        .to_lowercase();
+
+    // archives are matched on the CRCs of their members, like import_archive does
+    let (sizes_and_hashes, hash_algorithm) = if ARCHIVE_EXTENSIONS
+        .contains(&romfile_extension.as_str())
+        && !system.archive_is_rom
+    {
+        let mut sizes_and_hashes: Vec<(u64, String)> = Vec::new();
+        for sevenzip_info in sevenzip::parse_archive(progress_bar, romfile_path)? {
+            if header.is_some() {
+                sizes_and_hashes.push(
+                    get_archive_member_size_and_headerless_crc(
+                        connection,
+                        progress_bar,
+                        romfile_path,
+                        &sevenzip_info.path,
+                        &header,
+                    )
+                    .await?,
+                );
+            } else {
+                sizes_and_hashes.push((sevenzip_info.size, sevenzip_info.crc));
+            }
+        }
+        (sizes_and_hashes, HashAlgorithm::Crc)
+    } else {
+        // containers are expanded so that their contents can be hashed
+        let (_tmp_directory, probed_path) = if CHD_EXTENSION == romfile_extension {
+            cfg_if! {
+                if #[cfg(feature = "chd")] {
+                    // a CUE file next to the CHD identifies the game on its own
+                    let mut cue_path = romfile_path.clone();
+                    cue_path.set_extension(CUE_EXTENSION);
+                    if cue_path.is_file() {
+                        (None, cue_path)
+                    } else {
+                        let tmp_directory = create_tmp_directory(connection).await?;
+                        let extracted_path = if chdman::is_dvd_chd(progress_bar, romfile_path)? {
+                            chdman::extract_chd_to_iso(
+                                progress_bar,
+                                romfile_path,
+                                &tmp_directory.path(),
+                            )?
+                        } else {
+                            chdman::extract_chd_to_single_track(
+                                progress_bar,
+                                romfile_path,
+                                &tmp_directory.path(),
+                            )
+                            .await?
+                        };
+                        (Some(tmp_directory), extracted_path.into())
+                    }
+                } else {
+                    (None, romfile_path.clone())
+                }
+            }
+        } else if CSO_EXTENSION == romfile_extension || ZSO_EXTENSION == romfile_extension {
+            cfg_if! {
+                if #[cfg(feature = "cso")] {
+                    let tmp_directory = create_tmp_directory(connection).await?;
+                    let extracted_path = if CSO_EXTENSION == romfile_extension {
+                        maxcso::extract_cso(progress_bar, romfile_path, &tmp_directory.path())?
+                    } else {
+                        maxcso::extract_zso(progress_bar, romfile_path, &tmp_directory.path())?
+                    };
+                    (Some(tmp_directory), extracted_path.into())
+                } else {
+                    (None, romfile_path.clone())
+                }
+            }
+        } else if RVZ_EXTENSION == romfile_extension {
+            cfg_if! {
+                if #[cfg(feature = "rvz")] {
+                    let tmp_directory = create_tmp_directory(connection).await?;
+                    let extracted_path =
+                        dolphin::extract_rvz(progress_bar, romfile_path, &tmp_directory.path())?;
+                    (Some(tmp_directory), extracted_path.into())
+                } else {
+                    (None, romfile_path.clone())
+                }
+            }
+        } else {
+            (None::<TempDir>, romfile_path.clone())
+        };
+        let size_and_hash = get_size_and_hash(
+            connection,
+            progress_bar,
+            &probed_path,
+            &header,
+            1,
+            1,
+            &hash_algorithm,
+        )
+        .await?;
+        (vec![size_and_hash], hash_algorithm)
     };
 
-    for romfile_path in romfile_paths {
-        progress_bar.println(&format!("Processing \"{:?}\"", &romfile_path));
-        let romfile_path = get_canonicalized_path(&romfile_path).await?;
+    for (size, hash) in sizes_and_hashes {
+        let roms = match hash_algorithm {
+            HashAlgorithm::Crc => {
+                find_roms_without_romfile_by_size_and_crc_and_system_id(
+                    connection, size, &hash, system.id,
+                )
+                .await
+            }
+            HashAlgorithm::Md5 => {
+                find_roms_without_romfile_by_size_and_md5_and_system_id(
+                    connection, size, &hash, system.id,
+                )
+                .await
+            }
+            HashAlgorithm::Sha1 => {
+                find_roms_without_romfile_by_size_and_sha1_and_system_id(
+                    connection, size, &hash, system.id,
+                )
+                .await
+            }
+        };
+        if let Some(rom) = roms.first() {
+            return Ok(Some(find_game_by_id(connection, rom.game_id).await));
+        }
+    }
+    Ok(None)
+}
+
+pub async fn import_romfiles(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system: &System,
+    header: &Option<Header>,
+    romfile_paths: &[&PathBuf],
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let started_at = SystemTime::now();
+    let mut processed: usize = 0;
+    let result = import_system_romfiles(
+        connection,
+        progress_sink,
+        system,
+        header,
+        romfile_paths,
+        hash_algorithm,
+        &mut processed,
+        options,
+    )
+    .await;
+    if options.dry_run {
+        return result;
+    }
+    record_run(
+        connection,
+        "import-roms",
+        system,
+        processed,
+        started_at,
+        &result,
+    )
+    .await;
+    result
+}
+
+async fn import_system_romfiles(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system: &System,
+    header: &Option<Header>,
+    romfile_paths: &[&PathBuf],
+    hash_algorithm: &HashAlgorithm,
+    processed: &mut usize,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
+    let match_rate_guard = MatchRateGuard::new(progress_sink);
+    let progress_sink: &dyn ProgressSink = &match_rate_guard;
+
+    #[cfg(feature = "chd")]
+    let auto_convert_cd = get_string(connection, "AUTO_CONVERT_CD")
+        .await
+        .map(|value| value.to_uppercase());
+    #[cfg(feature = "cso")]
+    let auto_convert_iso = get_string(connection, "AUTO_CONVERT_ISO")
+        .await
+        .map(|value| value.to_uppercase());
+    let previous_romfile_ids: HashSet<i64> = find_romfiles_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .map(|romfile| romfile.id)
+        .collect();
+
+    load_min_free_space(connection, progress_bar).await?;
+    let rom_directory = get_rom_directory(connection).await;
+    let ignore_marker = get_string(connection, "IGNORE_MARKER").await;
+    // only the directories themselves are walked when not recursing
+    let max_depth = if options.recursive { usize::MAX } else { 1 };
+    let mut status_reporter = options.status_interval.map(|status_interval| {
+        StatusReporter::new(
+            status_interval,
+            romfile_paths
+                .iter()
+                .map(|romfile_path| count_files(romfile_path, max_depth, ignore_marker.as_deref()))
+                .sum(),
+        )
+    });
+    check_tools_available_for_files(
+        romfile_paths
+            .iter()
+            .flat_map(|romfile_path| list_files(romfile_path, max_depth, ignore_marker.as_deref())),
+        system.archive_is_rom,
+    )?;
+    let mut done: u64 = 0;
+
+    for (i, romfile_path) in romfile_paths.iter().enumerate() {
+        if options.limit.is_some_and(|limit| *processed >= limit) {
+            break;
+        }
+        progress_bar.println(fit_to_terminal(&format!(
+            "Processing \"{:?}\"",
+            &romfile_path
+        )));
+        // broken symlinks and vanished paths shouldn't abort the whole batch
+        let romfile_path = match get_canonicalized_path(&romfile_path).await {
+            Ok(romfile_path) => romfile_path,
+            Err(error) => {
+                let message = format!("{}, skipping", error);
+                progress_bar.println(&message);
+                warn!(%error, "Skipped");
+                progress_sink.on_warning(&message);
+                progress_bar.println("");
+                progress_sink.on_progress(i as u64 + 1, romfile_paths.len() as u64);
+                continue;
+            }
+        };
         if romfile_path.is_dir().await {
+            // games are moved out of the directory, count its files beforehand
+            let directory_files = status_reporter.as_ref().map_or(0, |_| {
+                count_files(&romfile_path, max_depth, ignore_marker.as_deref())
+            });
             cfg_if! {
                 if #[cfg(feature = "ird")] {
-                    if romfile_path.join(PS3_DISC_SFB).is_file().await {
-                        import_jbfolder(connection, progress_bar, &system, &romfile_path).await?;
-                    } else {
-                        let walker = WalkDir::new(&romfile_path).into_iter();
-                        for entry in walker.filter_map(|e| e.ok()) {
-                            if entry.path().is_file() {
-                                import_rom(
-                                    connection,
-                                    progress_bar,
-                                    &system,
-                                    &header,
-                                    &entry.path(),
-                                    &hash_algorithm,
-                                )
-                                .await?;
-                            }
-                        }
-                    }
+                    let ps3_jbfolder = romfile_path.join(PS3_DISC_SFB).is_file().await;
                 } else {
-                    let walker = WalkDir::new(&romfile_path).into_iter();
-                    for entry in walker.filter_map(|e| e.ok()) {
-                        if entry.path().is_file() {
-                            import_rom(
-                                connection,
-                                progress_bar,
-                                &system,
-                                &header,
-                                &entry.path(),
-                                &hash_algorithm,
-                            )
-                            .await?;
+                    let ps3_jbfolder = false;
+                }
+            }
+            if has_ignore_marker(&romfile_path, ignore_marker.as_deref()) {
+                progress_bar.println("Ignored");
+                debug!("Ignored");
+            } else if ps3_jbfolder {
+                #[cfg(feature = "ird")]
+                import_jbfolder(connection, progress_sink, system, &romfile_path, options).await?;
+                *processed += 1;
+                done += directory_files;
+                report_status(progress_sink, &mut status_reporter, done, rom_directory);
+            } else if import_directory_game(
+                connection,
+                progress_sink,
+                system,
+                &romfile_path,
+                hash_algorithm,
+                options,
+            )
+            .await?
+            {
+                *processed += 1;
+                done += directory_files;
+                report_status(progress_sink, &mut status_reporter, done, rom_directory);
+            } else {
+                // trashed files keep their path relative to the parent of the imported directory
+                let mut directory_options = options.clone();
+                if options.trash_preserve_structure {
+                    directory_options.source_directory = romfile_path
+                        .parent()
+                        .map(|parent| PathBuf::from(parent.as_os_str()));
+                }
+                let walker = WalkDir::new(&romfile_path)
+                    .max_depth(max_depth)
+                    .into_iter()
+                    .filter_entry(|e| !has_ignore_marker(&e.path(), ignore_marker.as_deref()));
+                for entry in walker.filter_map(|e| e.ok()) {
+                    if entry.path().is_file() {
+                        if options.limit.is_some_and(|limit| *processed >= limit) {
+                            break;
                         }
+                        match_rate_guard
+                            .check(connection, system, &directory_options)
+                            .await?;
+                        check_min_free_space(&rom_directory)?;
+                        import_rom(
+                            connection,
+                            progress_sink,
+                            system,
+                            header,
+                            &entry.path(),
+                            hash_algorithm,
+                            &directory_options,
+                        )
+                        .await?;
+                        *processed += 1;
+                        done += 1;
+                        report_status(progress_sink, &mut status_reporter, done, rom_directory);
                     }
                 }
             }
         } else {
+            match_rate_guard.check(connection, system, options).await?;
+            check_min_free_space(&rom_directory)?;
             import_rom(
                 connection,
-                progress_bar,
-                &system,
-                &header,
+                progress_sink,
+                system,
+                header,
                 &romfile_path,
-                &hash_algorithm,
+                hash_algorithm,
+                options,
             )
             .await?;
+            *processed += 1;
+            done += 1;
+            report_status(progress_sink, &mut status_reporter, done, rom_directory);
+        }
+        progress_bar.println("");
+        progress_sink.on_progress(i as u64 + 1, romfile_paths.len() as u64);
+    }
+    match_rate_guard.release(connection, system).await?;
+
+    // nothing was imported, completion and post-import steps have nothing to work on
+    if options.dry_run {
+        return Ok(());
+    }
+
+    mark_complete(connection, progress_bar, system).await;
+
+    if let Some(mirror_directory) = &options.mirror_directory {
+        mirror_archives(
+            connection,
+            progress_bar,
+            system,
+            &previous_romfile_ids,
+            mirror_directory,
+            options,
+        )
+        .await?;
+    }
+
+    if let Some(bios_directory) = get_directory(connection, "BIOS_DIRECTORY").await {
+        link_bios_romfiles(
+            connection,
+            progress_bar,
+            system,
+            &previous_romfile_ids,
+            &bios_directory,
+        )
+        .await?;
+    }
+
+    // compress freshly imported ISOs if requested, before the CD conversion picks them up
+    #[cfg(feature = "cso")]
+    if auto_convert_iso.as_deref() == Some(CSO_EXTENSION.to_uppercase().as_str()) && !system.arcade
+    {
+        maxcso::check_tool_available()?;
+        auto_convert_to_cso(
+            connection,
+            progress_bar,
+            system,
+            header,
+            &previous_romfile_ids,
+            hash_algorithm,
+            options,
+        )
+        .await?;
+    }
+
+    // convert freshly imported loose CDs if requested
+    #[cfg(feature = "chd")]
+    if auto_convert_cd.as_deref() == Some(CHD_EXTENSION.to_uppercase().as_str()) && !system.arcade {
+        chdman::check_tool_available()?;
+        auto_convert_to_chd(
+            connection,
+            progress_bar,
+            system,
+            header,
+            &previous_romfile_ids,
+            hash_algorithm,
+            options,
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+// entries are extracted one at a time, the whole tar never lands on disk
+pub async fn import_tar_stream<R: Read>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system: &System,
+    header: &Option<Header>,
+    reader: R,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
+    let match_rate_guard = MatchRateGuard::new(progress_sink);
+    let progress_sink: &dyn ProgressSink = &match_rate_guard;
+
+    load_min_free_space(connection, progress_bar).await?;
+    let rom_directory = get_rom_directory(connection).await;
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let tmp_path = get_canonicalized_path(&tmp_directory.path()).await?;
+
+    let mut archive = tar::Archive::new(reader);
+    let entries = try_with!(archive.entries(), "Failed to read tar stream");
+    let mut processed: usize = 0;
+    for entry in entries {
+        if options.limit.is_some_and(|limit| processed >= limit) {
+            break;
         }
+        let mut entry = try_with!(entry, "Failed to read tar entry");
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let entry_path = try_with!(entry.path(), "Failed to read tar entry path").into_owned();
+        progress_bar.println(fit_to_terminal(&format!("Processing {:?}", &entry_path)));
+        // entries escaping the temp directory are refused by the tar crate
+        if !try_with!(
+            entry.unpack_in(&tmp_path),
+            "Failed to extract {:?}",
+            &entry_path
+        ) {
+            progress_bar.println("Skipped");
+            continue;
+        }
+        // the stream can't be walked beforehand, the cached check runs once per tool
+        check_tools_available_for_files([&entry_path], system.archive_is_rom)?;
+        match_rate_guard.check(connection, system, options).await?;
+        check_min_free_space(&rom_directory)?;
+        import_rom(
+            connection,
+            progress_sink,
+            system,
+            header,
+            &tmp_path.join(&entry_path),
+            hash_algorithm,
+            options,
+        )
+        .await?;
+        processed += 1;
         progress_bar.println("");
     }
+    match_rate_guard.release(connection, system).await?;
+
+    if !options.dry_run {
+        mark_complete(connection, progress_bar, system).await;
+    }
+
+    Ok(())
+}
 
+async fn mark_complete(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+) {
     // mark games and system as complete if they are
     progress_bar.set_style(get_none_progress_style());
     progress_bar.enable_steady_tick(Duration::from_millis(100));
@@ -155,18 +1080,187 @@ pub async fn main(
         }
     }
     update_system_mark_complete(connection, system.id).await;
+}
 
-    Ok(())
+// a wrong header configuration makes every file miss, the first unmatched files are held back
+// rather than trashed until the match rate of the run is confirmed
+struct MatchRateGuard<'a> {
+    progress_sink: &'a dyn ProgressSink,
+    matched: Cell<usize>,
+    // the options of the file being imported, held files are trashed with them later
+    options: RefCell<ImportOptions>,
+    held: RefCell<Vec<(PathBuf, ImportOptions)>>,
+    checked: Cell<bool>,
+}
+
+impl<'a> MatchRateGuard<'a> {
+    fn new(progress_sink: &'a dyn ProgressSink) -> Self {
+        MatchRateGuard {
+            progress_sink,
+            matched: Cell::new(0),
+            options: RefCell::new(ImportOptions::default()),
+            held: RefCell::new(Vec::new()),
+            checked: Cell::new(false),
+        }
+    }
+
+    async fn check(
+        &self,
+        connection: &mut SqliteConnection,
+        system: &System,
+        options: &ImportOptions,
+    ) -> SimpleResult<()> {
+        if !self.checked.get() {
+            let held = self.held.borrow().len();
+            if self.matched.get() == 0 && held >= MATCH_RATE_GUARD_FILES {
+                let message = format!("0/{} matched, is the header configuration correct?", held);
+                self.progress_sink.progress_bar().println(&message);
+                warn!(held, "No match");
+                self.progress_sink.on_warning(&message);
+                if !options.assume_yes && !confirm(false)? {
+                    bail!("Import aborted");
+                }
+            }
+            if self.matched.get() > 0 || held >= MATCH_RATE_GUARD_FILES {
+                self.release(connection, system).await?;
+            }
+        }
+        *self.options.borrow_mut() = options.clone();
+        Ok(())
+    }
+
+    // trashes the files held so far, later ones are trashed right away
+    async fn release(
+        &self,
+        connection: &mut SqliteConnection,
+        system: &System,
+    ) -> SimpleResult<()> {
+        self.checked.set(true);
+        for (romfile_path, options) in self.held.take() {
+            move_to_trash(
+                connection,
+                self,
+                system,
+                &romfile_path,
+                TrashReason::NoMatch,
+                &options,
+            )
+            .await?;
+        }
+        Ok(())
+    }
+}
+
+impl ProgressSink for MatchRateGuard<'_> {
+    fn progress_bar(&self) -> &ProgressBar {
+        self.progress_sink.progress_bar()
+    }
+
+    fn on_file_start(&self, path: &Path) {
+        self.progress_sink.on_file_start(path);
+    }
+
+    fn on_match(&self, rom: &Rom) {
+        self.matched.set(self.matched.get() + 1);
+        self.progress_sink.on_match(rom);
+    }
+
+    fn on_moved(&self, old_path: &Path, new_path: &Path) {
+        self.progress_sink.on_moved(old_path, new_path);
+    }
+
+    fn on_trashed(&self, old_path: &Path, new_path: &Path) {
+        self.progress_sink.on_trashed(old_path, new_path);
+    }
+
+    fn on_progress(&self, position: u64, length: u64) {
+        self.progress_sink.on_progress(position, length);
+    }
+
+    fn on_status(&self, status: &Status) {
+        self.progress_sink.on_status(status);
+    }
+
+    fn on_warning(&self, message: &str) {
+        self.progress_sink.on_warning(message);
+    }
+
+    fn hold_unmatched(&self, path: &Path) -> bool {
+        if self.checked.get() || self.matched.get() > 0 {
+            return false;
+        }
+        self.held.borrow_mut().push((
+            PathBuf::from(path.as_os_str()),
+            self.options.borrow().clone(),
+        ));
+        true
+    }
+}
+
+fn count_files<P: AsRef<std::path::Path>>(
+    path: &P,
+    max_depth: usize,
+    ignore_marker: Option<&str>,
+) -> u64 {
+    list_files(path, max_depth, ignore_marker).len() as u64
+}
+
+fn list_files<P: AsRef<std::path::Path>>(
+    path: &P,
+    max_depth: usize,
+    ignore_marker: Option<&str>,
+) -> Vec<std::path::PathBuf> {
+    WalkDir::new(path)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|e| !has_ignore_marker(&e.path(), ignore_marker))
+        .filter_map(|e| e.ok())
+        .filter(|entry| entry.path().is_file())
+        .map(|entry| entry.into_path())
+        .collect()
+}
+
+// directories holding the marker file are skipped along with their subdirectories
+fn has_ignore_marker<P: AsRef<std::path::Path>>(path: &P, ignore_marker: Option<&str>) -> bool {
+    ignore_marker.map_or(false, |ignore_marker| {
+        path.as_ref().join(ignore_marker).is_file()
+    })
+}
+
+fn report_status(
+    progress_sink: &dyn ProgressSink,
+    status_reporter: &mut Option<StatusReporter>,
+    done: u64,
+    rom_directory: &Path,
+) {
+    if let Some(status) = status_reporter
+        .as_mut()
+        .and_then(|status_reporter| status_reporter.poll(done, &rom_directory))
+    {
+        progress_sink.progress_bar().println(status.to_string());
+        progress_sink.on_status(&status);
+    }
 }
 
+#[instrument(skip_all, fields(path = %romfile_path.as_ref().display()))]
 pub async fn import_rom<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
     system: &System,
     header: &Option<Header>,
     romfile_path: &P,
     hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
 ) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
+
+    // skip ignored files entirely
+    if is_ignored(connection, romfile_path).await? {
+        debug!("Ignored");
+        return Ok(());
+    }
+
+    progress_sink.on_file_start(romfile_path.as_ref());
     let mut transaction = begin_transaction(connection).await;
 
     // abort if the romfile is already in the database
@@ -178,6 +1272,7 @@ pub async fn import_rom<P: AsRef<Path>>(
     .is_some()
     {
         progress_bar.println("Already in database");
+        debug!("Already in database");
         return Ok(());
     }
 
@@ -191,16 +1286,42 @@ pub async fn import_rom<P: AsRef<Path>>(
 
     let system_directory = get_system_directory(&mut transaction, progress_bar, system).await?;
 
-    if ARCHIVE_EXTENSIONS.contains(&romfile_extension.as_str()) {
+    // systems without a DAT file, describe the file before importing it as is
+    if options.snapshot {
+        if snapshot_romfile(
+            &mut transaction,
+            progress_sink,
+            system,
+            header,
+            romfile_path,
+        )
+        .await?
+        {
+            import_other(
+                &mut transaction,
+                progress_sink,
+                &system_directory,
+                system,
+                header,
+                &romfile_path,
+                &romfile_extension,
+                hash_algorithm,
+                options,
+            )
+            .await?;
+        }
+    // some DATs hash the archive itself rather than its contents
+    } else if ARCHIVE_EXTENSIONS.contains(&romfile_extension.as_str()) && !system.archive_is_rom {
         import_archive(
             &mut transaction,
-            progress_bar,
+            progress_sink,
             &system_directory,
             system,
             header,
             &romfile_path,
             &romfile_extension,
             hash_algorithm,
+            options,
         )
         .await?;
     } else if CHD_EXTENSION == romfile_extension {
@@ -208,12 +1329,13 @@ pub async fn import_rom<P: AsRef<Path>>(
             if #[cfg(feature = "chd")] {
                 import_chd(
                     &mut transaction,
-                    progress_bar,
+                    progress_sink,
                     &system_directory,
                     system,
                     header,
                     &romfile_path,
                     hash_algorithm,
+                    options,
                 )
                 .await?;
             } else {
@@ -225,12 +1347,31 @@ pub async fn import_rom<P: AsRef<Path>>(
             if #[cfg(feature = "cso")] {
                 import_cso(
                     &mut transaction,
-                    progress_bar,
+                    progress_sink,
+                    &system_directory,
+                    system,
+                    header,
+                    &romfile_path,
+                    hash_algorithm,
+                    options,
+                )
+                .await?;
+            } else {
+                progress_bar.println("Please rebuild with the CSO feature enabled");
+            }
+        }
+    } else if ZSO_EXTENSION == romfile_extension {
+        cfg_if! {
+            if #[cfg(feature = "cso")] {
+                import_zso(
+                    &mut transaction,
+                    progress_sink,
                     &system_directory,
                     system,
                     header,
                     &romfile_path,
                     hash_algorithm,
+                    options,
                 )
                 .await?;
             } else {
@@ -242,44 +1383,153 @@ pub async fn import_rom<P: AsRef<Path>>(
             if #[cfg(feature = "rvz")] {
                 import_rvz(
                     &mut transaction,
-                    progress_bar,
+                    progress_sink,
                     &system_directory,
                     system,
                     header,
                     &romfile_path,
                     hash_algorithm,
+                    options,
                 )
                 .await?;
             } else {
                 progress_bar.println("Please rebuild with the RVZ feature enabled");
             }
         }
+    } else if PBP_EXTENSION == romfile_extension {
+        cfg_if! {
+            if #[cfg(feature = "pbp")] {
+                import_pbp(
+                    &mut transaction,
+                    progress_sink,
+                    &system_directory,
+                    system,
+                    header,
+                    &romfile_path,
+                    hash_algorithm,
+                    options,
+                )
+                .await?;
+            } else {
+                progress_bar.println("Please rebuild with the PBP feature enabled");
+            }
+        }
+    } else if ZST_EXTENSION == romfile_extension {
+        cfg_if! {
+            if #[cfg(feature = "zst")] {
+                import_zst(
+                    &mut transaction,
+                    progress_sink,
+                    &system_directory,
+                    system,
+                    header,
+                    &romfile_path,
+                    hash_algorithm,
+                    options,
+                )
+                .await?;
+            } else {
+                progress_bar.println("Please rebuild with the ZST feature enabled");
+            }
+        }
     } else {
         import_other(
             &mut transaction,
-            progress_bar,
+            progress_sink,
             &system_directory,
             system,
             header,
             &romfile_path,
             &romfile_extension,
             hash_algorithm,
+            options,
         )
         .await?;
     }
 
-    commit_transaction(transaction).await;
+    // the matching logic ran as usual, discard what it recorded
+    end_transaction(transaction, options).await;
 
     Ok(())
 }
 
+// every file becomes its own game with a single ROM
+async fn snapshot_romfile<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system: &System,
+    header: &Option<Header>,
+    romfile_path: &P,
+) -> SimpleResult<bool> {
+    let progress_bar = progress_sink.progress_bar();
+    let (size, crc, md5, sha1) =
+        get_size_and_hashes(connection, progress_bar, romfile_path, header, 1, 1).await?;
+
+    // a previous snapshot already describes this file
+    if !find_roms_without_romfile_by_size_and_sha1_and_system_id(connection, size, &sha1, system.id)
+        .await
+        .is_empty()
+    {
+        return Ok(true);
+    }
+
+    let rom_name = romfile_path
+        .as_ref()
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_owned();
+    let game_name = romfile_path
+        .as_ref()
+        .file_stem()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_owned();
+    if find_game_by_name_and_bios_and_system_id(connection, &game_name, false, system.id)
+        .await
+        .is_some()
+    {
+        progress_bar.println(format!("\"{}\" already exists", game_name));
+        return Ok(false);
+    }
+
+    let game_xml = GameXml {
+        name: game_name.clone(),
+        description: game_name,
+        comment: None,
+        year: None,
+        manufacturer: None,
+        cloneof: None,
+        romof: None,
+        isbios: None,
+        roms: vec![RomXml {
+            name: rom_name,
+            merge: None,
+            size: size_to_i64(size)?,
+            crc: Some(crc),
+            md5: Some(md5),
+            sha1: Some(sha1),
+            status: None,
+        }],
+    };
+    let game_id = create_game_from_xml(connection, &game_xml, "", system.id, None, None).await;
+    create_rom_from_xml(connection, &game_xml.roms[0], false, game_id, None).await;
+    progress_bar.println(format!("Created \"{}\"", game_xml.name));
+
+    Ok(true)
+}
+
 #[cfg(feature = "ird")]
 async fn import_jbfolder<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
     system: &System,
     folder_path: &P,
+    options: &ImportOptions,
 ) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
     let sfb_romfile_path = folder_path.as_ref().join(PS3_DISC_SFB);
 
     // abort if the romfile is already in the database
@@ -288,6 +1538,7 @@ async fn import_jbfolder<P: AsRef<Path>>(
         .is_some()
     {
         progress_bar.println("Already in database");
+        debug!("Already in database");
         return Ok(());
     }
 
@@ -305,10 +1556,18 @@ async fn import_jbfolder<P: AsRef<Path>>(
     )
     .await?;
     let sfb_rom =
-        match find_sfb_rom_by_md5(&mut transaction, size, &md5, system, progress_bar).await? {
+        match find_sfb_rom_by_md5(&mut transaction, size, &md5, system, progress_sink).await? {
             Some(rom) => rom,
             None => {
-                move_to_trash(&mut transaction, progress_bar, system, &folder_path).await?;
+                move_to_trash(
+                    &mut transaction,
+                    progress_sink,
+                    system,
+                    &folder_path,
+                    TrashReason::NoMatch,
+                    options,
+                )
+                .await?;
                 return Ok(());
             }
         };
@@ -316,80 +1575,80 @@ async fn import_jbfolder<P: AsRef<Path>>(
 
     let system_directory = get_system_directory(&mut transaction, progress_bar, system).await?;
 
-    let walker = WalkDir::new(folder_path.as_ref()).into_iter();
-    for entry in walker.filter_map(|e| e.ok()) {
-        if entry.path().is_file() {
-            progress_bar.println(&format!(
-                "Processing \"{}\"",
-                &entry.path().as_os_str().to_str().unwrap()
-            ));
-            // force MD5 as IRD files only provide those
-            let (size, md5) = get_size_and_hash(
-                &mut transaction,
-                progress_bar,
-                &entry.path(),
-                &None,
-                1,
-                1,
-                &HashAlgorithm::Md5,
-            )
-            .await?;
+    let entries: Vec<walkdir::DirEntry> = WalkDir::new(folder_path.as_ref())
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .collect();
+    let entry_paths: Vec<&std::path::Path> = entries.iter().map(|e| e.path()).collect();
+    // force MD5 as IRD files only provide those
+    let parallel_hashing = get_parallel_hashing(&mut transaction).await;
+    let preserve_empty_dirs = get_bool(&mut transaction, "PRESERVE_EMPTY_DIRS").await;
+    let sizes_and_md5s = get_sizes_and_hashes(
+        progress_bar,
+        &entry_paths,
+        &HashAlgorithm::Md5,
+        parallel_hashing,
+    )?;
+    for (entry, (size, md5)) in entries.iter().zip(sizes_and_md5s) {
+        progress_bar.println(&format!(
+            "Processing \"{}\"",
+            &entry.path().as_os_str().to_str().unwrap()
+        ));
 
-            let rom: Option<Rom>;
-            let mut roms = find_roms_without_romfile_by_size_and_md5_and_parent_id(
-                &mut transaction,
-                size,
-                &md5,
-                sfb_rom.parent_id.unwrap(),
-            )
-            .await;
+        let rom: Option<Rom>;
+        let mut roms = find_roms_without_romfile_by_size_and_md5_and_parent_id(
+            &mut transaction,
+            size,
+            &md5,
+            sfb_rom.parent_id.unwrap(),
+        )
+        .await;
 
-            // abort if no match
-            if roms.is_empty() {
-                progress_bar.println("No match");
-                continue;
-            }
+        // abort if no match
+        if roms.is_empty() {
+            progress_bar.println("No match");
+            continue;
+        }
 
-            // select the first rom if there is only one
-            if roms.len() == 1 {
-                rom = Some(roms.remove(0));
-                progress_bar.println(&format!("Matches \"{}\"", rom.as_ref().unwrap().name));
+        // select the first rom if there is only one
+        if roms.len() == 1 {
+            rom = Some(roms.remove(0));
+            progress_bar.println(&format!("Matches \"{}\"", rom.as_ref().unwrap().name));
+        } else {
+            // select the first rom that matches the file name if there multiple matches
+            if let Some(rom_index) = roms.iter().position(|rom| {
+                path_ends_with_name(entry.path().as_os_str().to_str().unwrap(), &rom.name)
+            }) {
+                rom = Some(roms.remove(rom_index));
             } else {
-                // select the first rom that matches the file name if there multiple matches
-                if let Some(rom_index) = roms.iter().position(|rom| {
-                    entry
-                        .path()
-                        .as_os_str()
-                        .to_str()
-                        .unwrap()
-                        .ends_with(&rom.name)
-                }) {
-                    rom = Some(roms.remove(rom_index));
-                } else {
-                    // let the user select the rom if all else fails
-                    rom = prompt_for_rom(&mut roms, None)?;
-                }
+                // let the user select the rom if all else fails
+                rom = prompt_for_rom(&mut roms, None)?;
             }
+        }
 
-            if let Some(rom) = rom {
-                // abort if rom already has a file
-                if rom.romfile_id.is_some() {
-                    let romfile =
-                        find_romfile_by_id(&mut transaction, rom.romfile_id.unwrap()).await;
-                    progress_bar.println(&format!("Duplicate of \"{}\"", romfile.path));
-                    continue;
-                }
+        if let Some(rom) = rom {
+            // abort if rom already has a file
+            if rom.romfile_id.is_some() {
+                let romfile = find_romfile_by_id(&mut transaction, rom.romfile_id.unwrap()).await;
+                progress_bar.println(&format!("Duplicate of \"{}\"", romfile.path));
+                continue;
+            }
 
-                // put arcade roms in subdirectories as their names aren't unique
-                let new_path = system_directory.join(&game.name).join(&rom.name);
+            // put arcade roms in subdirectories as their names aren't unique
+            let new_path = get_arcade_directory(&system_directory, system, [&rom])
+                .join(&game.name)
+                .join(&rom.name);
 
-                // move file if needed
-                rename_file(progress_bar, &entry.path(), &new_path, false).await?;
+            // move file if needed
+            rename_file(progress_bar, &entry.path(), &new_path, options).await?;
+            progress_sink.on_moved(Path::new(entry.path()), &new_path);
 
-                // persist in database
-                create_or_update_romfile(&mut transaction, &new_path, &[rom]).await;
+            // persist in database
+            create_or_update_romfile(&mut transaction, system, &new_path, &[rom], options).await;
 
-                // remove directories if empty
+            // remove directories if empty, unless asked to keep them
+            if !preserve_empty_dirs {
                 let mut directory = entry.path().parent().unwrap();
                 while directory.read_dir().unwrap().next().is_none() {
                     remove_directory(progress_bar, &directory, false).await?;
@@ -402,36 +1661,241 @@ async fn import_jbfolder<P: AsRef<Path>>(
         }
     }
 
-    commit_transaction(transaction).await;
+    end_transaction(transaction, options).await;
 
     Ok(())
 }
 
+async fn import_directory_game<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system: &System,
+    directory: &P,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<bool> {
+    let progress_bar = progress_sink.progress_bar();
+    let ignore_marker = get_string(connection, "IGNORE_MARKER").await;
+
+    let mut entries: Vec<walkdir::DirEntry> = Vec::new();
+    for entry in WalkDir::new(directory.as_ref())
+        .into_iter()
+        .filter_entry(|e| !has_ignore_marker(&e.path(), ignore_marker.as_deref()))
+        .filter_map(|e| e.ok())
+    {
+        if entry.path().is_file() && !is_ignored(connection, &entry.path()).await? {
+            entries.push(entry);
+        }
+    }
+
+    // a directory only qualifies if its files could make up the whole rom set of a single game
+    if entries.len() < 2 {
+        return Ok(false);
+    }
+    let mut size: u64 = 0;
+    for entry in &entries {
+        size += try_with!(entry.metadata(), "Failed to get metadata").len();
+    }
+    let game_ids = find_game_ids_without_romfiles_by_rom_count_and_size_and_system_id(
+        connection,
+        entries.len() as i64,
+        size_to_i64(size)?,
+        system.id,
+    )
+    .await;
+    if game_ids.is_empty() {
+        return Ok(false);
+    }
+
+    let entry_paths: Vec<&std::path::Path> = entries.iter().map(|e| e.path()).collect();
+    let parallel_hashing = get_parallel_hashing(connection).await;
+    let sizes_and_hashes =
+        get_sizes_and_hashes(progress_bar, &entry_paths, hash_algorithm, parallel_hashing)?;
+
+    for game_id in game_ids {
+        let mut roms = find_roms_by_game_id_no_parents(connection, game_id).await;
+        let mut entries_roms: Vec<(&std::path::Path, Rom)> = Vec::new();
+        for (entry_path, (size, hash)) in entry_paths.iter().zip(&sizes_and_hashes) {
+            let matching_roms: Vec<usize> = roms
+                .iter()
+                .enumerate()
+                .filter(|(_, rom)| {
+                    let rom_hash = match hash_algorithm {
+                        HashAlgorithm::Crc => rom.crc.as_ref(),
+                        HashAlgorithm::Md5 => rom.md5.as_ref(),
+                        HashAlgorithm::Sha1 => rom.sha1.as_ref(),
+                    };
+                    rom.size as u64 == *size && rom_hash == Some(hash)
+                })
+                .map(|(i, _)| i)
+                .collect();
+            // prefer the rom that matches the file name when several share the same content
+            let rom_index = matching_roms
+                .iter()
+                .find(|&&i| path_ends_with_name(entry_path.to_str().unwrap(), &roms[i].name))
+                .or_else(|| matching_roms.first());
+            match rom_index {
+                Some(&i) => entries_roms.push((entry_path, roms.remove(i))),
+                None => break,
+            }
+        }
+        if !roms.is_empty() {
+            continue;
+        }
+
+        let game = find_game_by_id(connection, game_id).await;
+        progress_bar.println(&format!("Matches \"{}\"", game.name));
+        info!(game = %game.name, "Matched");
+
+        let system_directory = get_system_directory(connection, progress_bar, system).await?;
+        let preserve_empty_dirs = get_bool(connection, "PRESERVE_EMPTY_DIRS").await;
+
+        let mut transaction = begin_transaction(connection).await;
+
+        update_game_jbfolder(&mut transaction, game.id, true).await;
+        for (entry_path, rom) in entries_roms {
+            let new_path = system_directory.join(&game.name).join(&rom.name);
+            rename_file(progress_bar, &entry_path, &new_path, options).await?;
+            progress_sink.on_moved(Path::new(entry_path), &new_path);
+            create_or_update_romfile(&mut transaction, system, &new_path, &[rom], options).await;
+        }
+
+        end_transaction(transaction, options).await;
+
+        if !preserve_empty_dirs
+            && !WalkDir::new(directory.as_ref())
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .any(|e| e.path().is_file())
+        {
+            remove_directory(progress_bar, directory, false).await?;
+        }
+
+        return Ok(true);
+    }
+
+    Ok(false)
+}
+
 async fn import_archive<P: AsRef<Path>, Q: AsRef<Path>>(
     connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
     system_directory: &Q,
     system: &System,
     header: &Option<Header>,
     romfile_path: &P,
     romfile_extension: &str,
     hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
 ) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
+    // match against the crcs reported by the archive regardless of the configured algorithm
+    let hash_algorithm = if options.crc_only_fast && header.is_none() {
+        &HashAlgorithm::Crc
+    } else {
+        hash_algorithm
+    };
     let tmp_directory = create_tmp_directory(connection).await?;
-    let sevenzip_infos = sevenzip::parse_archive(progress_bar, romfile_path)?;
+    let sevenzip_infos = match sevenzip::parse_archive(progress_bar, romfile_path) {
+        Ok(sevenzip_infos) => sevenzip_infos,
+        // malformed archives can't be processed reliably
+        Err(error)
+            if sevenzip::get_error_report(&error).map(|report| report.error_kind)
+                == Some(ErrorKind::DuplicateMemberNames) =>
+        {
+            progress_bar.println(error.as_str());
+            move_to_trash(
+                connection,
+                progress_sink,
+                system,
+                romfile_path,
+                TrashReason::Invalid,
+                options,
+            )
+            .await?;
+            return Ok(());
+        }
+        Err(error) => return Err(error),
+    };
+
+    // RAR archives can't be written to, they are repacked in the configured format instead
+    let read_only = romfile_extension == RAR_EXTENSION;
+    let archive_extension = if read_only {
+        get_repack_extension(connection).await
+    } else {
+        romfile_extension
+    };
+
+    // members without a usable crc are extracted and hashed concurrently in batches, so no more
+    // than PARALLEL_HASHING of them sit in the temporary directory at once, the database is only
+    // queried afterwards, headers are stripped through the async path only
+    let mut sizes_and_hashes: HashMap<&str, (u64, String)> = HashMap::new();
+    if header.is_none() {
+        let file_names: Vec<&str> = sevenzip_infos
+            .iter()
+            .filter(|sevenzip_info| {
+                sevenzip_info.crc.is_empty() || hash_algorithm != &HashAlgorithm::Crc
+            })
+            .map(|sevenzip_info| sevenzip_info.path.as_str())
+            .collect();
+        let parallel_hashing = get_parallel_hashing(connection).await;
+        for batch_file_names in file_names.chunks(parallel_hashing) {
+            let extracted_paths = sevenzip::extract_files_from_archive(
+                progress_bar,
+                romfile_path,
+                batch_file_names,
+                &tmp_directory.path(),
+            )?;
+            let extracted_sizes_and_hashes = get_sizes_and_hashes(
+                progress_bar,
+                &extracted_paths,
+                hash_algorithm,
+                parallel_hashing,
+            )?;
+            for extracted_path in &extracted_paths {
+                util::remove_file(progress_bar, extracted_path, true).await?;
+            }
+            sizes_and_hashes.extend(
+                batch_file_names
+                    .iter()
+                    .copied()
+                    .zip(extracted_sizes_and_hashes),
+            );
+        }
+    }
 
     let mut roms_sevenzip_infos: Vec<(Rom, &sevenzip::ArchiveInfo)> = Vec::new();
     let mut game_ids: HashSet<i64> = HashSet::new();
 
     for sevenzip_info in &sevenzip_infos {
-        progress_bar.println(&format!("Processing \"{}\"", &sevenzip_info.path));
+        progress_bar.println(fit_to_terminal(&format!(
+            "Processing \"{}\"",
+            &sevenzip_info.path
+        )));
 
         let size: u64;
         let hash: String;
+        let actual_size: u64;
 
-        // system has a header, crc is absent, or selected checksum is not crc
-        if header.is_some() || sevenzip_info.crc.is_empty() || hash_algorithm != &HashAlgorithm::Crc
-        {
+        if let Some(size_hash) = sizes_and_hashes.remove(sevenzip_info.path.as_str()) {
+            size = size_hash.0;
+            hash = size_hash.1;
+            actual_size = size;
+        // system has a header, use the cached headerless crc if the archive is unchanged
+        } else if header.is_some() && hash_algorithm == &HashAlgorithm::Crc {
+            let size_hash = get_archive_member_size_and_headerless_crc(
+                connection,
+                progress_bar,
+                romfile_path,
+                &sevenzip_info.path,
+                header,
+            )
+            .await?;
+            size = size_hash.0;
+            hash = size_hash.1;
+            actual_size = size;
+        // system has a header and selected checksum is not crc
+        } else if header.is_some() {
             let extracted_path = sevenzip::extract_files_from_archive(
                 progress_bar,
                 romfile_path,
@@ -449,155 +1913,565 @@ async fn import_archive<P: AsRef<Path>, Q: AsRef<Path>>(
                 hash_algorithm,
             )
             .await?;
-            remove_file(progress_bar, &extracted_path, true).await?;
+            util::remove_file(progress_bar, &extracted_path, true).await?;
             size = size_hash.0;
             hash = size_hash.1;
+            actual_size = size;
         } else {
             size = sevenzip_info.size;
             hash = sevenzip_info.crc.clone();
+            actual_size = if options.strict_size {
+                let extracted_path = sevenzip::extract_files_from_archive(
+                    progress_bar,
+                    romfile_path,
+                    &[&sevenzip_info.path],
+                    &tmp_directory.path(),
+                )?
+                .remove(0);
+                let actual_size = try_with!(
+                    extracted_path.metadata().await,
+                    "Failed to read {:?} metadata",
+                    extracted_path
+                )
+                .len();
+                util::remove_file(progress_bar, &extracted_path, true).await?;
+                actual_size
+            } else {
+                size
+            };
         }
 
         match find_rom_by_hash(
             connection,
-            progress_bar,
+            progress_sink,
+            &sevenzip_info.path,
             size,
             &hash,
             system,
             hash_algorithm,
+            options,
         )
         .await?
         {
-            Some(rom) => {
+            Some(rom) if check_strict_size(progress_sink, &rom, actual_size, options) => {
                 game_ids.insert(rom.game_id);
                 roms_sevenzip_infos.push((rom, sevenzip_info));
             }
+            Some(_) => {
+                if sevenzip_infos.len() == 1 {
+                    move_to_trash(
+                        connection,
+                        progress_sink,
+                        system,
+                        romfile_path,
+                        TrashReason::SizeMismatch,
+                        options,
+                    )
+                    .await?;
+                }
+            }
             None => {
                 if sevenzip_infos.len() == 1 {
-                    move_to_trash(connection, progress_bar, system, romfile_path).await?;
+                    move_to_trash(
+                        connection,
+                        progress_sink,
+                        system,
+                        romfile_path,
+                        TrashReason::NoMatch,
+                        options,
+                    )
+                    .await?;
                 }
             }
         }
     }
 
-    // archive contains a single full game with no invalid file
+    // archive contains a single game with no invalid file
     if roms_sevenzip_infos.len() == sevenzip_infos.len() && game_ids.len() == 1 {
         let game_id = game_ids.drain().last().unwrap();
-        let rom_ids: HashSet<i64> = find_roms_by_game_id_no_parents(connection, game_id)
+        let rom_ids: HashSet<i64> = roms_sevenzip_infos
+            .par_iter()
+            .map(|(rom, _)| rom.id)
+            .collect();
+        let other_roms: Vec<Rom> = find_roms_by_game_id_no_parents(connection, game_id)
             .await
-            .into_par_iter()
-            .map(|rom| rom.id)
+            .into_iter()
+            .filter(|rom| !rom_ids.contains(&rom.id))
             .collect();
-        if rom_ids
-            .difference(
-                &roms_sevenzip_infos
-                    .par_iter()
-                    .map(|(rom, _)| rom.id)
-                    .collect(),
+        // archive completes a game whose other ROMs were imported from previous archives
+        if !read_only
+            && !other_roms.is_empty()
+            && other_roms.iter().all(|rom| rom.romfile_id.is_some())
+            && merge_into_game_archive(
+                connection,
+                progress_sink,
+                system_directory,
+                system,
+                game_id,
+                romfile_path,
+                romfile_extension,
+                &roms_sevenzip_infos,
+                &other_roms,
+                &tmp_directory.path(),
+                hash_algorithm,
+                options,
             )
-            .count()
-            == 0
+            .await?
         {
+            return Ok(());
+        }
+        // archive contains a single full game
+        if other_roms.is_empty() {
             let game = find_game_by_id(connection, game_id).await;
-            for (rom, sevenzip_info) in &roms_sevenzip_infos {
-                if sevenzip_info.path != rom.name {
-                    sevenzip::rename_file_in_archive(
-                        progress_bar,
-                        romfile_path,
-                        &sevenzip_info.path,
-                        &rom.name,
-                    )?;
-                }
-            }
+            let archive_path = if read_only {
+                let archive_path = repack_archive(
+                    connection,
+                    progress_bar,
+                    romfile_path,
+                    archive_extension,
+                    &roms_sevenzip_infos,
+                    &tmp_directory.path(),
+                    options,
+                )
+                .await?;
+                remove_file(progress_bar, romfile_path, options).await?;
+                archive_path
+            } else {
+                rename_files_in_archive(
+                    connection,
+                    progress_bar,
+                    romfile_path,
+                    romfile_extension,
+                    &roms_sevenzip_infos,
+                    &tmp_directory.path(),
+                    options,
+                )
+                .await?;
+                PathBuf::from(romfile_path.as_ref())
+            };
 
-            let new_path = match roms_sevenzip_infos.len() {
-                1 => {
-                    let rom = &roms_sevenzip_infos.get(0).unwrap().0;
+            let disc_directory =
+                get_disc_directory(connection, system_directory, system, &game).await;
+            let directory = match &disc_directory {
+                Some(disc_directory) => disc_directory.clone(),
+                None => get_arcade_directory(
+                    system_directory,
+                    system,
+                    roms_sevenzip_infos.iter().map(|(rom, _)| rom),
+                ),
+            };
+            let game_archive_path =
+                directory.join(format!("{}.{}", &game.name, &archive_extension));
+            let new_path = match roms_sevenzip_infos.as_slice() {
+                [(rom, _)] => {
                     let rom_extension = Path::new(&rom.name)
                         .extension()
                         .unwrap_or(&OsString::new())
                         .to_str()
                         .unwrap()
                         .to_lowercase();
-                    let mut archive_path;
                     if system.arcade || PS3_EXTENSIONS.contains(&rom_extension.as_str()) {
-                        archive_path = system_directory
-                            .as_ref()
-                            .join(format!("{}.{}", &game.name, &romfile_extension));
+                        game_archive_path
                     } else {
-                        archive_path = system_directory.as_ref().join(&rom.name);
-                        archive_path.set_extension(romfile_extension);
+                        let mut archive_path = directory.join(&rom.name);
+                        archive_path.set_extension(archive_extension);
+                        archive_path
                     }
-                    archive_path
                 }
-                _ => system_directory
-                    .as_ref()
-                    .join(format!("{}.{}", &game.name, &romfile_extension)),
+                _ => game_archive_path,
             };
 
             // move file
-            rename_file(progress_bar, romfile_path, &new_path, false).await?;
+            if !move_to_destination(
+                connection,
+                progress_sink,
+                &archive_path,
+                &new_path,
+                hash_algorithm,
+                options,
+            )
+            .await?
+            {
+                return Ok(());
+            }
 
             // persist in database
             create_or_update_romfile(
                 connection,
+                system,
                 &new_path,
                 &roms_sevenzip_infos
                     .into_iter()
                     .map(|(rom, _)| rom)
                     .collect::<Vec<Rom>>(),
+                options,
             )
             .await;
+            store_archive_manifest(connection, progress_bar, &new_path, options).await?;
+
+            if let Some(disc_directory) = disc_directory {
+                write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
+            }
 
             return Ok(());
         }
-    }
-
-    // all other cases
-    for (rom, sevenzip_info) in roms_sevenzip_infos {
-        let extracted_path = sevenzip::extract_files_from_archive(
+    }
+
+    // all other cases
+    let delete_source_archive =
+        !roms_sevenzip_infos.is_empty() && get_bool(connection, "DELETE_SOURCE_ARCHIVES").await;
+    for (rom, sevenzip_info) in roms_sevenzip_infos {
+        let extracted_path = sevenzip::extract_files_from_archive(
+            progress_bar,
+            romfile_path,
+            &[&sevenzip_info.path],
+            &tmp_directory.path(),
+        )?
+        .remove(0);
+
+        let game = find_game_by_id(connection, rom.game_id).await;
+        let disc_directory = get_disc_directory(connection, system_directory, system, &game).await;
+
+        let new_path;
+        // put arcade roms and JB folders in subdirectories
+        if system.arcade || game.jbfolder {
+            new_path = get_arcade_directory(system_directory, system, [&rom])
+                .join(&game.name)
+                .join(&rom.name)
+        // use game name for PS3 updates and DLCs because rom name is usually gibberish
+        } else if PS3_EXTENSIONS.contains(&romfile_extension) {
+            new_path = system_directory
+                .as_ref()
+                .join(format!("{}.{}", &game.name, romfile_extension));
+        } else if let Some(disc_directory) = &disc_directory {
+            new_path = disc_directory.join(&rom.name);
+        } else {
+            new_path = system_directory.as_ref().join(&rom.name);
+        }
+
+        // move file
+        copy_file(progress_bar, &extracted_path, &new_path, options).await?;
+        progress_sink.on_moved(&extracted_path, &new_path);
+
+        // persist in database
+        create_or_update_romfile(connection, system, &new_path, &[rom], options).await;
+
+        if let Some(disc_directory) = disc_directory {
+            write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
+        }
+    }
+
+    // every matched file has been placed, the source archive is now redundant
+    if delete_source_archive {
+        remove_file(progress_bar, romfile_path, options).await?;
+    }
+
+    Ok(())
+}
+
+// remember the member list so that check-roms --quick can validate it without extracting
+async fn store_archive_manifest<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile_path: &P,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    if options.dry_run || !get_bool(connection, "STORE_ARCHIVE_MANIFESTS").await {
+        return Ok(());
+    }
+    let romfile = find_romfile_by_path(
+        connection,
+        romfile_path.as_ref().as_os_str().to_str().unwrap(),
+    )
+    .await
+    .unwrap();
+    let sevenzip_infos = sevenzip::parse_archive(progress_bar, romfile_path)?;
+    update_romfile_manifest(
+        connection,
+        romfile.id,
+        &sevenzip::build_manifest(&sevenzip_infos)?,
+    )
+    .await;
+    Ok(())
+}
+
+async fn get_repack_extension(connection: &mut SqliteConnection) -> &'static str {
+    match get_string(connection, "ARCHIVE_FORMAT")
+        .await
+        .map(|value| value.to_uppercase())
+        .as_deref()
+    {
+        Some("ZIP") => ZIP_EXTENSION,
+        _ => SEVENZIP_EXTENSION,
+    }
+}
+
+// extract every member under its ROM name and pack them into a new archive
+async fn repack_archive<P: AsRef<Path>, Q: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile_path: &P,
+    archive_extension: &str,
+    roms_sevenzip_infos: &[(Rom, &sevenzip::ArchiveInfo)],
+    tmp_directory: &Q,
+    options: &ImportOptions,
+) -> SimpleResult<PathBuf> {
+    let mut archive_path: PathBuf = tmp_directory
+        .as_ref()
+        .join(romfile_path.as_ref().file_name().unwrap())
+        .into();
+    archive_path.set_extension(archive_extension);
+    if options.dry_run {
+        print_dry_run(progress_bar, "repack", romfile_path);
+        return Ok(archive_path);
+    }
+    for (rom, sevenzip_info) in roms_sevenzip_infos {
+        let extracted_path = sevenzip::extract_files_from_archive(
+            progress_bar,
+            romfile_path,
+            &[&sevenzip_info.path],
+            tmp_directory,
+        )?
+        .remove(0);
+        let rom_path = tmp_directory.as_ref().join(&rom.name);
+        if extracted_path != rom_path {
+            util::rename_file(progress_bar, &extracted_path, &rom_path, true).await?;
+        }
+    }
+    let rom_names: Vec<&str> = roms_sevenzip_infos
+        .iter()
+        .map(|(rom, _)| rom.name.as_str())
+        .collect();
+    sevenzip::add_files_to_archive(
+        progress_bar,
+        &archive_path,
+        &rom_names,
+        tmp_directory,
+        false,
+        get_string(connection, "ARCHIVE_COMMENT").await.as_deref(),
+        get_bool(connection, "TORRENTZIP").await,
+    )?;
+    for rom_name in rom_names {
+        util::remove_file(progress_bar, &tmp_directory.as_ref().join(rom_name), true).await?;
+    }
+    Ok(archive_path)
+}
+
+async fn rename_files_in_archive<P: AsRef<Path>, Q: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    romfile_path: &P,
+    romfile_extension: &str,
+    roms_sevenzip_infos: &[(Rom, &sevenzip::ArchiveInfo)],
+    tmp_directory: &Q,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    if options.dry_run {
+        if roms_sevenzip_infos
+            .iter()
+            .any(|(rom, sevenzip_info)| !names_match(&sevenzip_info.path, &rom.name))
+        {
+            print_dry_run(progress_bar, "rename files in", romfile_path);
+        }
+        return Ok(());
+    }
+    let archive_comment = get_string(connection, "ARCHIVE_COMMENT").await;
+    let torrentzip = get_bool(connection, "TORRENTZIP").await;
+    for (rom, sevenzip_info) in roms_sevenzip_infos {
+        if !names_match(&sevenzip_info.path, &rom.name) {
+            sevenzip::rename_file_in_archive(
+                progress_bar,
+                romfile_path,
+                &sevenzip_info.path,
+                &rom.name,
+                tmp_directory,
+                archive_comment.as_deref(),
+                torrentzip,
+            )?;
+        }
+    }
+    // TorrentZip archives must be rewritten in their canonical layout
+    #[cfg(feature = "torrentzip")]
+    if romfile_extension == ZIP_EXTENSION
+        && torrentzip
+        && !torrentzip::is_torrentzipped(romfile_path)?
+    {
+        torrentzip::torrentzip_archive(progress_bar, romfile_path)?;
+    }
+    #[cfg(not(feature = "torrentzip"))]
+    if romfile_extension == ZIP_EXTENSION && torrentzip {
+        bail!(sevenzip::TORRENTZIP_DISABLED_ERROR);
+    }
+    Ok(())
+}
+
+// the game archive is named after the game, merge into it rather than clobbering it
+async fn merge_into_game_archive<P: AsRef<Path>, Q: AsRef<Path>, R: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system_directory: &Q,
+    system: &System,
+    game_id: i64,
+    romfile_path: &P,
+    romfile_extension: &str,
+    roms_sevenzip_infos: &[(Rom, &sevenzip::ArchiveInfo)],
+    other_roms: &[Rom],
+    tmp_directory: &R,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<bool> {
+    let progress_bar = progress_sink.progress_bar();
+    let archive_comment = get_string(connection, "ARCHIVE_COMMENT").await;
+    let torrentzip = get_bool(connection, "TORRENTZIP").await;
+    let game = find_game_by_id(connection, game_id).await;
+    let game_archive_path = get_arcade_directory(
+        system_directory,
+        system,
+        roms_sevenzip_infos
+            .iter()
+            .map(|(rom, _)| rom)
+            .chain(other_roms),
+    )
+    .join(format!("{}.{}", &game.name, &romfile_extension));
+
+    // only loose files and the game archive itself can be merged
+    let romfile_ids: HashSet<i64> = other_roms
+        .iter()
+        .map(|rom| rom.romfile_id.unwrap())
+        .collect();
+    let mut loose_romfiles: Vec<(Romfile, &Rom)> = Vec::new();
+    let mut game_archive_exists = false;
+    let romfiles =
+        find_romfiles_by_ids(connection, &romfile_ids.into_iter().collect::<Vec<i64>>()).await;
+    for romfile in romfiles {
+        if Path::new(&romfile.path) == game_archive_path.as_path() {
+            game_archive_exists = true;
+            continue;
+        }
+        let rom = other_roms
+            .iter()
+            .find(|rom| rom.romfile_id == Some(romfile.id))
+            .unwrap();
+        if Path::new(&romfile.path) != system_directory.as_ref().join(&rom.name).as_path() {
+            return Ok(false);
+        }
+        loose_romfiles.push((romfile, rom));
+    }
+    if game_archive_exists && !loose_romfiles.is_empty() {
+        return Ok(false);
+    }
+    if options.dry_run {
+        progress_bar.println(fit_to_terminal(&format!(
+            "Would merge into {:?}",
+            game_archive_path.as_os_str()
+        )));
+        return Ok(true);
+    }
+
+    if game_archive_exists {
+        for (rom, sevenzip_info) in roms_sevenzip_infos {
+            let extracted_path = sevenzip::extract_files_from_archive(
+                progress_bar,
+                romfile_path,
+                &[&sevenzip_info.path],
+                tmp_directory,
+            )?
+            .remove(0);
+            util::rename_file(
+                progress_bar,
+                &extracted_path,
+                &tmp_directory.as_ref().join(&rom.name),
+                true,
+            )
+            .await?;
+        }
+        sevenzip::add_files_to_archive(
+            progress_bar,
+            &game_archive_path,
+            &roms_sevenzip_infos
+                .iter()
+                .map(|(rom, _)| rom.name.as_str())
+                .collect::<Vec<&str>>(),
+            tmp_directory,
+            false,
+            archive_comment.as_deref(),
+            torrentzip,
+        )?;
+        remove_file(progress_bar, romfile_path, options).await?;
+        progress_sink.on_moved(romfile_path.as_ref(), &game_archive_path);
+    } else {
+        for (rom, sevenzip_info) in roms_sevenzip_infos {
+            if !names_match(&sevenzip_info.path, &rom.name) {
+                sevenzip::rename_file_in_archive(
+                    progress_bar,
+                    romfile_path,
+                    &sevenzip_info.path,
+                    &rom.name,
+                    tmp_directory,
+                    archive_comment.as_deref(),
+                    torrentzip,
+                )?;
+            }
+        }
+        sevenzip::add_files_to_archive(
             progress_bar,
             romfile_path,
-            &[&sevenzip_info.path],
-            &tmp_directory.path(),
-        )?
-        .remove(0);
-
-        let game = find_game_by_id(connection, rom.game_id).await;
-
-        let new_path;
-        // put arcade roms and JB folders in subdirectories
-        if system.arcade || game.jbfolder {
-            let game = find_game_by_id(connection, rom.game_id).await;
-            new_path = system_directory.as_ref().join(&game.name).join(&rom.name)
-        // use game name for PS3 updates and DLCs because rom name is usually gibberish
-        } else if PS3_EXTENSIONS.contains(&romfile_extension) {
-            new_path = system_directory
-                .as_ref()
-                .join(format!("{}.{}", &game.name, romfile_extension));
-        } else {
-            new_path = system_directory.as_ref().join(&rom.name);
+            &loose_romfiles
+                .iter()
+                .map(|(_, rom)| rom.name.as_str())
+                .collect::<Vec<&str>>(),
+            system_directory,
+            false,
+            archive_comment.as_deref(),
+            torrentzip,
+        )?;
+        if !move_to_destination(
+            connection,
+            progress_sink,
+            romfile_path,
+            &game_archive_path,
+            hash_algorithm,
+            options,
+        )
+        .await?
+        {
+            return Ok(true);
         }
+    }
 
-        // move file
-        copy_file(progress_bar, &extracted_path, &new_path, false).await?;
-
-        // persist in database
-        create_or_update_romfile(connection, &new_path, &[rom]).await;
+    // persist in database and remove the merged loose files
+    create_or_update_romfile(connection, system, &game_archive_path, other_roms, options).await;
+    let romfile = find_romfile_by_path(connection, game_archive_path.as_os_str().to_str().unwrap())
+        .await
+        .unwrap();
+    for (rom, _) in roms_sevenzip_infos {
+        update_rom_romfile(connection, rom.id, Some(romfile.id)).await;
+    }
+    store_archive_manifest(connection, progress_bar, &game_archive_path, options).await?;
+    for (romfile, _) in loose_romfiles {
+        remove_file(progress_bar, &romfile.path, options).await?;
+        delete_romfile_by_id(connection, romfile.id).await;
     }
 
-    Ok(())
+    // reflect the completion right away rather than at the end of the run
+    update_games_by_system_id_mark_complete(connection, system.id).await;
+    progress_bar.println(format!("Game \"{}\" is now complete", &game.name));
+
+    Ok(true)
 }
 
 #[cfg(feature = "chd")]
 async fn import_chd<P: AsRef<Path>, Q: AsRef<Path>>(
     connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
     system_directory: &Q,
     system: &System,
     header: &Option<Header>,
     romfile_path: &P,
     hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
 ) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
     let tmp_directory = create_tmp_directory(connection).await?;
 
     let mut cue_path = romfile_path.as_ref().to_path_buf();
@@ -617,17 +2491,27 @@ async fn import_chd<P: AsRef<Path>, Q: AsRef<Path>>(
         .await?;
         let cue_rom = match find_rom_by_hash(
             connection,
-            progress_bar,
+            progress_sink,
+            cue_path.file_name().unwrap().to_str().unwrap(),
             size,
             &hash,
             system,
             hash_algorithm,
+            options,
         )
         .await?
         {
             Some(rom) => rom,
             None => {
-                move_to_trash(connection, progress_bar, system, &cue_path).await?;
+                move_to_trash(
+                    connection,
+                    progress_sink,
+                    system,
+                    &cue_path,
+                    TrashReason::NoMatch,
+                    options,
+                )
+                .await?;
                 return Ok(());
             }
         };
@@ -650,20 +2534,10 @@ async fn import_chd<P: AsRef<Path>, Q: AsRef<Path>>(
             true,
         )
         .await?;
-        let mut hashes: Vec<String> = Vec::new();
-        for (i, bin_path) in bin_paths.iter().enumerate() {
-            let (_, hash) = get_size_and_hash(
-                connection,
-                progress_bar,
-                &bin_path,
-                header,
-                i,
-                bin_paths.len(),
-                hash_algorithm,
-            )
-            .await?;
-            hashes.push(hash);
-            remove_file(progress_bar, &bin_path, true).await?;
+        let hashes =
+            get_track_hashes(connection, progress_bar, &bin_paths, header, hash_algorithm).await?;
+        for bin_path in &bin_paths {
+            util::remove_file(progress_bar, bin_path, true).await?;
         }
 
         if roms
@@ -672,23 +2546,55 @@ async fn import_chd<P: AsRef<Path>, Q: AsRef<Path>>(
             .any(|(i, rom)| &hashes[i] != rom.crc.as_ref().unwrap())
         {
             progress_bar.println("CRC mismatch");
-            move_to_trash(connection, progress_bar, system, romfile_path).await?;
+            move_to_trash(
+                connection,
+                progress_sink,
+                system,
+                romfile_path,
+                TrashReason::CrcMismatch,
+                options,
+            )
+            .await?;
             return Ok(());
         }
 
-        let new_cue_path = system_directory.as_ref().join(&cue_rom.name);
+        let game = find_game_by_id(connection, cue_rom.game_id).await;
+        let disc_directory = get_disc_directory(connection, system_directory, system, &game).await;
+        let new_cue_path = match &disc_directory {
+            Some(disc_directory) => disc_directory.join(&cue_rom.name),
+            None => system_directory.as_ref().join(&cue_rom.name),
+        };
         let mut new_chd_path = new_cue_path.clone();
         new_chd_path.set_extension(CHD_EXTENSION);
 
         // move cue and chd if needed
-        rename_file(progress_bar, &cue_path, &new_cue_path, false).await?;
-        rename_file(progress_bar, romfile_path, &new_chd_path, false).await?;
+        rename_file(progress_bar, &cue_path, &new_cue_path, options).await?;
+        progress_sink.on_moved(&cue_path, &new_cue_path);
+        rename_file(progress_bar, romfile_path, &new_chd_path, options).await?;
+        progress_sink.on_moved(romfile_path.as_ref(), &new_chd_path);
 
         // persist in database
-        create_or_update_romfile(connection, &new_cue_path, &[cue_rom]).await;
-        create_or_update_romfile(connection, &new_chd_path, &roms).await;
+        create_or_update_romfile(connection, system, &new_cue_path, &[cue_rom], options).await;
+        create_or_update_romfile(connection, system, &new_chd_path, &roms, options).await;
+
+        if let Some(disc_directory) = disc_directory {
+            write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
+        }
 
         Ok(())
+    } else if chdman::is_dvd_chd(progress_bar, romfile_path)? {
+        progress_bar.println("DVD CHD found, using ISO mode");
+        import_dvd_chd(
+            connection,
+            progress_sink,
+            system_directory,
+            system,
+            header,
+            romfile_path,
+            hash_algorithm,
+            options,
+        )
+        .await
     } else {
         progress_bar.println("CUE file not found, using single track mode");
         let bin_path =
@@ -704,49 +2610,351 @@ async fn import_chd<P: AsRef<Path>, Q: AsRef<Path>>(
             hash_algorithm,
         )
         .await?;
-        remove_file(progress_bar, &bin_path, true).await?;
+        util::remove_file(progress_bar, &bin_path, true).await?;
         let rom = match find_rom_by_hash(
             connection,
-            progress_bar,
+            progress_sink,
+            bin_path.file_name().unwrap().to_str().unwrap(),
             size,
             &hash,
             system,
             hash_algorithm,
+            options,
         )
         .await?
         {
             Some(rom) => rom,
             None => {
-                move_to_trash(connection, progress_bar, system, romfile_path).await?;
+                move_to_trash(
+                    connection,
+                    progress_sink,
+                    system,
+                    romfile_path,
+                    TrashReason::NoMatch,
+                    options,
+                )
+                .await?;
                 return Ok(());
             }
         };
 
-        let mut new_chd_path = system_directory.as_ref().join(&rom.name);
+        let game = find_game_by_id(connection, rom.game_id).await;
+        let disc_directory = get_disc_directory(connection, system_directory, system, &game).await;
+        let mut new_chd_path = match &disc_directory {
+            Some(disc_directory) => disc_directory.join(&rom.name),
+            None => system_directory.as_ref().join(&rom.name),
+        };
         new_chd_path.set_extension(CHD_EXTENSION);
 
         // move CHD if needed
-        rename_file(progress_bar, romfile_path, &new_chd_path, false).await?;
+        if !move_to_destination(
+            connection,
+            progress_sink,
+            romfile_path,
+            &new_chd_path,
+            hash_algorithm,
+            options,
+        )
+        .await?
+        {
+            return Ok(());
+        }
+
+        // persist in database
+        create_or_update_romfile(connection, system, &new_chd_path, &[rom], options).await;
+
+        if let Some(disc_directory) = disc_directory {
+            write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(feature = "chd")]
+async fn import_dvd_chd<P: AsRef<Path>, Q: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system_directory: &Q,
+    system: &System,
+    header: &Option<Header>,
+    romfile_path: &P,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let iso_path = chdman::extract_chd_to_iso(progress_bar, romfile_path, &tmp_directory.path())?;
+    let (size, hash) = get_size_and_hash(
+        connection,
+        progress_bar,
+        &iso_path,
+        header,
+        1,
+        1,
+        hash_algorithm,
+    )
+    .await?;
+    util::remove_file(progress_bar, &iso_path, true).await?;
+    let rom = match find_rom_by_hash(
+        connection,
+        progress_sink,
+        iso_path.file_name().unwrap().to_str().unwrap(),
+        size,
+        &hash,
+        system,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        Some(rom) => rom,
+        None => {
+            move_to_trash(
+                connection,
+                progress_sink,
+                system,
+                romfile_path,
+                TrashReason::NoMatch,
+                options,
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    // the CHD only holds the ISO, the other files of the game have to be imported separately
+    let missing_roms: Vec<Rom> = find_roms_by_game_id_no_parents(connection, rom.game_id)
+        .await
+        .into_iter()
+        .filter(|game_rom| game_rom.id != rom.id && game_rom.romfile_id.is_none())
+        .collect();
+    if !missing_roms.is_empty() {
+        progress_bar.println(format!(
+            "Missing {} additional ROM(s): {}",
+            missing_roms.len(),
+            missing_roms
+                .iter()
+                .map(|missing_rom| format!("\"{}\"", missing_rom.name))
+                .collect::<Vec<String>>()
+                .join(", ")
+        ));
+    }
+
+    let game = find_game_by_id(connection, rom.game_id).await;
+    let disc_directory = get_disc_directory(connection, system_directory, system, &game).await;
+    let mut new_chd_path = match &disc_directory {
+        Some(disc_directory) => disc_directory.join(&rom.name),
+        None => system_directory.as_ref().join(&rom.name),
+    };
+    new_chd_path.set_extension(CHD_EXTENSION);
+
+    // move CHD if needed
+    if !move_to_destination(
+        connection,
+        progress_sink,
+        romfile_path,
+        &new_chd_path,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        return Ok(());
+    }
+
+    // persist in database
+    create_or_update_romfile(connection, system, &new_chd_path, &[rom], options).await;
+
+    if let Some(disc_directory) = disc_directory {
+        write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "cso")]
+async fn import_cso<P: AsRef<Path>, Q: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system_directory: &Q,
+    system: &System,
+    header: &Option<Header>,
+    romfile_path: &P,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let iso_path = maxcso::extract_cso(progress_bar, romfile_path, &tmp_directory.path())?;
+    let (size, hash) = get_size_and_hash(
+        connection,
+        progress_bar,
+        &iso_path,
+        header,
+        1,
+        1,
+        hash_algorithm,
+    )
+    .await?;
+    util::remove_file(progress_bar, &iso_path, true).await?;
+    let rom = match find_rom_by_hash(
+        connection,
+        progress_sink,
+        iso_path.file_name().unwrap().to_str().unwrap(),
+        size,
+        &hash,
+        system,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        Some(rom) => rom,
+        None => {
+            move_to_trash(
+                connection,
+                progress_sink,
+                system,
+                romfile_path,
+                TrashReason::NoMatch,
+                options,
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let game = find_game_by_id(connection, rom.game_id).await;
+    let disc_directory = get_disc_directory(connection, system_directory, system, &game).await;
+    let mut new_cso_path = match &disc_directory {
+        Some(disc_directory) => disc_directory.join(&rom.name),
+        None => system_directory.as_ref().join(&rom.name),
+    };
+    new_cso_path.set_extension(CSO_EXTENSION);
+
+    // move CSO if needed
+    if !move_to_destination(
+        connection,
+        progress_sink,
+        romfile_path,
+        &new_cso_path,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        return Ok(());
+    }
+
+    // persist in database
+    create_or_update_romfile(connection, system, &new_cso_path, &[rom], options).await;
+
+    if let Some(disc_directory) = disc_directory {
+        write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "cso")]
+async fn import_zso<P: AsRef<Path>, Q: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system_directory: &Q,
+    system: &System,
+    header: &Option<Header>,
+    romfile_path: &P,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let iso_path = maxcso::extract_zso(progress_bar, romfile_path, &tmp_directory.path())?;
+    let (size, hash) = get_size_and_hash(
+        connection,
+        progress_bar,
+        &iso_path,
+        header,
+        1,
+        1,
+        hash_algorithm,
+    )
+    .await?;
+    util::remove_file(progress_bar, &iso_path, true).await?;
+    let rom = match find_rom_by_hash(
+        connection,
+        progress_sink,
+        iso_path.file_name().unwrap().to_str().unwrap(),
+        size,
+        &hash,
+        system,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        Some(rom) => rom,
+        None => {
+            move_to_trash(
+                connection,
+                progress_sink,
+                system,
+                romfile_path,
+                TrashReason::NoMatch,
+                options,
+            )
+            .await?;
+            return Ok(());
+        }
+    };
+
+    let game = find_game_by_id(connection, rom.game_id).await;
+    let disc_directory = get_disc_directory(connection, system_directory, system, &game).await;
+    let mut new_zso_path = match &disc_directory {
+        Some(disc_directory) => disc_directory.join(&rom.name),
+        None => system_directory.as_ref().join(&rom.name),
+    };
+    new_zso_path.set_extension(ZSO_EXTENSION);
+
+    // move ZSO if needed
+    if !move_to_destination(
+        connection,
+        progress_sink,
+        romfile_path,
+        &new_zso_path,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        return Ok(());
+    }
 
-        // persist in database
-        create_or_update_romfile(connection, &new_chd_path, &[rom]).await;
+    // persist in database
+    create_or_update_romfile(connection, system, &new_zso_path, &[rom], options).await;
 
-        Ok(())
+    if let Some(disc_directory) = disc_directory {
+        write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
     }
+
+    Ok(())
 }
 
-#[cfg(feature = "cso")]
-async fn import_cso<P: AsRef<Path>, Q: AsRef<Path>>(
+#[cfg(feature = "rvz")]
+async fn import_rvz<P: AsRef<Path>, Q: AsRef<Path>>(
     connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
     system_directory: &Q,
     system: &System,
     header: &Option<Header>,
     romfile_path: &P,
     hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
 ) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
     let tmp_directory = create_tmp_directory(connection).await?;
-    let iso_path = maxcso::extract_cso(progress_bar, romfile_path, &tmp_directory.path())?;
+    let iso_path = dolphin::extract_rvz(progress_bar, romfile_path, &tmp_directory.path())?;
     let (size, hash) = get_size_and_hash(
         connection,
         progress_bar,
@@ -757,98 +2965,250 @@ async fn import_cso<P: AsRef<Path>, Q: AsRef<Path>>(
         hash_algorithm,
     )
     .await?;
-    remove_file(progress_bar, &iso_path, true).await?;
+    util::remove_file(progress_bar, &iso_path, true).await?;
     let rom = match find_rom_by_hash(
         connection,
-        progress_bar,
+        progress_sink,
+        iso_path.file_name().unwrap().to_str().unwrap(),
         size,
         &hash,
         system,
         hash_algorithm,
+        options,
     )
     .await?
     {
         Some(rom) => rom,
         None => {
-            move_to_trash(connection, progress_bar, system, romfile_path).await?;
+            move_to_trash(
+                connection,
+                progress_sink,
+                system,
+                romfile_path,
+                TrashReason::NoMatch,
+                options,
+            )
+            .await?;
             return Ok(());
         }
     };
 
-    let mut new_cso_path = system_directory.as_ref().join(&rom.name);
-    new_cso_path.set_extension(CSO_EXTENSION);
+    let game = find_game_by_id(connection, rom.game_id).await;
+    let disc_directory = get_disc_directory(connection, system_directory, system, &game).await;
+    let mut new_rvz_path = match &disc_directory {
+        Some(disc_directory) => disc_directory.join(&rom.name),
+        None => system_directory.as_ref().join(&rom.name),
+    };
+    new_rvz_path.set_extension(RVZ_EXTENSION);
 
-    // move CSO if needed
-    rename_file(progress_bar, romfile_path, &new_cso_path, false).await?;
+    // move RVZ if needed
+    if !move_to_destination(
+        connection,
+        progress_sink,
+        romfile_path,
+        &new_rvz_path,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        return Ok(());
+    }
 
     // persist in database
-    create_or_update_romfile(connection, &new_cso_path, &[rom]).await;
+    create_or_update_romfile(connection, system, &new_rvz_path, &[rom], options).await;
+
+    if let Some(disc_directory) = disc_directory {
+        write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
+    }
 
     Ok(())
 }
 
-#[cfg(feature = "rvz")]
-async fn import_rvz<P: AsRef<Path>, Q: AsRef<Path>>(
+#[cfg(feature = "pbp")]
+async fn import_pbp<P: AsRef<Path>, Q: AsRef<Path>>(
     connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
     system_directory: &Q,
     system: &System,
     header: &Option<Header>,
     romfile_path: &P,
     hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
 ) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
     let tmp_directory = create_tmp_directory(connection).await?;
-    let iso_path = dolphin::extract_rvz(progress_bar, romfile_path, &tmp_directory.path())?;
+    let bin_paths = pbp::extract_pbp(progress_bar, romfile_path, &tmp_directory.path())?;
+    let mut roms: Vec<Rom> = Vec::new();
+    for (i, bin_path) in bin_paths.iter().enumerate() {
+        let (size, hash) = get_size_and_hash(
+            connection,
+            progress_bar,
+            bin_path,
+            header,
+            i + 1,
+            bin_paths.len(),
+            hash_algorithm,
+        )
+        .await?;
+        util::remove_file(progress_bar, bin_path, true).await?;
+        if let Some(rom) = find_rom_by_hash(
+            connection,
+            progress_sink,
+            bin_path.file_name().unwrap().to_str().unwrap(),
+            size,
+            &hash,
+            system,
+            hash_algorithm,
+            options,
+        )
+        .await?
+        {
+            roms.push(rom);
+        }
+    }
+
+    if roms.is_empty() {
+        move_to_trash(
+            connection,
+            progress_sink,
+            system,
+            romfile_path,
+            TrashReason::NoMatch,
+            options,
+        )
+        .await?;
+        return Ok(());
+    }
+
+    // use the game name for single game PBPs, keep the original name otherwise
+    let game_ids: HashSet<i64> = roms.iter().map(|rom| rom.game_id).collect();
+    let new_pbp_path = match game_ids.len() {
+        1 => {
+            let game = find_game_by_id(connection, roms[0].game_id).await;
+            system_directory
+                .as_ref()
+                .join(format!("{}.{}", &game.name, PBP_EXTENSION))
+        }
+        _ => system_directory
+            .as_ref()
+            .join(romfile_path.as_ref().file_name().unwrap()),
+    };
+
+    // move PBP if needed
+    if !move_to_destination(
+        connection,
+        progress_sink,
+        romfile_path,
+        &new_pbp_path,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        return Ok(());
+    }
+
+    // persist in database
+    create_or_update_romfile(connection, system, &new_pbp_path, &roms, options).await;
+
+    Ok(())
+}
+
+#[cfg(feature = "zst")]
+async fn import_zst<P: AsRef<Path>, Q: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system_directory: &Q,
+    system: &System,
+    header: &Option<Header>,
+    romfile_path: &P,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
+    let tmp_directory = create_tmp_directory(connection).await?;
+    let extracted_path = zst::extract_zst(progress_bar, romfile_path, &tmp_directory.path())?;
     let (size, hash) = get_size_and_hash(
         connection,
         progress_bar,
-        &iso_path,
+        &extracted_path,
         header,
         1,
         1,
         hash_algorithm,
     )
     .await?;
-    remove_file(progress_bar, &iso_path, true).await?;
     let rom = match find_rom_by_hash(
         connection,
-        progress_bar,
+        progress_sink,
+        extracted_path.file_name().unwrap().to_str().unwrap(),
         size,
         &hash,
         system,
         hash_algorithm,
+        options,
     )
     .await?
     {
         Some(rom) => rom,
         None => {
-            move_to_trash(connection, progress_bar, system, romfile_path).await?;
+            move_to_trash(
+                connection,
+                progress_sink,
+                system,
+                romfile_path,
+                TrashReason::NoMatch,
+                options,
+            )
+            .await?;
             return Ok(());
         }
     };
+    let game = find_game_by_id(connection, rom.game_id).await;
 
-    let mut new_rvz_path = system_directory.as_ref().join(&rom.name);
-    new_rvz_path.set_extension(RVZ_EXTENSION);
+    // put arcade roms in subdirectories
+    let new_path = if system.arcade {
+        get_arcade_directory(system_directory, system, [&rom])
+            .join(&game.name)
+            .join(&rom.name)
+    } else {
+        system_directory.as_ref().join(&rom.name)
+    };
 
-    // move RVZ if needed
-    rename_file(progress_bar, romfile_path, &new_rvz_path, false).await?;
+    // store the decompressed file, the ZST is redundant afterwards
+    if !move_to_destination(
+        connection,
+        progress_sink,
+        &extracted_path,
+        &new_path,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        return Ok(());
+    }
+    remove_file(progress_bar, romfile_path, options).await?;
 
     // persist in database
-    create_or_update_romfile(connection, &new_rvz_path, &[rom]).await;
+    create_or_update_romfile(connection, system, &new_path, &[rom], options).await;
 
     Ok(())
 }
 
 async fn import_other<P: AsRef<Path>, Q: AsRef<Path>>(
     connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
     system_directory: &Q,
     system: &System,
     header: &Option<Header>,
     romfile_path: &P,
     romfile_extension: &str,
     hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
 ) -> SimpleResult<()> {
+    let progress_bar = progress_sink.progress_bar();
     let (size, hash) = get_size_and_hash(
         connection,
         progress_bar,
@@ -861,53 +3221,454 @@ async fn import_other<P: AsRef<Path>, Q: AsRef<Path>>(
     .await?;
     let rom = match find_rom_by_hash(
         connection,
-        progress_bar,
+        progress_sink,
+        romfile_path.as_ref().file_name().unwrap().to_str().unwrap(),
         size,
         &hash,
         system,
         hash_algorithm,
+        options,
     )
     .await?
     {
         Some(rom) => rom,
         None => {
-            move_to_trash(connection, progress_bar, system, romfile_path).await?;
+            // data discs may hold loose ROM files rather than being a ROM themselves
+            if romfile_extension == ISO_EXTENSION
+                && get_bool(connection, "IMPORT_ISO_CONTENTS").await
+                && import_iso_contents(
+                    connection,
+                    progress_sink,
+                    system,
+                    header,
+                    romfile_path,
+                    hash_algorithm,
+                    options,
+                )
+                .await?
+            {
+                return Ok(());
+            }
+            move_to_trash(
+                connection,
+                progress_sink,
+                system,
+                romfile_path,
+                TrashReason::NoMatch,
+                options,
+            )
+            .await?;
             return Ok(());
         }
     };
     let game = find_game_by_id(connection, rom.game_id).await;
+    // disk sides and tape parts are loose files of the same game
+    let parts = find_roms_by_game_id_no_parents(connection, game.id)
+        .await
+        .len();
+    let multi_part = !system.arcade && !game.jbfolder && parts > 1;
+    let disc_directory = get_disc_directory(connection, system_directory, system, &game).await;
 
     let new_path;
     // put arcade roms and JB folders in subdirectories
-    if system.arcade || game.jbfolder {
-        let game = find_game_by_id(connection, rom.game_id).await;
-        new_path = system_directory.as_ref().join(&game.name).join(&rom.name)
+    if system.arcade
+        || game.jbfolder
+        || multi_part && get_bool(connection, "GROUP_MULTI_PART_GAMES").await
+    {
+        new_path = get_arcade_directory(system_directory, system, [&rom])
+            .join(&game.name)
+            .join(&rom.name)
     // use game name for PS3 updates and DLCs because rom name is usually gibberish
     } else if PS3_EXTENSIONS.contains(&romfile_extension) {
         new_path = system_directory
             .as_ref()
             .join(format!("{}.{}", &game.name, romfile_extension));
+    } else if let Some(disc_directory) = &disc_directory {
+        new_path = disc_directory.join(&rom.name);
     } else {
         new_path = system_directory.as_ref().join(&rom.name);
     }
 
-    // move file if needed
-    rename_file(progress_bar, romfile_path, &new_path, false).await?;
-
-    // persist in database
-    create_or_update_romfile(connection, &new_path, &[rom]).await;
+    // move file if needed
+    if !move_to_destination(
+        connection,
+        progress_sink,
+        romfile_path,
+        &new_path,
+        hash_algorithm,
+        options,
+    )
+    .await?
+    {
+        return Ok(());
+    }
+
+    if options.repair_cue && romfile_extension == CUE_EXTENSION {
+        repair_cue(connection, progress_bar, &new_path, &rom, options).await?;
+    }
+
+    // persist in database
+    create_or_update_romfile(connection, system, &new_path, &[rom], options).await;
+
+    if multi_part {
+        report_parts(connection, progress_bar, &game).await;
+    }
+
+    if let Some(disc_directory) = disc_directory {
+        write_m3u(connection, progress_bar, system, &disc_directory, options).await?;
+    }
+
+    Ok(())
+}
+
+// emulators follow the FILE references of CUE files, point them to the stored BIN names
+async fn repair_cue<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    cue_path: &P,
+    cue_rom: &Rom,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    if options.dry_run {
+        return Ok(());
+    }
+    let bin_names: Vec<String> = find_roms_by_game_id_no_parents(connection, cue_rom.game_id)
+        .await
+        .into_iter()
+        .filter(|rom| rom.id != cue_rom.id)
+        .map(|rom| rom.name)
+        .collect();
+    let cue = try_with!(
+        fs::read_to_string(cue_path.as_ref()).await,
+        "Failed to read {:?}",
+        cue_path.as_ref()
+    );
+
+    // only FILE lines are rewritten, unknown names are replaced in order
+    let mut repaired_cue = String::new();
+    let mut file_index = 0;
+    for line in cue.split_inclusive('\n') {
+        let captures = match CUE_FILE_REGEX.captures(line) {
+            Some(captures) => captures,
+            None => {
+                repaired_cue.push_str(line);
+                continue;
+            }
+        };
+        let file_name = captures.get(2).unwrap().as_str();
+        let bin_name = bin_names
+            .iter()
+            .find(|bin_name| bin_name.to_lowercase() == file_name.to_lowercase())
+            .or_else(|| bin_names.get(file_index));
+        file_index += 1;
+        match bin_name {
+            Some(bin_name) => repaired_cue.push_str(&format!(
+                "{}\"{}\"{}",
+                captures.get(1).unwrap().as_str(),
+                bin_name,
+                captures.get(3).unwrap().as_str()
+            )),
+            None => repaired_cue.push_str(line),
+        }
+    }
+
+    if repaired_cue != cue {
+        progress_bar.println("Repairing CUE file references");
+        try_with!(
+            fs::write(cue_path.as_ref(), repaired_cue).await,
+            "Failed to write {:?}",
+            cue_path.as_ref()
+        );
+    }
+
+    Ok(())
+}
+
+async fn report_parts(connection: &mut SqliteConnection, progress_bar: &ProgressBar, game: &Game) {
+    let roms = find_roms_by_game_id_no_parents(connection, game.id).await;
+    let imported = roms.iter().filter(|rom| rom.romfile_id.is_some()).count();
+    if imported == roms.len() {
+        progress_bar.println(format!(
+            "All {} parts of \"{}\" imported, game is complete",
+            roms.len(),
+            game.name
+        ));
+    } else {
+        progress_bar.println(format!(
+            "{}/{} parts of \"{}\" imported",
+            imported,
+            roms.len(),
+            game.name
+        ));
+    }
+}
+
+// the discs of a game share a directory named after it, without the disc token
+async fn get_disc_directory<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    system_directory: &P,
+    system: &System,
+    game: &Game,
+) -> Option<async_std::path::PathBuf> {
+    if system.arcade || game.jbfolder || !get_bool(connection, "GROUP_MULTI_DISC_GAMES").await {
+        return None;
+    }
+    get_disc_base_name(&game.name).map(|base_name| system_directory.as_ref().join(base_name))
+}
+
+// rebuild the whole playlist from the database to include discs imported in previous runs
+async fn write_m3u<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    disc_directory: &P,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    if options.dry_run {
+        return Ok(());
+    }
+    let base_name = disc_directory
+        .as_ref()
+        .file_name()
+        .unwrap()
+        .to_str()
+        .unwrap();
+    let games: Vec<Game> = find_games_with_romfiles_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .filter(|game| get_disc_base_name(&game.name).as_deref() == Some(base_name))
+        .collect();
+
+    let mut entries: Vec<String> = Vec::new();
+    for game in games {
+        let romfile_ids: Vec<i64> = find_roms_with_romfile_by_game_ids(connection, &[game.id])
+            .await
+            .into_iter()
+            .map(|rom| rom.romfile_id.unwrap())
+            .collect();
+        let mut file_names: Vec<String> = find_romfiles_by_ids(connection, &romfile_ids)
+            .await
+            .into_iter()
+            .map(|romfile| Path::new(&romfile.path).to_path_buf())
+            .filter(|path| path.parent() == Some(disc_directory.as_ref()))
+            .map(|path| path.file_name().unwrap().to_str().unwrap().to_owned())
+            .collect();
+        file_names.sort();
+        file_names.dedup();
+        // CHDs embed their CUE, otherwise the CUE is what references the BINs
+        let entry = file_names
+            .iter()
+            .find(|file_name| file_name.ends_with(&format!(".{}", CHD_EXTENSION)))
+            .or_else(|| {
+                file_names
+                    .iter()
+                    .find(|file_name| file_name.ends_with(".cue"))
+            })
+            .or_else(|| file_names.first());
+        if let Some(entry) = entry {
+            entries.push(entry.to_owned());
+        }
+    }
+
+    let m3u_path = disc_directory
+        .as_ref()
+        .join(format!("{}.{}", base_name, M3U_EXTENSION));
+    progress_bar.println(fit_to_terminal(&format!(
+        "Writing {:?}",
+        m3u_path.as_os_str()
+    )));
+    try_with!(
+        fs::write(&m3u_path, entries.join("\n") + "\n").await,
+        "Failed to write {:?}",
+        m3u_path
+    );
+
+    Ok(())
+}
+
+async fn import_iso_contents<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system: &System,
+    header: &Option<Header>,
+    romfile_path: &P,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<bool> {
+    let progress_bar = progress_sink.progress_bar();
+    let iso_files = match iso9660::parse_iso(progress_bar, romfile_path) {
+        Ok(iso_files) if !iso_files.is_empty() => iso_files,
+        _ => return Ok(false),
+    };
+    progress_bar.println("Importing the files contained in the ISO");
+
+    // import every file like a directory walk, the ISO itself is left untouched
+    check_tools_available_for_files(
+        iso_files.iter().map(|iso_file| &iso_file.path),
+        system.archive_is_rom,
+    )?;
+    let tmp_directory = create_tmp_directory(connection).await?;
+    for iso_file in &iso_files {
+        let extracted_path = iso9660::extract_file_from_iso(
+            progress_bar,
+            romfile_path,
+            iso_file,
+            &tmp_directory.path(),
+        )?;
+        Box::pin(import_rom(
+            connection,
+            progress_sink,
+            system,
+            header,
+            &extracted_path,
+            hash_algorithm,
+            options,
+        ))
+        .await?;
+    }
+
+    Ok(true)
+}
+
+async fn move_to_destination<P: AsRef<Path>, Q: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    romfile_path: &P,
+    new_path: &Q,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<bool> {
+    let progress_bar = progress_sink.progress_bar();
+    // files left over by a previous run or put there manually aren't tracked
+    if romfile_path.as_ref() == new_path.as_ref()
+        || !new_path.as_ref().is_file().await
+        || find_romfile_by_path(connection, new_path.as_ref().as_os_str().to_str().unwrap())
+            .await
+            .is_some()
+    {
+        rename_file(progress_bar, romfile_path, new_path, options).await?;
+        info!(destination = %new_path.as_ref().display(), "Moved");
+        progress_sink.on_moved(romfile_path.as_ref(), new_path.as_ref());
+        return Ok(true);
+    }
+
+    progress_bar.println(format!(
+        "Destination {:?} already exists and is untracked",
+        new_path.as_ref().as_os_str()
+    ));
+    let untracked_destination = match get_string(connection, "UNTRACKED_DESTINATION")
+        .await
+        .map(|value| value.to_uppercase())
+        .as_deref()
+    {
+        Some("SKIP") => UntrackedDestination::Skip,
+        Some("ADOPT") => UntrackedDestination::Adopt,
+        Some("OVERWRITE") => UntrackedDestination::Overwrite,
+        Some(_) | None => FromPrimitive::from_usize(select(
+            UNTRACKED_DESTINATIONS,
+            "Please select an action",
+            None,
+            None,
+        )?)
+        .unwrap(),
+    };
+
+    match untracked_destination {
+        UntrackedDestination::Skip => {
+            progress_bar.println("Skipping");
+            Ok(false)
+        }
+        UntrackedDestination::Adopt => {
+            let (size, hash) = get_size_and_hash(
+                connection,
+                progress_bar,
+                romfile_path,
+                &None,
+                1,
+                2,
+                hash_algorithm,
+            )
+            .await?;
+            let (new_size, new_hash) = get_size_and_hash(
+                connection,
+                progress_bar,
+                new_path,
+                &None,
+                2,
+                2,
+                hash_algorithm,
+            )
+            .await?;
+            if size != new_size || hash != new_hash {
+                progress_bar.println("Destination doesn't match, skipping");
+                return Ok(false);
+            }
+            progress_bar.println("Adopting destination");
+            remove_file(progress_bar, romfile_path, options).await?;
+            progress_sink.on_moved(romfile_path.as_ref(), new_path.as_ref());
+            Ok(true)
+        }
+        UntrackedDestination::Overwrite => {
+            remove_file(progress_bar, new_path, options).await?;
+            rename_file(progress_bar, romfile_path, new_path, options).await?;
+            progress_sink.on_moved(romfile_path.as_ref(), new_path.as_ref());
+            Ok(true)
+        }
+    }
+}
+
+async fn is_ignored<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    romfile_path: &P,
+) -> SimpleResult<bool> {
+    let romfile_name = romfile_path.as_ref().file_name().unwrap().to_str().unwrap();
+    for pattern in get_list(connection, "IGNORE_PATTERNS").await {
+        let regex = format!(
+            "(?i)^{}$",
+            regex::escape(&pattern)
+                .replace("\\*", ".*")
+                .replace("\\?", ".")
+        );
+        if try_with!(Regex::new(&regex), "Failed to parse ignore pattern").is_match(romfile_name) {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
 
-    Ok(())
+// archives report sizes and CRCs from their headers, a CRC32 collision could slip through
+fn check_strict_size(
+    progress_sink: &dyn ProgressSink,
+    rom: &Rom,
+    actual_size: u64,
+    options: &ImportOptions,
+) -> bool {
+    if !options.strict_size || actual_size == rom.size as u64 {
+        return true;
+    }
+    progress_sink.progress_bar().println(format!(
+        "Size mismatch: {} bytes instead of {}",
+        actual_size, rom.size
+    ));
+    warn!(actual_size, expected_size = rom.size, "Size mismatch");
+    false
 }
 
+#[instrument(
+    level = "debug",
+    skip(connection, progress_sink, system, hash_algorithm, options)
+)]
 async fn find_rom_by_hash(
     connection: &mut SqliteConnection,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
+    name: &str,
     size: u64,
     hash: &str,
     system: &System,
     hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
 ) -> SimpleResult<Option<Rom>> {
+    let progress_bar = progress_sink.progress_bar();
     let rom: Option<Rom>;
     let mut roms = match hash_algorithm {
         HashAlgorithm::Crc => {
@@ -930,16 +3691,42 @@ async fn find_rom_by_hash(
         }
     };
 
+    // fall back to name and size for ROMs without any hash, this is less reliable
+    if roms.is_empty() && get_bool(connection, "MATCH_BY_NAME_SIZE").await {
+        roms = find_roms_without_romfile_and_hashes_by_name_and_size_and_system_id(
+            connection, name, size, system.id,
+        )
+        .await;
+        if !roms.is_empty() {
+            progress_bar.println("Matching by name and size");
+        }
+    }
+
     // abort if no match
     if roms.is_empty() {
         progress_bar.println("No match");
+        info!("No match");
         return Ok(None);
     }
 
-    // let user choose the rom if there are multiple matches
+    // let user choose the rom if there are multiple matches, unless the choice was remembered
+    let preferred_rom_ids = if roms.len() > 1 {
+        find_match_preference_rom_ids_by_size_and_hash(connection, size_to_i64(size)?, hash).await
+    } else {
+        Vec::new()
+    };
     if roms.len() == 1 {
         rom = Some(roms.remove(0));
         progress_bar.println(&format!("Matches \"{}\"", rom.as_ref().unwrap().name));
+    } else if let Some(i) = roms
+        .iter()
+        .position(|rom| preferred_rom_ids.contains(&rom.id))
+    {
+        rom = Some(roms.remove(i));
+        progress_bar.println(&format!(
+            "Matches remembered \"{}\"",
+            rom.as_ref().unwrap().name
+        ));
     } else {
         let mut roms_games: Vec<(Rom, Game)> = vec![];
         for rom in roms {
@@ -947,6 +3734,11 @@ async fn find_rom_by_hash(
             roms_games.push((rom, game));
         }
         rom = prompt_for_rom_game(&mut roms_games)?;
+        if let Some(rom) = &rom {
+            if options.remember_matches {
+                remember_match_preference(connection, system, size_to_i64(size)?, hash, rom).await;
+            }
+        }
     }
 
     // abort if rom already has a file
@@ -954,20 +3746,40 @@ async fn find_rom_by_hash(
         let romfile =
             find_romfile_by_id(connection, rom.as_ref().unwrap().romfile_id.unwrap()).await;
         progress_bar.println(&format!("Duplicate of \"{}\"", romfile.path));
+        warn!(duplicate = %romfile.path, "Duplicate");
         return Ok(None);
     }
 
+    if let Some(rom) = &rom {
+        info!(rom = %rom.name, "Matched");
+        progress_sink.on_match(rom);
+    }
+
     Ok(rom)
 }
 
+// a single choice is kept per collision and system
+async fn remember_match_preference(
+    connection: &mut SqliteConnection,
+    system: &System,
+    size: i64,
+    hash: &str,
+    rom: &Rom,
+) {
+    delete_match_preferences_by_size_and_hash_and_system_id(connection, size, hash, system.id)
+        .await;
+    create_match_preference(connection, size, hash, rom.id).await;
+}
+
 #[cfg(feature = "ird")]
 async fn find_sfb_rom_by_md5(
     connection: &mut SqliteConnection,
     size: u64,
     md5: &str,
     system: &System,
-    progress_bar: &ProgressBar,
+    progress_sink: &dyn ProgressSink,
 ) -> SimpleResult<Option<Rom>> {
+    let progress_bar = progress_sink.progress_bar();
     let rom: Option<Rom>;
     let mut roms = find_roms_without_romfile_by_name_and_size_and_md5_and_system_id(
         connection,
@@ -981,6 +3793,7 @@ async fn find_sfb_rom_by_md5(
     // abort if no match
     if roms.is_empty() {
         progress_bar.println("No match");
+        info!("No match");
         return Ok(None);
     }
 
@@ -1002,17 +3815,29 @@ async fn find_sfb_rom_by_md5(
         let romfile =
             find_romfile_by_id(connection, rom.as_ref().unwrap().romfile_id.unwrap()).await;
         progress_bar.println(&format!("Duplicate of \"{}\"", romfile.path));
+        warn!(duplicate = %romfile.path, "Duplicate");
         return Ok(None);
     }
 
+    if let Some(rom) = &rom {
+        info!(rom = %rom.name, "Matched");
+        progress_sink.on_match(rom);
+    }
+
     Ok(rom)
 }
 
 async fn create_or_update_romfile<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
+    system: &System,
     romfile_path: &P,
     roms: &[Rom],
+    options: &ImportOptions,
 ) {
+    if options.dry_run {
+        debug!(path = %romfile_path.as_ref().display(), "Would record");
+        return;
+    }
     let romfile = find_romfile_by_path(
         connection,
         romfile_path.as_ref().as_os_str().to_str().unwrap(),
@@ -1027,6 +3852,8 @@ async fn create_or_update_romfile<P: AsRef<Path>>(
                 romfile_path.as_ref().metadata().await.unwrap().len(),
             )
             .await;
+            // the file was rewritten, its cached checksums are stale
+            clear_romfile_checksum_cache(connection, romfile.id).await;
             romfile.id
         }
         None => {
@@ -1038,21 +3865,455 @@ async fn create_or_update_romfile<P: AsRef<Path>>(
             .await
         }
     };
+    // keep track of the DAT version the romfile was matched against
+    update_romfile_matched_dat_version(connection, romfile_id, &system.version).await;
     for rom in roms {
         update_rom_romfile(connection, rom.id, Some(romfile_id)).await;
     }
 }
 
-async fn move_to_trash<P: AsRef<Path>>(
+#[cfg(feature = "chd")]
+async fn auto_convert_to_chd(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    header: &Option<Header>,
+    previous_romfile_ids: &HashSet<i64>,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let games: Vec<Game> = find_games_with_romfiles_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .filter(|game| game.complete)
+        .collect();
+    let romfiles_by_id: HashMap<i64, Romfile> = find_romfiles_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .map(|romfile| (romfile.id, romfile))
+        .collect();
+
+    for game in games {
+        let roms = find_roms_with_romfile_by_game_ids(connection, &[game.id]).await;
+
+        // only convert games imported during this run
+        if roms
+            .iter()
+            .all(|rom| previous_romfile_ids.contains(&rom.romfile_id.unwrap()))
+        {
+            continue;
+        }
+
+        // only convert loose CUE/BIN and ISO files
+        let (cue_roms, roms): (Vec<Rom>, Vec<Rom>) = roms
+            .into_iter()
+            .partition(|rom| rom.name.ends_with(CUE_EXTENSION));
+        let is_cue_bin = cue_roms.len() == 1
+            && !roms.is_empty()
+            && roms.iter().all(|rom| {
+                romfiles_by_id
+                    .get(&rom.romfile_id.unwrap())
+                    .unwrap()
+                    .path
+                    .ends_with(BIN_EXTENSION)
+            });
+        let is_iso = cue_roms.is_empty()
+            && roms.len() == 1
+            && romfiles_by_id
+                .get(&roms[0].romfile_id.unwrap())
+                .unwrap()
+                .path
+                .ends_with(ISO_EXTENSION);
+        if !is_cue_bin && !is_iso {
+            continue;
+        }
+
+        progress_bar.println(format!("Converting \"{}\" to CHD", game.name));
+
+        let source_path = match cue_roms.first() {
+            Some(cue_rom) => {
+                &romfiles_by_id
+                    .get(&cue_rom.romfile_id.unwrap())
+                    .unwrap()
+                    .path
+            }
+            None => {
+                &romfiles_by_id
+                    .get(&roms[0].romfile_id.unwrap())
+                    .unwrap()
+                    .path
+            }
+        };
+        if options.dry_run {
+            print_dry_run(progress_bar, "convert", source_path);
+            continue;
+        }
+        let chd_path = chdman::create_chd(
+            progress_bar,
+            source_path,
+            &Path::new(source_path).parent().unwrap(),
+        )?;
+
+        // verify the CHD round-trip before removing anything
+        let tmp_directory = create_tmp_directory(connection).await?;
+        let extracted_paths = if is_cue_bin {
+            let names_sizes: Vec<(&str, u64)> = roms
+                .iter()
+                .map(|rom| (rom.name.as_str(), rom.size as u64))
+                .collect();
+            chdman::extract_chd_to_multiple_tracks(
+                progress_bar,
+                &chd_path,
+                &tmp_directory.path(),
+                &names_sizes,
+                true,
+            )
+            .await?
+        } else {
+            vec![
+                chdman::extract_chd_to_single_track(progress_bar, &chd_path, &tmp_directory.path())
+                    .await?,
+            ]
+        };
+        let mut valid = extracted_paths.len() == roms.len();
+        for (i, (rom, extracted_path)) in roms.iter().zip(extracted_paths.iter()).enumerate() {
+            let (size, hash) = get_size_and_hash(
+                connection,
+                progress_bar,
+                extracted_path,
+                header,
+                i,
+                roms.len(),
+                hash_algorithm,
+            )
+            .await?;
+            util::remove_file(progress_bar, extracted_path, true).await?;
+            let expected_hash = match hash_algorithm {
+                HashAlgorithm::Crc => rom.crc.as_ref(),
+                HashAlgorithm::Md5 => rom.md5.as_ref(),
+                HashAlgorithm::Sha1 => rom.sha1.as_ref(),
+            };
+            if size != rom.size as u64 || Some(&hash) != expected_hash {
+                valid = false;
+            }
+        }
+        if !valid {
+            progress_bar.println("CHD verification failed, keeping original files");
+            remove_file(progress_bar, &chd_path, options).await?;
+            continue;
+        }
+
+        // persist in database and remove loose files
+        let mut transaction = begin_transaction(connection).await;
+        create_or_update_romfile(&mut transaction, system, &chd_path, &roms, options).await;
+        for rom in &roms {
+            let romfile = romfiles_by_id.get(&rom.romfile_id.unwrap()).unwrap();
+            delete_romfile_by_id(&mut transaction, romfile.id).await;
+            remove_file(progress_bar, &romfile.path, options).await?;
+        }
+        commit_transaction(transaction).await;
+    }
+
+    Ok(())
+}
+
+#[cfg(feature = "cso")]
+async fn auto_convert_to_cso(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    header: &Option<Header>,
+    previous_romfile_ids: &HashSet<i64>,
+    hash_algorithm: &HashAlgorithm,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    let games: Vec<Game> = find_games_with_romfiles_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .filter(|game| game.complete)
+        .collect();
+
+    for game in games {
+        let roms = find_roms_with_romfile_by_game_ids(connection, &[game.id]).await;
+
+        // only convert single loose ISO files imported during this run
+        if roms.len() != 1 || previous_romfile_ids.contains(&roms[0].romfile_id.unwrap()) {
+            continue;
+        }
+        let rom = &roms[0];
+        let romfile = find_romfile_by_id(connection, rom.romfile_id.unwrap()).await;
+        if !romfile.path.ends_with(ISO_EXTENSION) {
+            continue;
+        }
+
+        progress_bar.println(format!("Converting \"{}\" to CSO", game.name));
+
+        if options.dry_run {
+            print_dry_run(progress_bar, "convert", &romfile.path);
+            continue;
+        }
+        let cso_path = maxcso::create_cso(
+            progress_bar,
+            &romfile.path,
+            &Path::new(&romfile.path).parent().unwrap(),
+        )?;
+
+        // verify the CSO round-trip before removing anything
+        let tmp_directory = create_tmp_directory(connection).await?;
+        let extracted_path = maxcso::extract_cso(progress_bar, &cso_path, &tmp_directory.path())?;
+        let (size, hash) = get_size_and_hash(
+            connection,
+            progress_bar,
+            &extracted_path,
+            header,
+            1,
+            1,
+            hash_algorithm,
+        )
+        .await?;
+        util::remove_file(progress_bar, &extracted_path, true).await?;
+        let expected_hash = match hash_algorithm {
+            HashAlgorithm::Crc => rom.crc.as_ref(),
+            HashAlgorithm::Md5 => rom.md5.as_ref(),
+            HashAlgorithm::Sha1 => rom.sha1.as_ref(),
+        };
+        if size != rom.size as u64 || Some(&hash) != expected_hash {
+            progress_bar.println("CSO verification failed, keeping original file");
+            remove_file(progress_bar, &cso_path, options).await?;
+            continue;
+        }
+
+        // persist in database and remove the loose file
+        let mut transaction = begin_transaction(connection).await;
+        create_or_update_romfile(&mut transaction, system, &cso_path, &roms, options).await;
+        delete_romfile_by_id(&mut transaction, romfile.id).await;
+        remove_file(progress_bar, &romfile.path, options).await?;
+        commit_transaction(transaction).await;
+    }
+
+    Ok(())
+}
+
+// the mirror is a convenience view, files are overwritten and never tracked
+async fn mirror_archives<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    previous_romfile_ids: &HashSet<i64>,
+    mirror_directory: &P,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    if system.archive_is_rom {
+        return Ok(());
+    }
+    let romfiles: Vec<Romfile> = find_romfiles_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .filter(|romfile| {
+            !previous_romfile_ids.contains(&romfile.id)
+                && ARCHIVE_EXTENSIONS.contains(
+                    &Path::new(&romfile.path)
+                        .extension()
+                        .unwrap_or_default()
+                        .to_str()
+                        .unwrap(),
+                )
+        })
+        .collect();
+    if romfiles.is_empty() {
+        return Ok(());
+    }
+    sevenzip::check_tool_available()?;
+
+    for romfile in romfiles {
+        let roms = find_roms_by_romfile_id(connection, romfile.id).await;
+        let rom_names: Vec<&str> = roms.iter().map(|rom| rom.name.as_str()).collect();
+        // 7z would prompt before overwriting, extract aside first
+        let tmp_directory = create_tmp_directory(connection).await?;
+        let extracted_paths = sevenzip::extract_files_from_archive(
+            progress_bar,
+            &romfile.path,
+            &rom_names,
+            &tmp_directory.path(),
+        )?;
+        for (rom_name, extracted_path) in rom_names.iter().zip(extracted_paths) {
+            rename_file(
+                progress_bar,
+                &extracted_path,
+                &mirror_directory.as_ref().join(rom_name),
+                options,
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+// emulators look for BIOS files in a single directory, under their canonical names
+async fn link_bios_romfiles<P: AsRef<Path>>(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
     system: &System,
+    previous_romfile_ids: &HashSet<i64>,
+    bios_directory: &P,
+) -> SimpleResult<()> {
+    let bios_game_ids: HashSet<i64> = find_games_by_system_id(connection, system.id)
+        .await
+        .into_iter()
+        .filter(|game| game.bios)
+        .map(|game| game.id)
+        .collect();
+    if bios_game_ids.is_empty() {
+        return Ok(());
+    }
+    for romfile in find_romfiles_by_system_id(connection, system.id).await {
+        if previous_romfile_ids.contains(&romfile.id) {
+            continue;
+        }
+        if find_roms_by_romfile_id(connection, romfile.id)
+            .await
+            .iter()
+            .any(|rom| bios_game_ids.contains(&rom.game_id))
+        {
+            let romfile_path = Path::new(&romfile.path);
+            create_symlink(
+                progress_bar,
+                &romfile_path,
+                &bios_directory
+                    .as_ref()
+                    .join(romfile_path.file_name().unwrap()),
+                false,
+            )
+            .await?;
+        }
+    }
+    Ok(())
+}
+
+fn print_dry_run<P: AsRef<Path>>(progress_bar: &ProgressBar, action: &str, path: &P) {
+    progress_bar.println(fit_to_terminal(&format!(
+        "Would {} {:?}",
+        action,
+        path.as_ref().as_os_str()
+    )));
+}
+
+// the files being imported are only touched through the wrappers below, dry runs stop there
+async fn rename_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    old_path: &P,
+    new_path: &Q,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    if options.dry_run {
+        print_dry_run(progress_bar, "move to", new_path);
+        return Ok(());
+    }
+    util::rename_file(progress_bar, old_path, new_path, false).await
+}
+
+async fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    old_path: &P,
+    new_path: &Q,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    if options.dry_run {
+        print_dry_run(progress_bar, "copy to", new_path);
+        return Ok(());
+    }
+    util::copy_file(progress_bar, old_path, new_path, false).await
+}
+
+async fn remove_file<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    path: &P,
+    options: &ImportOptions,
+) -> SimpleResult<()> {
+    if options.dry_run {
+        print_dry_run(progress_bar, "delete", path);
+        return Ok(());
+    }
+    util::remove_file(progress_bar, path, false).await
+}
+
+// the matching logic runs as usual on dry runs, what it recorded is discarded
+async fn end_transaction(transaction: Transaction<'_, Sqlite>, options: &ImportOptions) {
+    if options.dry_run {
+        rollback_transaction(transaction).await;
+    } else {
+        commit_transaction(transaction).await;
+    }
+}
+
+pub async fn move_to_trash<P: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_sink: &dyn ProgressSink,
+    system: &System,
     romfile_path: &P,
+    reason: TrashReason,
+    options: &ImportOptions,
 ) -> SimpleResult<()> {
-    let new_path = get_trash_directory(connection, progress_bar, system)
-        .await?
-        .join(romfile_path.as_ref().file_name().unwrap());
-    rename_file(progress_bar, romfile_path, &new_path, false).await?;
+    let progress_bar = progress_sink.progress_bar();
+    if options.dry_run {
+        progress_bar.println(fit_to_terminal(&format!(
+            "Would move {:?} to the trash",
+            romfile_path.as_ref().as_os_str()
+        )));
+        return Ok(());
+    }
+    if matches!(reason, TrashReason::NoMatch) && progress_sink.hold_unmatched(romfile_path.as_ref())
+    {
+        return Ok(());
+    }
+    delete_headerless_crcs_by_archive_path(
+        connection,
+        romfile_path.as_ref().as_os_str().to_str().unwrap(),
+    )
+    .await;
+    // unknown files may be valuable, unlike corrupt ones they are set aside for review
+    let unmatched_directory = match reason {
+        TrashReason::NoMatch => options.unmatched_directory.as_ref(),
+        _ => None,
+    };
+    if let Some(unmatched_directory) = unmatched_directory {
+        let new_path = unmatched_directory.join(romfile_path.as_ref().file_name().unwrap());
+        util::rename_file(progress_bar, romfile_path, &new_path, false).await?;
+        warn!(?reason, destination = %new_path.display(), "Set aside for review");
+        return Ok(());
+    }
+    if get_trash_backend(connection).await == TrashBackend::SystemTrash {
+        move_to_system_trash(progress_bar, romfile_path, false).await?;
+        warn!(?reason, "Trashed to the system trash");
+        if let Some(romfile) = find_romfile_by_path(
+            connection,
+            romfile_path.as_ref().as_os_str().to_str().unwrap(),
+        )
+        .await
+        {
+            delete_romfile_by_id(connection, romfile.id).await;
+        }
+        return Ok(());
+    }
+    let trash_directory =
+        get_trash_reason_directory(connection, progress_bar, system, &reason).await?;
+    let source_relative_path = options
+        .source_directory
+        .as_ref()
+        .and_then(|source_directory| {
+            AsRef::<std::path::Path>::as_ref(romfile_path.as_ref())
+                .strip_prefix(source_directory)
+                .ok()
+                .map(PathBuf::from)
+        });
+    let new_path = match source_relative_path {
+        Some(source_relative_path) => trash_directory.join(source_relative_path),
+        None => trash_directory.join(romfile_path.as_ref().file_name().unwrap()),
+    };
+    util::rename_file(progress_bar, romfile_path, &new_path, false).await?;
+    warn!(?reason, destination = %new_path.display(), "Trashed");
+    progress_sink.on_trashed(romfile_path.as_ref(), &new_path);
     match find_romfile_by_path(connection, &new_path.as_os_str().to_str().unwrap()).await {
         Some(romfile) => {
             update_romfile(
@@ -1075,6 +4336,8 @@ async fn move_to_trash<P: AsRef<Path>>(
     Ok(())
 }
 
+#[cfg(all(test, feature = "chd"))]
+mod test_chd_dvd;
 #[cfg(all(test, feature = "chd"))]
 mod test_chd_multiple_tracks;
 #[cfg(all(test, feature = "chd"))]
@@ -1083,11 +4346,85 @@ mod test_chd_multiple_tracks_without_cue_should_fail;
 mod test_chd_single_track;
 #[cfg(all(test, feature = "cso"))]
 mod test_cso;
+#[cfg(all(test, feature = "chd"))]
+mod test_cue_bin_auto_convert_chd;
+#[cfg(test)]
+mod test_directory_game;
+#[cfg(all(test, feature = "cso"))]
+mod test_iso_auto_convert_cso;
+#[cfg(test)]
+mod test_iso_contents;
+#[cfg(all(test, feature = "ird"))]
+mod test_jbfolder_preserve_empty_dirs;
 #[cfg(test)]
 mod test_original;
 #[cfg(test)]
+mod test_original_auto_system;
+#[cfg(test)]
+mod test_original_auto_system_duplicate;
+#[cfg(test)]
+mod test_original_bios_directory;
+#[cfg(all(test, unix))]
+mod test_original_broken_symlink;
+#[cfg(test)]
+mod test_original_cue_repair;
+#[cfg(test)]
+mod test_original_directory_limit;
+#[cfg(test)]
+mod test_original_directory_no_recursive;
+#[cfg(test)]
+mod test_original_dry_run;
+#[cfg(test)]
 mod test_original_headered;
 #[cfg(test)]
+mod test_original_headered_a7800;
+#[cfg(test)]
+mod test_original_headered_mismatch_guard;
+#[cfg(test)]
+mod test_original_ignore_marker;
+#[cfg(test)]
+mod test_original_ignore_patterns;
+#[cfg(test)]
+mod test_original_match_by_name_size;
+#[cfg(test)]
+mod test_original_match_preference;
+#[cfg(test)]
+mod test_original_matched_dat_version;
+#[cfg(test)]
+mod test_original_min_free_space;
+#[cfg(test)]
+mod test_original_multi_disc;
+#[cfg(test)]
+mod test_original_multi_part;
+#[cfg(test)]
+mod test_original_progress_sink;
+#[cfg(test)]
+mod test_original_report_unmatched_dir;
+#[cfg(test)]
+mod test_original_snapshot;
+#[cfg(test)]
+mod test_original_status_lines;
+#[cfg(test)]
+mod test_original_stdin_tar;
+#[cfg(test)]
+mod test_original_strict_size;
+#[cfg(test)]
+mod test_original_system_from_dir;
+#[cfg(test)]
+mod test_original_tracing;
+#[cfg(test)]
+mod test_original_trash_preserve_structure;
+#[cfg(test)]
+mod test_original_untracked_destination_adopt;
+#[cfg(test)]
+mod test_original_untracked_destination_skip;
+#[cfg(all(test, feature = "pbp"))]
+mod test_pbp;
+#[cfg(test)]
+mod test_rar_multiple_tracks;
+#[cfg(test)]
+mod test_rar_single_file;
+#[cfg(test)]
 mod test_sevenzip_multiple_files_full_game;
 #[cfg(test)]
 mod test_sevenzip_multiple_files_headered_mixed_games;
@@ -1096,8 +4433,30 @@ mod test_sevenzip_multiple_files_mixed_games;
 #[cfg(test)]
 mod test_sevenzip_multiple_files_partial_game;
 #[cfg(test)]
+mod test_sevenzip_multiple_files_partial_game_delete_source;
+#[cfg(test)]
 mod test_sevenzip_single_file;
 #[cfg(test)]
 mod test_sevenzip_single_file_headered;
 #[cfg(test)]
+mod test_zip_arcade_samples;
+#[cfg(test)]
+mod test_zip_auto_system;
+#[cfg(test)]
+mod test_zip_crc_only_fast;
+#[cfg(test)]
+mod test_zip_duplicate_member_names;
+#[cfg(test)]
+mod test_zip_mirror_loose;
+#[cfg(test)]
+mod test_zip_multiple_archives_partial_game;
+#[cfg(test)]
+mod test_zip_multiple_files_parallel_hashing;
+#[cfg(test)]
 mod test_zip_single_file;
+#[cfg(test)]
+mod test_zip_single_file_fast_path;
+#[cfg(all(test, feature = "cso"))]
+mod test_zso;
+#[cfg(all(test, feature = "zst"))]
+mod test_zst;
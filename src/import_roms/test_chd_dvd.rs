@@ -0,0 +1,81 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::path::PathBuf;
+use std::process::Command;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (DVD).chd");
+    let status = Command::new("chdman")
+        .arg("createdvd")
+        .arg("-i")
+        .arg(test_directory.join("Test Game (USA, Europe).iso"))
+        .arg("-o")
+        .arg(&romfile_path)
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    let games = find_games_with_romfiles_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 1);
+
+    let game = games.get(0).unwrap();
+    assert_eq!(game.name, "Test Game (USA, Europe) (ISO)");
+    assert!(game.complete);
+
+    let rom = roms.get(0).unwrap();
+    assert_eq!(rom.name, "Test Game (USA, Europe).iso");
+    assert_eq!(rom.game_id, game.id);
+
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).chd")
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(Path::new(&romfile.path).is_file().await);
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+}
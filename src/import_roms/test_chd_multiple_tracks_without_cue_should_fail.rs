@@ -51,6 +51,7 @@ async fn test() {
         &None,
         &romfile_path,
         &HashAlgorithm::Crc,
+        &ImportOptions::default(),
     )
     .await
     .unwrap();
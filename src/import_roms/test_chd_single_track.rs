@@ -50,6 +50,7 @@ async fn test() {
         &None,
         &romfile_path,
         &HashAlgorithm::Crc,
+        &ImportOptions::default(),
     )
     .await
     .unwrap();
@@ -59,6 +59,7 @@ async fn test() {
         &None,
         &romfile_path,
         &HashAlgorithm::Crc,
+        &ImportOptions::default(),
     )
     .await
     .unwrap();
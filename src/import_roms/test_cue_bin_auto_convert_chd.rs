@@ -0,0 +1,119 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let setting = find_setting_by_key(&mut connection, "AUTO_CONVERT_CD")
+        .await
+        .unwrap();
+    update_setting(&mut connection, setting.id, Some(String::from("CHD"))).await;
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<String> = Vec::new();
+    for file_name in &[
+        "Test Game (USA, Europe) (Multiple Tracks).cue",
+        "Test Game (USA, Europe) (Track 01).bin",
+        "Test Game (USA, Europe) (Track 02).bin",
+    ] {
+        let romfile_path = tmp_directory.join(file_name);
+        fs::copy(test_directory.join(file_name), &romfile_path)
+            .await
+            .unwrap();
+        romfile_paths.push(romfile_path.as_os_str().to_str().unwrap().to_owned());
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let mut args = vec!["import-roms"];
+    args.extend(romfile_paths.iter().map(String::as_str));
+    let matches = subcommand().get_matches_from(&args);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let games = find_games_with_romfiles_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 1);
+    let game = games.get(0).unwrap();
+    assert!(game.complete);
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 3);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).chd")
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(Path::new(&romfile.path).is_file().await);
+    assert_eq!(romfile.matched_dat_version.as_deref(), Some("20200721"));
+
+    let rom = roms.get(0).unwrap();
+    assert_eq!(rom.name, "Test Game (USA, Europe) (Track 01).bin");
+    assert_eq!(rom.game_id, game.id);
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+    assert!(
+        !system_directory
+            .join("Test Game (USA, Europe) (Track 01).bin")
+            .is_file()
+            .await
+    );
+    let rom = roms.get(1).unwrap();
+    assert_eq!(rom.name, "Test Game (USA, Europe) (Track 02).bin");
+    assert_eq!(rom.game_id, game.id);
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+    assert!(
+        !system_directory
+            .join("Test Game (USA, Europe) (Track 02).bin")
+            .is_file()
+            .await
+    );
+
+    let rom = roms.get(2).unwrap();
+    assert_eq!(rom.name, "Test Game (USA, Europe).cue");
+    let romfile = romfiles.get(1).unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).cue")
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(Path::new(&romfile.path).is_file().await);
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+}
@@ -0,0 +1,93 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20221227) (Directory).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let folder_path = tmp_directory.join("Test Game");
+    fs::create_dir_all(folder_path.join("DATA")).await.unwrap();
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        folder_path.join("GAME.ROM"),
+    )
+    .await
+    .unwrap();
+    fs::copy(
+        test_directory.join("Test Game (Japan).rom"),
+        folder_path.join("DATA").join("EXTRA.ROM"),
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    let matches =
+        subcommand().get_matches_from(&["import-roms", folder_path.as_os_str().to_str().unwrap()]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let game = find_games_by_system_id(&mut connection, system.id)
+        .await
+        .into_iter()
+        .find(|game| game.name == "Test Game (USA, Europe)")
+        .unwrap();
+    assert!(game.jbfolder);
+    assert!(game.complete);
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+
+    for rom in roms {
+        assert_eq!(rom.game_id, game.id);
+        let romfile = romfiles
+            .iter()
+            .find(|romfile| Some(romfile.id) == rom.romfile_id)
+            .unwrap();
+        assert_eq!(
+            romfile.path,
+            system_directory
+                .join(&game.name)
+                .join(&rom.name)
+                .as_os_str()
+                .to_str()
+                .unwrap()
+        );
+        assert!(Path::new(&romfile.path).is_file().await);
+    }
+    assert!(!folder_path.is_dir().await);
+}
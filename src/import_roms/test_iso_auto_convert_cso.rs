@@ -0,0 +1,96 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use std::env;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    env::set_var(
+        "PATH",
+        format!(
+            "{}:{}",
+            test_directory.as_os_str().to_str().unwrap(),
+            env::var("PATH").unwrap()
+        ),
+    );
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let setting = find_setting_by_key(&mut connection, "AUTO_CONVERT_ISO")
+        .await
+        .unwrap();
+    update_setting(&mut connection, setting.id, Some(String::from("cso"))).await;
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).iso");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).iso"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let games = find_games_with_romfiles_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 1);
+    let game = games.get(0).unwrap();
+    assert!(game.complete);
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let rom = roms.get(0).unwrap();
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(rom.name, "Test Game (USA, Europe).iso");
+    assert_eq!(rom.game_id, game.id);
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).cso")
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(Path::new(&romfile.path).is_file().await);
+    assert!(
+        !system_directory
+            .join("Test Game (USA, Europe).iso")
+            .is_file()
+            .await
+    );
+}
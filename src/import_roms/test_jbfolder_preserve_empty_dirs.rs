@@ -0,0 +1,106 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches =
+        config::subcommand().get_matches_from(&["config", "-s", "PRESERVE_EMPTY_DIRS", "true"]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let game = find_games_by_system_id(&mut connection, system.id)
+        .await
+        .into_iter()
+        .find(|game| game.name == "Test Game (USA, Europe) (ISO)")
+        .unwrap();
+    let parent_rom = find_roms_by_game_id_no_parents(&mut connection, game.id)
+        .await
+        .remove(0);
+
+    // mimic an imported IRD
+    create_rom(
+        &mut connection,
+        PS3_DISC_SFB,
+        8,
+        "c8ab04e4e408dd9c6554b4cfa54f85c1",
+        game.id,
+        Some(parent_rom.id),
+    )
+    .await;
+    create_rom(
+        &mut connection,
+        "PS3_GAME/USRDIR/EBOOT.BIN",
+        5,
+        "8282d26205a5eb3cfeb05a6f956d387d",
+        game.id,
+        Some(parent_rom.id),
+    )
+    .await;
+    update_game_jbfolder(&mut connection, game.id, true).await;
+
+    let folder_path = tmp_directory.join("Test Game (USA, Europe)");
+    let usrdir_path = folder_path.join("PS3_GAME").join("USRDIR");
+    fs::create_dir_all(&usrdir_path).await.unwrap();
+    fs::write(folder_path.join(PS3_DISC_SFB), "PS3 disc")
+        .await
+        .unwrap();
+    fs::write(usrdir_path.join("EBOOT.BIN"), "EBOOT")
+        .await
+        .unwrap();
+
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    import_jbfolder(
+        &mut connection,
+        &progress_bar,
+        &system,
+        &folder_path,
+        &ImportOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert!(
+        system_directory
+            .join(&game.name)
+            .join("PS3_GAME/USRDIR/EBOOT.BIN")
+            .is_file()
+            .await
+    );
+    assert!(!usrdir_path.join("EBOOT.BIN").is_file().await);
+    assert!(usrdir_path.is_dir().await);
+}
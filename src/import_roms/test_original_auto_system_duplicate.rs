@@ -0,0 +1,89 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20200721).dat",
+        "tests/Test System II (20230114).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let incoming_directory = tmp_directory.join("incoming");
+    fs::create_dir_all(&incoming_directory).await.unwrap();
+    let romfile_path = incoming_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        incoming_directory.as_os_str().to_str().unwrap(),
+        "--auto-system",
+        "--duplicate-across-systems",
+        "-q",
+    ]);
+    PROBED_SYSTEMS.store(0, Ordering::SeqCst);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(PROBED_SYSTEMS.load(Ordering::SeqCst), 2);
+    assert!(!romfile_path.is_file().await);
+
+    for system_name in ["Test System", "Test System II"] {
+        let system = find_system_by_name(&mut connection, system_name)
+            .await
+            .unwrap();
+        let romfiles = find_romfiles_by_system_id(&mut connection, system.id).await;
+        assert_eq!(romfiles.len(), 1);
+        let romfile = romfiles.get(0).unwrap();
+        assert!(romfile.path.ends_with("Test Game (USA, Europe).rom"));
+        assert!(Path::new(&romfile.path).is_file().await);
+
+        let roms = find_roms_by_romfile_id(&mut connection, romfile.id).await;
+        assert_eq!(roms.len(), 1);
+        assert_eq!(roms.get(0).unwrap().name, "Test Game (USA, Europe).rom");
+    }
+    assert_eq!(find_romfiles(&mut connection).await.len(), 2);
+
+    // the duplicate is a hard link rather than a second copy
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+        let inodes: Vec<u64> = find_romfiles(&mut connection)
+            .await
+            .iter()
+            .map(|romfile| std::fs::metadata(&romfile.path).unwrap().ino())
+            .collect();
+        assert_eq!(inodes[0], inodes[1]);
+    }
+}
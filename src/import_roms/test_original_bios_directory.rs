@@ -0,0 +1,78 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let bios_directory = tmp_directory.join("BIOS");
+    fs::create_dir_all(&bios_directory).await.unwrap();
+    let matches = config::subcommand().get_matches_from(&[
+        "config",
+        "-s",
+        "BIOS_DIRECTORY",
+        bios_directory.as_os_str().to_str().unwrap(),
+    ]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20230108) (BIOS).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (Japan).rom");
+    fs::copy(test_directory.join("Test Game (Japan).rom"), &romfile_path)
+        .await
+        .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        "-s",
+        "1",
+        "-q",
+        romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    let romfile = romfiles.get(0).unwrap();
+    assert!(romfile.path.ends_with("bios_jp.rom"));
+
+    let link_path = bios_directory.join("bios_jp.rom");
+    assert!(fs::symlink_metadata(&link_path)
+        .await
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(
+        fs::read_link(&link_path).await.unwrap(),
+        PathBuf::from(&romfile.path)
+    );
+}
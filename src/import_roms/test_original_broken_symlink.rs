@@ -0,0 +1,102 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use std::os::unix;
+use std::sync::Mutex;
+use tempfile::{NamedTempFile, TempDir};
+
+struct MockProgressSink {
+    progress_bar: ProgressBar,
+    events: Mutex<Vec<String>>,
+}
+
+impl ProgressSink for MockProgressSink {
+    fn progress_bar(&self) -> &ProgressBar {
+        &self.progress_bar
+    }
+
+    fn on_match(&self, rom: &Rom) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("match {}", rom.name));
+    }
+
+    fn on_warning(&self, message: &str) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("warning {}", message));
+    }
+}
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_sink = MockProgressSink {
+        progress_bar: ProgressBar::hidden(),
+        events: Mutex::new(Vec::new()),
+    };
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_sink.progress_bar)
+        .await
+        .unwrap();
+
+    let broken_path = tmp_directory.join("Broken.rom");
+    unix::fs::symlink(tmp_directory.join("Vanished.rom"), &broken_path).unwrap();
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let header = find_header_by_system_id(&mut connection, system.id).await;
+
+    let romfile_paths = [
+        std::path::PathBuf::from(broken_path.as_os_str()),
+        std::path::PathBuf::from(romfile_path.as_os_str()),
+    ];
+
+    // when
+    import_romfiles(
+        &mut connection,
+        &progress_sink,
+        &system,
+        &header,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let events = progress_sink.events.lock().unwrap().clone();
+    assert_eq!(events.len(), 2);
+    assert!(events[0].starts_with("warning Failed to get canonicalized path"));
+    assert!(events[0].ends_with("skipping"));
+    assert_eq!(events[1], "match Test Game (USA, Europe).rom");
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(fs::symlink_metadata(&broken_path).await.is_ok());
+}
@@ -0,0 +1,71 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20230105) (Lowercase CUE).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // the FILE references are lowercased, the BINs are stored under their ROM names
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe) (Lowercase).cue");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Lowercase).cue"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        romfile_path.as_os_str().to_str().unwrap(),
+        "--repair-cue",
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let cue_path = system_directory.join("Test Game (USA, Europe).cue");
+    let cue = fs::read_to_string(&cue_path).await.unwrap();
+    assert_eq!(
+        cue,
+        fs::read_to_string(test_directory.join("Test Game (USA, Europe) (Multiple Tracks).cue"))
+            .await
+            .unwrap()
+    );
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(romfile.path, cue_path.as_os_str().to_str().unwrap());
+    assert_eq!(romfile.size, cue.len() as i64);
+}
@@ -0,0 +1,73 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let incoming_directory = tmp_directory.join("incoming");
+    let nested_directory = incoming_directory.join("nested");
+    fs::create_dir_all(&nested_directory).await.unwrap();
+    for romfile_name in ["Test Game (Asia).rom", "Test Game (Japan).rom"] {
+        fs::copy(
+            test_directory.join(romfile_name),
+            incoming_directory.join(romfile_name),
+        )
+        .await
+        .unwrap();
+    }
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        nested_directory.join("Test Game (USA, Europe).rom"),
+    )
+    .await
+    .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        incoming_directory.as_os_str().to_str().unwrap(),
+        "--no-recursive",
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    assert!(romfiles
+        .iter()
+        .all(|romfile| !romfile.path.ends_with("Test Game (USA, Europe).rom")));
+
+    assert!(
+        nested_directory
+            .join("Test Game (USA, Europe).rom")
+            .is_file()
+            .await
+    );
+}
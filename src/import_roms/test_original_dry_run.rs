@@ -0,0 +1,86 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let source_directory = tmp_directory.join("Downloads");
+    fs::create_dir_all(&source_directory).await.unwrap();
+    let romfile_path = source_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+    let unknown_path = source_directory.join("Unknown Game.rom");
+    fs::write(&unknown_path, b"not listed in any DAT")
+        .await
+        .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        "-s",
+        "1",
+        "-q",
+        "--dry-run",
+        source_directory.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(romfile_path.is_file().await);
+    assert!(unknown_path.is_file().await);
+
+    let system = find_systems(&mut connection).await.remove(0);
+    assert!(!system.complete);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+    assert!(
+        !system_directory
+            .join("Test Game (USA, Europe).rom")
+            .is_file()
+            .await
+    );
+    assert!(
+        !system_directory
+            .join("Trash")
+            .join("Unknown Game.rom")
+            .is_file()
+            .await
+    );
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert!(romfiles.is_empty());
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert!(roms.is_empty());
+}
@@ -52,6 +52,7 @@ async fn test() {
         &romfile_path,
         romfile_path.extension().unwrap().to_str().unwrap(),
         &HashAlgorithm::Crc,
+        &ImportOptions::default(),
     )
     .await
     .unwrap();
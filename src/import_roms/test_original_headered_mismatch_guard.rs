@@ -0,0 +1,105 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use std::sync::Mutex;
+use tempfile::{NamedTempFile, TempDir};
+
+struct MockProgressSink {
+    progress_bar: ProgressBar,
+    events: Mutex<Vec<String>>,
+}
+
+impl ProgressSink for MockProgressSink {
+    fn progress_bar(&self) -> &ProgressBar {
+        &self.progress_bar
+    }
+
+    fn on_trashed(&self, old_path: &Path, _new_path: &Path) {
+        self.events.lock().unwrap().push(format!(
+            "trashed {}",
+            old_path.file_name().unwrap().to_str().unwrap()
+        ));
+    }
+
+    fn on_warning(&self, message: &str) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("warning {}", message));
+    }
+}
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_sink = MockProgressSink {
+        progress_bar: ProgressBar::hidden(),
+        events: Mutex::new(Vec::new()),
+    };
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    // the DAT is headerless, the files are headered
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_sink.progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<std::path::PathBuf> = Vec::new();
+    for i in 1..=6 {
+        let romfile_path = tmp_directory.join(format!("Test Game {} (Headered).rom", i));
+        fs::copy(
+            test_directory.join("Test Game (USA, Europe) (Headered).rom"),
+            &romfile_path,
+        )
+        .await
+        .unwrap();
+        romfile_paths.push(std::path::PathBuf::from(romfile_path.as_os_str()));
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let header = find_header_by_system_id(&mut connection, system.id).await;
+
+    // when
+    import_romfiles(
+        &mut connection,
+        &progress_sink,
+        &system,
+        &header,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions {
+            assume_yes: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(
+        *progress_sink.events.lock().unwrap(),
+        vec![
+            "warning 0/5 matched, is the header configuration correct?",
+            "trashed Test Game 1 (Headered).rom",
+            "trashed Test Game 2 (Headered).rom",
+            "trashed Test Game 3 (Headered).rom",
+            "trashed Test Game 4 (Headered).rom",
+            "trashed Test Game 5 (Headered).rom",
+            "trashed Test Game 6 (Headered).rom",
+        ]
+    );
+}
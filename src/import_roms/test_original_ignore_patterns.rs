@@ -0,0 +1,68 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    add_to_list(&mut connection, "IGNORE_PATTERNS", "*.nfo").await;
+
+    let import_directory = tmp_directory.join("import");
+    fs::create_dir_all(&import_directory).await.unwrap();
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        import_directory.join("Test Game (USA, Europe).rom"),
+    )
+    .await
+    .unwrap();
+    let nfo_path = import_directory.join("Release.NFO");
+    fs::write(&nfo_path, "scene release notes").await.unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let header = find_header_by_system_id(&mut connection, system.id).await;
+    let romfile_paths = [std::path::PathBuf::from(import_directory.as_os_str())];
+
+    // when
+    import_romfiles(
+        &mut connection,
+        &progress_bar,
+        &system,
+        &header,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(!romfiles
+        .iter()
+        .any(|romfile| romfile.path.ends_with("Release.NFO")));
+    assert!(nfo_path.is_file().await);
+}
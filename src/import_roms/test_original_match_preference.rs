@@ -0,0 +1,78 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::super::match_preferences;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20221223) (CRC Collision).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let japan_rom = find_roms_without_romfile_by_size_and_crc_and_system_id(
+        &mut connection,
+        256,
+        "cc721e14",
+        system.id,
+    )
+    .await
+    .into_iter()
+    .find(|rom| rom.name == "Test Game (Japan).rom")
+    .unwrap();
+
+    // the choice made at the prompt of a previous run with --remember
+    remember_match_preference(&mut connection, &system, 256, "cc721e14", &japan_rom).await;
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        romfile_path.as_os_str().to_str().unwrap(),
+        "--hash",
+        "CRC",
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    assert_eq!(roms.get(0).unwrap().id, japan_rom.id);
+
+    assert_eq!(
+        match_preferences::get_match_preferences(&mut connection).await,
+        vec!["Test System: cc721e14 (256) -> \"Test Game (Japan).rom\" (Test Game (Japan))"]
+    );
+}
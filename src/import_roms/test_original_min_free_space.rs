@@ -0,0 +1,117 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+struct FillingProgressSink {
+    progress_bar: ProgressBar,
+}
+
+impl ProgressSink for FillingProgressSink {
+    fn progress_bar(&self) -> &ProgressBar {
+        &self.progress_bar
+    }
+
+    // simulate the disk filling up once the first file is imported
+    fn on_moved(&self, _old_path: &Path, _new_path: &Path) {
+        *SIMULATED_FREE_SPACE.lock().unwrap() = Some(1 << 30);
+    }
+}
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_sink = FillingProgressSink {
+        progress_bar: ProgressBar::hidden(),
+    };
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = config::subcommand().get_matches_from(&["config", "-s", "MIN_FREE_SPACE", "10G"]);
+    config::main(&mut connection, &matches, &progress_sink.progress_bar)
+        .await
+        .unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_sink.progress_bar)
+        .await
+        .unwrap();
+
+    let first_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &first_path,
+    )
+    .await
+    .unwrap();
+    let second_path = tmp_directory.join("Test Game (Japan).rom");
+    fs::copy(test_directory.join("Test Game (Japan).rom"), &second_path)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let header = find_header_by_system_id(&mut connection, system.id).await;
+    let system_directory =
+        get_system_directory(&mut connection, &progress_sink.progress_bar, &system)
+            .await
+            .unwrap();
+
+    let romfile_paths = [
+        std::path::PathBuf::from(first_path.as_os_str()),
+        std::path::PathBuf::from(second_path.as_os_str()),
+    ];
+    *SIMULATED_FREE_SPACE.lock().unwrap() = Some(20 << 30);
+
+    // when
+    let result = import_romfiles(
+        &mut connection,
+        &progress_sink,
+        &system,
+        &header,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions::default(),
+    )
+    .await;
+
+    *SIMULATED_FREE_SPACE.lock().unwrap() = None;
+    set_min_free_space(0);
+
+    // then
+    assert!(result
+        .err()
+        .unwrap()
+        .as_str()
+        .contains("below minimum free space of 10.00 GiB"));
+
+    assert!(
+        system_directory
+            .join("Test Game (USA, Europe).rom")
+            .is_file()
+            .await
+    );
+    assert!(second_path.is_file().await);
+    assert!(
+        !system_directory
+            .join("Test Game (Japan).rom")
+            .is_file()
+            .await
+    );
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+}
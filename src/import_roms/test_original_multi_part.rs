@@ -0,0 +1,105 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches =
+        config::subcommand().get_matches_from(&["config", "-s", "GROUP_MULTI_PART_GAMES", "true"]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20221226) (Multiple Files).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // the game is made of "Test Game (USA, Europe) (Part 1).rom" and "Test Game (USA, Europe) (Part 2).rom"
+    let first_part_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &first_part_path,
+    )
+    .await
+    .unwrap();
+    let second_part_path = tmp_directory.join("Test Game (Japan).rom");
+    fs::copy(
+        test_directory.join("Test Game (Japan).rom"),
+        &second_part_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let matches = subcommand()
+        .get_matches_from(&["import-roms", first_part_path.as_os_str().to_str().unwrap()]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    let game = find_games_by_system_id(&mut connection, system.id)
+        .await
+        .remove(0);
+    let partial = game.complete;
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        second_part_path.as_os_str().to_str().unwrap(),
+    ]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(!partial);
+
+    let game = find_games_by_system_id(&mut connection, system.id)
+        .await
+        .remove(0);
+    assert_eq!(game.name, "Test Game (USA, Europe)");
+    assert!(game.complete);
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    for rom in roms {
+        assert_eq!(rom.game_id, game.id);
+        let romfile = find_romfile_by_id(&mut connection, rom.romfile_id.unwrap()).await;
+        assert_eq!(
+            romfile.path,
+            system_directory
+                .join(&game.name)
+                .join(&rom.name)
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+        );
+        assert!(Path::new(&romfile.path).is_file().await);
+    }
+}
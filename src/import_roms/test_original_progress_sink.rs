@@ -0,0 +1,130 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use std::sync::Mutex;
+use tempfile::{NamedTempFile, TempDir};
+
+struct MockProgressSink {
+    progress_bar: ProgressBar,
+    events: Mutex<Vec<String>>,
+}
+
+impl ProgressSink for MockProgressSink {
+    fn progress_bar(&self) -> &ProgressBar {
+        &self.progress_bar
+    }
+
+    fn on_file_start(&self, path: &Path) {
+        self.events.lock().unwrap().push(format!(
+            "start {}",
+            path.file_name().unwrap().to_str().unwrap()
+        ));
+    }
+
+    fn on_match(&self, rom: &Rom) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("match {}", rom.name));
+    }
+
+    fn on_moved(&self, _old_path: &Path, new_path: &Path) {
+        self.events.lock().unwrap().push(format!(
+            "moved {}",
+            new_path.file_name().unwrap().to_str().unwrap()
+        ));
+    }
+
+    fn on_trashed(&self, old_path: &Path, _new_path: &Path) {
+        self.events.lock().unwrap().push(format!(
+            "trashed {}",
+            old_path.file_name().unwrap().to_str().unwrap()
+        ));
+    }
+
+    fn on_progress(&self, position: u64, length: u64) {
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("progress {}/{}", position, length));
+    }
+}
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_sink = MockProgressSink {
+        progress_bar: ProgressBar::hidden(),
+        events: Mutex::new(Vec::new()),
+    };
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_sink.progress_bar)
+        .await
+        .unwrap();
+
+    let matched_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &matched_path,
+    )
+    .await
+    .unwrap();
+    let unmatched_path = tmp_directory.join("Test Game (USA, Europe) (Headered).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Headered).rom"),
+        &unmatched_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let header = find_header_by_system_id(&mut connection, system.id).await;
+
+    let romfile_paths = [
+        std::path::PathBuf::from(matched_path.as_os_str()),
+        std::path::PathBuf::from(unmatched_path.as_os_str()),
+    ];
+
+    // when
+    import_romfiles(
+        &mut connection,
+        &progress_sink,
+        &system,
+        &header,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(
+        *progress_sink.events.lock().unwrap(),
+        vec![
+            "start Test Game (USA, Europe).rom",
+            "match Test Game (USA, Europe).rom",
+            "moved Test Game (USA, Europe).rom",
+            "progress 1/2",
+            "start Test Game (USA, Europe) (Headered).rom",
+            "trashed Test Game (USA, Europe) (Headered).rom",
+            "progress 2/2",
+        ]
+    );
+}
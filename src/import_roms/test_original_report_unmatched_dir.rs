@@ -0,0 +1,68 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Unknown Game.rom");
+    fs::write(&romfile_path, b"not listed in any DAT")
+        .await
+        .unwrap();
+    let unmatched_directory = tmp_directory.join("Unidentified");
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        "-s",
+        "1",
+        "-q",
+        "--report-unmatched-dir",
+        unmatched_directory.as_os_str().to_str().unwrap(),
+        romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert!(!romfile_path.is_file().await);
+    assert!(unmatched_directory.join("Unknown Game.rom").is_file().await);
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+    assert!(
+        !system_directory
+            .join("Trash")
+            .join("Unknown Game.rom")
+            .is_file()
+            .await
+    );
+    assert!(find_romfiles(&mut connection).await.is_empty());
+}
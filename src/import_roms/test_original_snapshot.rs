@@ -0,0 +1,80 @@
+use super::super::check_roms;
+use super::super::create_system;
+use super::super::database::*;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let system = create_system::create_system(&mut connection, "Homebrew")
+        .await
+        .unwrap();
+
+    let import_directory = tmp_directory.join("import");
+    fs::create_dir_all(&import_directory).await.unwrap();
+    for name in ["Test Game (USA, Europe).rom", "Test Game (Japan).rom"] {
+        fs::copy(test_directory.join(name), import_directory.join(name))
+            .await
+            .unwrap();
+    }
+    let romfile_paths = [std::path::PathBuf::from(import_directory.as_os_str())];
+
+    // when
+    import_romfiles(
+        &mut connection,
+        &progress_bar,
+        &system,
+        &None,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions {
+            snapshot: true,
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    check_roms::check_system(
+        &mut connection,
+        &progress_bar,
+        &ProgressBar::hidden(),
+        &system,
+        &HashAlgorithm::Crc,
+        &check_roms::CheckOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let games = find_games_by_system_id(&mut connection, system.id).await;
+    assert_eq!(games.len(), 2);
+    assert!(games.iter().all(|game| game.complete));
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 2);
+    for romfile in romfiles {
+        assert!(!romfile.path.contains("/Trash/"));
+        assert!(Path::new(&romfile.path).is_file().await);
+    }
+}
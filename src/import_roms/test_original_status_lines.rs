@@ -0,0 +1,93 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use std::sync::Mutex;
+use tempfile::{NamedTempFile, TempDir};
+
+struct MockProgressSink {
+    progress_bar: ProgressBar,
+    statuses: Mutex<Vec<String>>,
+}
+
+impl ProgressSink for MockProgressSink {
+    fn progress_bar(&self) -> &ProgressBar {
+        &self.progress_bar
+    }
+
+    fn on_status(&self, status: &Status) {
+        assert!(status.free_space.is_some());
+        self.statuses.lock().unwrap().push(status.to_string());
+    }
+}
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_sink = MockProgressSink {
+        progress_bar: ProgressBar::hidden(),
+        statuses: Mutex::new(Vec::new()),
+    };
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_sink.progress_bar)
+        .await
+        .unwrap();
+
+    let folder_path = tmp_directory.join("roms");
+    fs::create_dir_all(&folder_path).await.unwrap();
+    for name in [
+        "Test Game (Asia).rom",
+        "Test Game (Japan).rom",
+        "Test Game (USA, Europe).rom",
+    ] {
+        fs::copy(test_directory.join(name), folder_path.join(name))
+            .await
+            .unwrap();
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let header = find_header_by_system_id(&mut connection, system.id).await;
+
+    let romfile_paths = [std::path::PathBuf::from(folder_path.as_os_str())];
+
+    // when
+    import_romfiles(
+        &mut connection,
+        &progress_sink,
+        &system,
+        &header,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions {
+            status_interval: Some(Duration::ZERO),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+
+    // then
+    let statuses = progress_sink.statuses.lock().unwrap();
+    assert_eq!(statuses.len(), 3);
+    assert!(statuses[0].starts_with("1/3 files done, 2 remaining, elapsed "));
+    assert!(statuses[2].starts_with("3/3 files done, 0 remaining, elapsed "));
+    for status in statuses.iter() {
+        assert!(status.contains(", ETA "));
+        assert!(status.ends_with(" free"));
+    }
+}
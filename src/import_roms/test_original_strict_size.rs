@@ -0,0 +1,67 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let rom = find_roms_by_system_id_no_parents(&mut connection, system.id)
+        .await
+        .into_iter()
+        .find(|rom| rom.name == "Test Game (USA, Europe).rom")
+        .unwrap();
+
+    // 4 bytes were appended to "Test Game (USA, Europe).rom" to keep its CRC
+    let (size, hash) = get_size_and_hash(
+        &mut connection,
+        &progress_bar,
+        &test_directory.join("Test Game (USA, Europe) (CRC Forged).rom"),
+        &None,
+        1,
+        1,
+        &HashAlgorithm::Crc,
+    )
+    .await
+    .unwrap();
+
+    // when
+    let lenient = check_strict_size(&progress_bar, &rom, size, &ImportOptions::default());
+    let strict = check_strict_size(
+        &progress_bar,
+        &rom,
+        size,
+        &ImportOptions {
+            strict_size: true,
+            ..Default::default()
+        },
+    );
+
+    // then
+    assert_eq!(Some(hash), rom.crc);
+    assert_ne!(size, rom.size as u64);
+    assert!(lenient);
+    assert!(!strict);
+}
@@ -0,0 +1,86 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let incoming_directory = tmp_directory.join("Incoming");
+    let system_directory = incoming_directory.join("Test System");
+    let unknown_directory = incoming_directory.join("Unknown System");
+    fs::create_dir_all(&system_directory).await.unwrap();
+    fs::create_dir_all(&unknown_directory).await.unwrap();
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        system_directory.join("Test Game (USA, Europe).rom"),
+    )
+    .await
+    .unwrap();
+    fs::copy(
+        test_directory.join("Test Game (Japan).rom"),
+        unknown_directory.join("Test Game (Japan).rom"),
+    )
+    .await
+    .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        incoming_directory.as_os_str().to_str().unwrap(),
+        "--system-from-dir",
+        "-q",
+    ]);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let system = find_systems(&mut connection).await.remove(0);
+    assert_eq!(system.name, "Test System");
+
+    let romfiles = find_romfiles_by_system_id(&mut connection, system.id).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(romfiles
+        .get(0)
+        .unwrap()
+        .path
+        .ends_with("Test Game (USA, Europe).rom"));
+    assert_eq!(find_romfiles(&mut connection).await.len(), 1);
+
+    assert!(
+        !system_directory
+            .join("Test Game (USA, Europe).rom")
+            .is_file()
+            .await
+    );
+    assert!(
+        unknown_directory
+            .join("Test Game (Japan).rom")
+            .is_file()
+            .await
+    );
+}
@@ -0,0 +1,141 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use tempfile::{NamedTempFile, TempDir};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer};
+use tracing_subscriber::prelude::*;
+
+struct CaptureLayer {
+    records: Arc<Mutex<Vec<String>>>,
+}
+
+struct MessageVisitor {
+    message: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_new_span(&self, attributes: &Attributes<'_>, _id: &Id, _context: Context<'_, S>) {
+        self.records.lock().unwrap().push(format!(
+            "{} span {}",
+            attributes.metadata().level(),
+            attributes.metadata().name()
+        ));
+    }
+
+    fn on_event(&self, event: &Event<'_>, _context: Context<'_, S>) {
+        let mut visitor = MessageVisitor {
+            message: String::new(),
+        };
+        event.record(&mut visitor);
+        self.records.lock().unwrap().push(format!(
+            "{} {}",
+            event.metadata().level(),
+            visitor.message
+        ));
+    }
+}
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let records = Arc::new(Mutex::new(Vec::new()));
+    let _subscriber =
+        tracing::subscriber::set_default(tracing_subscriber::registry().with(CaptureLayer {
+            records: records.clone(),
+        }));
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matched_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &matched_path,
+    )
+    .await
+    .unwrap();
+    let unmatched_path = tmp_directory.join("Test Game (USA, Europe) (Headered).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe) (Headered).rom"),
+        &unmatched_path,
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let header = find_header_by_system_id(&mut connection, system.id).await;
+    let romfile_paths = [
+        std::path::PathBuf::from(matched_path.as_os_str()),
+        std::path::PathBuf::from(unmatched_path.as_os_str()),
+    ];
+    records.lock().unwrap().clear();
+
+    // when
+    import_romfiles(
+        &mut connection,
+        &progress_bar,
+        &system,
+        &header,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let records = records.lock().unwrap();
+    for span in [
+        "INFO span import_rom",
+        "DEBUG span get_size_and_hash",
+        "DEBUG span find_rom_by_hash",
+        "TRACE span find_roms_without_romfile_by_size_and_crc_and_system_id",
+    ] {
+        assert!(records.iter().any(|record| record == span), "{}", span);
+    }
+    assert_eq!(
+        records
+            .iter()
+            .filter(|record| !record.contains(" span ")
+                && (record.starts_with("INFO") || record.starts_with("WARN")))
+            .collect::<Vec<&String>>(),
+        vec![
+            "INFO Matched",
+            "INFO Moved",
+            "INFO No match",
+            "WARN Trashed"
+        ]
+    );
+}
@@ -0,0 +1,95 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20230106) (Split BIN).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (Europe).rar");
+    fs::copy(
+        test_directory.join("Test Game (Europe).rar"),
+        &romfile_path.as_os_str().to_str().unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    import_archive(
+        &mut connection,
+        &progress_bar,
+        &system_directory,
+        &system,
+        &None,
+        &romfile_path,
+        romfile_path.extension().unwrap().to_str().unwrap(),
+        &HashAlgorithm::Crc,
+        &ImportOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 3);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (Europe).7z")
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(Path::new(&romfile.path).is_file().await);
+    assert!(roms.iter().all(|rom| rom.romfile_id == Some(romfile.id)));
+    assert!(!romfile_path.is_file().await);
+
+    let mut names: Vec<String> =
+        sevenzip::parse_archive(&progress_bar, &PathBuf::from(&romfile.path))
+            .unwrap()
+            .into_iter()
+            .map(|sevenzip_info| sevenzip_info.path)
+            .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "Test Game (Europe) (Track 1).bin",
+            "Test Game (Europe) (Track 2).bin",
+            "Test Game (Europe).cue",
+        ]
+    );
+}
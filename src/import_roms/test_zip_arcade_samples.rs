@@ -0,0 +1,87 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::path::PathBuf;
+use std::io::Write;
+use tempfile::{NamedTempFile, TempDir};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "--arcade",
+        "tests/Test Arcade (20230115) (Samples).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("testsamples.zip");
+    let mut zip_writer = ZipWriter::new(std::fs::File::create(&romfile_path).unwrap());
+    for (member_name, file_name) in [
+        ("1.wav", "Test Game (Japan).rom"),
+        ("2.wav", "Test Game (Asia).rom"),
+    ] {
+        zip_writer
+            .start_file(
+                member_name,
+                FileOptions::default().compression_method(CompressionMethod::Stored),
+            )
+            .unwrap();
+        zip_writer
+            .write_all(&std::fs::read(test_directory.join(file_name)).unwrap())
+            .unwrap();
+    }
+    zip_writer.finish().unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let matches =
+        subcommand().get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("samples")
+            .join("testsamples.zip")
+            .as_os_str()
+            .to_str()
+            .unwrap()
+    );
+    assert!(Path::new(&romfile.path).is_file().await);
+    assert!(find_romfiles_in_trash(&mut connection).await.is_empty());
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    assert!(roms.iter().all(|rom| rom.romfile_id == Some(romfile.id)));
+    let game = find_game_by_id(&mut connection, roms[0].game_id).await;
+    assert_eq!(game.name, "testsamples");
+}
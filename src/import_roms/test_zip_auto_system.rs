@@ -0,0 +1,70 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20200721).dat",
+        "tests/Test System (20230106) (Split BIN).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let incoming_directory = tmp_directory.join("incoming");
+    fs::create_dir_all(&incoming_directory).await.unwrap();
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom.zip"),
+        incoming_directory.join("Test Game (USA, Europe).rom.zip"),
+    )
+    .await
+    .unwrap();
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        incoming_directory.as_os_str().to_str().unwrap(),
+        "--auto-system",
+        "-q",
+    ]);
+    PROBED_SYSTEMS.store(0, Ordering::SeqCst);
+
+    // when
+    main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // then
+    assert_eq!(PROBED_SYSTEMS.load(Ordering::SeqCst), 2);
+
+    let system = find_system_by_name(&mut connection, "Test System")
+        .await
+        .unwrap();
+    let romfiles = find_romfiles_by_system_id(&mut connection, system.id).await;
+    assert_eq!(romfiles.len(), 1);
+    assert!(romfiles
+        .first()
+        .unwrap()
+        .path
+        .ends_with("Test Game (USA, Europe).zip"));
+    assert_eq!(find_romfiles(&mut connection).await.len(), 1);
+}
@@ -0,0 +1,107 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use std::sync::atomic::Ordering;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom.zip");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom.zip"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+
+    // simulate the listing of the archive, any extraction would fail
+    let wrapper_path = tmp_directory.join("7z");
+    fs::write(
+        &wrapper_path,
+        "#!/bin/sh\n\
+        if [ \"$1\" != \"l\" ]; then exit 0; fi\n\
+        echo \"Path = $3\"\n\
+        echo \"\"\n\
+        echo \"Path = Test Game (USA, Europe).rom\"\n\
+        echo \"Size = 256\"\n\
+        echo \"CRC = CC721E14\"\n",
+    )
+    .await
+    .unwrap();
+    fs::set_permissions(&wrapper_path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+    let previous_path = env::var(sevenzip::SEVENZIP_PATH).ok();
+    env::set_var(sevenzip::SEVENZIP_PATH, &wrapper_path);
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+    let extractions = sevenzip::EXTRACTIONS.load(Ordering::SeqCst);
+
+    let matches = subcommand().get_matches_from(&[
+        "import-roms",
+        "--hash",
+        "SHA1",
+        "--crc-only-fast",
+        romfile_path.as_os_str().to_str().unwrap(),
+    ]);
+
+    // when
+    let result = main(&mut connection, &matches, &progress_bar).await;
+
+    match previous_path {
+        Some(previous_path) => env::set_var(sevenzip::SEVENZIP_PATH, previous_path),
+        None => env::remove_var(sevenzip::SEVENZIP_PATH),
+    }
+    result.unwrap();
+
+    // then
+    assert_eq!(sevenzip::EXTRACTIONS.load(Ordering::SeqCst), extractions);
+
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 1);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let rom = roms.get(0).unwrap();
+    assert_eq!(rom.name, "Test Game (USA, Europe).rom");
+
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).zip")
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(Path::new(&romfile.path).is_file().await);
+    assert_eq!(rom.romfile_id, Some(romfile.id));
+}
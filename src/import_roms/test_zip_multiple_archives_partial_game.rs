@@ -0,0 +1,122 @@
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use async_std::path::PathBuf;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20221226) (Multiple Files).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<std::path::PathBuf> = Vec::new();
+    for (rom_name, part_name) in [
+        (
+            "Test Game (USA, Europe).rom",
+            "Test Game (USA, Europe) (Part 1).rom",
+        ),
+        (
+            "Test Game (Japan).rom",
+            "Test Game (USA, Europe) (Part 2).rom",
+        ),
+    ] {
+        let part_directory = tmp_directory.join(part_name);
+        fs::create_dir_all(&part_directory).await.unwrap();
+        fs::copy(
+            test_directory.join(rom_name),
+            part_directory.join(part_name),
+        )
+        .await
+        .unwrap();
+        let archive_path = tmp_directory.join(format!("{}.zip", part_name));
+        sevenzip::add_files_to_archive(
+            &progress_bar,
+            &archive_path,
+            &[part_name],
+            &part_directory,
+            false,
+            None,
+            false,
+        )
+        .unwrap();
+        romfile_paths.push(std::path::PathBuf::from(archive_path.as_os_str()));
+    }
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    import_romfiles(
+        &mut connection,
+        &progress_bar,
+        &system,
+        &None,
+        &romfile_paths.iter().collect::<Vec<&std::path::PathBuf>>(),
+        &HashAlgorithm::Crc,
+        &ImportOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let roms = find_roms_with_romfile_by_system_id(&mut connection, system.id).await;
+    assert_eq!(roms.len(), 2);
+    let romfiles = find_romfiles(&mut connection).await;
+    assert_eq!(romfiles.len(), 1);
+
+    let games = find_games_by_ids(&mut connection, &[roms.get(0).unwrap().game_id]).await;
+    let game = games.get(0).unwrap();
+    assert_eq!(game.name, "Test Game (USA, Europe)");
+    assert!(game.complete);
+
+    let romfile = romfiles.get(0).unwrap();
+    assert_eq!(
+        romfile.path,
+        system_directory
+            .join("Test Game (USA, Europe).zip")
+            .as_os_str()
+            .to_str()
+            .unwrap(),
+    );
+    assert!(Path::new(&romfile.path).is_file().await);
+    assert_eq!(
+        sevenzip::parse_archive(&progress_bar, &romfile.path)
+            .unwrap()
+            .len(),
+        2
+    );
+    assert!(
+        !system_directory
+            .join("Test Game (USA, Europe) (Part 1).rom")
+            .is_file()
+            .await
+    );
+
+    for rom in roms {
+        assert_eq!(rom.game_id, game.id);
+        assert_eq!(rom.romfile_id, Some(romfile.id));
+    }
+}
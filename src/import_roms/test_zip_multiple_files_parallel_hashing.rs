@@ -0,0 +1,111 @@
+use super::super::config;
+use super::super::database::*;
+use super::super::import_dats;
+use super::*;
+use async_std::path::PathBuf;
+use std::io::Write;
+use tempfile::{NamedTempFile, TempDir};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const FILE_NAMES: [&str; 5] = [
+    "Test Game (Asia).rom",
+    "Test Game (Japan).rom",
+    "Test Game (USA, Europe) (Beta).rom",
+    "Test Game (USA, Europe).iso",
+    "Test Game (USA, Europe).rom",
+];
+
+async fn import_with_parallel_hashing(parallel_hashing: &str) -> Vec<(String, String)> {
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let matches = config::subcommand().get_matches_from(&[
+        "config",
+        "-s",
+        "PARALLEL_HASHING",
+        parallel_hashing,
+    ]);
+    config::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Games.zip");
+    let mut zip_writer = ZipWriter::new(std::fs::File::create(&romfile_path).unwrap());
+    for file_name in FILE_NAMES {
+        zip_writer
+            .start_file(
+                file_name,
+                FileOptions::default().compression_method(CompressionMethod::Stored),
+            )
+            .unwrap();
+        zip_writer
+            .write_all(&std::fs::read(test_directory.join(file_name)).unwrap())
+            .unwrap();
+    }
+    zip_writer.finish().unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    import_rom(
+        &mut connection,
+        &progress_bar,
+        &system,
+        &None,
+        &romfile_path,
+        &HashAlgorithm::Md5,
+        &ImportOptions::default(),
+    )
+    .await
+    .unwrap();
+
+    let mut roms_romfiles: Vec<(String, String)> = Vec::new();
+    for romfile in find_romfiles_by_system_id(&mut connection, system.id).await {
+        for rom in find_roms_by_romfile_id(&mut connection, romfile.id).await {
+            roms_romfiles.push((
+                rom.name,
+                romfile
+                    .path
+                    .strip_prefix(rom_directory.path().to_str().unwrap())
+                    .unwrap()
+                    .to_owned(),
+            ));
+        }
+    }
+    roms_romfiles.sort();
+    roms_romfiles
+}
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let sequential_roms_romfiles = import_with_parallel_hashing("1").await;
+
+    // when
+    let parallel_roms_romfiles = import_with_parallel_hashing("4").await;
+
+    // then
+    assert_eq!(parallel_roms_romfiles, sequential_roms_romfiles);
+    assert_eq!(parallel_roms_romfiles.len(), FILE_NAMES.len());
+    for ((rom_name, romfile_path), file_name) in parallel_roms_romfiles.iter().zip(FILE_NAMES) {
+        assert_eq!(rom_name, file_name);
+        assert!(romfile_path.ends_with(file_name));
+    }
+}
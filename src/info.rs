@@ -0,0 +1,58 @@
+use super::database::*;
+use super::SimpleResult;
+use clap::{Arg, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+
+pub fn subcommand() -> Command {
+    Command::new("info").about("Display games metadata").arg(
+        Arg::new("NAME")
+            .help("Set the game name, supports SQL LIKE wildcards (eg: \"%Mario%\")")
+            .required(true)
+            .num_args(1)
+            .index(1),
+    )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let lines = get_info(connection, matches.get_one::<String>("NAME").unwrap()).await;
+    if lines.is_empty() {
+        progress_bar.println("No game found");
+    }
+    for line in lines {
+        progress_bar.println(line);
+    }
+    Ok(())
+}
+
+pub async fn get_info(connection: &mut SqliteConnection, name: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for game in find_games_by_name(connection, name).await {
+        let system = find_system_by_id(connection, game.system_id).await;
+        lines.push(format!("\"{}\"", game.name));
+        lines.push(format!("  System: {}", system.name));
+        lines.push(format!("  Description: {}", game.description));
+        if let Some(year) = &game.year {
+            lines.push(format!("  Year: {}", year));
+        }
+        if let Some(manufacturer) = &game.manufacturer {
+            lines.push(format!("  Manufacturer: {}", manufacturer));
+        }
+        if let Some(comment) = &game.comment {
+            lines.push(format!("  Comment: {}", comment));
+        }
+        if !game.regions.is_empty() {
+            lines.push(format!("  Regions: {}", game.regions));
+        }
+        lines.push(format!("  Complete: {}", game.complete));
+        lines.push(String::from(""));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod test_metadata;
@@ -0,0 +1,48 @@
+use super::super::config::*;
+use super::super::import_dats;
+use super::*;
+use async_std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20221216) (Metadata).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // when
+    let lines = get_info(&mut connection, "Test Game (USA, Europe)").await;
+
+    // then
+    let games = find_games_by_name(&mut connection, "Test Game (USA, Europe)").await;
+    assert_eq!(games.len(), 1);
+    let game = games.get(0).unwrap();
+    assert_eq!(game.year.as_deref(), Some("1991"));
+    assert_eq!(game.manufacturer.as_deref(), Some("Test Manufacturer"));
+
+    assert!(lines.contains(&String::from("\"Test Game (USA, Europe)\"")));
+    assert!(lines.contains(&String::from("  Year: 1991")));
+    assert!(lines.contains(&String::from("  Manufacturer: Test Manufacturer")));
+
+    let games = find_games_by_name(&mut connection, "Test Game (Japan)").await;
+    let game = games.get(0).unwrap();
+    assert_eq!(game.year, None);
+    assert_eq!(game.manufacturer, None);
+}
@@ -0,0 +1,239 @@
+use super::database::*;
+use super::model::*;
+use super::SimpleResult;
+use async_std::path::PathBuf;
+use clap::builder::PossibleValuesParser;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use futures::stream::TryStreamExt;
+use indicatif::ProgressBar;
+use regex::{Regex, RegexBuilder};
+use serde_json::Value;
+use sqlx::sqlite::SqliteConnection;
+use std::fs::File;
+use std::io;
+use std::io::Write;
+
+const FORMATS: &[&str] = &["csv", "json", "tsv"];
+// must follow the serialized field names of InventoryRow
+const FIELDS: &[&str] = &[
+    "system", "game", "rom", "size", "crc", "md5", "sha1", "path", "complete",
+];
+
+pub fn subcommand() -> Command {
+    Command::new("inventory")
+        .about("Export an inventory of the whole library")
+        .arg(
+            Arg::new("FORMAT")
+                .short('f')
+                .long("format")
+                .help("Set the output format")
+                .required(false)
+                .num_args(1)
+                .value_parser(PossibleValuesParser::new(FORMATS))
+                .default_value("csv"),
+        )
+        .arg(
+            Arg::new("OUTPUT")
+                .short('o')
+                .long("output")
+                .help("Write the inventory to a file")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("EMIT_STDOUT")
+                .long("emit-stdout")
+                .help("Also write the inventory to stdout when writing to a file")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .requires("OUTPUT"),
+        )
+        .arg(
+            Arg::new("HAVE_ONLY")
+                .long("have-only")
+                .help("Only export ROMs with a ROM file")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("FILTER")
+                .long("filter")
+                .help("Only export games whose name contains the filter, case-insensitively")
+                .required(false)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("REGEX")
+                .long("regex")
+                .help("Match the filter as a regular expression")
+                .required(false)
+                .action(ArgAction::SetTrue)
+                .requires("FILTER"),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    _progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let filter = match matches.get_one::<String>("FILTER") {
+        Some(filter) => Some(get_filter(filter, matches.get_flag("REGEX"))?),
+        None => None,
+    };
+    let format = matches.get_one::<String>("FORMAT").unwrap();
+    let mut writer: Box<dyn Write> = match matches.get_one::<PathBuf>("OUTPUT") {
+        Some(output_path) => {
+            let file = io::BufWriter::new(try_with!(
+                File::create(output_path),
+                "Failed to create {:?}",
+                output_path
+            ));
+            if matches.get_flag("EMIT_STDOUT") {
+                Box::new(TeeWriter(file, io::BufWriter::new(io::stdout())))
+            } else {
+                Box::new(file)
+            }
+        }
+        None => Box::new(io::BufWriter::new(io::stdout())),
+    };
+    write_inventory(
+        connection,
+        &mut writer,
+        format,
+        matches.get_flag("HAVE_ONLY"),
+        filter.as_ref(),
+    )
+    .await?;
+    try_with!(writer.flush(), "Failed to write inventory");
+    Ok(())
+}
+
+fn get_filter(filter: &str, regex: bool) -> SimpleResult<Regex> {
+    let filter = if regex {
+        Regex::new(filter)
+    } else {
+        RegexBuilder::new(&regex::escape(filter))
+            .case_insensitive(true)
+            .build()
+    };
+    Ok(try_with!(filter, "Failed to parse filter"))
+}
+
+pub async fn write_inventory<W: Write>(
+    connection: &mut SqliteConnection,
+    writer: &mut W,
+    format: &str,
+    have_only: bool,
+    filter: Option<&Regex>,
+) -> SimpleResult<()> {
+    // JSON is written as an array of objects, the other formats as delimited rows
+    let delimiter = match format {
+        "csv" => Some(','),
+        "tsv" => Some('\t'),
+        "json" => None,
+        _ => bail!("Unsupported format {}", format),
+    };
+    match delimiter {
+        Some(delimiter) => write_row(writer, FIELDS, delimiter)?,
+        None => try_with!(write!(writer, "["), "Failed to write inventory"),
+    }
+    let mut first = true;
+    // stream rows as large libraries may not fit in memory
+    let mut rows = find_inventory_rows(connection, have_only);
+    while let Some(row) = try_with!(rows.try_next().await, "Failed to read inventory") {
+        if filter.is_some_and(|filter| !filter.is_match(&row.game_name)) {
+            continue;
+        }
+        match delimiter {
+            Some(delimiter) => {
+                let fields = get_fields(&row)?;
+                write_row(
+                    writer,
+                    &fields.iter().map(String::as_str).collect::<Vec<&str>>(),
+                    delimiter,
+                )?;
+            }
+            None => {
+                try_with!(
+                    write!(writer, "{}\n  ", if first { "" } else { "," }),
+                    "Failed to write inventory"
+                );
+                try_with!(
+                    serde_json::to_writer(&mut *writer, &row),
+                    "Failed to write inventory"
+                );
+            }
+        }
+        first = false;
+    }
+    if delimiter.is_none() {
+        try_with!(
+            writeln!(writer, "{}]", if first { "" } else { "\n" }),
+            "Failed to write inventory"
+        );
+    }
+    Ok(())
+}
+
+// go through serde so that every format shares the same field names and values
+fn get_fields(row: &InventoryRow) -> SimpleResult<Vec<String>> {
+    let value = try_with!(serde_json::to_value(row), "Failed to serialize inventory");
+    Ok(FIELDS
+        .iter()
+        .map(|&field| match &value[field] {
+            Value::Null => String::new(),
+            Value::String(string) => string.to_owned(),
+            value => value.to_string(),
+        })
+        .collect())
+}
+
+fn write_row<W: Write>(writer: &mut W, fields: &[&str], delimiter: char) -> SimpleResult<()> {
+    let line = fields
+        .iter()
+        .map(|field| match delimiter {
+            ',' => escape_csv_field(field),
+            _ => escape_tsv_field(field),
+        })
+        .collect::<Vec<String>>()
+        .join(&delimiter.to_string());
+    try_with!(writeln!(writer, "{}", line), "Failed to write inventory");
+    Ok(())
+}
+
+fn escape_csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+// TSV has no quoting, tabs and line breaks can't appear in a field
+fn escape_tsv_field(field: &str) -> String {
+    field.replace(['\t', '\n', '\r'], " ")
+}
+
+struct TeeWriter<A: Write, B: Write>(A, B);
+
+impl<A: Write, B: Write> Write for TeeWriter<A, B> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_all(buf)?;
+        self.1.write_all(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.0.flush()?;
+        self.1.flush()
+    }
+}
+
+#[cfg(test)]
+mod test_csv;
+#[cfg(test)]
+mod test_csv_filter;
+#[cfg(test)]
+mod test_formats;
@@ -0,0 +1,99 @@
+use super::super::config::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::util::*;
+use super::*;
+use async_std::fs;
+use async_std::path::{Path, PathBuf};
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let mut romfile_paths: Vec<String> = Vec::new();
+    for romfile_name in ["Test Game (USA, Europe).rom", "Test Game (Japan).rom"] {
+        let romfile_path = tmp_directory.join(romfile_name);
+        fs::copy(test_directory.join(romfile_name), &romfile_path)
+            .await
+            .unwrap();
+        romfile_paths.push(romfile_path.as_os_str().to_str().unwrap().to_owned());
+    }
+    let mut args = vec!["import-roms"];
+    args.extend(romfile_paths.iter().map(String::as_str));
+    let matches = import_roms::subcommand().get_matches_from(&args);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let mut have_only_output: Vec<u8> = Vec::new();
+    write_inventory(&mut connection, &mut have_only_output, "csv", true, None)
+        .await
+        .unwrap();
+    let mut all_output: Vec<u8> = Vec::new();
+    write_inventory(&mut connection, &mut all_output, "csv", false, None)
+        .await
+        .unwrap();
+
+    // then
+    let have_only_lines: Vec<String> = String::from_utf8(have_only_output)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(have_only_lines.len(), 3);
+    assert_eq!(
+        have_only_lines.first().unwrap(),
+        "system,game,rom,size,crc,md5,sha1,path,complete"
+    );
+    assert_eq!(
+        have_only_lines.get(1).unwrap(),
+        &format!(
+            "Test System,Test Game (Japan),Test Game (Japan).rom,256,310212e8,d14b417004b9cc868286a0eafb257d2b,5fcce2e41101d4e0f22a9279e65839145fa61846,{},true",
+            system_directory.join("Test Game (Japan).rom").as_os_str().to_str().unwrap()
+        )
+    );
+    assert_eq!(
+        have_only_lines.get(2).unwrap(),
+        &format!(
+            "Test System,\"Test Game (USA, Europe)\",\"Test Game (USA, Europe).rom\",256,cc721e14,c1fe7a7ed317bd069b0cb7bdbc40be01,4b3e49f0f22c7ce5f1eb7c30a2bbd6fb4fdc6f3d,\"{}\",true",
+            system_directory.join("Test Game (USA, Europe).rom").as_os_str().to_str().unwrap()
+        )
+    );
+
+    let all_lines: Vec<String> = String::from_utf8(all_output)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(all_lines.len(), 9);
+    assert_eq!(
+        all_lines.get(1).unwrap(),
+        "Test System,Test Game (Asia),Test Game (Asia).rom,256,1c3321f3,2b9e40254b661c1970524071059965c7,31b561b11280b242e797147d4b39aa4d33920d8d,,false"
+    );
+}
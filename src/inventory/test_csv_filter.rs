@@ -0,0 +1,73 @@
+use super::super::config::*;
+use super::super::import_dats;
+use super::*;
+use tempfile::NamedTempFile;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20221230) (Names).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    // when
+    let mut substring_output: Vec<u8> = Vec::new();
+    write_inventory(
+        &mut connection,
+        &mut substring_output,
+        "csv",
+        false,
+        Some(&get_filter("Mario", false).unwrap()),
+    )
+    .await
+    .unwrap();
+    let mut regex_output: Vec<u8> = Vec::new();
+    write_inventory(
+        &mut connection,
+        &mut regex_output,
+        "csv",
+        false,
+        Some(&get_filter("^Super Mario", true).unwrap()),
+    )
+    .await
+    .unwrap();
+
+    // then
+    let substring_lines: Vec<String> = String::from_utf8(substring_output)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(substring_lines.len(), 3);
+    assert!(substring_lines
+        .get(1)
+        .unwrap()
+        .starts_with("Test System,\"Super Mario Test (USA, Europe)\","));
+    assert!(substring_lines
+        .get(2)
+        .unwrap()
+        .starts_with("Test System,Test mario Kart (Asia),"));
+
+    let regex_lines: Vec<String> = String::from_utf8(regex_output)
+        .unwrap()
+        .lines()
+        .map(String::from)
+        .collect();
+    assert_eq!(regex_lines.len(), 2);
+    assert!(regex_lines
+        .get(1)
+        .unwrap()
+        .starts_with("Test System,\"Super Mario Test (USA, Europe)\","));
+
+    assert!(get_filter("(", true).is_err());
+}
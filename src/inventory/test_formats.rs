@@ -0,0 +1,141 @@
+use super::super::config::*;
+use super::super::import_dats;
+use super::super::import_roms;
+use super::super::util::*;
+use super::*;
+use async_std::fs;
+use async_std::path::Path;
+use serde_json::Map;
+use tempfile::{NamedTempFile, TempDir};
+
+fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields: Vec<String> = Vec::new();
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => fields.push(std::mem::take(&mut field)),
+            c => field.push(c),
+        }
+    }
+    fields.push(field);
+    fields
+}
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand()
+        .get_matches_from(&["import-dats", "tests/Test System (20200721).dat"]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let romfile_path = tmp_directory.join("Test Game (USA, Europe).rom");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom"),
+        &romfile_path,
+    )
+    .await
+    .unwrap();
+    let matches = import_roms::subcommand()
+        .get_matches_from(&["import-roms", romfile_path.as_os_str().to_str().unwrap()]);
+    import_roms::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    let system = find_systems(&mut connection).await.remove(0);
+    let system_directory = get_system_directory(&mut connection, &progress_bar, &system)
+        .await
+        .unwrap();
+
+    // when
+    let mut csv_output: Vec<u8> = Vec::new();
+    write_inventory(&mut connection, &mut csv_output, "csv", false, None)
+        .await
+        .unwrap();
+    let mut tsv_output: Vec<u8> = Vec::new();
+    write_inventory(&mut connection, &mut tsv_output, "tsv", false, None)
+        .await
+        .unwrap();
+    let mut json_output: Vec<u8> = Vec::new();
+    write_inventory(&mut connection, &mut json_output, "json", false, None)
+        .await
+        .unwrap();
+
+    // then
+    let csv_output = String::from_utf8(csv_output).unwrap();
+    let mut csv_lines = csv_output.lines().map(parse_csv_line);
+    assert_eq!(csv_lines.next().unwrap(), FIELDS);
+    let csv_records: Vec<Vec<String>> = csv_lines.collect();
+
+    let tsv_output = String::from_utf8(tsv_output).unwrap();
+    let mut tsv_lines = tsv_output
+        .lines()
+        .map(|line| line.split('\t').map(String::from).collect::<Vec<String>>());
+    assert_eq!(tsv_lines.next().unwrap(), FIELDS);
+    let tsv_records: Vec<Vec<String>> = tsv_lines.collect();
+
+    let json_objects: Vec<Map<String, Value>> = serde_json::from_slice(&json_output).unwrap();
+    let json_records: Vec<Vec<String>> = json_objects
+        .iter()
+        .map(|object| {
+            let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+            let mut fields = FIELDS.to_vec();
+            keys.sort_unstable();
+            fields.sort_unstable();
+            assert_eq!(keys, fields);
+            FIELDS
+                .iter()
+                .map(|&field| match &object[field] {
+                    Value::Null => String::new(),
+                    Value::String(string) => string.to_owned(),
+                    value => value.to_string(),
+                })
+                .collect()
+        })
+        .collect();
+
+    assert_eq!(csv_records.len(), 8);
+    assert_eq!(csv_records, tsv_records);
+    assert_eq!(csv_records, json_records);
+    assert!(csv_records.contains(
+        &[
+            "Test System",
+            "Test Game (USA, Europe)",
+            "Test Game (USA, Europe).rom",
+            "256",
+            "cc721e14",
+            "c1fe7a7ed317bd069b0cb7bdbc40be01",
+            "4b3e49f0f22c7ce5f1eb7c30a2bbd6fb4fdc6f3d",
+            system_directory
+                .join("Test Game (USA, Europe).rom")
+                .as_os_str()
+                .to_str()
+                .unwrap(),
+            "true",
+        ]
+        .map(String::from)
+        .to_vec()
+    ));
+}
@@ -0,0 +1,232 @@
+use super::progress::*;
+use super::SimpleResult;
+use async_std::path::{Path, PathBuf};
+use indicatif::ProgressBar;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::time::Duration;
+
+const SECTOR_SIZE: u64 = 2048;
+const FIRST_DESCRIPTOR_SECTOR: u64 = 16;
+const STANDARD_IDENTIFIER: &[u8] = b"CD001";
+const PRIMARY_DESCRIPTOR_TYPE: u8 = 1;
+const SUPPLEMENTARY_DESCRIPTOR_TYPE: u8 = 2;
+const JOLIET_ESCAPE_SEQUENCES: [&[u8]; 3] = [b"%/@", b"%/C", b"%/E"];
+const TERMINATOR_DESCRIPTOR_TYPE: u8 = 255;
+const DIRECTORY_FLAG: u8 = 0b10;
+
+pub struct IsoFile {
+    pub path: String,
+    pub offset: u64,
+    pub size: u64,
+}
+
+struct DirectoryRecord {
+    name: String,
+    extent: u64,
+    size: u64,
+    directory: bool,
+}
+
+pub fn parse_iso<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    iso_path: &P,
+) -> SimpleResult<Vec<IsoFile>> {
+    progress_bar.set_message("Parsing ISO filesystem");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    let mut file = try_with!(File::open(iso_path.as_ref()), "Failed to open ISO");
+    let iso_size = try_with!(file.metadata(), "Failed to read ISO metadata").len();
+    let (root, block_size, joliet) = read_root_record(&mut file)?;
+
+    // walk the whole directory tree, crafted images may point back to a parent
+    let mut files: Vec<IsoFile> = Vec::new();
+    let mut visited_extents: HashSet<u64> = HashSet::from([root.extent]);
+    let mut directories: Vec<(String, DirectoryRecord)> = vec![(String::new(), root)];
+    while let Some((parent, directory)) = directories.pop() {
+        for record in read_directory(&mut file, &directory, block_size, joliet, iso_size)? {
+            let path = format!("{}{}", parent, record.name);
+            if record.directory {
+                if !visited_extents.insert(record.extent) {
+                    bail!("ISO directory loop at \"{}\"", path);
+                }
+                directories.push((format!("{}/", path), record));
+            } else {
+                files.push(IsoFile {
+                    path,
+                    offset: record.extent * block_size,
+                    size: record.size,
+                });
+            }
+        }
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    Ok(files)
+}
+
+pub fn extract_file_from_iso<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    iso_path: &P,
+    iso_file: &IsoFile,
+    directory: &Q,
+) -> SimpleResult<PathBuf> {
+    progress_bar.println(format!("Extracting \"{}\"", &iso_file.path));
+
+    // never write outside of the extraction directory
+    if !iso_file.path.split('/').all(is_valid_name) {
+        bail!("Invalid ISO file name \"{}\"", &iso_file.path);
+    }
+    let extracted_path = directory.as_ref().join(&iso_file.path);
+    try_with!(
+        fs::create_dir_all(extracted_path.parent().unwrap()),
+        "Failed to create {:?}",
+        extracted_path.parent().unwrap()
+    );
+    let mut file = try_with!(File::open(iso_path.as_ref()), "Failed to open ISO");
+    try_with!(
+        file.seek(SeekFrom::Start(iso_file.offset)),
+        "Failed to seek ISO"
+    );
+    let mut extracted_file = try_with!(
+        File::create(&extracted_path),
+        "Failed to create {:?}",
+        extracted_path
+    );
+    try_with!(
+        io::copy(&mut file.take(iso_file.size), &mut extracted_file),
+        "Failed to extract {:?}",
+        &iso_file.path
+    );
+
+    Ok(extracted_path)
+}
+
+// prefer Joliet names, plain ISO9660 names are limited and uppercase
+fn read_root_record(file: &mut File) -> SimpleResult<(DirectoryRecord, u64, bool)> {
+    let mut sector = [0u8; SECTOR_SIZE as usize];
+    let mut index = FIRST_DESCRIPTOR_SECTOR;
+    let mut primary: Option<(DirectoryRecord, u64)> = None;
+    loop {
+        try_with!(
+            file.seek(SeekFrom::Start(index * SECTOR_SIZE)),
+            "Failed to seek ISO"
+        );
+        try_with!(file.read_exact(&mut sector), "Not an ISO9660 filesystem");
+        if &sector[1..6] != STANDARD_IDENTIFIER {
+            bail!("Not an ISO9660 filesystem");
+        }
+        let block_size = u16::from_le_bytes([sector[128], sector[129]]) as u64;
+        match sector[0] {
+            PRIMARY_DESCRIPTOR_TYPE => {
+                primary = Some((
+                    parse_directory_record(&sector[156..190], false)?,
+                    block_size,
+                ));
+            }
+            SUPPLEMENTARY_DESCRIPTOR_TYPE if JOLIET_ESCAPE_SEQUENCES.contains(&&sector[88..91]) => {
+                let root = parse_directory_record(&sector[156..190], true)?;
+                return Ok((root, block_size, true));
+            }
+            TERMINATOR_DESCRIPTOR_TYPE => break,
+            _ => {}
+        }
+        index += 1;
+    }
+    match primary {
+        Some((root, block_size)) => Ok((root, block_size, false)),
+        None => bail!("Not an ISO9660 filesystem"),
+    }
+}
+
+fn read_directory(
+    file: &mut File,
+    directory: &DirectoryRecord,
+    block_size: u64,
+    joliet: bool,
+    iso_size: u64,
+) -> SimpleResult<Vec<DirectoryRecord>> {
+    // the size comes from the image, don't trust it beyond the end of the file
+    if directory
+        .extent
+        .checked_mul(block_size)
+        .and_then(|offset| offset.checked_add(directory.size))
+        .is_none_or(|end| end > iso_size)
+    {
+        bail!("Malformed ISO directory record");
+    }
+    let mut data = vec![0u8; directory.size as usize];
+    try_with!(
+        file.seek(SeekFrom::Start(directory.extent * block_size)),
+        "Failed to seek ISO"
+    );
+    try_with!(file.read_exact(&mut data), "Failed to read ISO directory");
+
+    let mut records: Vec<DirectoryRecord> = Vec::new();
+    let mut position = 0;
+    while position < data.len() {
+        let length = data[position] as usize;
+        // records never span sectors, the rest of the sector is padding
+        if length == 0 {
+            position = (position / block_size as usize + 1) * block_size as usize;
+            continue;
+        }
+        if position + length > data.len() {
+            bail!("Malformed ISO directory record");
+        }
+        let record = parse_directory_record(&data[position..position + length], joliet)?;
+        // skip the current and parent directory entries
+        if !record.name.is_empty() {
+            if !is_valid_name(&record.name) {
+                bail!("Invalid ISO file name \"{}\"", &record.name);
+            }
+            records.push(record);
+        }
+        position += length;
+    }
+
+    Ok(records)
+}
+
+fn is_valid_name(name: &str) -> bool {
+    !name.is_empty() && name != "." && name != ".." && !name.contains(['/', '\\', '\0'])
+}
+
+fn parse_directory_record(data: &[u8], joliet: bool) -> SimpleResult<DirectoryRecord> {
+    if data.len() < 34 || data.len() < 33 + data[32] as usize {
+        bail!("Malformed ISO directory record");
+    }
+    let name = &data[33..33 + data[32] as usize];
+    let name = match name {
+        [0] | [1] => String::new(),
+        _ => {
+            let name = if joliet {
+                String::from_utf16_lossy(
+                    &name
+                        .chunks_exact(2)
+                        .map(|chunk| u16::from_be_bytes([chunk[0], chunk[1]]))
+                        .collect::<Vec<u16>>(),
+                )
+            } else {
+                String::from_utf8_lossy(name).into_owned()
+            };
+            // drop the file version and the trailing dot of extension-less names
+            let name = name.split(';').next().unwrap();
+            name.strip_suffix('.').unwrap_or(name).to_owned()
+        }
+    };
+    Ok(DirectoryRecord {
+        name,
+        extent: u32::from_le_bytes([data[2], data[3], data[4], data[5]]) as u64,
+        size: u32::from_le_bytes([data[10], data[11], data[12], data[13]]) as u64,
+        directory: data[25] & DIRECTORY_FLAG != 0,
+    })
+}
+
+#[cfg(test)]
+mod test_parse_iso_malformed;
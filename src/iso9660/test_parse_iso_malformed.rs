@@ -0,0 +1,82 @@
+use super::*;
+use std::io::Write;
+use tempfile::NamedTempFile;
+
+fn directory_record(name: &[u8], extent: u32, size: u32, directory: bool) -> Vec<u8> {
+    let mut record = vec![0u8; 33];
+    record[0] = (33 + name.len()) as u8;
+    record[2..6].copy_from_slice(&extent.to_le_bytes());
+    record[10..14].copy_from_slice(&size.to_le_bytes());
+    record[25] = if directory { DIRECTORY_FLAG } else { 0 };
+    record[32] = name.len() as u8;
+    record.extend_from_slice(name);
+    record
+}
+
+// a primary descriptor whose root directory holds the given record
+fn write_iso(record: &[u8], root_size: u32) -> NamedTempFile {
+    let root_extent = FIRST_DESCRIPTOR_SECTOR as u32 + 2;
+    let mut image = vec![0u8; (SECTOR_SIZE * (FIRST_DESCRIPTOR_SECTOR + 3)) as usize];
+
+    let primary = (SECTOR_SIZE * FIRST_DESCRIPTOR_SECTOR) as usize;
+    image[primary] = PRIMARY_DESCRIPTOR_TYPE;
+    image[primary + 1..primary + 6].copy_from_slice(STANDARD_IDENTIFIER);
+    image[primary + 128..primary + 130].copy_from_slice(&(SECTOR_SIZE as u16).to_le_bytes());
+    image[primary + 156..primary + 190].copy_from_slice(&directory_record(
+        &[0],
+        root_extent,
+        root_size,
+        true,
+    ));
+
+    let terminator = primary + SECTOR_SIZE as usize;
+    image[terminator] = TERMINATOR_DESCRIPTOR_TYPE;
+    image[terminator + 1..terminator + 6].copy_from_slice(STANDARD_IDENTIFIER);
+
+    let mut root = directory_record(&[0], root_extent, SECTOR_SIZE as u32, true);
+    root.extend(directory_record(
+        &[1],
+        root_extent,
+        SECTOR_SIZE as u32,
+        true,
+    ));
+    root.extend_from_slice(record);
+    let root_offset = terminator + SECTOR_SIZE as usize;
+    image[root_offset..root_offset + root.len()].copy_from_slice(&root);
+
+    let mut iso_file = NamedTempFile::new().unwrap();
+    iso_file.write_all(&image).unwrap();
+    iso_file
+}
+
+#[test]
+fn test() {
+    let progress_bar = ProgressBar::hidden();
+    let root_extent = FIRST_DESCRIPTOR_SECTOR as u32 + 2;
+
+    // a plain file is listed
+    let iso_file = write_iso(
+        &directory_record(b"GAME.BIN;1", 0, 0, false),
+        SECTOR_SIZE as u32,
+    );
+    let files = parse_iso(&progress_bar, &iso_file.path()).unwrap();
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].path, "GAME.BIN");
+
+    // names escaping the extraction directory are rejected
+    for name in [&b"..;1"[..], b"../EVIL.BIN;1", b"EVIL\0.BIN;1"] {
+        let iso_file = write_iso(&directory_record(name, 0, 0, false), SECTOR_SIZE as u32);
+        assert!(parse_iso(&progress_bar, &iso_file.path()).is_err());
+    }
+
+    // a directory pointing back to the root is a loop
+    let iso_file = write_iso(
+        &directory_record(b"LOOP", root_extent, SECTOR_SIZE as u32, true),
+        SECTOR_SIZE as u32,
+    );
+    assert!(parse_iso(&progress_bar, &iso_file.path()).is_err());
+
+    // directory sizes beyond the end of the image are rejected
+    let iso_file = write_iso(&directory_record(b"GAME.BIN;1", 0, 0, false), u32::MAX);
+    assert!(parse_iso(&progress_bar, &iso_file.path()).is_err());
+}
@@ -1,4 +1,5 @@
 use super::progress::*;
+use super::util::*;
 use super::SimpleResult;
 use async_std::path::Path;
 use indicatif::ProgressBar;
@@ -7,6 +8,15 @@ use regex::Regex;
 use std::process::Command;
 use std::time::Duration;
 
+const ISOINFO: &str = "isoinfo";
+const ISOINFO_PATH: &str = "ISOINFO_PATH";
+
+pub fn check_tool_available() -> SimpleResult<String> {
+    get_cached_tool_version(ISOINFO, ISOINFO_PATH, || {
+        get_tool_version(ISOINFO, ISOINFO_PATH, &["-version"])
+    })
+}
+
 lazy_static! {
     static ref DIRECTORY_RE: Regex = Regex::new(r"^Directory listing of /(.+)$").unwrap();
     static ref FILE_RE: Regex = Regex::new(
@@ -23,17 +33,19 @@ pub fn parse_iso<P: AsRef<Path>>(
     progress_bar.set_style(get_none_progress_style());
     progress_bar.enable_steady_tick(Duration::from_millis(100));
 
-    let output = Command::new("isoinfo")
-        .arg("-i")
-        .arg(iso_path.as_ref())
-        .arg("-J")
-        .arg("-l")
-        .output()
-        .expect("Failed to parse ISO header");
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(ISOINFO, ISOINFO_PATH))
+            .arg("-i")
+            .arg(iso_path.as_ref())
+            .arg("-J")
+            .arg("-l"),
+        ISOINFO,
+        ISOINFO_PATH,
+        "Failed to parse ISO header",
+    )?;
 
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+    check_tool_status(ISOINFO, &output)?;
 
     let mut files: Vec<(String, i64, u64)> = Vec::new();
     let mut directory = "";
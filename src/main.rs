@@ -23,6 +23,8 @@ extern crate indicatif;
 extern crate lazy_static;
 #[cfg(feature = "ird")]
 extern crate md5;
+#[cfg(unix)]
+extern crate nix;
 extern crate num_derive;
 extern crate num_traits;
 extern crate once_cell;
@@ -32,6 +34,7 @@ extern crate rayon;
 extern crate regex;
 extern crate rust_embed;
 extern crate serde;
+extern crate serde_json;
 extern crate sha1;
 #[macro_use]
 extern crate simple_error;
@@ -42,9 +45,13 @@ extern crate surf;
 extern crate tempfile;
 #[cfg(feature = "server")]
 extern crate tide;
+extern crate tracing;
+extern crate tracing_subscriber;
+extern crate unicode_normalization;
 extern crate vec_drain_where;
-#[cfg(feature = "ird")]
 extern crate walkdir;
+#[cfg(feature = "zst")]
+extern crate zstd;
 
 #[cfg(feature = "benchmark")]
 mod benchmark;
@@ -52,48 +59,112 @@ mod benchmark;
 mod chdman;
 mod check_roms;
 mod checksum;
+mod collisions;
 mod config;
 mod convert_roms;
+mod create_system;
 mod database;
+mod dedup_formats;
+mod doctor;
 #[cfg(feature = "rvz")]
 mod dolphin;
 mod download_dats;
+mod export_playlist;
+mod fingerprint;
+mod history;
+mod identify;
 mod import_dats;
 #[cfg(feature = "ird")]
 mod import_irds;
 mod import_roms;
+mod info;
+mod inventory;
+mod iso9660;
 #[cfg(feature = "ird")]
 mod isoinfo;
+mod match_preferences;
 #[cfg(feature = "cso")]
 mod maxcso;
 mod model;
+mod optimize_db;
+#[cfg(feature = "pbp")]
+mod pbp;
 mod progress;
 mod prompt;
 mod purge_roms;
 mod purge_systems;
 mod rebuild_roms;
+mod rename_system;
 #[cfg(feature = "server")]
 mod server;
+mod set_system_archive_is_rom;
+mod set_system_hash;
 mod sevenzip;
 mod sort_roms;
+mod split_bin;
+#[cfg(feature = "torrentzip")]
+mod torrentzip;
 mod util;
+mod verify_against;
+#[cfg(feature = "zst")]
+mod zst;
 
 use async_std::path::PathBuf;
 use cfg_if::cfg_if;
-use clap::Command;
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgAction, Command};
 use database::*;
 use dotenv::dotenv;
+use model::{ErrorKind, ErrorReport};
 use progress::*;
 use simple_error::SimpleError;
+use std::collections::BTreeMap;
+use std::process;
+use tracing_subscriber::EnvFilter;
 use util::*;
 
 type SimpleResult<T> = Result<T, SimpleError>;
 
+const LOG_LEVELS: &[&str] = &["off", "error", "warn", "info", "debug", "trace"];
+const READ_ONLY_SUBCOMMANDS: &[&str] = &[
+    "collisions",
+    "export-playlist",
+    "fingerprint",
+    "history",
+    "identify",
+    "info",
+    "inventory",
+];
+
 #[async_std::main]
+async fn main() {
+    if let Err(error) = run().await {
+        let error_report = get_error_report(&error);
+        if get_json_errors() {
+            eprintln!("{}", serde_json::to_string(&error_report).unwrap());
+        } else {
+            eprintln!("Error: {:?}", error);
+        }
+        process::exit(get_exit_code(&error_report));
+    }
+}
+
+// each module classifies the errors it raises
+fn get_error_report(error: &SimpleError) -> ErrorReport {
+    util::get_error_report(error)
+        .or_else(|| sevenzip::get_error_report(error))
+        .unwrap_or_else(|| ErrorReport {
+            error_kind: ErrorKind::Other,
+            message: error.as_str().to_owned(),
+            context: BTreeMap::new(),
+        })
+}
+
 #[allow(unused_mut)]
-async fn main() -> SimpleResult<()> {
+async fn run() -> SimpleResult<()> {
     let mut subcommands = vec![
         config::subcommand(),
+        create_system::subcommand(),
         import_dats::subcommand(),
         download_dats::subcommand(),
         import_roms::subcommand(),
@@ -103,6 +174,22 @@ async fn main() -> SimpleResult<()> {
         check_roms::subcommand(),
         purge_roms::subcommand(),
         purge_systems::subcommand(),
+        doctor::subcommand(),
+        info::subcommand(),
+        inventory::subcommand(),
+        set_system_hash::subcommand(),
+        set_system_archive_is_rom::subcommand(),
+        rename_system::subcommand(),
+        collisions::subcommand(),
+        match_preferences::subcommand(),
+        fingerprint::subcommand(),
+        verify_against::subcommand(),
+        split_bin::subcommand(),
+        history::subcommand(),
+        dedup_formats::subcommand(),
+        export_playlist::subcommand(),
+        identify::subcommand(),
+        optimize_db::subcommand(),
     ];
     cfg_if! {
         if #[cfg(feature = "ird")] {
@@ -123,12 +210,61 @@ async fn main() -> SimpleResult<()> {
         .version(env!("CARGO_PKG_VERSION"))
         .about(env!("CARGO_PKG_DESCRIPTION"))
         .author(env!("CARGO_PKG_AUTHORS"))
+        .arg(
+            Arg::new("LOG_LEVEL")
+                .long("log-level")
+                .help("Set the log level, overrides RUST_LOG")
+                .required(false)
+                .global(true)
+                .num_args(1)
+                .value_parser(PossibleValuesParser::new(LOG_LEVELS)),
+        )
+        .arg(
+            Arg::new("CONTINUE_ON_TOOL_VERSION_MISMATCH")
+                .long("continue-on-tool-version-mismatch")
+                .help("Use external programs older than the minimum supported version")
+                .required(false)
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("JSON_ERRORS")
+                .long("json-errors")
+                .help("Print errors as JSON objects")
+                .required(false)
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("SHOW_COMMANDS")
+                .long("show-commands")
+                .help("Print external commands before running them")
+                .required(false)
+                .global(true)
+                .action(ArgAction::SetTrue),
+        )
         .subcommands(subcommands)
         .get_matches();
 
-    if matches.subcommand().is_some() {
+    if let Some((_, subcommand_matches)) = matches.subcommand() {
         dotenv().ok();
 
+        // diagnostics are logged to stderr, the progress bar only handles the interactive UI
+        let env_filter = match subcommand_matches.get_one::<String>("LOG_LEVEL") {
+            Some(log_level) => EnvFilter::new(log_level),
+            None => EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("off")),
+        };
+        tracing_subscriber::fmt()
+            .with_env_filter(env_filter)
+            .with_writer(std::io::stderr)
+            .init();
+
+        set_continue_on_tool_version_mismatch(
+            subcommand_matches.get_flag("CONTINUE_ON_TOOL_VERSION_MISMATCH"),
+        );
+        set_show_commands(subcommand_matches.get_flag("SHOW_COMMANDS"));
+        set_json_errors(subcommand_matches.get_flag("JSON_ERRORS"));
+
         let progress_bar = get_progress_bar(0, get_none_progress_style());
 
         let data_directory = PathBuf::from(dirs::data_dir().unwrap()).join("oxyromon");
@@ -138,7 +274,15 @@ async fn main() -> SimpleResult<()> {
         if !db_file.is_file().await {
             create_file(&progress_bar, &db_file, true).await?;
         }
-        let pool = establish_connection(db_file.as_os_str().to_str().unwrap()).await;
+        let db_url = db_file.as_os_str().to_str().unwrap();
+        let pool = if matches
+            .subcommand_name()
+            .map_or(false, |name| READ_ONLY_SUBCOMMANDS.contains(&name))
+        {
+            establish_read_only_connection(db_url).await
+        } else {
+            establish_connection(db_url).await
+        };
 
         match matches.subcommand_name() {
             Some("config") => {
@@ -149,6 +293,14 @@ async fn main() -> SimpleResult<()> {
                 )
                 .await?
             }
+            Some("create-system") => {
+                create_system::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("create-system").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
             Some("import-dats") => {
                 import_dats::main(
                     &mut pool.acquire().await.unwrap(),
@@ -227,6 +379,129 @@ async fn main() -> SimpleResult<()> {
             Some("purge-systems") => {
                 purge_systems::main(&mut pool.acquire().await.unwrap(), &progress_bar).await?
             }
+            Some("doctor") => doctor::main(&progress_bar)?,
+            Some("info") => {
+                info::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("info").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("inventory") => {
+                inventory::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("inventory").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("set-system-hash") => {
+                set_system_hash::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("set-system-hash").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("set-system-archive-is-rom") => {
+                set_system_archive_is_rom::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches
+                        .subcommand_matches("set-system-archive-is-rom")
+                        .unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("rename-system") => {
+                rename_system::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("rename-system").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("collisions") => {
+                collisions::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("collisions").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("match-preferences") => {
+                match_preferences::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("match-preferences").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("fingerprint") => {
+                fingerprint::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("fingerprint").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("verify-against") => {
+                verify_against::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("verify-against").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("split-bin") => {
+                split_bin::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("split-bin").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("history") => {
+                history::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("history").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("dedup-formats") => {
+                dedup_formats::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("dedup-formats").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("export-playlist") => {
+                export_playlist::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("export-playlist").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("identify") => {
+                identify::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("identify").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
+            Some("optimize-db") => {
+                optimize_db::main(
+                    &mut pool.acquire().await.unwrap(),
+                    matches.subcommand_matches("optimize-db").unwrap(),
+                    &progress_bar,
+                )
+                .await?
+            }
             Some("benchmark") => {
                 cfg_if! {
                     if #[cfg(feature = "benchmark")] {
@@ -0,0 +1,50 @@
+use super::database::*;
+use super::SimpleResult;
+use clap::{Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+
+pub fn subcommand() -> Command {
+    Command::new("match-preferences")
+        .about("List or clear the remembered choices for ambiguous matches")
+        .arg(
+            Arg::new("CLEAR")
+                .long("clear")
+                .help("Forget all remembered choices")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    if matches.get_flag("CLEAR") {
+        delete_match_preferences(connection).await;
+        progress_bar.println("Forgot all remembered choices");
+        return Ok(());
+    }
+    let lines = get_match_preferences(connection).await;
+    if lines.is_empty() {
+        progress_bar.println("No remembered choice");
+    }
+    for line in lines {
+        progress_bar.println(line);
+    }
+    Ok(())
+}
+
+pub async fn get_match_preferences(connection: &mut SqliteConnection) -> Vec<String> {
+    find_match_preference_rows(connection)
+        .await
+        .into_iter()
+        .map(|row| {
+            format!(
+                "{}: {} ({}) -> \"{}\" ({})",
+                row.system_name, row.hash, row.size, row.rom_name, row.game_name
+            )
+        })
+        .collect()
+}
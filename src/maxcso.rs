@@ -1,37 +1,101 @@
 use super::config::*;
 use super::progress::*;
+use super::util::*;
 use super::SimpleResult;
 use async_std::path::{Path, PathBuf};
 use indicatif::ProgressBar;
 use std::process::Command;
 use std::time::Duration;
 
+const MAXCSO: &str = "maxcso";
+const MAXCSO_PATH: &str = "MAXCSO_PATH";
+const MAXCSO_MINIMUM_VERSION: &str = "1.10.0";
+
+pub fn check_tool_available() -> SimpleResult<String> {
+    get_cached_tool_version(MAXCSO, MAXCSO_PATH, || {
+        check_tool_version(
+            MAXCSO,
+            get_tool_version(MAXCSO, MAXCSO_PATH, &["--version"])?,
+            MAXCSO_MINIMUM_VERSION,
+            get_continue_on_tool_version_mismatch(),
+        )
+    })
+}
+
 pub fn create_cso<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     iso_path: &P,
     directory: &Q,
 ) -> SimpleResult<PathBuf> {
-    progress_bar.set_message("Creating CSO");
+    create(progress_bar, iso_path, directory, CSO_EXTENSION, "CSO", &[])
+}
+
+pub fn create_zso<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    iso_path: &P,
+    directory: &Q,
+) -> SimpleResult<PathBuf> {
+    create(
+        progress_bar,
+        iso_path,
+        directory,
+        ZSO_EXTENSION,
+        "ZSO",
+        &["--format=zso"],
+    )
+}
+
+pub fn extract_cso<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    cso_path: &P,
+    directory: &Q,
+) -> SimpleResult<PathBuf> {
+    extract(progress_bar, cso_path, directory, "CSO")
+}
+
+pub fn extract_zso<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    zso_path: &P,
+    directory: &Q,
+) -> SimpleResult<PathBuf> {
+    extract(progress_bar, zso_path, directory, "ZSO")
+}
+
+fn create<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    iso_path: &P,
+    directory: &Q,
+    extension: &str,
+    name: &str,
+    args: &[&str],
+) -> SimpleResult<PathBuf> {
+    progress_bar.set_message(format!("Creating {}", name));
     progress_bar.set_style(get_none_progress_style());
     progress_bar.enable_steady_tick(Duration::from_millis(100));
 
     let mut cso_path = directory
         .as_ref()
         .join(iso_path.as_ref().file_name().unwrap());
-    cso_path.set_extension(CSO_EXTENSION);
+    cso_path.set_extension(extension);
 
     progress_bar.println(format!("Creating {:?}", cso_path.file_name().unwrap()));
 
-    let output = Command::new("maxcso")
-        .arg(iso_path.as_ref())
-        .arg("-o")
-        .arg(&cso_path)
-        .output()
-        .expect("Failed to create CSO");
+    write_atomically(progress_bar, &cso_path, |part_path| {
+        let output = get_tool_output(
+            progress_bar,
+            Command::new(get_tool_path(MAXCSO, MAXCSO_PATH))
+                .args(args)
+                .arg(iso_path.as_ref())
+                .arg("-o")
+                .arg(part_path),
+            MAXCSO,
+            MAXCSO_PATH,
+            &format!("Failed to create {}", name),
+        )?;
 
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+        check_tool_status(MAXCSO, &output)?;
+        Ok(())
+    })?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
@@ -39,12 +103,13 @@ pub fn create_cso<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(cso_path)
 }
 
-pub fn extract_cso<P: AsRef<Path>, Q: AsRef<Path>>(
+fn extract<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     cso_path: &P,
     directory: &Q,
+    name: &str,
 ) -> SimpleResult<PathBuf> {
-    progress_bar.set_message("Extracting CSO");
+    progress_bar.set_message(format!("Extracting {}", name));
     progress_bar.set_style(get_none_progress_style());
     progress_bar.enable_steady_tick(Duration::from_millis(100));
 
@@ -58,17 +123,22 @@ pub fn extract_cso<P: AsRef<Path>, Q: AsRef<Path>>(
         .join(cso_path.as_ref().file_name().unwrap());
     iso_path.set_extension(ISO_EXTENSION);
 
-    let output = Command::new("maxcso")
-        .arg("--decompress")
-        .arg(cso_path.as_ref())
-        .arg("-o")
-        .arg(&iso_path)
-        .output()
-        .expect("Failed to extract CSO");
-
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+    write_atomically(progress_bar, &iso_path, |part_path| {
+        let output = get_tool_output(
+            progress_bar,
+            Command::new(get_tool_path(MAXCSO, MAXCSO_PATH))
+                .arg("--decompress")
+                .arg(cso_path.as_ref())
+                .arg("-o")
+                .arg(part_path),
+            MAXCSO,
+            MAXCSO_PATH,
+            &format!("Failed to extract {}", name),
+        )?;
+
+        check_tool_status(MAXCSO, &output)?;
+        Ok(())
+    })?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
@@ -1,8 +1,9 @@
 #[cfg(feature = "server")]
 use async_graphql::{Enum, SimpleObject};
 use num_derive::FromPrimitive;
-use serde::Deserialize;
+use serde::{de, Deserialize, Deserializer, Serialize};
 use sqlx::{FromRow, Type};
+use std::collections::BTreeMap;
 #[cfg(feature = "ird")]
 use std::collections::HashMap;
 
@@ -28,6 +29,9 @@ pub struct System {
     pub complete: bool,
     pub arcade: bool,
     pub merging: i64,
+    pub hash_algorithm: Option<String>,
+    pub archive_is_rom: bool,
+    pub recommended_hash_algorithm: Option<String>,
 }
 
 #[cfg_attr(feature = "server", derive(Clone, SimpleObject))]
@@ -45,6 +49,10 @@ pub struct Rule {
     pub start_byte: i64,
     pub hex_value: String,
     pub header_id: i64,
+    pub rule_index: i64,
+    pub start_offset: i64,
+    pub end_offset: Option<i64>,
+    pub result: bool,
 }
 
 #[derive(FromPrimitive, Type)]
@@ -56,6 +64,30 @@ pub enum Sorting {
     Ignored = 2,
 }
 
+#[derive(FromPrimitive)]
+pub enum UntrackedDestination {
+    Skip = 0,
+    Adopt = 1,
+    Overwrite = 2,
+}
+
+#[derive(PartialEq, Eq)]
+pub enum TrashBackend {
+    Folder,
+    SystemTrash,
+}
+
+#[derive(Debug)]
+pub enum TrashReason {
+    NoMatch,
+    SizeMismatch,
+    CrcMismatch,
+    Md5Mismatch,
+    Sha1Mismatch,
+    Invalid,
+    Redundant,
+}
+
 #[derive(FromRow)]
 #[cfg_attr(feature = "server", derive(Clone, SimpleObject))]
 #[cfg_attr(feature = "server", graphql(complex))]
@@ -64,6 +96,8 @@ pub struct Game {
     pub name: String,
     pub description: String,
     pub comment: Option<String>,
+    pub year: Option<String>,
+    pub manufacturer: Option<String>,
     pub external_id: Option<String>,
     pub bios: bool,
     pub jbfolder: bool,
@@ -98,6 +132,53 @@ pub struct Romfile {
     pub id: i64,
     pub path: String,
     pub size: i64,
+    pub matched_dat_version: Option<String>,
+    pub manifest: Option<String>,
+    pub cached_size: Option<i64>,
+    pub cached_mtime: Option<i64>,
+    pub cached_crc: Option<String>,
+    pub cached_md5: Option<String>,
+    pub cached_sha1: Option<String>,
+}
+
+pub struct HeaderlessCrc {
+    pub size: i64,
+    pub crc: String,
+}
+
+pub struct MatchPreferenceRow {
+    pub system_name: String,
+    pub game_name: String,
+    pub rom_name: String,
+    pub size: i64,
+    pub hash: String,
+}
+
+pub struct RunHistoryRow {
+    pub date: String,
+    pub command: String,
+    pub system_name: String,
+    pub files: i64,
+    pub duration: i64,
+    pub outcome: String,
+}
+
+// the serialized field names are the inventory schema, shared by every format
+#[derive(Serialize)]
+pub struct InventoryRow {
+    #[serde(rename = "system")]
+    pub system_name: String,
+    #[serde(rename = "game")]
+    pub game_name: String,
+    #[serde(rename = "rom")]
+    pub rom_name: String,
+    pub size: i64,
+    pub crc: Option<String>,
+    pub md5: Option<String>,
+    pub sha1: Option<String>,
+    #[serde(rename = "path")]
+    pub romfile_path: Option<String>,
+    pub complete: bool,
 }
 
 #[cfg_attr(feature = "server", derive(Clone, SimpleObject))]
@@ -141,6 +222,8 @@ pub struct GameXml {
     pub name: String,
     pub description: String,
     pub comment: Option<String>,
+    pub year: Option<String>,
+    pub manufacturer: Option<String>,
     pub cloneof: Option<String>,
     pub romof: Option<String>,
     pub isbios: Option<String>,
@@ -152,6 +235,7 @@ pub struct GameXml {
 pub struct RomXml {
     pub name: String,
     pub merge: Option<String>,
+    #[serde(deserialize_with = "deserialize_rom_size")]
     pub size: i64,
     pub crc: Option<String>,
     pub md5: Option<String>,
@@ -159,16 +243,35 @@ pub struct RomXml {
     pub status: Option<String>,
 }
 
+// sizes are stored as signed integers, reject what they can't hold instead of wrapping around
+fn deserialize_rom_size<'de, D: Deserializer<'de>>(deserializer: D) -> Result<i64, D::Error> {
+    let size = String::deserialize(deserializer)?;
+    size.trim()
+        .parse::<u64>()
+        .ok()
+        .and_then(|parsed_size| i64::try_from(parsed_size).ok())
+        .ok_or_else(|| {
+            de::Error::custom(format!(
+                "invalid ROM size \"{}\", expected a positive integer up to {}",
+                size,
+                i64::MAX
+            ))
+        })
+}
+
 #[derive(Deserialize)]
 pub struct DetectorXml {
     pub name: String,
     pub version: String,
-    pub rule: RuleXml,
+    #[serde(rename = "rule")]
+    pub rules: Vec<RuleXml>,
 }
 
 #[derive(Deserialize)]
 pub struct RuleXml {
     pub start_offset: String,
+    pub end_offset: Option<String>,
+    #[serde(default)]
     pub data: Vec<DataXml>,
 }
 
@@ -176,6 +279,7 @@ pub struct RuleXml {
 pub struct DataXml {
     pub offset: String,
     pub value: String,
+    pub result: Option<String>,
 }
 
 #[cfg(feature = "ird")]
@@ -191,3 +295,20 @@ pub struct Irdfile {
     pub files_count: usize,
     pub files_hashes: HashMap<u64, String>,
 }
+
+// kinds are part of the command line interface, never rename them
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    ToolNotFound,
+    ToolVersionMismatch,
+    DuplicateMemberNames,
+    Other,
+}
+
+#[derive(Serialize)]
+pub struct ErrorReport {
+    pub error_kind: ErrorKind,
+    pub message: String,
+    pub context: BTreeMap<&'static str, String>,
+}
@@ -0,0 +1,67 @@
+use super::database::*;
+use super::progress::*;
+use super::SimpleResult;
+use async_std::path::{Path, PathBuf};
+use clap::{ArgMatches, Command};
+use indicatif::{HumanBytes, ProgressBar};
+use sqlx::sqlite::SqliteConnection;
+use std::time::Duration;
+
+pub fn subcommand() -> Command {
+    Command::new("optimize-db")
+        .about("Reclaim unused space and refresh the statistics of the database")
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    _matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let (size_before, size_after) = optimize_db(connection, progress_bar).await?;
+    progress_bar.println(format!(
+        "Database size: {} -> {}, {} reclaimed",
+        HumanBytes(size_before),
+        HumanBytes(size_after),
+        HumanBytes(size_before.saturating_sub(size_after))
+    ));
+    Ok(())
+}
+
+pub async fn optimize_db(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<(u64, u64)> {
+    let database_path = PathBuf::from(find_database_path(connection).await);
+    let size_before = get_database_size(&database_path).await?;
+
+    progress_bar.set_message("Optimizing database");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    vacuum_and_analyze(connection).await;
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    let size_after = get_database_size(&database_path).await?;
+    Ok((size_before, size_after))
+}
+
+// pages not checkpointed yet live in the write-ahead log next to the database file
+async fn get_database_size<P: AsRef<Path>>(database_path: &P) -> SimpleResult<u64> {
+    let mut size = try_with!(
+        database_path.as_ref().metadata().await,
+        "Failed to read {:?} metadata",
+        database_path.as_ref()
+    )
+    .len();
+    let mut wal_path = database_path.as_ref().as_os_str().to_owned();
+    wal_path.push("-wal");
+    if let Ok(metadata) = Path::new(&wal_path).metadata().await {
+        size += metadata.len();
+    }
+    Ok(size)
+}
+
+#[cfg(test)]
+mod test_optimize_db;
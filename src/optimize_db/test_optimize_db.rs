@@ -0,0 +1,52 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::import_dats;
+use super::*;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20200721).dat",
+        "tests/Test System (20221225) (Revisions).dat",
+        "tests/Test System (20221226) (Multiple Files).dat",
+        "tests/Test System (20230102) (Multiple Discs).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+    for system in find_systems(&mut connection).await {
+        delete_system_by_id(&mut connection, system.id).await;
+    }
+
+    // when
+    let (size_before, size_after) = optimize_db(&mut connection, &progress_bar).await.unwrap();
+
+    // then
+    assert!(find_systems(&mut connection).await.is_empty());
+    assert_eq!(
+        Path::new(&find_database_path(&mut connection).await),
+        Path::new(db_file.path())
+    );
+    assert!(size_after < size_before);
+    assert_eq!(
+        size_after,
+        db_file.path().metadata().unwrap().len(),
+        "the write-ahead log should be empty"
+    );
+}
@@ -0,0 +1,167 @@
+use super::config::*;
+use super::progress::*;
+use super::util::*;
+use super::SimpleResult;
+use async_std::path::{Path, PathBuf};
+use flate2::read::DeflateDecoder;
+use indicatif::ProgressBar;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+
+const PBP_MAGIC: &[u8] = b"\0PBP";
+const PBP_HEADER_SIZE: usize = 0x28;
+const PSAR_OFFSET_POSITION: usize = 0x24;
+const PSTITLEIMG_MAGIC: &[u8] = b"PSTITLEIMG000000";
+const PSISOIMG_MAGIC: &[u8] = b"PSISOIMG0000";
+const DISC_OFFSETS_POSITION: u64 = 0x200;
+const MAX_DISCS: usize = 5;
+const ISO_INDEX_POSITION: u64 = 0x4000;
+const ISO_INDEX_ENTRY_SIZE: usize = 32;
+const ISO_DATA_POSITION: u64 = 0x100000;
+const ISO_BLOCK_SIZE: usize = 0x9300;
+
+pub fn extract_pbp<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    pbp_path: &P,
+    directory: &Q,
+) -> SimpleResult<Vec<PathBuf>> {
+    progress_bar.set_message("Extracting PBP");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    progress_bar.println(format!(
+        "Extracting {:?}",
+        pbp_path.as_ref().file_name().unwrap()
+    ));
+
+    let mut file = open_file_sync(pbp_path)?;
+    let psar_position = read_psar_position(&mut file)?;
+    let disc_positions = read_disc_positions(&mut file, psar_position)?;
+
+    let file_stem = pbp_path.as_ref().file_stem().unwrap().to_str().unwrap();
+    let mut bin_paths: Vec<PathBuf> = Vec::new();
+    for (i, disc_position) in disc_positions.iter().enumerate() {
+        let bin_path = match disc_positions.len() {
+            1 => directory
+                .as_ref()
+                .join(format!("{}.{}", file_stem, BIN_EXTENSION)),
+            _ => {
+                directory
+                    .as_ref()
+                    .join(format!("{} (Disc {}).{}", file_stem, i + 1, BIN_EXTENSION))
+            }
+        };
+        extract_disc(&mut file, *disc_position, &bin_path)?;
+        bin_paths.push(bin_path);
+    }
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    Ok(bin_paths)
+}
+
+fn read_psar_position(file: &mut File) -> SimpleResult<u64> {
+    let mut header = [0u8; PBP_HEADER_SIZE];
+    try_with!(file.read_exact(&mut header), "Failed to read PBP header");
+    if &header[..PBP_MAGIC.len()] != PBP_MAGIC {
+        bail!("Not a PBP file");
+    }
+    Ok(read_u32(&header, PSAR_OFFSET_POSITION) as u64)
+}
+
+fn read_disc_positions(file: &mut File, psar_position: u64) -> SimpleResult<Vec<u64>> {
+    let mut magic = [0u8; 16];
+    try_with!(
+        file.seek(SeekFrom::Start(psar_position)),
+        "Failed to seek PBP"
+    );
+    try_with!(file.read_exact(&mut magic), "Failed to read PSAR header");
+
+    // single disc
+    if magic.starts_with(PSISOIMG_MAGIC) {
+        return Ok(vec![psar_position]);
+    }
+
+    // multiple discs
+    if magic.starts_with(PSTITLEIMG_MAGIC) {
+        let mut offsets = [0u8; MAX_DISCS * 4];
+        try_with!(
+            file.seek(SeekFrom::Start(psar_position + DISC_OFFSETS_POSITION)),
+            "Failed to seek PBP"
+        );
+        try_with!(file.read_exact(&mut offsets), "Failed to read disc offsets");
+        let disc_positions: Vec<u64> = (0..MAX_DISCS)
+            .map(|i| read_u32(&offsets, i * 4) as u64)
+            .take_while(|&offset| offset != 0)
+            .map(|offset| psar_position + offset)
+            .collect();
+        if disc_positions.is_empty() {
+            bail!("PBP contains no disc");
+        }
+        return Ok(disc_positions);
+    }
+
+    bail!("Unsupported PBP data, only PlayStation disc images are supported");
+}
+
+fn extract_disc<P: AsRef<Path>>(
+    file: &mut File,
+    disc_position: u64,
+    bin_path: &P,
+) -> SimpleResult<()> {
+    let mut magic = [0u8; 12];
+    try_with!(
+        file.seek(SeekFrom::Start(disc_position)),
+        "Failed to seek PBP"
+    );
+    try_with!(file.read_exact(&mut magic), "Failed to read disc header");
+    if magic != PSISOIMG_MAGIC {
+        bail!("Invalid disc header");
+    }
+
+    // the index table lists the offset and length of each block
+    let mut index = vec![0u8; (ISO_DATA_POSITION - ISO_INDEX_POSITION) as usize];
+    try_with!(
+        file.seek(SeekFrom::Start(disc_position + ISO_INDEX_POSITION)),
+        "Failed to seek PBP"
+    );
+    try_with!(file.read_exact(&mut index), "Failed to read disc index");
+    let blocks: Vec<(u64, usize)> = index
+        .chunks_exact(ISO_INDEX_ENTRY_SIZE)
+        .map(|entry| (read_u32(entry, 0) as u64, read_u16(entry, 4) as usize))
+        .take_while(|&(_, length)| length != 0)
+        .collect();
+
+    let mut bin_file = try_with!(File::create(bin_path.as_ref()), "Failed to create BIN");
+    for (offset, length) in blocks {
+        let mut data = vec![0u8; length];
+        try_with!(
+            file.seek(SeekFrom::Start(disc_position + ISO_DATA_POSITION + offset)),
+            "Failed to seek PBP"
+        );
+        try_with!(file.read_exact(&mut data), "Failed to read disc block");
+        // full-size blocks are stored as is, others are deflated
+        if length == ISO_BLOCK_SIZE {
+            try_with!(bin_file.write_all(&data), "Failed to write BIN");
+        } else {
+            let mut block: Vec<u8> = Vec::with_capacity(ISO_BLOCK_SIZE);
+            try_with!(
+                DeflateDecoder::new(data.as_slice()).read_to_end(&mut block),
+                "Failed to inflate disc block"
+            );
+            try_with!(bin_file.write_all(&block), "Failed to write BIN");
+        }
+    }
+
+    Ok(())
+}
+
+fn read_u32(bytes: &[u8], position: usize) -> u32 {
+    u32::from_le_bytes(bytes[position..position + 4].try_into().unwrap())
+}
+
+fn read_u16(bytes: &[u8], position: usize) -> u16 {
+    u16::from_le_bytes(bytes[position..position + 2].try_into().unwrap())
+}
@@ -1,4 +1,17 @@
-use indicatif::{ProgressBar, ProgressStyle};
+use super::model::Rom;
+use super::util::get_free_space;
+use async_std::path::Path;
+use console::Term;
+use indicatif::{
+    HumanBytes, HumanDuration, MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle,
+    TermLike,
+};
+use is_terminal::IsTerminal;
+use std::fmt;
+use std::io::{self, Write};
+use std::mem;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
 
 const PROGRESS_CHARS: &str = "#-";
 const NONE_TEMPLATE: &str = "{spinner} {wide_msg}";
@@ -6,27 +19,319 @@ const COUNT_TEMPLATE: &str =
     "{spinner} {wide_msg} {pos}/{len} {per_sec} {elapsed_precise} ({eta_precise}) [{bar:80}]";
 const BYTES_TEMPLATE: &str =
     "{spinner} {wide_msg} {bytes}/{total_bytes} {bytes_per_sec} {elapsed_precise} ({eta_precise}) [{bar:80}]";
+const PLAIN_NONE_TEMPLATE: &str = "{msg}";
+const PLAIN_COUNT_TEMPLATE: &str = "{msg} {pos}/{len} {per_sec} {elapsed_precise} ({eta_precise})";
+const PLAIN_BYTES_TEMPLATE: &str =
+    "{msg} {bytes}/{total_bytes} {bytes_per_sec} {elapsed_precise} ({eta_precise})";
+const PLAIN_INTERVAL: Duration = Duration::from_secs(5);
+const STATUS_FILES: u64 = 100;
+const ELLIPSIS: &str = "…";
+const DEFAULT_WIDTH: usize = 80;
+
+// structured events for front-ends embedding oxyromon, the CLI only uses the progress bar
+pub trait ProgressSink {
+    fn progress_bar(&self) -> &ProgressBar;
+    fn on_file_start(&self, _path: &Path) {}
+    fn on_match(&self, _rom: &Rom) {}
+    fn on_moved(&self, _old_path: &Path, _new_path: &Path) {}
+    fn on_trashed(&self, _old_path: &Path, _new_path: &Path) {}
+    fn on_progress(&self, _position: u64, _length: u64) {}
+    fn on_status(&self, _status: &Status) {}
+    fn on_warning(&self, _message: &str) {}
+    // unmatched files are left in place for now when this returns true
+    fn hold_unmatched(&self, _path: &Path) -> bool {
+        false
+    }
+}
+
+impl ProgressSink for ProgressBar {
+    fn progress_bar(&self) -> &ProgressBar {
+        self
+    }
+}
+
+lazy_static! {
+    // indicatif draws on stderr, spinners and bars only clutter it when it isn't a terminal
+    static ref PLAIN_OUTPUT: bool = !io::stderr().is_terminal();
+}
 
 pub fn get_progress_bar(length: u64, style: ProgressStyle) -> ProgressBar {
-    ProgressBar::new(length).with_style(style)
+    if *PLAIN_OUTPUT {
+        ProgressBar::with_draw_target(Some(length), get_plain_draw_target()).with_style(style)
+    } else {
+        ProgressBar::new(length).with_style(style)
+    }
+}
+
+pub fn get_multi_progress(progress_bar: &ProgressBar) -> MultiProgress {
+    // keep child bars hidden when the parent one is (eg: in tests)
+    if progress_bar.is_hidden() {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    } else if *PLAIN_OUTPUT {
+        MultiProgress::with_draw_target(get_plain_draw_target())
+    } else {
+        MultiProgress::new()
+    }
+}
+
+fn get_plain_draw_target() -> ProgressDrawTarget {
+    ProgressDrawTarget::term_like(Box::new(PlainTerm::new(io::stderr(), PLAIN_INTERVAL)))
+}
+
+#[derive(Default)]
+struct PlainTermState {
+    previous_lines: Vec<String>,
+    lines: Vec<String>,
+    partial: Option<String>,
+    cleared_lines: usize,
+    last_progress_lines: Vec<String>,
+    last_progress_time: Option<Instant>,
+}
+
+// replays indicatif frames as plain lines, a frame starts by clearing the progress lines of the
+// previous one so whatever came before them was printed and is written as is, while progress
+// lines are only written once per interval when they changed
+pub struct PlainTerm {
+    writer: Mutex<Box<dyn Write + Send>>,
+    interval: Duration,
+    state: Mutex<PlainTermState>,
+}
+
+impl PlainTerm {
+    pub fn new<W: Write + Send + 'static>(writer: W, interval: Duration) -> PlainTerm {
+        PlainTerm {
+            writer: Mutex::new(Box::new(writer)),
+            interval,
+            state: Mutex::new(PlainTermState::default()),
+        }
+    }
+
+    fn write_lines(&self, lines: &[String]) -> io::Result<()> {
+        let mut writer = self.writer.lock().unwrap();
+        for line in lines {
+            writeln!(writer, "{}", line)?;
+        }
+        writer.flush()
+    }
+}
+
+impl fmt::Debug for PlainTerm {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("PlainTerm")
+            .field("interval", &self.interval)
+            .finish_non_exhaustive()
+    }
+}
+
+impl TermLike for PlainTerm {
+    fn width(&self) -> u16 {
+        DEFAULT_WIDTH as u16
+    }
+
+    fn move_cursor_up(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_down(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_right(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn move_cursor_left(&self, _n: usize) -> io::Result<()> {
+        Ok(())
+    }
+
+    fn write_line(&self, s: &str) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        let mut line = state.partial.take().unwrap_or_default();
+        line.push_str(s);
+        state.lines.push(line);
+        Ok(())
+    }
+
+    fn write_str(&self, s: &str) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        state.partial.get_or_insert_with(String::new).push_str(s);
+        Ok(())
+    }
+
+    fn clear_line(&self) -> io::Result<()> {
+        self.state.lock().unwrap().cleared_lines += 1;
+        Ok(())
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        let mut state = self.state.lock().unwrap();
+        if let Some(partial) = state.partial.take() {
+            // the last line is padded to the terminal width
+            state.lines.push(partial.trim_end().to_owned());
+        }
+        let lines = mem::take(&mut state.lines);
+        let previous_lines = mem::replace(&mut state.previous_lines, lines);
+        let progress_count = state.cleared_lines.min(previous_lines.len());
+        state.cleared_lines = 0;
+        let (printed_lines, progress_lines) =
+            previous_lines.split_at(previous_lines.len() - progress_count);
+        self.write_lines(printed_lines)?;
+        if !progress_lines.is_empty()
+            && progress_lines != state.last_progress_lines
+            && state
+                .last_progress_time
+                .map_or(true, |time| time.elapsed() >= self.interval)
+        {
+            self.write_lines(progress_lines)?;
+            state.last_progress_lines = progress_lines.to_vec();
+            state.last_progress_time = Some(Instant::now());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for PlainTerm {
+    // the last frame is never cleared, write it whole unless its progress lines were already
+    fn drop(&mut self) {
+        let state = self.state.get_mut().unwrap();
+        let mut lines = mem::take(&mut state.previous_lines);
+        if lines.ends_with(&state.last_progress_lines) {
+            lines.truncate(lines.len() - state.last_progress_lines.len());
+        }
+        let _ = self.write_lines(&lines);
+    }
+}
+
+// indicatif draws on stderr, fall back to a sane width when it isn't a terminal
+pub fn get_terminal_width() -> usize {
+    Term::stderr()
+        .size_checked()
+        .map(|(_, width)| width as usize)
+        .unwrap_or(DEFAULT_WIDTH)
+}
+
+// cut long messages in the middle, the end of a path is what tells files apart
+pub fn truncate_middle(message: &str, width: usize) -> String {
+    let chars: Vec<char> = message.chars().collect();
+    if chars.len() <= width {
+        return message.to_owned();
+    }
+    let kept = width.saturating_sub(ELLIPSIS.chars().count());
+    let head = kept / 2;
+    let tail = kept - head;
+    format!(
+        "{}{}{}",
+        chars[..head].iter().collect::<String>(),
+        ELLIPSIS,
+        chars[chars.len() - tail..].iter().collect::<String>()
+    )
+}
+
+pub fn fit_to_terminal(message: &str) -> String {
+    truncate_middle(message, get_terminal_width())
 }
 
 pub fn get_none_progress_style() -> ProgressStyle {
     ProgressStyle::default_bar()
-        .template(NONE_TEMPLATE)
+        .template(if *PLAIN_OUTPUT {
+            PLAIN_NONE_TEMPLATE
+        } else {
+            NONE_TEMPLATE
+        })
         .expect("Failed to create progress bar")
 }
 
 pub fn get_count_progress_style() -> ProgressStyle {
     ProgressStyle::default_bar()
-        .template(COUNT_TEMPLATE)
+        .template(if *PLAIN_OUTPUT {
+            PLAIN_COUNT_TEMPLATE
+        } else {
+            COUNT_TEMPLATE
+        })
         .expect("Failed to create progress bar")
         .progress_chars(PROGRESS_CHARS)
 }
 
 pub fn get_bytes_progress_style() -> ProgressStyle {
     ProgressStyle::default_bar()
-        .template(BYTES_TEMPLATE)
+        .template(if *PLAIN_OUTPUT {
+            PLAIN_BYTES_TEMPLATE
+        } else {
+            BYTES_TEMPLATE
+        })
         .expect("Failed to create progress bar")
         .progress_chars(PROGRESS_CHARS)
 }
+
+pub struct Status {
+    pub done: u64,
+    pub total: u64,
+    pub elapsed: Duration,
+    pub eta: Duration,
+    pub free_space: Option<u64>,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}/{} files done, {} remaining, elapsed {}, ETA {}",
+            self.done,
+            self.total,
+            self.total.saturating_sub(self.done),
+            HumanDuration(self.elapsed),
+            HumanDuration(self.eta)
+        )?;
+        if let Some(free_space) = self.free_space {
+            write!(f, ", {} free", HumanBytes(free_space))?;
+        }
+        Ok(())
+    }
+}
+
+// throttles the status lines of long runs to one every interval or every STATUS_FILES files
+pub struct StatusReporter {
+    interval: Duration,
+    total: u64,
+    start: Instant,
+    last_time: Instant,
+    last_done: u64,
+}
+
+impl StatusReporter {
+    pub fn new(interval: Duration, total: u64) -> StatusReporter {
+        let now = Instant::now();
+        StatusReporter {
+            interval,
+            total,
+            start: now,
+            last_time: now,
+            last_done: 0,
+        }
+    }
+
+    pub fn poll<P: AsRef<Path>>(&mut self, done: u64, path: &P) -> Option<Status> {
+        let now = Instant::now();
+        if done == self.last_done
+            || (now.duration_since(self.last_time) < self.interval
+                && done - self.last_done < STATUS_FILES)
+        {
+            return None;
+        }
+        self.last_time = now;
+        self.last_done = done;
+        let elapsed = now.duration_since(self.start);
+        Some(Status {
+            done,
+            total: self.total,
+            elapsed,
+            eta: elapsed.mul_f64(self.total.saturating_sub(done) as f64 / done as f64),
+            free_space: get_free_space(path),
+        })
+    }
+}
+
+#[cfg(test)]
+mod test_plain_term;
+#[cfg(test)]
+mod test_truncate_middle;
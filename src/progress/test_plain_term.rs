@@ -0,0 +1,61 @@
+use super::*;
+use std::sync::Arc;
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn test() {
+    // given
+    let buffer = SharedBuffer::default();
+    let progress_bar = ProgressBar::with_draw_target(
+        Some(3),
+        ProgressDrawTarget::term_like(Box::new(PlainTerm::new(buffer.clone(), Duration::ZERO))),
+    )
+    .with_style(
+        ProgressStyle::default_bar()
+            .template(PLAIN_COUNT_TEMPLATE)
+            .unwrap(),
+    );
+    progress_bar.set_message("Processed");
+
+    // when
+    for file_name in ["a.rom", "b.rom", "c.rom"] {
+        progress_bar.println(format!("Processing \"{}\"", file_name));
+        progress_bar.inc(1);
+    }
+    progress_bar.finish();
+    drop(progress_bar);
+
+    // then
+    let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+    assert!(output.ends_with('\n'));
+    assert!(!output.contains('\x1b'));
+    assert!(!output.contains('\r'));
+    let lines: Vec<&str> = output.lines().collect();
+    assert_eq!(
+        lines
+            .iter()
+            .filter(|line| line.starts_with("Processing"))
+            .copied()
+            .collect::<Vec<&str>>(),
+        vec![
+            "Processing \"a.rom\"",
+            "Processing \"b.rom\"",
+            "Processing \"c.rom\""
+        ]
+    );
+    assert!(lines.iter().any(|line| line.starts_with("Processed 1/3 ")));
+    assert!(lines.last().unwrap().starts_with("Processed 3/3 "));
+    assert!(lines.iter().all(|line| !line.ends_with(' ')));
+}
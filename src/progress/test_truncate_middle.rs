@@ -0,0 +1,18 @@
+use super::*;
+
+#[test]
+fn test() {
+    // given
+    let path = "/roms/Test System/Test Game (USA, Europe)/PS3_GAME/USRDIR/Resources/Test Game (USA, Europe) (Track 01).bin";
+    let width = 40;
+
+    // when
+    let truncated = truncate_middle(path, width);
+
+    // then
+    assert_eq!(truncated.chars().count(), width);
+    assert!(truncated.starts_with("/roms/Test System/"));
+    assert!(truncated.ends_with("(Track 01).bin"));
+    assert!(truncated.contains(ELLIPSIS));
+    assert_eq!(truncate_middle("Test Game.rom", width), "Test Game.rom");
+}
@@ -154,6 +154,21 @@ pub fn prompt_for_rom_game(roms_games: &mut Vec<(Rom, Game)>) -> SimpleResult<Op
     Ok(index.map(|i| roms_games.remove(i).0))
 }
 
+pub fn prompt_for_system_game(
+    systems_games: &mut Vec<(System, Game)>,
+) -> SimpleResult<Option<System>> {
+    let index = select_opt(
+        &systems_games
+            .iter()
+            .map(|(system, game)| format!("{} ({})", &system.name, &game.name))
+            .collect::<Vec<String>>(),
+        "Please select a system",
+        None,
+        None,
+    )?;
+    Ok(index.map(|i| systems_games.remove(i).0))
+}
+
 pub fn confirm(default: bool) -> SimpleResult<bool> {
     Ok(try_with!(
         Confirm::new()
@@ -36,6 +36,20 @@ pub fn subcommand() -> Command {
                 .required(false)
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new("ARCHIVE_COMMENT")
+                .long("archive-comment")
+                .help("Embed a comment in created ZIP archives")
+                .required(false)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("TORRENTZIP")
+                .long("torrent-zip")
+                .help("Create ZIP archives in the TorrentZip format")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new("ALL")
                 .short('a')
@@ -74,6 +88,12 @@ pub async fn main(
         .unwrap(),
     };
 
+    let archive_comment = match matches.get_one::<String>("ARCHIVE_COMMENT") {
+        Some(archive_comment) => Some(archive_comment.to_owned()),
+        None => get_string(connection, "ARCHIVE_COMMENT").await,
+    };
+    let torrentzip = matches.get_flag("TORRENTZIP") || get_bool(connection, "TORRENTZIP").await;
+
     progress_bar.enable_steady_tick(Duration::from_millis(100));
 
     for system in systems {
@@ -88,6 +108,8 @@ pub async fn main(
             &system,
             merging,
             matches.get_flag("SOLID"),
+            archive_comment.as_deref(),
+            torrentzip,
         )
         .await?;
         progress_bar.println("");
@@ -102,6 +124,8 @@ async fn rebuild_system(
     system: &System,
     merging: Merging,
     solid: bool,
+    archive_comment: Option<&str>,
+    torrentzip: bool,
 ) -> SimpleResult<()> {
     progress_bar.println(&format!("Processing \"{}\"", system.name));
 
@@ -111,7 +135,17 @@ async fn rebuild_system(
         && (merging == Merging::NonMerged || merging == Merging::FullNonMerged)
     {
         for game in games {
-            expand_game(connection, progress_bar, system, &game, merging, solid).await?;
+            expand_game(
+                connection,
+                progress_bar,
+                system,
+                &game,
+                merging,
+                solid,
+                archive_comment,
+                torrentzip,
+            )
+            .await?;
         }
     } else if (system.merging == Merging::NonMerged as i64
         || system.merging == Merging::FullNonMerged as i64)
@@ -141,6 +175,8 @@ async fn expand_game(
     game: &Game,
     merging: Merging,
     solid: bool,
+    archive_comment: Option<&str>,
+    torrentzip: bool,
 ) -> SimpleResult<()> {
     progress_bar.println(&format!("Processing \"{}\"", game.name));
     let tmp_directory = get_tmp_directory(connection).await;
@@ -181,6 +217,8 @@ async fn expand_game(
             &archive_romfile,
             tmp_directory,
             solid,
+            archive_comment,
+            torrentzip,
         )
         .await?;
     }
@@ -242,6 +280,8 @@ async fn add_rom(
     archive_romfile: &Romfile,
     tmp_directory: &PathBuf,
     solid: bool,
+    archive_comment: Option<&str>,
+    torrentzip: bool,
 ) -> SimpleResult<()> {
     match rom.romfile_id {
         Some(romfile_id) => {
@@ -261,6 +301,8 @@ async fn add_rom(
                         &file_names,
                         tmp_directory,
                         false,
+                        archive_comment,
+                        torrentzip,
                     )?;
                     remove_file(progress_bar, &tmp_directory.join(&rom.name), true).await?;
                 } else {
@@ -270,6 +312,8 @@ async fn add_rom(
                         &file_names,
                         &Path::new(&romfile.path).parent().unwrap(),
                         solid,
+                        archive_comment,
+                        torrentzip,
                     )?;
                 }
                 update_rom_romfile(transaction, rom.id, Some(archive_romfile.id)).await;
@@ -317,6 +361,8 @@ async fn add_rom(
                         &[rom.name.as_str()],
                         tmp_directory,
                         false,
+                        archive_comment,
+                        torrentzip,
                     )?;
                     remove_file(progress_bar, &tmp_directory.join(&rom.name), true).await?;
                 } else if existing_rom.name != rom.name {
@@ -333,6 +379,8 @@ async fn add_rom(
                         &[rom.name.as_str()],
                         tmp_directory,
                         solid,
+                        archive_comment,
+                        torrentzip,
                     )?;
                     remove_file(progress_bar, &tmp_directory.join(&rom.name), true).await?;
                 } else {
@@ -342,6 +390,8 @@ async fn add_rom(
                         &file_names,
                         &Path::new(&existing_romfile.path).parent().unwrap(),
                         solid,
+                        archive_comment,
+                        torrentzip,
                     )?;
                 }
                 update_rom_romfile(transaction, rom.id, Some(archive_romfile.id)).await;
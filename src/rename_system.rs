@@ -0,0 +1,100 @@
+use super::database::*;
+use super::import_dats::SYSTEM_NAME_REGEX;
+use super::prompt::*;
+use super::util::*;
+use super::SimpleResult;
+use async_std::fs;
+use clap::{Arg, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use std::str::FromStr;
+
+pub fn subcommand() -> Command {
+    Command::new("rename-system")
+        .about("Rename a system and its directory")
+        .arg(
+            Arg::new("NAME")
+                .help("Set the new system name")
+                .required(true)
+                .num_args(1)
+                .index(1),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Set the system number to use")
+                .required(false)
+                .num_args(1),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let system = prompt_for_system(
+        connection,
+        matches
+            .get_one::<String>("SYSTEM")
+            .map(|s| FromStr::from_str(s).expect("Failed to parse number")),
+    )
+    .await?;
+    let name = matches.get_one::<String>("NAME").unwrap().trim();
+    if name.is_empty() {
+        bail!("System name can't be empty");
+    }
+    if find_system_by_name(connection, name).await.is_some() {
+        bail!("System \"{}\" already exists", name);
+    }
+
+    let system_directory =
+        get_canonicalized_path(&get_system_directory(connection, progress_bar, &system).await?)
+            .await?;
+    let new_system_directory = system_directory
+        .parent()
+        .unwrap()
+        .join(SYSTEM_NAME_REGEX.replace(name, "").trim());
+    if new_system_directory != system_directory && new_system_directory.exists().await {
+        bail!("Directory {:?} already exists", new_system_directory);
+    }
+
+    let mut transaction = begin_transaction(connection).await;
+
+    update_system_name(&mut transaction, system.id, name).await;
+    for romfile in find_romfiles_by_system_id(&mut transaction, system.id).await {
+        if let Ok(relative_path) =
+            std::path::Path::new(&romfile.path).strip_prefix(&system_directory)
+        {
+            let new_path = new_system_directory.join(relative_path);
+            update_romfile(
+                &mut transaction,
+                romfile.id,
+                new_path.as_os_str().to_str().unwrap(),
+                romfile.size as u64,
+            )
+            .await;
+        }
+    }
+
+    // the transaction is rolled back if the directory can't be moved
+    if new_system_directory != system_directory {
+        progress_bar.println(format!("Moving to {:?}", new_system_directory.as_os_str()));
+        try_with!(
+            fs::rename(&system_directory, &new_system_directory).await,
+            "Failed to move {:?} to {:?}",
+            system_directory,
+            new_system_directory
+        );
+    }
+
+    commit_transaction(transaction).await;
+
+    progress_bar.println(format!("\"{}\" renamed to \"{}\"", system.name, name));
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_rename_system;
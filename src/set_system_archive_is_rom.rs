@@ -0,0 +1,57 @@
+use super::database::*;
+use super::prompt::*;
+use super::SimpleResult;
+use clap::{value_parser, Arg, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use std::str::FromStr;
+
+pub fn subcommand() -> Command {
+    Command::new("set-system-archive-is-rom")
+        .about("Hash archives as a whole instead of their contents for a system")
+        .arg(
+            Arg::new("ARCHIVE_IS_ROM")
+                .help("Set whether archives are ROMs themselves")
+                .required(true)
+                .num_args(1)
+                .index(1)
+                .value_parser(value_parser!(bool)),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Set the system number to use")
+                .required(false)
+                .num_args(1),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let system = prompt_for_system(
+        connection,
+        matches
+            .get_one::<String>("SYSTEM")
+            .map(|s| FromStr::from_str(s).expect("Failed to parse number")),
+    )
+    .await?;
+    let archive_is_rom = *matches.get_one::<bool>("ARCHIVE_IS_ROM").unwrap();
+    update_system_archive_is_rom(connection, system.id, archive_is_rom).await;
+    progress_bar.println(format!(
+        "\"{}\" archives are {}",
+        system.name,
+        if archive_is_rom {
+            "hashed as a whole"
+        } else {
+            "hashed by contents"
+        }
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_import_archive_as_rom;
@@ -0,0 +1,55 @@
+use super::config::*;
+use super::database::*;
+use super::prompt::*;
+use super::SimpleResult;
+use clap::builder::PossibleValuesParser;
+use clap::{Arg, ArgMatches, Command};
+use indicatif::ProgressBar;
+use sqlx::sqlite::SqliteConnection;
+use std::str::FromStr;
+
+pub fn subcommand() -> Command {
+    Command::new("set-system-hash")
+        .about("Override the hash algorithm used for a system")
+        .arg(
+            Arg::new("HASH")
+                .help("Set the hash algorithm, use the global setting if omitted")
+                .required(false)
+                .num_args(1)
+                .index(1)
+                .value_parser(PossibleValuesParser::new(HASH_ALGORITHMS)),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Set the system number to use")
+                .required(false)
+                .num_args(1),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let system = prompt_for_system(
+        connection,
+        matches
+            .get_one::<String>("SYSTEM")
+            .map(|s| FromStr::from_str(s).expect("Failed to parse number")),
+    )
+    .await?;
+    let hash_algorithm = matches.get_one::<String>("HASH").map(String::as_str);
+    update_system_hash_algorithm(connection, system.id, hash_algorithm).await;
+    progress_bar.println(format!(
+        "\"{}\" hash algorithm set to {}",
+        system.name,
+        hash_algorithm.unwrap_or("the global setting")
+    ));
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_import_uses_system_hash;
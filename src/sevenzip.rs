@@ -1,10 +1,46 @@
+use super::config::*;
+use super::model::{ErrorKind, ErrorReport};
 use super::progress::*;
+#[cfg(feature = "torrentzip")]
+use super::torrentzip;
+use super::util::*;
 use super::SimpleResult;
 use async_std::path::{Path, PathBuf};
 use indicatif::ProgressBar;
+use serde::Serialize;
+use simple_error::SimpleError;
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Seek;
 use std::process::Command;
 use std::str::FromStr;
+#[cfg(test)]
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
+use tracing::instrument;
+use zip::ZipWriter;
+
+const SEVENZIP: &str = "7z";
+pub const SEVENZIP_PATH: &str = "SEVENZIP_PATH";
+const SEVENZIP_MINIMUM_VERSION: &str = "16.02";
+pub const DUPLICATE_MEMBER_NAMES_ERROR: &str = "Archive contains duplicate member names";
+pub const MANIFEST_MISMATCH_ERROR: &str = "Archive members don't match the stored manifest";
+#[cfg(not(feature = "torrentzip"))]
+pub const TORRENTZIP_DISABLED_ERROR: &str = "TorrentZip requires the torrentzip feature";
+
+#[cfg(test)]
+pub static EXTRACTIONS: AtomicUsize = AtomicUsize::new(0);
+
+pub fn check_tool_available() -> SimpleResult<String> {
+    get_cached_tool_version(SEVENZIP, SEVENZIP_PATH, || {
+        check_tool_version(
+            SEVENZIP,
+            get_tool_version(SEVENZIP, SEVENZIP_PATH, &[])?,
+            SEVENZIP_MINIMUM_VERSION,
+            get_continue_on_tool_version_mismatch(),
+        )
+    })
+}
 
 #[derive(PartialEq, Eq)]
 pub enum ArchiveType {
@@ -12,6 +48,7 @@ pub enum ArchiveType {
     Zip,
 }
 
+#[derive(Serialize)]
 pub struct ArchiveInfo {
     pub path: String,
     pub size: u64,
@@ -26,16 +63,18 @@ pub fn parse_archive<P: AsRef<Path>>(
     progress_bar.set_style(get_none_progress_style());
     progress_bar.enable_steady_tick(Duration::from_millis(100));
 
-    let output = Command::new("7z")
-        .arg("l")
-        .arg("-slt")
-        .arg(archive_path.as_ref())
-        .output()
-        .expect("Failed to parse archive");
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(SEVENZIP, SEVENZIP_PATH))
+            .arg("l")
+            .arg("-slt")
+            .arg(archive_path.as_ref()),
+        SEVENZIP,
+        SEVENZIP_PATH,
+        "Failed to parse archive",
+    )?;
 
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str());
-    }
+    check_tool_status(SEVENZIP, &output)?;
 
     let stdout = String::from_utf8(output.stdout).unwrap();
     let lines: Vec<&str> = stdout
@@ -61,14 +100,60 @@ pub fn parse_archive<P: AsRef<Path>>(
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
 
+    // members are looked up and extracted by name, duplicates can't be told apart
+    let mut paths: Vec<&str> = sevenzip_infos
+        .iter()
+        .map(|sevenzip_info| sevenzip_info.path.as_str())
+        .collect();
+    paths.sort_unstable();
+    if let Some(path) = paths.windows(2).find(|paths| paths[0] == paths[1]) {
+        bail!("{}: \"{}\"", DUPLICATE_MEMBER_NAMES_ERROR, path[0]);
+    }
+
     Ok(sevenzip_infos)
 }
 
-pub fn rename_file_in_archive<P: AsRef<Path>>(
+pub fn get_error_report(error: &SimpleError) -> Option<ErrorReport> {
+    if !error.as_str().starts_with(DUPLICATE_MEMBER_NAMES_ERROR) {
+        return None;
+    }
+    Some(ErrorReport {
+        error_kind: ErrorKind::DuplicateMemberNames,
+        message: error.as_str().to_owned(),
+        context: BTreeMap::new(),
+    })
+}
+
+// members are sorted by name so that the manifest doesn't depend on the archive layout
+pub fn build_manifest(sevenzip_infos: &[ArchiveInfo]) -> SimpleResult<String> {
+    let mut sevenzip_infos: Vec<&ArchiveInfo> = sevenzip_infos.iter().collect();
+    sevenzip_infos.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(try_with!(
+        serde_json::to_string(&sevenzip_infos),
+        "Failed to serialize manifest"
+    ))
+}
+
+pub fn check_manifest<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    archive_path: &P,
+    manifest: &str,
+) -> SimpleResult<()> {
+    let sevenzip_infos = parse_archive(progress_bar, archive_path)?;
+    if build_manifest(&sevenzip_infos)? != manifest {
+        bail!(MANIFEST_MISMATCH_ERROR);
+    }
+    Ok(())
+}
+
+pub fn rename_file_in_archive<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     archive_path: &P,
     file_name: &str,
     new_file_name: &str,
+    directory: &Q,
+    comment: Option<&str>,
+    torrentzip: bool,
 ) -> SimpleResult<()> {
     progress_bar.set_message("Renaming file in archive");
     progress_bar.set_style(get_none_progress_style());
@@ -78,16 +163,45 @@ pub fn rename_file_in_archive<P: AsRef<Path>>(
         file_name, new_file_name
     ));
 
-    let output = Command::new("7z")
-        .arg("rn")
-        .arg(archive_path.as_ref())
-        .arg(file_name)
-        .arg(new_file_name)
-        .output()
-        .expect("Failed to rename file in archive");
-
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str());
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(SEVENZIP, SEVENZIP_PATH))
+            .arg("rn")
+            .arg(archive_path.as_ref())
+            .arg(file_name)
+            .arg(new_file_name),
+        SEVENZIP,
+        SEVENZIP_PATH,
+        "Failed to rename file in archive",
+    )?;
+
+    if let Err(error) = check_tool_status(SEVENZIP, &output) {
+        if !is_unsupported_operation(error.as_str()) {
+            return Err(error);
+        }
+        // some 7z builds can't rename in place, extract and re-add the file instead
+        progress_bar.println("Renaming in place is not supported, falling back to extraction");
+        let extracted_path =
+            extract_files_from_archive(progress_bar, archive_path, &[file_name], directory)?
+                .remove(0);
+        try_with!(
+            std::fs::rename(&extracted_path, directory.as_ref().join(new_file_name)),
+            "Failed to rename extracted file"
+        );
+        add_files_to_archive(
+            progress_bar,
+            archive_path,
+            &[new_file_name],
+            directory,
+            false,
+            comment,
+            torrentzip,
+        )?;
+        remove_files_from_archive(progress_bar, archive_path, &[file_name])?;
+        try_with!(
+            std::fs::remove_file(directory.as_ref().join(new_file_name)),
+            "Failed to remove extracted file"
+        );
     }
 
     progress_bar.set_message("");
@@ -96,6 +210,14 @@ pub fn rename_file_in_archive<P: AsRef<Path>>(
     Ok(())
 }
 
+fn is_unsupported_operation(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    stderr.contains("e_notimpl")
+        || stderr.contains("not implemented")
+        || stderr.contains("unsupported command")
+}
+
+#[instrument(level = "debug", skip_all, fields(archive = %archive_path.as_ref().display()))]
 pub fn extract_files_from_archive<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     archive_path: &P,
@@ -110,17 +232,22 @@ pub fn extract_files_from_archive<P: AsRef<Path>, Q: AsRef<Path>>(
         progress_bar.println(format!("Extracting \"{}\"", file_name));
     }
 
-    let output = Command::new("7z")
-        .arg("x")
-        .arg(archive_path.as_ref())
-        .args(file_names)
-        .current_dir(directory.as_ref())
-        .output()
-        .expect("Failed to extract archive");
+    #[cfg(test)]
+    EXTRACTIONS.fetch_add(1, Ordering::SeqCst);
 
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(SEVENZIP, SEVENZIP_PATH))
+            .arg("x")
+            .arg(archive_path.as_ref())
+            .args(file_names)
+            .current_dir(directory.as_ref()),
+        SEVENZIP,
+        SEVENZIP_PATH,
+        "Failed to extract archive",
+    )?;
+
+    check_tool_status(SEVENZIP, &output)?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
@@ -137,7 +264,30 @@ pub fn add_files_to_archive<P: AsRef<Path>, Q: AsRef<Path>>(
     file_names: &[&str],
     directory: &Q,
     solid: bool,
+    comment: Option<&str>,
+    torrentzip: bool,
 ) -> SimpleResult<()> {
+    let is_zip = archive_path
+        .as_ref()
+        .extension()
+        .map(|extension| extension == ZIP_EXTENSION)
+        .unwrap_or(false);
+    // TorrentZip archives are always rewritten in their canonical layout, existing ones included,
+    // the TorrentZip comment replaces any archive comment
+    #[cfg(feature = "torrentzip")]
+    if torrentzip && is_zip {
+        return torrentzip::add_files_to_torrentzip(
+            progress_bar,
+            archive_path,
+            file_names,
+            directory,
+        );
+    }
+    #[cfg(not(feature = "torrentzip"))]
+    if torrentzip && is_zip {
+        bail!(TORRENTZIP_DISABLED_ERROR);
+    }
+
     progress_bar.set_message("Compressing files");
     progress_bar.set_style(get_none_progress_style());
     progress_bar.enable_steady_tick(Duration::from_millis(100));
@@ -150,22 +300,49 @@ pub fn add_files_to_archive<P: AsRef<Path>, Q: AsRef<Path>>(
     if solid {
         args.push("-ms=on")
     }
-    let output = Command::new("7z")
-        .arg("a")
-        .arg(archive_path.as_ref())
-        .args(file_names)
-        .args(args)
-        .current_dir(directory.as_ref())
-        .output()
-        .expect("Failed to add files to archive");
-
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(SEVENZIP, SEVENZIP_PATH))
+            .arg("a")
+            .arg(archive_path.as_ref())
+            .args(file_names)
+            .args(args)
+            .current_dir(directory.as_ref()),
+        SEVENZIP,
+        SEVENZIP_PATH,
+        "Failed to add files to archive",
+    )?;
+
+    check_tool_status(SEVENZIP, &output)?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
 
+    // 7z can't write comments, and only the ZIP format supports them anyway
+    if let Some(comment) = comment {
+        if is_zip {
+            set_zip_comment(archive_path, comment)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn set_zip_comment<P: AsRef<Path>>(archive_path: &P, comment: &str) -> SimpleResult<()> {
+    let file = try_with!(
+        OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(archive_path.as_ref()),
+        "Failed to open {:?}",
+        archive_path.as_ref()
+    );
+    let mut zip_writer = try_with!(ZipWriter::new_append(file), "Failed to read ZIP");
+    zip_writer.set_comment(comment);
+    let mut file = try_with!(zip_writer.finish(), "Failed to write ZIP comment");
+    // drop any leftover from a previous longer comment
+    let position = try_with!(file.stream_position(), "Failed to write ZIP comment");
+    try_with!(file.set_len(position), "Failed to write ZIP comment");
     Ok(())
 }
 
@@ -182,19 +359,26 @@ pub fn remove_files_from_archive<P: AsRef<Path>>(
         progress_bar.println(format!("Deleting \"{}\"", file_name));
     }
 
-    let output = Command::new("7z")
-        .arg("d")
-        .arg(archive_path.as_ref())
-        .args(file_names)
-        .output()
-        .expect("Failed to remove files from archive");
+    let output = get_tool_output(
+        progress_bar,
+        Command::new(get_tool_path(SEVENZIP, SEVENZIP_PATH))
+            .arg("d")
+            .arg(archive_path.as_ref())
+            .args(file_names),
+        SEVENZIP,
+        SEVENZIP_PATH,
+        "Failed to remove files from archive",
+    )?;
 
-    if !output.status.success() {
-        bail!(String::from_utf8(output.stderr).unwrap().as_str())
-    }
+    check_tool_status(SEVENZIP, &output)?;
 
     progress_bar.set_message("");
     progress_bar.disable_steady_tick();
 
     Ok(())
 }
+
+#[cfg(all(test, unix))]
+mod test_parse_archive_duplicate_member_names;
+#[cfg(all(test, unix))]
+mod test_rename_file_in_archive_fallback;
@@ -0,0 +1,60 @@
+use super::*;
+use async_std::fs;
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_path = Path::new(tmp_directory.path());
+
+    // simulate the listing of a malformed archive
+    let wrapper_path = tmp_path.join("7z");
+    fs::write(
+        &wrapper_path,
+        "#!/bin/sh\n\
+        echo \"Path = $3\"\n\
+        for i in 1 2; do\n\
+        echo \"\"\n\
+        echo \"Path = Test Game (USA, Europe).rom\"\n\
+        echo \"Size = 256\"\n\
+        echo \"CRC = CC721E14\"\n\
+        done\n",
+    )
+    .await
+    .unwrap();
+    fs::set_permissions(&wrapper_path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+    let previous_path = env::var(SEVENZIP_PATH).ok();
+    env::set_var(SEVENZIP_PATH, &wrapper_path);
+
+    // when
+    let result = parse_archive(
+        &progress_bar,
+        &test_directory.join("Test Game (USA, Europe) (Duplicate Members).zip"),
+    );
+
+    match previous_path {
+        Some(previous_path) => env::set_var(SEVENZIP_PATH, previous_path),
+        None => env::remove_var(SEVENZIP_PATH),
+    }
+
+    // then
+    let error = result.err().unwrap();
+    assert_eq!(
+        error.as_str(),
+        "Archive contains duplicate member names: \"Test Game (USA, Europe).rom\""
+    );
+    assert_eq!(
+        get_error_report(&error).unwrap().error_kind,
+        ErrorKind::DuplicateMemberNames
+    );
+}
@@ -0,0 +1,74 @@
+use super::*;
+use async_std::fs;
+use std::env;
+use std::os::unix::fs::PermissionsExt;
+use tempfile::TempDir;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_path = Path::new(tmp_directory.path());
+
+    let archive_path = tmp_path.join("Test Game (USA, Europe).rom.zip");
+    fs::copy(
+        test_directory.join("Test Game (USA, Europe).rom.zip"),
+        &archive_path,
+    )
+    .await
+    .unwrap();
+
+    // simulate a 7z build that doesn't support renaming
+    let wrapper_path = tmp_path.join("7z");
+    fs::write(
+        &wrapper_path,
+        format!(
+            "#!/bin/sh\nif [ \"$1\" = \"rn\" ]; then echo \"ERROR: E_NOTIMPL\" >&2; exit 2; fi\nexec {} \"$@\"\n",
+            get_tool_path(SEVENZIP, SEVENZIP_PATH)
+        ),
+    )
+    .await
+    .unwrap();
+    fs::set_permissions(&wrapper_path, std::fs::Permissions::from_mode(0o755))
+        .await
+        .unwrap();
+    let extract_directory = TempDir::new_in(&tmp_path).unwrap();
+    let previous_path = env::var(SEVENZIP_PATH).ok();
+    env::set_var(SEVENZIP_PATH, &wrapper_path);
+
+    // when
+    let result = rename_file_in_archive(
+        &progress_bar,
+        &archive_path,
+        "Test Game (USA, Europe).rom",
+        "Renamed Game (USA, Europe).rom",
+        &extract_directory.path(),
+        Some("Test comment"),
+        false,
+    );
+
+    match previous_path {
+        Some(previous_path) => env::set_var(SEVENZIP_PATH, previous_path),
+        None => env::remove_var(SEVENZIP_PATH),
+    }
+
+    // then
+    result.unwrap();
+    let sevenzip_infos = parse_archive(&progress_bar, &archive_path).unwrap();
+    assert_eq!(sevenzip_infos.len(), 1);
+    let sevenzip_info = sevenzip_infos.get(0).unwrap();
+    assert_eq!(sevenzip_info.path, "Renamed Game (USA, Europe).rom");
+
+    let output = std::process::Command::new("7z")
+        .arg("l")
+        .arg(&archive_path)
+        .output()
+        .unwrap();
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("Comment = Test comment"));
+}
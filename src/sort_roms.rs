@@ -17,7 +17,7 @@ use shiratsu_naming::region::Region;
 use sqlx::sqlite::SqliteConnection;
 use std::cmp::Ordering;
 use std::collections::HashMap;
-use std::ffi::OsString;
+use std::ffi::{OsStr, OsString};
 use std::time::Duration;
 
 pub fn subcommand() -> Command {
@@ -76,6 +76,7 @@ pub async fn main(
     let one_regions = get_regions(connection, matches, "REGIONS_ONE").await;
     let ignored_releases = get_list(connection, "DISCARD_RELEASES").await;
     let ignored_flags = get_list(connection, "DISCARD_FLAGS").await;
+    let deprioritized_releases = get_list(connection, "DEPRIORITIZED_RELEASES").await;
 
     for system in systems {
         sort_system(
@@ -93,6 +94,10 @@ pub async fn main(
                 .iter()
                 .map(String::as_str)
                 .collect::<Vec<&str>>(),
+            &deprioritized_releases
+                .iter()
+                .map(String::as_str)
+                .collect::<Vec<&str>>(),
         )
         .await?;
 
@@ -133,6 +138,7 @@ async fn sort_system(
     one_regions: &[Region],
     ignored_releases: &[&str],
     ignored_flags: &[&str],
+    deprioritized_releases: &[&str],
 ) -> SimpleResult<()> {
     progress_bar.enable_steady_tick(Duration::from_millis(100));
     progress_bar.println(&format!("Processing \"{}\"", system.name));
@@ -172,6 +178,10 @@ async fn sort_system(
                 games = Vec::new();
             }
             games.insert(0, parent_game);
+            // put prereleases last and newer revisions first, parents win ties
+            games.sort_by(|game_a, game_b| {
+                sort_games_by_release_and_version(game_a, game_b, deprioritized_releases)
+            });
 
             // trim ignored games
             if !ignored_releases.is_empty() || !ignored_flags.is_empty() {
@@ -543,7 +553,39 @@ fn trim_ignored_games(
     }
 }
 
+fn sort_games_by_release_and_version(
+    game_a: &Game,
+    game_b: &Game,
+    deprioritized_releases: &[&str],
+) -> Ordering {
+    get_release_rank(game_a, deprioritized_releases)
+        .cmp(&get_release_rank(game_b, deprioritized_releases))
+        .then_with(|| sort_games_by_version_desc(game_a, game_b))
+}
+
+fn get_release_rank(game: &Game, deprioritized_releases: &[&str]) -> usize {
+    // final releases come first, then deprioritized ones in the configured order
+    if let Ok(name) = NoIntroName::try_parse(&game.name) {
+        for token in name.iter() {
+            if let NoIntroToken::Release(release, _) = token {
+                if let Some(i) = deprioritized_releases
+                    .iter()
+                    .position(|deprioritized_release| deprioritized_release == release)
+                {
+                    return i + 1;
+                }
+            }
+        }
+    }
+    0
+}
+
 fn sort_games_by_version_or_name_desc(game_a: &Game, game_b: &Game) -> Ordering {
+    sort_games_by_version_desc(game_a, game_b)
+        .then_with(|| game_b.name.partial_cmp(&game_a.name).unwrap())
+}
+
+fn sort_games_by_version_desc(game_a: &Game, game_b: &Game) -> Ordering {
     let mut version_a = None;
     let mut version_b = None;
     if let Ok(name) = NoIntroName::try_parse(&game_a.name) {
@@ -569,7 +611,7 @@ fn sort_games_by_version_or_name_desc(game_a: &Game, game_b: &Game) -> Ordering
     if version_b.is_some() {
         return Ordering::Greater;
     }
-    game_b.name.partial_cmp(&game_a.name).unwrap()
+    Ordering::Equal
 }
 
 async fn compute_new_path<P: AsRef<Path>>(
@@ -588,23 +630,44 @@ async fn compute_new_path<P: AsRef<Path>>(
         .to_lowercase();
     let new_romfile_path: PathBuf;
 
+    // keep multi-disc games in their shared subdirectory
+    let directory = match get_disc_base_name(&game.name) {
+        Some(base_name) if is_in_directory(romfile_path, &base_name) => {
+            directory.as_ref().join(base_name)
+        }
+        _ => directory.as_ref().to_path_buf(),
+    };
+    let directory = get_arcade_directory(&directory, system, [rom]);
+
     if ARCHIVE_EXTENSIONS.contains(&romfile_extension.as_str())
         || romfile_extension == CHD_EXTENSION
         || romfile_extension == CSO_EXTENSION
+        || romfile_extension == ZSO_EXTENSION
         || PS3_EXTENSIONS.contains(&romfile_extension.as_str()) && !game.jbfolder
         || romfile_extension == RVZ_EXTENSION
     {
-        new_romfile_path = directory
-            .as_ref()
-            .join(format!("{}.{}", &game.name, &romfile_extension));
-    } else if system.arcade || game.jbfolder {
-        new_romfile_path = directory.as_ref().join(&game.name).join(&rom.name);
+        new_romfile_path = directory.join(format!("{}.{}", &game.name, &romfile_extension));
+    // keep multi-part games grouped in their subdirectory
+    } else if system.arcade || game.jbfolder || is_in_game_directory(romfile_path, game) {
+        new_romfile_path = directory.join(&game.name).join(&rom.name);
     } else {
-        new_romfile_path = directory.as_ref().join(&rom.name);
+        new_romfile_path = directory.join(&rom.name);
     }
     Ok(new_romfile_path)
 }
 
+fn is_in_game_directory(romfile_path: &Path, game: &Game) -> bool {
+    is_in_directory(romfile_path, &game.name)
+}
+
+fn is_in_directory(romfile_path: &Path, name: &str) -> bool {
+    romfile_path
+        .parent()
+        .and_then(|parent| parent.file_name())
+        .map(|directory_name| directory_name == OsStr::new(name))
+        .unwrap_or(false)
+}
+
 #[cfg(test)]
 mod test_all_regions_from_db;
 #[cfg(test)]
@@ -614,6 +677,8 @@ mod test_one_region_from_db;
 #[cfg(test)]
 mod test_one_region_from_matches;
 #[cfg(test)]
+mod test_order_beta_revision_vs_vanilla;
+#[cfg(test)]
 mod test_order_revision_vs_revision;
 #[cfg(test)]
 mod test_order_revision_vs_vanilla;
@@ -638,6 +703,8 @@ mod test_sort_1g1r;
 #[cfg(test)]
 mod test_sort_1g1r_discard_asia_and_beta;
 #[cfg(test)]
+mod test_sort_1g1r_revisions;
+#[cfg(test)]
 mod test_sort_1g1r_without_parent_clone;
 #[cfg(test)]
 mod test_sort_discard_asia;
@@ -0,0 +1,46 @@
+use super::*;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let game_a = Game {
+        id: 1,
+        name: String::from("Game (USA) (Rev 2) (Beta)"),
+        description: String::from(""),
+        comment: None,
+        year: None,
+        manufacturer: None,
+        external_id: None,
+        bios: false,
+        jbfolder: false,
+        regions: String::from(""),
+        sorting: Sorting::AllRegions as i64,
+        complete: true,
+        system_id: 1,
+        parent_id: None,
+        bios_id: None,
+    };
+    let game_b = Game {
+        id: 1,
+        name: String::from("Game (USA)"),
+        description: String::from(""),
+        comment: None,
+        year: None,
+        manufacturer: None,
+        external_id: None,
+        bios: false,
+        jbfolder: false,
+        regions: String::from(""),
+        sorting: Sorting::AllRegions as i64,
+        complete: true,
+        system_id: 1,
+        parent_id: None,
+        bios_id: None,
+    };
+
+    // when
+    let ordering = sort_games_by_release_and_version(&game_a, &game_b, &["Beta"]);
+
+    // then
+    assert_eq!(ordering, Ordering::Greater);
+}
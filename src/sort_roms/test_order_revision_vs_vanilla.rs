@@ -8,6 +8,8 @@ async fn test() {
         name: String::from("Game (USA) (Rev 2"),
         description: String::from(""),
         comment: None,
+        year: None,
+        manufacturer: None,
         external_id: None,
         bios: false,
         jbfolder: false,
@@ -23,6 +25,8 @@ async fn test() {
         name: String::from("Game (USA)"),
         description: String::from(""),
         comment: None,
+        year: None,
+        manufacturer: None,
         external_id: None,
         bios: false,
         jbfolder: false,
@@ -12,6 +12,9 @@ async fn test() {
         url: Some(String::from("")),
         arcade: false,
         merging: Merging::Split as i64,
+        hash_algorithm: None,
+        archive_is_rom: false,
+        recommended_hash_algorithm: None,
         complete: false,
     };
     let game = Game {
@@ -19,6 +22,8 @@ async fn test() {
         name: String::from("game name"),
         description: String::from(""),
         comment: None,
+        year: None,
+        manufacturer: None,
         external_id: None,
         bios: false,
         jbfolder: false,
@@ -46,10 +51,17 @@ async fn test() {
         id: 1,
         path: String::from("romfile.7z"),
         size: 0,
+        matched_dat_version: None,
+        manifest: None,
+        cached_size: None,
+        cached_mtime: None,
+        cached_crc: None,
+        cached_md5: None,
+        cached_sha1: None,
     };
 
     // when
-    let path = compute_new_path(&system, &game, &rom, &romfile,  &test_directory)
+    let path = compute_new_path(&system, &game, &rom, &romfile, &test_directory)
         .await
         .unwrap();
 
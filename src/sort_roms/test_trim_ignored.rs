@@ -9,6 +9,8 @@ async fn test() {
             name: String::from("Game (USA)"),
             description: String::from(""),
             comment: None,
+            year: None,
+            manufacturer: None,
             external_id: None,
             bios: false,
             jbfolder: false,
@@ -24,6 +26,8 @@ async fn test() {
             name: String::from("Game (USA) (Beta)"),
             description: String::from(""),
             comment: None,
+            year: None,
+            manufacturer: None,
             external_id: None,
             bios: false,
             jbfolder: false,
@@ -39,6 +43,8 @@ async fn test() {
             name: String::from("Game (USA) (Beta 1)"),
             description: String::from(""),
             comment: None,
+            year: None,
+            manufacturer: None,
             external_id: None,
             bios: false,
             jbfolder: false,
@@ -54,6 +60,8 @@ async fn test() {
             name: String::from("Game (USA) (Virtual Console, Switch Online)"),
             description: String::from(""),
             comment: None,
+            year: None,
+            manufacturer: None,
             external_id: None,
             bios: false,
             jbfolder: false,
@@ -0,0 +1,346 @@
+use super::checksum::*;
+use super::config::*;
+use super::database::*;
+use super::model::*;
+use super::prompt::*;
+use super::SimpleResult;
+use async_std::path::{Path, PathBuf};
+use clap::{value_parser, Arg, ArgMatches, Command};
+use indicatif::ProgressBar;
+use regex::Regex;
+use sqlx::sqlite::SqliteConnection;
+use std::fs;
+use std::io;
+use std::str::FromStr;
+
+const FRAMES_PER_SECOND: u64 = 75;
+const SECONDS_PER_MINUTE: u64 = 60;
+
+lazy_static! {
+    static ref CUE_FILE_REGEX: Regex =
+        Regex::new(r#"(?i)^\s*FILE\s+"([^"]+)"\s+(\S+)\s*$"#).unwrap();
+    static ref CUE_TRACK_REGEX: Regex =
+        Regex::new(r"(?i)^\s*TRACK\s+(\d+)\s+(AUDIO|MODE1/\d+|MODE2/\d+|CDG|CDI/\d+)\s*$").unwrap();
+    static ref CUE_INDEX_REGEX: Regex =
+        Regex::new(r"(?i)^\s*INDEX\s+(\d+)\s+(\d+):(\d+):(\d+)\s*$").unwrap();
+}
+
+pub fn subcommand() -> Command {
+    Command::new("split-bin")
+        .about("Split a combined multi-track BIN file into per-track BIN files")
+        .arg(
+            Arg::new("CUE")
+                .help("Set the CUE file referencing the combined BIN file")
+                .required(true)
+                .index(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("SYSTEM")
+                .short('s')
+                .long("system")
+                .help("Set the system number to use")
+                .required(false)
+                .num_args(1),
+        )
+        .arg(
+            Arg::new("OUTPUT")
+                .short('o')
+                .long("output")
+                .help("Set the output directory, defaults to a directory named after the CUE file")
+                .required(false)
+                .num_args(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let system = prompt_for_system(
+        connection,
+        matches
+            .get_one::<String>("SYSTEM")
+            .map(|s| FromStr::from_str(s).expect("Failed to parse number")),
+    )
+    .await?;
+    let cue_path = matches.get_one::<PathBuf>("CUE").unwrap();
+    let output_directory = match matches.get_one::<PathBuf>("OUTPUT") {
+        Some(output_directory) => output_directory.clone(),
+        None => cue_path.with_extension(""),
+    };
+    let hash_algorithm = get_hash_algorithm(connection, &system).await?;
+
+    let unmatched = split_bin(
+        connection,
+        progress_bar,
+        &system,
+        cue_path,
+        &output_directory,
+        &hash_algorithm,
+    )
+    .await?;
+    if unmatched > 0 {
+        bail!("{} tracks don't match the DAT", unmatched);
+    }
+
+    Ok(())
+}
+
+struct Track {
+    number: u32,
+    mode: String,
+    sector_size: u64,
+    // frames relative to the start of the combined file
+    indexes: Vec<(u32, u64)>,
+}
+
+impl Track {
+    // pregaps belong to their own track in Redump dumps
+    fn start(&self) -> u64 {
+        self.indexes.first().map(|(_, frames)| *frames).unwrap_or(0)
+    }
+}
+
+fn parse_msf(minutes: &str, seconds: &str, frames: &str) -> u64 {
+    (u64::from_str(minutes).unwrap() * SECONDS_PER_MINUTE + u64::from_str(seconds).unwrap())
+        * FRAMES_PER_SECOND
+        + u64::from_str(frames).unwrap()
+}
+
+fn format_msf(frames: u64) -> String {
+    format!(
+        "{:02}:{:02}:{:02}",
+        frames / FRAMES_PER_SECOND / SECONDS_PER_MINUTE,
+        frames / FRAMES_PER_SECOND % SECONDS_PER_MINUTE,
+        frames % FRAMES_PER_SECOND
+    )
+}
+
+fn get_sector_size(mode: &str) -> u64 {
+    match mode.split_once('/') {
+        Some((_, sector_size)) => u64::from_str(sector_size).unwrap(),
+        None => 2352,
+    }
+}
+
+fn parse_cue(cue: &str) -> SimpleResult<(String, Vec<Track>)> {
+    let mut bin_name: Option<String> = None;
+    let mut tracks: Vec<Track> = Vec::new();
+    for line in cue.lines() {
+        if let Some(captures) = CUE_FILE_REGEX.captures(line) {
+            if bin_name.is_some() {
+                bail!("CUE file already references several BIN files");
+            }
+            bin_name = Some(captures.get(1).unwrap().as_str().to_owned());
+        } else if let Some(captures) = CUE_TRACK_REGEX.captures(line) {
+            let mode = captures.get(2).unwrap().as_str().to_uppercase();
+            tracks.push(Track {
+                number: u32::from_str(captures.get(1).unwrap().as_str()).unwrap(),
+                sector_size: get_sector_size(&mode),
+                mode,
+                indexes: Vec::new(),
+            });
+        } else if let Some(captures) = CUE_INDEX_REGEX.captures(line) {
+            let track = match tracks.last_mut() {
+                Some(track) => track,
+                None => bail!("INDEX found before any TRACK"),
+            };
+            track.indexes.push((
+                u32::from_str(captures.get(1).unwrap().as_str()).unwrap(),
+                parse_msf(
+                    captures.get(2).unwrap().as_str(),
+                    captures.get(3).unwrap().as_str(),
+                    captures.get(4).unwrap().as_str(),
+                ),
+            ));
+        }
+    }
+    let bin_name = match bin_name {
+        Some(bin_name) => bin_name,
+        None => bail!("CUE file doesn't reference any BIN file"),
+    };
+    if tracks.len() < 2 {
+        bail!("CUE file has less than 2 tracks, nothing to split");
+    }
+    if tracks.iter().any(|track| track.indexes.is_empty()) {
+        bail!("CUE file has tracks without INDEX");
+    }
+    Ok((bin_name, tracks))
+}
+
+// sector sizes may differ between tracks, the last one takes the remaining bytes
+fn get_track_lengths(tracks: &[Track], bin_size: u64) -> SimpleResult<Vec<u64>> {
+    let mut lengths: Vec<u64> = Vec::new();
+    let mut offset = 0;
+    for (i, track) in tracks.iter().enumerate() {
+        let length = match tracks.get(i + 1) {
+            Some(next_track) => {
+                if next_track.start() < track.start() {
+                    bail!(
+                        "Track {} starts before track {}",
+                        next_track.number,
+                        track.number
+                    );
+                }
+                (next_track.start() - track.start()) * track.sector_size
+            }
+            None => {
+                if bin_size < offset {
+                    bail!("BIN file is shorter than its CUE file");
+                }
+                bin_size - offset
+            }
+        };
+        lengths.push(length);
+        offset += length;
+    }
+    if offset != bin_size {
+        bail!("BIN file size doesn't match its CUE file");
+    }
+    Ok(lengths)
+}
+
+pub async fn split_bin<P: AsRef<Path>, Q: AsRef<Path>>(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    cue_path: &P,
+    output_directory: &Q,
+    hash_algorithm: &HashAlgorithm,
+) -> SimpleResult<usize> {
+    let cue = try_with!(
+        fs::read_to_string(cue_path.as_ref()),
+        "Failed to read {:?}",
+        cue_path.as_ref()
+    );
+    let (bin_name, tracks) = parse_cue(&cue)?;
+    let bin_path = cue_path.as_ref().parent().unwrap().join(&bin_name);
+    let bin_size = try_with!(fs::metadata(&bin_path), "Failed to read {:?}", &bin_path).len();
+    let lengths = get_track_lengths(&tracks, bin_size)?;
+
+    try_with!(
+        fs::create_dir_all(output_directory.as_ref()),
+        "Failed to create {:?}",
+        output_directory.as_ref()
+    );
+    let mut bin_file = try_with!(fs::File::open(&bin_path), "Failed to open {:?}", &bin_path);
+    let bin_stem = Path::new(&bin_name)
+        .file_stem()
+        .unwrap()
+        .to_str()
+        .unwrap()
+        .to_owned();
+    let mut track_paths: Vec<PathBuf> = Vec::new();
+    for (track, length) in tracks.iter().zip(&lengths) {
+        let track_path = output_directory.as_ref().join(format!(
+            "{} (Track {}).{}",
+            bin_stem, track.number, BIN_EXTENSION
+        ));
+        progress_bar.println(format!(
+            "Writing track {} to {:?}",
+            track.number,
+            track_path.file_name().unwrap()
+        ));
+        let mut track_file = try_with!(
+            fs::File::create(&track_path),
+            "Failed to create {:?}",
+            &track_path
+        );
+        // tracks are contiguous, the combined file is read sequentially
+        try_with!(
+            io::copy(&mut io::Read::take(&mut bin_file, *length), &mut track_file),
+            "Failed to write {:?}",
+            &track_path
+        );
+        track_paths.push(track_path);
+    }
+
+    // name the tracks after the DAT when they match
+    let roms = find_roms_by_system_id_no_parents(connection, system.id).await;
+    let mut unmatched = 0;
+    let mut track_names: Vec<String> = Vec::new();
+    let mut game_ids: Vec<i64> = Vec::new();
+    for (i, (track, track_path)) in tracks.iter().zip(&track_paths).enumerate() {
+        let (size, hash) = get_size_and_hash(
+            connection,
+            progress_bar,
+            track_path,
+            &None,
+            i + 1,
+            tracks.len(),
+            hash_algorithm,
+        )
+        .await?;
+        let rom = roms.iter().find(|rom| {
+            let rom_hash = match hash_algorithm {
+                HashAlgorithm::Crc => rom.crc.as_ref(),
+                HashAlgorithm::Md5 => rom.md5.as_ref(),
+                HashAlgorithm::Sha1 => rom.sha1.as_ref(),
+            };
+            rom.size as u64 == size && rom_hash == Some(&hash)
+        });
+        match rom {
+            Some(rom) => {
+                progress_bar.println(format!("Track {} matches \"{}\"", track.number, rom.name));
+                let new_path = output_directory.as_ref().join(&rom.name);
+                try_with!(
+                    fs::rename(track_path, &new_path),
+                    "Failed to rename {:?} to {:?}",
+                    track_path,
+                    &new_path
+                );
+                track_names.push(rom.name.clone());
+                game_ids.push(rom.game_id);
+            }
+            None => {
+                progress_bar.println(format!("Track {} doesn't match", track.number));
+                unmatched += 1;
+                track_names.push(track_path.file_name().unwrap().to_str().unwrap().to_owned());
+            }
+        }
+    }
+
+    // indexes are rebased on the start of each track file
+    let mut new_cue = String::new();
+    for (track, track_name) in tracks.iter().zip(&track_names) {
+        new_cue.push_str(&format!("FILE \"{}\" BINARY\r\n", track_name));
+        new_cue.push_str(&format!("  TRACK {:02} {}\r\n", track.number, track.mode));
+        for (number, frames) in &track.indexes {
+            new_cue.push_str(&format!(
+                "    INDEX {:02} {}\r\n",
+                number,
+                format_msf(frames - track.start())
+            ));
+        }
+    }
+    game_ids.dedup();
+    let cue_rom = match (unmatched, game_ids.as_slice()) {
+        (0, [game_id]) => roms.iter().find(|rom| {
+            rom.game_id == *game_id
+                && Path::new(&rom.name)
+                    .extension()
+                    .and_then(|extension| extension.to_str())
+                    == Some(CUE_EXTENSION)
+        }),
+        _ => None,
+    };
+    let new_cue_path = match cue_rom {
+        Some(cue_rom) => output_directory.as_ref().join(&cue_rom.name),
+        None => output_directory
+            .as_ref()
+            .join(cue_path.as_ref().file_name().unwrap()),
+    };
+    try_with!(
+        fs::write(&new_cue_path, new_cue),
+        "Failed to write {:?}",
+        &new_cue_path
+    );
+
+    Ok(unmatched)
+}
+
+#[cfg(test)]
+mod test_split_bin;
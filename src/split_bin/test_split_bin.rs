@@ -0,0 +1,87 @@
+use super::super::config::{set_rom_directory, set_tmp_directory, MUTEX};
+use super::super::import_dats;
+use super::*;
+use async_std::fs;
+use tempfile::{NamedTempFile, TempDir};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let db_file = NamedTempFile::new().unwrap();
+    let pool = establish_connection(db_file.path().to_str().unwrap()).await;
+    let mut connection = pool.acquire().await.unwrap();
+
+    let rom_directory = TempDir::new_in(&test_directory).unwrap();
+    set_rom_directory(PathBuf::from(rom_directory.path()));
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = set_tmp_directory(PathBuf::from(tmp_directory.path()));
+
+    let matches = import_dats::subcommand().get_matches_from(&[
+        "import-dats",
+        "tests/Test System (20230106) (Split BIN).dat",
+    ]);
+    import_dats::main(&mut connection, &matches, &progress_bar)
+        .await
+        .unwrap();
+
+    for name in [
+        "Test Game (Europe) (Combined).cue",
+        "Test Game (Europe) (Combined).bin",
+    ] {
+        fs::copy(test_directory.join(name), tmp_directory.join(name))
+            .await
+            .unwrap();
+    }
+    let output_directory = tmp_directory.join("split");
+
+    let system = find_systems(&mut connection).await.remove(0);
+
+    // when
+    let unmatched = split_bin(
+        &mut connection,
+        &progress_bar,
+        &system,
+        &tmp_directory.join("Test Game (Europe) (Combined).cue"),
+        &output_directory,
+        &HashAlgorithm::Crc,
+    )
+    .await
+    .unwrap();
+
+    // then
+    assert_eq!(unmatched, 0);
+
+    let mut names: Vec<String> = std::fs::read_dir(&output_directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .collect();
+    names.sort();
+    assert_eq!(
+        names,
+        vec![
+            "Test Game (Europe) (Track 1).bin",
+            "Test Game (Europe) (Track 2).bin",
+            "Test Game (Europe).cue",
+        ]
+    );
+
+    // the regenerated cue points to the split tracks and matches the DAT
+    let (size, crc) = get_size_and_hash(
+        &mut connection,
+        &progress_bar,
+        &output_directory.join("Test Game (Europe).cue"),
+        &None,
+        1,
+        1,
+        &HashAlgorithm::Crc,
+    )
+    .await
+    .unwrap();
+    assert_eq!(size, 206);
+    assert_eq!(crc, "cbe4ae62");
+}
@@ -0,0 +1,345 @@
+use super::progress::*;
+use super::sevenzip;
+use super::util::*;
+use super::SimpleResult;
+use async_std::path::Path;
+use flate2::write::DeflateEncoder;
+use flate2::Compression;
+use indicatif::ProgressBar;
+use std::fs::File;
+use std::io::{self, BufWriter, Read, Seek, SeekFrom, Write};
+use std::time::Duration;
+use tempfile::TempDir;
+use zip::ZipArchive;
+
+const TORRENTZIP_COMMENT_PREFIX: &str = "TORRENTZIPPED-";
+// 1996-12-24 23:32:00, the timestamp every TorrentZip member carries
+const DOS_TIME: u16 = 0xBC00;
+const DOS_DATE: u16 = 0x2198;
+const VERSION_NEEDED: u16 = 20;
+// bit 1 flags the maximum compression level
+const FLAGS: u16 = 0x0002;
+const DEFLATE_METHOD: u16 = 8;
+const LOCAL_HEADER_SIGNATURE: u32 = 0x04034b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x02014b50;
+const END_OF_CENTRAL_DIRECTORY_SIGNATURE: u32 = 0x06054b50;
+const END_OF_CENTRAL_DIRECTORY_SIZE: usize = 22;
+const COMMENT_SIZE: usize = 22;
+
+struct Entry {
+    name: String,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+pub fn write_torrentzip<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    archive_path: &P,
+    file_names: &[&str],
+    directory: &Q,
+) -> SimpleResult<()> {
+    progress_bar.set_message("Compressing files");
+    progress_bar.set_style(get_none_progress_style());
+    progress_bar.enable_steady_tick(Duration::from_millis(100));
+
+    // members are sorted case-insensitively
+    let mut file_names = file_names.to_vec();
+    file_names.sort_by_key(|file_name| file_name.to_lowercase());
+
+    for &file_name in &file_names {
+        progress_bar.println(format!("Compressing \"{}\"", file_name));
+    }
+
+    write_atomically(progress_bar, archive_path, |part_path| {
+        let file = try_with!(File::create(part_path), "Failed to create {:?}", part_path);
+        try_with!(
+            write_archive(BufWriter::new(file), &file_names, directory.as_ref()),
+            "Failed to write {:?}",
+            part_path
+        );
+        Ok(())
+    })?;
+
+    progress_bar.set_message("");
+    progress_bar.disable_steady_tick();
+
+    Ok(())
+}
+
+fn write_archive<W: Write + Seek>(
+    mut writer: W,
+    file_names: &[&str],
+    directory: &Path,
+) -> io::Result<()> {
+    let mut entries: Vec<Entry> = Vec::new();
+    for &file_name in file_names {
+        let offset = writer.stream_position()?;
+        let name = file_name.replace('\\', "/");
+        // sizes and crc are filled in once the member is compressed
+        writer.write_all(&local_header(&name, 0, 0, 0))?;
+        let data_offset = writer.stream_position()?;
+
+        let mut file = File::open(directory.join(file_name))?;
+        let mut hasher = crc32fast::Hasher::new();
+        let mut size: u64 = 0;
+        let mut encoder = DeflateEncoder::new(&mut writer, Compression::best());
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            let count = file.read(&mut buffer)?;
+            if count == 0 {
+                break;
+            }
+            hasher.update(&buffer[..count]);
+            encoder.write_all(&buffer[..count])?;
+            size += count as u64;
+        }
+        encoder.finish()?;
+        let end_offset = writer.stream_position()?;
+
+        let entry = Entry {
+            name,
+            crc: hasher.finalize(),
+            compressed_size: to_u32(end_offset - data_offset)?,
+            size: to_u32(size)?,
+            offset: to_u32(offset)?,
+        };
+        writer.seek(SeekFrom::Start(offset))?;
+        writer.write_all(&local_header(
+            &entry.name,
+            entry.crc,
+            entry.compressed_size,
+            entry.size,
+        ))?;
+        writer.seek(SeekFrom::Start(end_offset))?;
+        entries.push(entry);
+    }
+
+    let central_directory_offset = to_u32(writer.stream_position()?)?;
+    let mut central_directory: Vec<u8> = Vec::new();
+    for entry in &entries {
+        central_directory.extend(central_header(entry));
+    }
+    writer.write_all(&central_directory)?;
+
+    let entry_count = u16::try_from(entries.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "Too many files for TorrentZip"))?;
+    let mut end_of_central_directory: Vec<u8> = Vec::new();
+    end_of_central_directory.extend(END_OF_CENTRAL_DIRECTORY_SIGNATURE.to_le_bytes());
+    end_of_central_directory.extend(0u16.to_le_bytes());
+    end_of_central_directory.extend(0u16.to_le_bytes());
+    end_of_central_directory.extend(entry_count.to_le_bytes());
+    end_of_central_directory.extend(entry_count.to_le_bytes());
+    end_of_central_directory.extend(to_u32(central_directory.len() as u64)?.to_le_bytes());
+    end_of_central_directory.extend(central_directory_offset.to_le_bytes());
+    end_of_central_directory.extend((COMMENT_SIZE as u16).to_le_bytes());
+    end_of_central_directory.extend(get_comment(&central_directory).into_bytes());
+    writer.write_all(&end_of_central_directory)?;
+    writer.flush()
+}
+
+fn local_header(name: &str, crc: u32, compressed_size: u32, size: u32) -> Vec<u8> {
+    let mut header: Vec<u8> = Vec::new();
+    header.extend(LOCAL_HEADER_SIGNATURE.to_le_bytes());
+    header.extend(VERSION_NEEDED.to_le_bytes());
+    header.extend(FLAGS.to_le_bytes());
+    header.extend(DEFLATE_METHOD.to_le_bytes());
+    header.extend(DOS_TIME.to_le_bytes());
+    header.extend(DOS_DATE.to_le_bytes());
+    header.extend(crc.to_le_bytes());
+    header.extend(compressed_size.to_le_bytes());
+    header.extend(size.to_le_bytes());
+    header.extend((name.len() as u16).to_le_bytes());
+    header.extend(0u16.to_le_bytes());
+    header.extend(name.as_bytes());
+    header
+}
+
+fn central_header(entry: &Entry) -> Vec<u8> {
+    let mut header: Vec<u8> = Vec::new();
+    header.extend(CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+    // version made by MS-DOS, the host TorrentZip pretends to be
+    header.extend(0u16.to_le_bytes());
+    header.extend(VERSION_NEEDED.to_le_bytes());
+    header.extend(FLAGS.to_le_bytes());
+    header.extend(DEFLATE_METHOD.to_le_bytes());
+    header.extend(DOS_TIME.to_le_bytes());
+    header.extend(DOS_DATE.to_le_bytes());
+    header.extend(entry.crc.to_le_bytes());
+    header.extend(entry.compressed_size.to_le_bytes());
+    header.extend(entry.size.to_le_bytes());
+    header.extend((entry.name.len() as u16).to_le_bytes());
+    // extra field, comment, disk number, internal and external attributes
+    header.extend(0u16.to_le_bytes());
+    header.extend(0u16.to_le_bytes());
+    header.extend(0u16.to_le_bytes());
+    header.extend(0u16.to_le_bytes());
+    header.extend(0u32.to_le_bytes());
+    header.extend(entry.offset.to_le_bytes());
+    header.extend(entry.name.as_bytes());
+    header
+}
+
+fn get_comment(central_directory: &[u8]) -> String {
+    format!(
+        "{}{:08X}",
+        TORRENTZIP_COMMENT_PREFIX,
+        crc32fast::hash(central_directory)
+    )
+}
+
+fn to_u32(value: u64) -> io::Result<u32> {
+    u32::try_from(value)
+        .map_err(|_| io::Error::new(io::ErrorKind::Other, "File too large for TorrentZip"))
+}
+
+pub fn is_torrentzipped<P: AsRef<Path>>(archive_path: &P) -> SimpleResult<bool> {
+    let mut file = try_with!(
+        File::open(archive_path.as_ref()),
+        "Failed to open {:?}",
+        archive_path.as_ref()
+    );
+    Ok(try_with!(
+        read_is_torrentzipped(&mut file),
+        "Failed to read {:?}",
+        archive_path.as_ref()
+    ))
+}
+
+fn read_is_torrentzipped<R: Read + Seek>(reader: &mut R) -> io::Result<bool> {
+    // the comment always has the same length, so the end record sits at a fixed offset
+    let length = reader.seek(SeekFrom::End(0))?;
+    if length < (END_OF_CENTRAL_DIRECTORY_SIZE + COMMENT_SIZE) as u64 {
+        return Ok(false);
+    }
+    let mut end = [0u8; END_OF_CENTRAL_DIRECTORY_SIZE + COMMENT_SIZE];
+    reader.seek(SeekFrom::End(-(end.len() as i64)))?;
+    reader.read_exact(&mut end)?;
+    let read_u32 = |offset: usize| {
+        u32::from_le_bytes([
+            end[offset],
+            end[offset + 1],
+            end[offset + 2],
+            end[offset + 3],
+        ])
+    };
+    if read_u32(0) != END_OF_CENTRAL_DIRECTORY_SIGNATURE
+        || u16::from_le_bytes([end[20], end[21]]) as usize != COMMENT_SIZE
+    {
+        return Ok(false);
+    }
+    let central_directory_size = read_u32(12) as u64;
+    let central_directory_offset = read_u32(16) as u64;
+    if central_directory_offset + central_directory_size > length {
+        return Ok(false);
+    }
+    let mut central_directory = vec![0u8; central_directory_size as usize];
+    reader.seek(SeekFrom::Start(central_directory_offset))?;
+    reader.read_exact(&mut central_directory)?;
+    Ok(end[END_OF_CENTRAL_DIRECTORY_SIZE..] == *get_comment(&central_directory).as_bytes())
+}
+
+// appending would break the canonical layout, the members already there are extracted next to
+// the new files and the whole archive is written again
+pub fn add_files_to_torrentzip<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    archive_path: &P,
+    file_names: &[&str],
+    directory: &Q,
+) -> SimpleResult<()> {
+    if std::fs::metadata(archive_path.as_ref()).is_err() {
+        return write_torrentzip(progress_bar, archive_path, file_names, directory);
+    }
+    progress_bar.println(format!(
+        "TorrentZipping {:?}",
+        archive_path.as_ref().file_name().unwrap()
+    ));
+    let tmp_directory = try_with!(
+        TempDir::new_in(archive_path.as_ref().parent().unwrap()),
+        "Failed to create temporary directory"
+    );
+    let tmp_path = Path::new(tmp_directory.path());
+
+    let mut all_file_names: Vec<String> = file_names
+        .iter()
+        .map(|file_name| file_name.to_string())
+        .collect();
+    for file_name in file_names {
+        let file_path = directory.as_ref().join(file_name);
+        let tmp_file_path = tmp_path.join(file_name);
+        if std::fs::hard_link(&file_path, &tmp_file_path).is_err() {
+            try_with!(
+                std::fs::copy(&file_path, &tmp_file_path),
+                "Failed to copy {:?}",
+                file_path
+            );
+        }
+    }
+
+    let file = try_with!(
+        File::open(archive_path.as_ref()),
+        "Failed to open {:?}",
+        archive_path.as_ref()
+    );
+    let mut zip_archive = try_with!(ZipArchive::new(file), "Failed to read ZIP");
+    for index in 0..zip_archive.len() {
+        let mut zip_file = try_with!(zip_archive.by_index(index), "Failed to read ZIP");
+        let name = zip_file.name().to_owned();
+        // added files replace the members of the same name
+        if zip_file.is_dir() || all_file_names.contains(&name) {
+            continue;
+        }
+        if zip_file.enclosed_name().is_none() {
+            bail!("Unsafe member name \"{}\"", name);
+        }
+        let member_path = tmp_path.join(&name);
+        try_with!(
+            std::fs::create_dir_all(member_path.parent().unwrap()),
+            "Failed to create {:?}",
+            member_path.parent().unwrap()
+        );
+        let mut member_file = try_with!(
+            File::create(&member_path),
+            "Failed to create {:?}",
+            member_path
+        );
+        try_with!(
+            io::copy(&mut zip_file, &mut member_file),
+            "Failed to extract \"{}\"",
+            name
+        );
+        all_file_names.push(name);
+    }
+
+    let all_file_names: Vec<&str> = all_file_names.iter().map(String::as_str).collect();
+    write_torrentzip(progress_bar, archive_path, &all_file_names, &tmp_path)
+}
+
+// extract every member and write them back in the canonical layout
+pub fn torrentzip_archive<P: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    archive_path: &P,
+) -> SimpleResult<()> {
+    progress_bar.println(format!(
+        "TorrentZipping {:?}",
+        archive_path.as_ref().file_name().unwrap()
+    ));
+    let sevenzip_infos = sevenzip::parse_archive(progress_bar, archive_path)?;
+    let file_names: Vec<&str> = sevenzip_infos
+        .iter()
+        .map(|sevenzip_info| sevenzip_info.path.as_str())
+        .collect();
+    let tmp_directory = try_with!(
+        TempDir::new_in(archive_path.as_ref().parent().unwrap()),
+        "Failed to create temporary directory"
+    );
+    let tmp_path = Path::new(tmp_directory.path());
+    sevenzip::extract_files_from_archive(progress_bar, archive_path, &file_names, &tmp_path)?;
+    write_torrentzip(progress_bar, archive_path, &file_names, &tmp_path)
+}
+
+#[cfg(test)]
+mod test_add_files_to_torrentzip;
+#[cfg(test)]
+mod test_torrentzip;
@@ -0,0 +1,34 @@
+use super::*;
+use async_std::path::PathBuf;
+use tempfile::TempDir;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_path = PathBuf::from(tmp_directory.path());
+    let archive_directory = TempDir::new_in(&test_directory).unwrap();
+    let archive_path = PathBuf::from(archive_directory.path()).join("Test Game.zip");
+
+    let file_names = [
+        "Test Game (USA, Europe).rom",
+        "Test Game (Japan).rom",
+        "Test Game (Asia).rom",
+    ];
+    for file_name in file_names {
+        std::fs::copy(test_directory.join(file_name), tmp_path.join(file_name)).unwrap();
+    }
+    write_torrentzip(&progress_bar, &archive_path, &file_names[..2], &tmp_path).unwrap();
+
+    // when
+    add_files_to_torrentzip(&progress_bar, &archive_path, &file_names[2..], &tmp_path).unwrap();
+
+    // then
+    let data = std::fs::read(&archive_path).unwrap();
+    assert_eq!(format!("{:08x}", crc32fast::hash(&data)), "4e34e8c3");
+    assert!(is_torrentzipped(&archive_path).unwrap());
+    assert!(tmp_path.join("Test Game (Asia).rom").is_file().await);
+}
@@ -0,0 +1,59 @@
+use super::*;
+use async_std::path::PathBuf;
+use tempfile::TempDir;
+use zip::ZipArchive;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_path = PathBuf::from(tmp_directory.path());
+
+    let file_names = [
+        "Test Game (USA, Europe).rom",
+        "Test Game (Japan).rom",
+        "Test Game (Asia).rom",
+    ];
+    for file_name in file_names {
+        std::fs::copy(test_directory.join(file_name), tmp_path.join(file_name)).unwrap();
+    }
+
+    // when
+    let archive_path = tmp_path.join("Test Game.zip");
+    write_torrentzip(&progress_bar, &archive_path, &file_names, &tmp_path).unwrap();
+    let data = std::fs::read(&archive_path).unwrap();
+
+    let other_archive_path = tmp_path.join("Test Game (Reversed).zip");
+    let mut reversed_file_names = file_names;
+    reversed_file_names.reverse();
+    write_torrentzip(
+        &progress_bar,
+        &other_archive_path,
+        &reversed_file_names,
+        &tmp_path,
+    )
+    .unwrap();
+
+    // then
+    assert_eq!(std::fs::read(&other_archive_path).unwrap(), data);
+    assert_eq!(format!("{:08x}", crc32fast::hash(&data)), "4e34e8c3");
+    assert!(is_torrentzipped(&archive_path).unwrap());
+    assert!(!is_torrentzipped(&test_directory.join("Test Game (USA, Europe).rom.zip")).unwrap());
+
+    let mut zip_archive = ZipArchive::new(std::fs::File::open(&archive_path).unwrap()).unwrap();
+    let names: Vec<String> = (0..zip_archive.len())
+        .map(|index| zip_archive.by_index(index).unwrap().name().to_owned())
+        .collect();
+    assert_eq!(
+        names,
+        vec![
+            "Test Game (Asia).rom",
+            "Test Game (Japan).rom",
+            "Test Game (USA, Europe).rom",
+        ]
+    );
+    assert_eq!(zip_archive.comment(), b"TORRENTZIPPED-BF138FFF");
+}
@@ -1,15 +1,75 @@
+#[cfg(feature = "chd")]
+use super::chdman;
 use super::config::*;
+#[cfg(feature = "rvz")]
+use super::dolphin;
 use super::import_dats::SYSTEM_NAME_REGEX;
+#[cfg(feature = "cso")]
+use super::maxcso;
 use super::model::*;
+use super::progress::fit_to_terminal;
+use super::sevenzip;
 use super::SimpleResult;
 use async_std::fs;
 use async_std::path::{Path, PathBuf};
-use indicatif::ProgressBar;
+use cfg_if::cfg_if;
+use indicatif::{HumanBytes, ProgressBar};
+use rayon::prelude::*;
+use rayon::ThreadPoolBuilder;
+use regex::Regex;
+use simple_error::SimpleError;
 use sqlx::sqlite::SqliteConnection;
 use std::cmp::Ordering;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::env;
+use std::io;
+#[cfg(target_os = "linux")]
+use std::os::unix::fs::MetadataExt;
+use std::process::{Command, Output};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering as AtomicOrdering};
+use std::time::UNIX_EPOCH;
 #[cfg(any(feature = "ird", feature = "benchmark"))]
 use tempfile::NamedTempFile;
 use tempfile::TempDir;
+use unicode_normalization::UnicodeNormalization;
+
+const TOOL_NOT_FOUND_ERROR: &str = "not found in PATH";
+const TOOL_VERSION_MISMATCH_ERROR: &str = "is older than the minimum supported version";
+pub const TOOL_NOT_FOUND_EXIT_CODE: i32 = 3;
+
+static CONTINUE_ON_TOOL_VERSION_MISMATCH: AtomicBool = AtomicBool::new(false);
+static SHOW_COMMANDS: AtomicBool = AtomicBool::new(false);
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+static MIN_FREE_SPACE: AtomicU64 = AtomicU64::new(0);
+
+lazy_static! {
+    static ref TOOL_VERSION_REGEX: Regex = Regex::new(r"\d+(?:\.\d+)+").unwrap();
+    static ref TOOL_NOT_FOUND_REGEX: Regex =
+        Regex::new(r"^(.+) not found in PATH \(or set (\w+)\)$").unwrap();
+    static ref TOOL_VERSION_MISMATCH_REGEX: Regex =
+        Regex::new(r"^(.+) is older than the minimum supported version (\S+) \((.*)\), ").unwrap();
+    static ref DISC_TOKEN_REGEX: Regex = Regex::new(r"\s*\(Disc [^)]+\)").unwrap();
+    // keyed by tool path, tests point the same tool to different wrappers
+    static ref TOOL_VERSIONS: std::sync::Mutex<HashMap<String, SimpleResult<String>>> =
+        std::sync::Mutex::new(HashMap::new());
+}
+
+#[cfg(test)]
+lazy_static! {
+    pub static ref SHOWN_COMMANDS: std::sync::Mutex<Vec<String>> =
+        std::sync::Mutex::new(Vec::new());
+    pub static ref SIMULATED_FREE_SPACE: std::sync::Mutex<Option<u64>> =
+        std::sync::Mutex::new(None);
+}
+
+// sizes are stored as signed integers in the database
+pub fn size_to_i64(size: u64) -> SimpleResult<i64> {
+    Ok(try_with!(
+        i64::try_from(size),
+        "Size {} doesn't fit in a signed 64-bit integer",
+        size
+    ))
+}
 
 pub async fn get_canonicalized_path<P: AsRef<Path>>(path: &P) -> SimpleResult<PathBuf> {
     let canonicalized_path = try_with!(
@@ -20,6 +80,32 @@ pub async fn get_canonicalized_path<P: AsRef<Path>>(path: &P) -> SimpleResult<Pa
     Ok(canonicalized_path)
 }
 
+pub async fn get_size_and_mtime<P: AsRef<Path>>(path: &P) -> SimpleResult<(i64, i64)> {
+    let metadata = try_with!(
+        path.as_ref().metadata().await,
+        "Failed to read {:?} metadata",
+        path.as_ref()
+    );
+    let modified = try_with!(
+        metadata.modified(),
+        "Failed to read {:?} modification time",
+        path.as_ref()
+    );
+    // nanoseconds, a file rewritten within the same second must not hit the cache
+    let mtime = try_with!(
+        modified.duration_since(UNIX_EPOCH),
+        "Failed to compute {:?} modification time",
+        path.as_ref()
+    )
+    .as_nanos();
+    let mtime = try_with!(
+        i64::try_from(mtime),
+        "Modification time {} doesn't fit in a signed 64-bit integer",
+        mtime
+    );
+    Ok((size_to_i64(metadata.len())?, mtime))
+}
+
 #[cfg(any(feature = "chd", feature = "cso", feature = "rvz"))]
 pub async fn open_file<P: AsRef<Path>>(path: &P) -> SimpleResult<fs::File> {
     let file = try_with!(
@@ -52,7 +138,10 @@ pub async fn create_file<P: AsRef<Path>>(
     quiet: bool,
 ) -> SimpleResult<fs::File> {
     if !quiet {
-        progress_bar.println(&format!("Creating {:?}", path.as_ref().as_os_str()));
+        progress_bar.println(fit_to_terminal(&format!(
+            "Creating {:?}",
+            path.as_ref().as_os_str()
+        )));
     }
     let file = try_with!(
         fs::File::create(path).await,
@@ -82,8 +171,12 @@ pub async fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
         if !new_directory.is_dir().await {
             create_directory(progress_bar, &new_directory, quiet).await?;
         }
+        check_min_free_space(&new_directory)?;
         if !quiet {
-            progress_bar.println(&format!("Copying to {:?}", new_path.as_ref().as_os_str()));
+            progress_bar.println(fit_to_terminal(&format!(
+                "Copying to {:?}",
+                new_path.as_ref().as_os_str()
+            )));
         }
         try_with!(
             fs::copy(old_path, new_path).await,
@@ -95,6 +188,23 @@ pub async fn copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(())
 }
 
+// hard links share the data, files on another filesystem are copied instead
+pub async fn link_or_copy_file<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    old_path: &P,
+    new_path: &Q,
+    quiet: bool,
+) -> SimpleResult<()> {
+    let new_directory = new_path.as_ref().parent().unwrap();
+    if !new_directory.is_dir().await {
+        create_directory(progress_bar, &new_directory, quiet).await?;
+    }
+    if fs::hard_link(old_path, new_path).await.is_err() {
+        copy_file(progress_bar, old_path, new_path, quiet).await?;
+    }
+    Ok(())
+}
+
 pub async fn rename_file<P: AsRef<Path>, Q: AsRef<Path>>(
     progress_bar: &ProgressBar,
     old_path: &P,
@@ -106,8 +216,12 @@ pub async fn rename_file<P: AsRef<Path>, Q: AsRef<Path>>(
         if !new_directory.is_dir().await {
             create_directory(progress_bar, &new_directory, quiet).await?;
         }
+        check_min_free_space(&new_directory)?;
         if !quiet {
-            progress_bar.println(&format!("Moving to {:?}", new_path.as_ref().as_os_str()));
+            progress_bar.println(fit_to_terminal(&format!(
+                "Moving to {:?}",
+                new_path.as_ref().as_os_str()
+            )));
         }
         let result = fs::rename(old_path, new_path).await;
         // rename doesn't work across filesystems, use copy/remove as fallback
@@ -119,13 +233,61 @@ pub async fn rename_file<P: AsRef<Path>, Q: AsRef<Path>>(
     Ok(())
 }
 
+// only links are replaced, files put there by hand are left alone
+pub async fn create_symlink<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    target_path: &P,
+    link_path: &Q,
+    quiet: bool,
+) -> SimpleResult<()> {
+    let link_directory = link_path.as_ref().parent().unwrap();
+    if !link_directory.is_dir().await {
+        create_directory(progress_bar, &link_directory, quiet).await?;
+    }
+    if let Ok(metadata) = fs::symlink_metadata(link_path).await {
+        if !metadata.file_type().is_symlink() {
+            if !quiet {
+                progress_bar.println(fit_to_terminal(&format!(
+                    "{:?} already exists, skipping",
+                    link_path.as_ref().as_os_str()
+                )));
+            }
+            return Ok(());
+        }
+        remove_file(progress_bar, link_path, true).await?;
+    }
+    if !quiet {
+        progress_bar.println(fit_to_terminal(&format!(
+            "Linking to {:?}",
+            link_path.as_ref().as_os_str()
+        )));
+    }
+    cfg_if! {
+        if #[cfg(unix)] {
+            let result = async_std::os::unix::fs::symlink(target_path, link_path).await;
+        } else {
+            let result = async_std::os::windows::fs::symlink_file(target_path, link_path).await;
+        }
+    }
+    try_with!(
+        result,
+        "Failed to link {:?} to {:?}",
+        link_path.as_ref().as_os_str(),
+        target_path.as_ref().as_os_str()
+    );
+    Ok(())
+}
+
 pub async fn remove_file<P: AsRef<Path>>(
     progress_bar: &ProgressBar,
     path: &P,
     quiet: bool,
 ) -> SimpleResult<()> {
     if !quiet {
-        progress_bar.println(&format!("Deleting {:?}", path.as_ref().as_os_str()));
+        progress_bar.println(fit_to_terminal(&format!(
+            "Deleting {:?}",
+            path.as_ref().as_os_str()
+        )));
     }
     try_with!(
         fs::remove_file(path).await,
@@ -135,13 +297,107 @@ pub async fn remove_file<P: AsRef<Path>>(
     Ok(())
 }
 
+cfg_if! {
+    if #[cfg(test)] {
+        use async_std::sync::Mutex;
+
+        lazy_static! {
+            pub static ref SYSTEM_TRASH: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+        }
+
+        // tests must not litter the OS trash, record the path and delete the file instead
+        pub async fn move_to_system_trash<P: AsRef<Path>>(
+            progress_bar: &ProgressBar,
+            path: &P,
+            quiet: bool,
+        ) -> SimpleResult<()> {
+            remove_file(progress_bar, path, quiet).await?;
+            SYSTEM_TRASH.lock().await.push(path.as_ref().to_path_buf());
+            Ok(())
+        }
+    } else {
+        pub async fn move_to_system_trash<P: AsRef<Path>>(
+            progress_bar: &ProgressBar,
+            path: &P,
+            quiet: bool,
+        ) -> SimpleResult<()> {
+            if !quiet {
+                progress_bar.println(&format!(
+                    "Moving {:?} to the system trash",
+                    path.as_ref().as_os_str()
+                ));
+            }
+            try_with!(
+                trash::delete(path.as_ref()),
+                "Failed to move {:?} to the system trash",
+                path.as_ref()
+            );
+            Ok(())
+        }
+    }
+}
+
+#[cfg(any(
+    feature = "chd",
+    feature = "cso",
+    feature = "rvz",
+    feature = "torrentzip",
+    feature = "zst"
+))]
+pub fn get_part_path<P: AsRef<Path>>(path: &P) -> PathBuf {
+    let mut part_path = path.as_ref().as_os_str().to_os_string();
+    part_path.push(".");
+    part_path.push(PART_EXTENSION);
+    PathBuf::from(part_path)
+}
+
+#[cfg(any(
+    feature = "chd",
+    feature = "cso",
+    feature = "rvz",
+    feature = "torrentzip",
+    feature = "zst"
+))]
+// outputs are written to a partial file first and renamed on success,
+// so that an interrupted run never leaves a file that looks complete
+pub fn write_atomically<P, F>(progress_bar: &ProgressBar, path: &P, write: F) -> SimpleResult<()>
+where
+    P: AsRef<Path>,
+    F: FnOnce(&Path) -> SimpleResult<()>,
+{
+    let part_path = get_part_path(path);
+
+    // discard leftovers from a previous interrupted run
+    match std::fs::remove_file(&part_path) {
+        Ok(_) => progress_bar.println(format!("Deleting stale {:?}", part_path.as_os_str())),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => {}
+        Err(error) => bail!("Failed to delete {:?}: {}", part_path.as_os_str(), error),
+    }
+
+    if let Err(error) = write(&part_path) {
+        std::fs::remove_file(&part_path).ok();
+        return Err(error);
+    }
+
+    try_with!(
+        std::fs::rename(&part_path, path.as_ref()),
+        "Failed to rename {:?} to {:?}",
+        part_path.as_os_str(),
+        path.as_ref().as_os_str()
+    );
+    Ok(())
+}
+
 pub async fn create_directory<P: AsRef<Path>>(
     progress_bar: &ProgressBar,
     path: &P,
     quiet: bool,
 ) -> SimpleResult<()> {
     if !quiet {
-        progress_bar.println(&format!("Creating {:?}", path.as_ref().as_os_str()));
+        progress_bar.println(fit_to_terminal(&format!(
+            "Creating {:?}",
+            path.as_ref().as_os_str()
+        )));
     }
     if !path.as_ref().is_dir().await {
         try_with!(
@@ -167,7 +423,10 @@ pub async fn remove_directory<P: AsRef<Path>>(
     quiet: bool,
 ) -> SimpleResult<()> {
     if !quiet {
-        progress_bar.println(&format!("Deleting {:?}", path.as_ref().as_os_str()));
+        progress_bar.println(fit_to_terminal(&format!(
+            "Deleting {:?}",
+            path.as_ref().as_os_str()
+        )));
     }
     try_with!(
         fs::remove_dir_all(path).await,
@@ -189,6 +448,30 @@ pub async fn get_system_directory(
     Ok(system_directory)
 }
 
+// MAME samples are sets of WAV files, distinct from the romsets whose names they usually share
+pub fn is_sample_set<'a, I: IntoIterator<Item = &'a Rom>>(system: &System, roms: I) -> bool {
+    system.arcade
+        && roms.into_iter().all(|rom| {
+            Path::new(&rom.name)
+                .extension()
+                .map(|extension| extension.eq_ignore_ascii_case(WAV_EXTENSION))
+                .unwrap_or(false)
+        })
+}
+
+// keep sample sets in their own subdirectory so they don't clash with the romsets
+pub fn get_arcade_directory<'a, P: AsRef<Path>, I: IntoIterator<Item = &'a Rom>>(
+    system_directory: &P,
+    system: &System,
+    roms: I,
+) -> PathBuf {
+    if is_sample_set(system, roms) {
+        system_directory.as_ref().join(SAMPLES_DIRECTORY)
+    } else {
+        system_directory.as_ref().to_path_buf()
+    }
+}
+
 pub async fn get_one_region_directory(
     connection: &mut SqliteConnection,
     progress_bar: &ProgressBar,
@@ -213,6 +496,29 @@ pub async fn get_trash_directory(
     Ok(trash_directory)
 }
 
+pub async fn get_trash_reason_directory(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    system: &System,
+    reason: &TrashReason,
+) -> SimpleResult<PathBuf> {
+    let trash_directory = get_trash_directory(connection, progress_bar, system).await?;
+    if !get_bool(connection, "TRASH_BY_REASON").await {
+        return Ok(trash_directory);
+    }
+    let trash_reason_directory = trash_directory.join(match reason {
+        TrashReason::NoMatch => "NoMatch",
+        TrashReason::SizeMismatch => "SizeMismatch",
+        TrashReason::CrcMismatch => "CrcMismatch",
+        TrashReason::Md5Mismatch => "Md5Mismatch",
+        TrashReason::Sha1Mismatch => "Sha1Mismatch",
+        TrashReason::Invalid => "Invalid",
+        TrashReason::Redundant => "Redundant",
+    });
+    create_directory(progress_bar, &trash_reason_directory, true).await?;
+    Ok(trash_reason_directory)
+}
+
 pub fn is_update(progress_bar: &ProgressBar, old_version: &str, new_version: &str) -> bool {
     match new_version.cmp(old_version) {
         Ordering::Less => {
@@ -235,3 +541,409 @@ pub fn is_update(progress_bar: &ProgressBar, old_version: &str, new_version: &st
         }
     }
 }
+
+pub fn get_tool_path(tool: &str, key: &str) -> String {
+    env::var(key).unwrap_or_else(|_| String::from(tool))
+}
+
+pub fn get_tool_version(tool: &str, key: &str, args: &[&str]) -> SimpleResult<String> {
+    let output = match Command::new(get_tool_path(tool, key)).args(args).output() {
+        Ok(output) => output,
+        Err(_) => bail!("{} {} (or set {})", tool, TOOL_NOT_FOUND_ERROR, key),
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // a tool that can't start, e.g. a missing shared library, only complains on stderr
+    if !output.status.success() && stdout.trim().is_empty() {
+        bail!(
+            "{} failed to run ({}): {}",
+            tool,
+            output.status,
+            stderr.trim()
+        );
+    }
+    let version = stdout
+        .lines()
+        .chain(stderr.lines())
+        .map(|line| line.trim())
+        .find(|line| !line.is_empty())
+        .unwrap_or_default()
+        .to_owned();
+    Ok(version)
+}
+
+// tools are only run once per path, later checks reuse the outcome
+pub fn get_cached_tool_version<F: FnOnce() -> SimpleResult<String>>(
+    tool: &str,
+    key: &str,
+    get_version: F,
+) -> SimpleResult<String> {
+    TOOL_VERSIONS
+        .lock()
+        .unwrap()
+        .entry(get_tool_path(tool, key))
+        .or_insert_with(get_version)
+        .clone()
+}
+
+// every tool the files will need is checked before the first one is processed
+pub fn check_tools_available_for_files<I, P>(
+    file_paths: I,
+    archive_is_rom: bool,
+) -> SimpleResult<()>
+where
+    I: IntoIterator<Item = P>,
+    P: AsRef<std::path::Path>,
+{
+    let extensions: HashSet<String> = file_paths
+        .into_iter()
+        .filter_map(|file_path| {
+            file_path
+                .as_ref()
+                .extension()
+                .and_then(|extension| extension.to_str())
+                .map(|extension| extension.to_lowercase())
+        })
+        .collect();
+    let needs = |tool_extensions: &[&str]| {
+        tool_extensions
+            .iter()
+            .any(|tool_extension| extensions.contains(*tool_extension))
+    };
+    if !archive_is_rom && needs(&ARCHIVE_EXTENSIONS) {
+        sevenzip::check_tool_available()?;
+    }
+    #[cfg(feature = "chd")]
+    if needs(&[CHD_EXTENSION]) {
+        chdman::check_tool_available()?;
+    }
+    #[cfg(feature = "cso")]
+    if needs(&[CSO_EXTENSION, ZSO_EXTENSION]) {
+        maxcso::check_tool_available()?;
+    }
+    #[cfg(feature = "rvz")]
+    if needs(&[RVZ_EXTENSION]) {
+        dolphin::check_tool_available()?;
+    }
+    Ok(())
+}
+
+pub fn set_continue_on_tool_version_mismatch(continue_on_mismatch: bool) {
+    CONTINUE_ON_TOOL_VERSION_MISMATCH.store(continue_on_mismatch, AtomicOrdering::SeqCst);
+}
+
+pub fn get_continue_on_tool_version_mismatch() -> bool {
+    CONTINUE_ON_TOOL_VERSION_MISMATCH.load(AtomicOrdering::SeqCst)
+}
+
+// first dotted number in the output, e.g. "0.252" in "chdman - MAME ... manager 0.252 (mame0252)"
+pub fn parse_tool_version(version: &str) -> Option<Vec<u32>> {
+    TOOL_VERSION_REGEX.find(version).map(|version| {
+        version
+            .as_str()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(u32::MAX))
+            .collect()
+    })
+}
+
+// unparsable versions are let through, the output format of some tools is not versioned
+pub fn check_tool_version(
+    tool: &str,
+    version: String,
+    minimum_version: &str,
+    continue_on_mismatch: bool,
+) -> SimpleResult<String> {
+    let (parsed_version, parsed_minimum_version) = match (
+        parse_tool_version(&version),
+        parse_tool_version(minimum_version),
+    ) {
+        (Some(parsed_version), Some(parsed_minimum_version)) => {
+            (parsed_version, parsed_minimum_version)
+        }
+        _ => return Ok(version),
+    };
+    if parsed_version >= parsed_minimum_version {
+        return Ok(version);
+    }
+    if !continue_on_mismatch {
+        bail!(
+            "{} {} {} ({}), use --continue-on-tool-version-mismatch to proceed anyway",
+            tool,
+            TOOL_VERSION_MISMATCH_ERROR,
+            minimum_version,
+            version
+        );
+    }
+    tracing::warn!(tool, %version, minimum_version, "Tool version mismatch");
+    Ok(format!(
+        "{} (older than the minimum supported version {})",
+        version, minimum_version
+    ))
+}
+
+pub fn set_show_commands(show_commands: bool) {
+    SHOW_COMMANDS.store(show_commands, AtomicOrdering::SeqCst);
+}
+
+// a command line that can be pasted in a shell to reproduce a failure
+pub fn format_command(command: &Command) -> String {
+    let mut words: Vec<String> = vec![quote_word(&command.get_program().to_string_lossy())];
+    words.extend(
+        command
+            .get_args()
+            .map(|arg| quote_word(&arg.to_string_lossy())),
+    );
+    match command.get_current_dir() {
+        Some(directory) => format!(
+            "cd {} && {}",
+            quote_word(&directory.to_string_lossy()),
+            words.join(" ")
+        ),
+        None => words.join(" "),
+    }
+}
+
+fn quote_word(word: &str) -> String {
+    if !word.is_empty()
+        && !word
+            .chars()
+            .any(|c| c.is_whitespace() || "\"'\\$`!&|;()<>*?[]{}#~".contains(c))
+    {
+        return word.to_owned();
+    }
+    format!("'{}'", word.replace('\'', "'\\''"))
+}
+
+pub fn get_tool_output(
+    progress_bar: &ProgressBar,
+    command: &mut Command,
+    tool: &str,
+    key: &str,
+    message: &str,
+) -> SimpleResult<Output> {
+    if SHOW_COMMANDS.load(AtomicOrdering::SeqCst) {
+        let command_line = format_command(command);
+        progress_bar.println(&command_line);
+        #[cfg(test)]
+        SHOWN_COMMANDS.lock().unwrap().push(command_line);
+    }
+    match command.output() {
+        Ok(output) => Ok(output),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => {
+            bail!("{} {} (or set {})", tool, TOOL_NOT_FOUND_ERROR, key)
+        }
+        Err(error) => bail!("{}: {}", message, error),
+    }
+}
+
+// success is keyed off the exit status only, some tool builds are chatty on stderr
+pub fn check_tool_status(tool: &str, output: &Output) -> SimpleResult<()> {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    if !output.status.success() {
+        bail!("{}", stderr);
+    }
+    if !stderr.trim().is_empty() {
+        tracing::debug!(tool, stderr = %stderr.trim(), "Tool output");
+    }
+    Ok(())
+}
+
+pub fn set_json_errors(json_errors: bool) {
+    JSON_ERRORS.store(json_errors, AtomicOrdering::SeqCst);
+}
+
+pub fn get_json_errors() -> bool {
+    JSON_ERRORS.load(AtomicOrdering::SeqCst)
+}
+
+// classifies the tool errors raised above
+pub fn get_error_report(error: &SimpleError) -> Option<ErrorReport> {
+    let message = error.as_str();
+    let mut context = BTreeMap::new();
+    let error_kind = if let Some(captures) = TOOL_NOT_FOUND_REGEX.captures(message) {
+        context.insert("tool", captures[1].to_owned());
+        context.insert("variable", captures[2].to_owned());
+        ErrorKind::ToolNotFound
+    } else if let Some(captures) = TOOL_VERSION_MISMATCH_REGEX.captures(message) {
+        context.insert("tool", captures[1].to_owned());
+        context.insert("minimum_version", captures[2].to_owned());
+        context.insert("version", captures[3].to_owned());
+        ErrorKind::ToolVersionMismatch
+    } else {
+        return None;
+    };
+    Some(ErrorReport {
+        error_kind,
+        message: message.to_owned(),
+        context,
+    })
+}
+
+// let scripts tell a misconfigured environment apart from data problems
+pub fn get_exit_code(error_report: &ErrorReport) -> i32 {
+    match error_report.error_kind {
+        ErrorKind::ToolNotFound => TOOL_NOT_FOUND_EXIT_CODE,
+        _ => 1,
+    }
+}
+
+pub fn map_with_parallelism<T, R, F>(items: &[T], parallelism: usize, f: F) -> SimpleResult<Vec<R>>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync + Send,
+{
+    // strictly sequential, in order
+    if parallelism <= 1 {
+        return Ok(items.iter().map(f).collect());
+    }
+    let thread_pool = try_with!(
+        ThreadPoolBuilder::new().num_threads(parallelism).build(),
+        "Failed to create thread pool"
+    );
+    Ok(thread_pool.install(|| items.par_iter().map(f).collect()))
+}
+
+#[cfg(target_os = "linux")]
+pub fn is_rotational<P: AsRef<Path>>(path: &P) -> bool {
+    let dev = match std::fs::metadata(path.as_ref()) {
+        Ok(metadata) => metadata.dev(),
+        Err(_) => return false,
+    };
+    let major = ((dev >> 8) & 0xfff) | ((dev >> 32) & !0xfff);
+    let minor = (dev & 0xff) | ((dev >> 12) & !0xff);
+    let mut block_path = match std::fs::canonicalize(format!("/sys/dev/block/{}:{}", major, minor))
+    {
+        Ok(block_path) => block_path,
+        Err(_) => return false,
+    };
+    // partitions don't have a queue, their parent disk does
+    if block_path.join("partition").is_file() {
+        block_path.pop();
+    }
+    match std::fs::read_to_string(block_path.join("queue").join("rotational")) {
+        Ok(rotational) => rotational.trim() == "1",
+        Err(_) => false,
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn is_rotational<P: AsRef<Path>>(_: &P) -> bool {
+    false
+}
+
+// statvfs field types differ between platforms
+#[cfg(unix)]
+#[allow(clippy::unnecessary_cast)]
+pub fn get_free_space<P: AsRef<Path>>(path: &P) -> Option<u64> {
+    let path: &std::path::Path = path.as_ref().as_ref();
+    match nix::sys::statvfs::statvfs(path) {
+        Ok(statvfs) => Some(statvfs.blocks_available() as u64 * statvfs.fragment_size() as u64),
+        Err(_) => None,
+    }
+}
+
+#[cfg(not(unix))]
+pub fn get_free_space<P: AsRef<Path>>(_: &P) -> Option<u64> {
+    None
+}
+
+pub async fn load_min_free_space(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let min_free_space = match get_string(connection, "MIN_FREE_SPACE").await {
+        Some(min_free_space) => parse_size(&min_free_space)?,
+        None => 0,
+    };
+    // get_free_space is only implemented with statvfs
+    if min_free_space > 0 && !cfg!(unix) {
+        progress_bar
+            .println("Free space can't be checked on this platform, ignoring MIN_FREE_SPACE");
+    }
+    set_min_free_space(min_free_space);
+    Ok(())
+}
+
+pub fn set_min_free_space(min_free_space: u64) {
+    MIN_FREE_SPACE.store(min_free_space, AtomicOrdering::SeqCst);
+}
+
+// a full disk can corrupt the database, stop while there is still some room left
+pub fn check_min_free_space<P: AsRef<Path>>(path: &P) -> SimpleResult<()> {
+    let min_free_space = MIN_FREE_SPACE.load(AtomicOrdering::SeqCst);
+    if min_free_space == 0 {
+        return Ok(());
+    }
+    #[cfg(test)]
+    let free_space = SIMULATED_FREE_SPACE
+        .lock()
+        .unwrap()
+        .or_else(|| get_free_space(path));
+    #[cfg(not(test))]
+    let free_space = get_free_space(path);
+    if let Some(free_space) = free_space.filter(|&free_space| free_space < min_free_space) {
+        bail!(
+            "{} free in {:?}, below minimum free space of {}",
+            HumanBytes(free_space),
+            path.as_ref(),
+            HumanBytes(min_free_space)
+        );
+    }
+    Ok(())
+}
+
+// sizes like "10G", suffixes are powers of 1024
+pub fn parse_size(size: &str) -> SimpleResult<u64> {
+    let size = size.trim().to_uppercase();
+    let (number, multiplier) = match size.chars().last() {
+        Some('K') => (&size[..size.len() - 1], 1u64 << 10),
+        Some('M') => (&size[..size.len() - 1], 1u64 << 20),
+        Some('G') => (&size[..size.len() - 1], 1u64 << 30),
+        Some('T') => (&size[..size.len() - 1], 1u64 << 40),
+        _ => (size.as_str(), 1),
+    };
+    match number.trim().parse::<u64>() {
+        Ok(number) => Ok(number * multiplier),
+        Err(_) => bail!("Invalid size \"{}\"", size),
+    }
+}
+
+// filesystems may decompose names (NFD on macOS) and some tools prepend a BOM
+pub fn normalize_name(name: &str) -> String {
+    name.trim_start_matches('\u{feff}').nfc().collect()
+}
+
+pub fn names_match(name: &str, other_name: &str) -> bool {
+    normalize_name(name) == normalize_name(other_name)
+}
+
+pub fn path_ends_with_name(path: &str, name: &str) -> bool {
+    normalize_name(path).ends_with(&normalize_name(name))
+}
+
+// "Game (USA) (Disc 1)" becomes "Game (USA)", games without a disc token have no base name
+pub fn get_disc_base_name(name: &str) -> Option<String> {
+    if !DISC_TOKEN_REGEX.is_match(name) {
+        return None;
+    }
+    Some(DISC_TOKEN_REGEX.replace(name, "").trim().to_owned())
+}
+
+#[cfg(test)]
+mod test_cached_tool_version;
+#[cfg(test)]
+mod test_json_errors;
+#[cfg(test)]
+mod test_names_match;
+#[cfg(test)]
+mod test_show_commands;
+#[cfg(test)]
+mod test_tool_not_found_exit_code;
+#[cfg(test)]
+mod test_tool_startup_failure;
+#[cfg(test)]
+mod test_tool_status;
+#[cfg(test)]
+mod test_tool_version;
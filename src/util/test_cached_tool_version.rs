@@ -0,0 +1,23 @@
+use super::*;
+use std::cell::Cell;
+
+#[test]
+fn test() {
+    // given
+    let tool = "oxyromon-cached-tool";
+    let key = "OXYROMON_TEST_UNSET_TOOL_PATH";
+    let runs = Cell::new(0);
+    let get_version = || {
+        runs.set(runs.get() + 1);
+        bail!("{} {} (or set {})", tool, TOOL_NOT_FOUND_ERROR, key)
+    };
+
+    // when
+    let first_result = get_cached_tool_version(tool, key, get_version);
+    let second_result = get_cached_tool_version(tool, key, get_version);
+
+    // then
+    assert_eq!(runs.get(), 1);
+    assert!(first_result.is_err());
+    assert_eq!(first_result, second_result);
+}
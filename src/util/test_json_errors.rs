@@ -0,0 +1,47 @@
+use super::super::sevenzip;
+use super::*;
+use indicatif::ProgressBar;
+use serde_json::{json, Value};
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let previous_path = env::var(sevenzip::SEVENZIP_PATH).ok();
+    env::set_var(sevenzip::SEVENZIP_PATH, "/nonexistent/7z");
+
+    let error = sevenzip::parse_archive(
+        &progress_bar,
+        &test_directory.join("Test Game (USA, Europe).rom.zip"),
+    )
+    .err()
+    .unwrap();
+
+    match previous_path {
+        Some(previous_path) => env::set_var(sevenzip::SEVENZIP_PATH, previous_path),
+        None => env::remove_var(sevenzip::SEVENZIP_PATH),
+    }
+
+    // when
+    let report: Value =
+        serde_json::from_str(&serde_json::to_string(&get_error_report(&error).unwrap()).unwrap())
+            .unwrap();
+
+    // then
+    assert_eq!(
+        report,
+        json!({
+            "error_kind": "tool_not_found",
+            "message": "7z not found in PATH (or set SEVENZIP_PATH)",
+            "context": {
+                "tool": "7z",
+                "variable": "SEVENZIP_PATH",
+            },
+        })
+    );
+    assert!(get_error_report(&SimpleError::new("Failed to parse archive")).is_none());
+}
@@ -0,0 +1,23 @@
+use super::*;
+
+#[test]
+fn test() {
+    // given
+    let dat_name = "Test Game (Fran\u{e7}ais) (R\u{e9}v 1).rom";
+    let nfd_name = "Test Game (Franc\u{327}ais) (Re\u{301}v 1).rom";
+    let bom_name = "\u{feff}Test Game (Fran\u{e7}ais) (R\u{e9}v 1).rom";
+
+    // when
+    let nfd_match = names_match(nfd_name, dat_name);
+    let bom_match = names_match(bom_name, dat_name);
+
+    // then
+    assert_ne!(nfd_name, dat_name);
+    assert!(nfd_match);
+    assert!(bom_match);
+    assert!(!names_match("Test Game (Francais) (Rev 1).rom", dat_name));
+    assert!(path_ends_with_name(
+        &format!("/roms/Test System/{}", nfd_name),
+        dat_name
+    ));
+}
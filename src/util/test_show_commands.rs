@@ -0,0 +1,43 @@
+use super::super::sevenzip;
+use super::*;
+use indicatif::ProgressBar;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    SHOWN_COMMANDS.lock().unwrap().clear();
+    set_show_commands(true);
+
+    // when
+    let result = sevenzip::parse_archive(
+        &progress_bar,
+        &test_directory.join("Test Game (USA, Europe).rom.zip"),
+    );
+
+    set_show_commands(false);
+
+    // then
+    result.unwrap();
+    assert_eq!(
+        *SHOWN_COMMANDS.lock().unwrap(),
+        vec![format!(
+            "{} l -slt 'tests/Test Game (USA, Europe).rom.zip'",
+            quote_word(&get_tool_path("7z", sevenzip::SEVENZIP_PATH))
+        )]
+    );
+
+    let mut command = Command::new("7z");
+    command
+        .arg("x")
+        .arg("/tmp/it's.zip")
+        .current_dir("/tmp/some dir");
+    assert_eq!(
+        format_command(&command),
+        "cd '/tmp/some dir' && 7z x '/tmp/it'\\''s.zip'"
+    );
+}
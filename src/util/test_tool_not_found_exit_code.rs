@@ -0,0 +1,46 @@
+use super::super::sevenzip;
+use super::*;
+use indicatif::ProgressBar;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let _guard = MUTEX.lock().await;
+
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let previous_path = env::var(sevenzip::SEVENZIP_PATH).ok();
+    env::set_var(sevenzip::SEVENZIP_PATH, "/nonexistent/7z");
+
+    // when
+    let error = sevenzip::parse_archive(
+        &progress_bar,
+        &test_directory.join("Test Game (USA, Europe).rom.zip"),
+    )
+    .err()
+    .unwrap();
+
+    match previous_path {
+        Some(previous_path) => env::set_var(sevenzip::SEVENZIP_PATH, previous_path),
+        None => env::remove_var(sevenzip::SEVENZIP_PATH),
+    }
+
+    // then
+    assert_eq!(
+        error.as_str(),
+        "7z not found in PATH (or set SEVENZIP_PATH)"
+    );
+    assert_eq!(
+        get_exit_code(&get_error_report(&error).unwrap()),
+        TOOL_NOT_FOUND_EXIT_CODE
+    );
+    assert_eq!(
+        get_exit_code(&ErrorReport {
+            error_kind: ErrorKind::Other,
+            message: String::from("Failed to parse archive"),
+            context: BTreeMap::new(),
+        }),
+        1
+    );
+}
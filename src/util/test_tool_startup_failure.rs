@@ -0,0 +1,26 @@
+use super::*;
+
+#[test]
+fn test() {
+    // given
+    let tool = "sh";
+    let key = "OXYROMON_TEST_UNSET_TOOL_PATH";
+
+    // when
+    let failed_result = get_tool_version(
+        tool,
+        key,
+        &[
+            "-c",
+            "echo 'error while loading shared libraries: libuv.so.1' >&2; exit 127",
+        ],
+    );
+    let usage_result = get_tool_version(tool, key, &["-c", "echo 'Usage: tool'; exit 1"]);
+
+    // then
+    assert_eq!(
+        failed_result.err().unwrap().as_str(),
+        "sh failed to run (exit status: 127): error while loading shared libraries: libuv.so.1"
+    );
+    assert_eq!(usage_result.unwrap(), "Usage: tool");
+}
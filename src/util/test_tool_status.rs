@@ -0,0 +1,27 @@
+use super::*;
+
+#[test]
+fn test() {
+    // given
+    let chatty_output = Command::new("sh")
+        .arg("-c")
+        .arg("echo 'Compression complete ... final ratio = 42.0%' >&2")
+        .output()
+        .unwrap();
+    let failed_output = Command::new("sh")
+        .arg("-c")
+        .arg("echo 'Error: file not found' >&2; exit 1")
+        .output()
+        .unwrap();
+
+    // when
+    let chatty_result = check_tool_status("chdman", &chatty_output);
+    let failed_result = check_tool_status("chdman", &failed_output);
+
+    // then
+    assert!(chatty_result.is_ok());
+    assert_eq!(
+        failed_result.err().unwrap().as_str(),
+        "Error: file not found\n"
+    );
+}
@@ -0,0 +1,45 @@
+use super::*;
+
+#[test]
+fn test() {
+    // given
+    let version =
+        String::from("chdman - MAME Compressed Hunks of Data (CHD) manager 0.139 (mame0139)");
+
+    // when
+    let error = check_tool_version("chdman", version.clone(), "0.145", false)
+        .err()
+        .unwrap();
+    let overridden_version = check_tool_version("chdman", version.clone(), "0.145", true).unwrap();
+
+    // then
+    assert_eq!(parse_tool_version(&version), Some(vec![0, 139]));
+    assert_eq!(
+        error.as_str(),
+        "chdman is older than the minimum supported version 0.145 (chdman - MAME Compressed Hunks of Data (CHD) manager 0.139 (mame0139)), use --continue-on-tool-version-mismatch to proceed anyway"
+    );
+    assert_eq!(
+        overridden_version,
+        "chdman - MAME Compressed Hunks of Data (CHD) manager 0.139 (mame0139) (older than the minimum supported version 0.145)"
+    );
+    assert_eq!(
+        check_tool_version(
+            "chdman",
+            String::from("chdman - MAME Compressed Hunks of Data (CHD) manager 0.252 (mame0252)"),
+            "0.145",
+            false
+        )
+        .unwrap(),
+        "chdman - MAME Compressed Hunks of Data (CHD) manager 0.252 (mame0252)"
+    );
+    assert_eq!(
+        check_tool_version(
+            "dolphin-tool",
+            String::from("Usage: dolphin-tool"),
+            "1.0",
+            false
+        )
+        .unwrap(),
+        "Usage: dolphin-tool"
+    );
+}
@@ -0,0 +1,144 @@
+use super::check_roms;
+use super::checksum::*;
+use super::database::*;
+use super::fingerprint::*;
+use super::model::*;
+use super::SimpleResult;
+use async_std::path::PathBuf;
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
+use indicatif::ProgressBar;
+use regex::Regex;
+use sqlx::sqlite::SqliteConnection;
+use std::fs;
+use std::str::FromStr;
+
+lazy_static! {
+    static ref REFERENCE_LINE_REGEX: Regex = Regex::new(r#"^(\S+) ([0-9a-f]+) "(.+)"$"#).unwrap();
+}
+
+pub fn subcommand() -> Command {
+    Command::new("verify-against")
+        .about("Verify ROM files against a reference fingerprint file")
+        .arg(
+            Arg::new("FINGERPRINT_FILE")
+                .help("Set the reference fingerprint file")
+                .required(true)
+                .index(1)
+                .value_parser(value_parser!(PathBuf)),
+        )
+        .arg(
+            Arg::new("EXPORT")
+                .short('e')
+                .long("export")
+                .help("Write the current fingerprints of all systems to the reference file")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new("CHECK")
+                .short('c')
+                .long("check")
+                .help("Run check-roms on the systems that drifted")
+                .required(false)
+                .action(ArgAction::SetTrue),
+        )
+}
+
+pub async fn main(
+    connection: &mut SqliteConnection,
+    matches: &ArgMatches,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<()> {
+    let fingerprint_file = matches.get_one::<PathBuf>("FINGERPRINT_FILE").unwrap();
+
+    if matches.get_flag("EXPORT") {
+        let lines = get_reference_lines(connection, progress_bar).await?;
+        try_with!(
+            fs::write(fingerprint_file, lines.join("\n") + "\n"),
+            "Failed to write {:?}",
+            fingerprint_file
+        );
+        progress_bar.println(format!("Exported {} fingerprints", lines.len()));
+        return Ok(());
+    }
+
+    let reference = try_with!(
+        fs::read_to_string(fingerprint_file),
+        "Failed to read {:?}",
+        fingerprint_file
+    );
+    let drifted_systems = verify_against(connection, progress_bar, &reference).await?;
+    if drifted_systems.is_empty() {
+        progress_bar.println("No system drifted");
+    }
+
+    if matches.get_flag("CHECK") {
+        for system in drifted_systems {
+            progress_bar.println(format!("Processing \"{}\"", system.name));
+            let hash_algorithm = get_hash_algorithm(connection, &system).await?;
+            check_roms::check_system(
+                connection,
+                progress_bar,
+                &ProgressBar::hidden(),
+                &system,
+                &hash_algorithm,
+                &check_roms::CheckOptions::default(),
+            )
+            .await?;
+        }
+    }
+
+    Ok(())
+}
+
+pub async fn get_reference_lines(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+) -> SimpleResult<Vec<String>> {
+    let mut lines: Vec<String> = Vec::new();
+    for system in find_systems(connection).await {
+        let hash_algorithm = get_hash_algorithm(connection, &system).await?;
+        let fingerprint =
+            get_romfiles_fingerprint(connection, progress_bar, &system, &hash_algorithm).await?;
+        lines.push(format!(
+            "{} {} \"{}\"",
+            hash_algorithm.name(),
+            fingerprint,
+            system.name
+        ));
+    }
+    Ok(lines)
+}
+
+pub async fn verify_against(
+    connection: &mut SqliteConnection,
+    progress_bar: &ProgressBar,
+    reference: &str,
+) -> SimpleResult<Vec<System>> {
+    let mut drifted_systems: Vec<System> = Vec::new();
+    for line in reference.lines().filter(|line| !line.trim().is_empty()) {
+        let captures = match REFERENCE_LINE_REGEX.captures(line) {
+            Some(captures) => captures,
+            None => bail!("Malformed fingerprint line: {}", line),
+        };
+        let hash_algorithm = HashAlgorithm::from_str(&captures[1])?;
+        let system_name = &captures[3];
+        let system = match find_system_by_name(connection, system_name).await {
+            Some(system) => system,
+            None => {
+                progress_bar.println(format!("\"{}\" is missing", system_name));
+                continue;
+            }
+        };
+        let fingerprint =
+            get_romfiles_fingerprint(connection, progress_bar, &system, &hash_algorithm).await?;
+        if fingerprint != captures[2] {
+            progress_bar.println(format!("\"{}\" drifted", system.name));
+            drifted_systems.push(system);
+        }
+    }
+    Ok(drifted_systems)
+}
+
+#[cfg(test)]
+mod test_altered_rom;
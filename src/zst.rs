@@ -0,0 +1,46 @@
+use super::progress::*;
+use super::util::*;
+use super::SimpleResult;
+use async_std::path::{Path, PathBuf};
+use indicatif::ProgressBar;
+use std::fs::File;
+
+pub fn extract_zst<P: AsRef<Path>, Q: AsRef<Path>>(
+    progress_bar: &ProgressBar,
+    zst_path: &P,
+    directory: &Q,
+) -> SimpleResult<PathBuf> {
+    progress_bar.println(format!(
+        "Extracting {:?}",
+        zst_path.as_ref().file_name().unwrap()
+    ));
+
+    // the decompressed file keeps the name without the ZST extension
+    let rom_path = directory
+        .as_ref()
+        .join(zst_path.as_ref().file_stem().unwrap());
+
+    let zst_file = try_with!(File::open(zst_path.as_ref()), "Failed to open ZST");
+
+    progress_bar.reset();
+    progress_bar.set_message("Extracting ZST");
+    progress_bar.set_style(get_bytes_progress_style());
+    progress_bar.set_length(try_with!(zst_file.metadata(), "Failed to read ZST metadata").len());
+
+    write_atomically(progress_bar, &rom_path, |part_path| {
+        let rom_file = try_with!(File::create(part_path), "Failed to create file");
+        try_with!(
+            zstd::stream::copy_decode(progress_bar.wrap_read(zst_file), rom_file),
+            "Failed to extract ZST"
+        );
+        Ok(())
+    })?;
+
+    progress_bar.set_message("");
+    progress_bar.set_style(get_none_progress_style());
+
+    Ok(rom_path)
+}
+
+#[cfg(test)]
+mod test_interrupted_extraction;
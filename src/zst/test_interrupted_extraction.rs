@@ -0,0 +1,35 @@
+use super::*;
+use async_std::fs;
+use tempfile::TempDir;
+
+#[async_std::test]
+async fn test() {
+    // given
+    let test_directory = Path::new("tests");
+    let progress_bar = ProgressBar::hidden();
+
+    let tmp_directory = TempDir::new_in(&test_directory).unwrap();
+    let tmp_directory = PathBuf::from(tmp_directory.path());
+
+    // mimic a previous run that got interrupted halfway through
+    let part_path = tmp_directory.join("Test Game (USA, Europe).rom.part");
+    fs::write(&part_path, "interrupted").await.unwrap();
+
+    // when
+    let rom_path = extract_zst(
+        &progress_bar,
+        &test_directory.join("Test Game (USA, Europe).rom.zst"),
+        &tmp_directory,
+    )
+    .unwrap();
+
+    // then
+    assert_eq!(rom_path, tmp_directory.join("Test Game (USA, Europe).rom"));
+    assert!(!part_path.is_file().await);
+    assert_eq!(
+        fs::read(&rom_path).await.unwrap(),
+        fs::read(test_directory.join("Test Game (USA, Europe).rom"))
+            .await
+            .unwrap()
+    );
+}
@@ -0,0 +1,6 @@
+FILE "Test Game (Europe) (Combined).bin" BINARY
+  TRACK 01 MODE2/2352
+    INDEX 01 00:00:00
+  TRACK 02 AUDIO
+    INDEX 00 00:00:03
+    INDEX 01 00:00:04
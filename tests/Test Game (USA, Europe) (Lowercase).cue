@@ -0,0 +1,8 @@
+CATALOG 0000000000000
+FILE "test game (usa, europe) (track 01).bin" BINARY
+  TRACK 01 AUDIO
+    INDEX 01 00:00:00
+FILE "test game (usa, europe) (track 02).bin" BINARY
+  TRACK 02 MODE1/2352
+    INDEX 00 00:00:00
+    INDEX 01 00:03:06